@@ -275,17 +275,14 @@ chrome.runtime.onMessage.addListener((message, sender, sendResponse) => {
     return true;
   }
 
-  if (message.type === 'save_passkey_to_server') {
-    // Backend expects passkey fields directly, not nested
-    callAPI('/save_passkey', message.passkey).then(sendResponse);
+  // Key generation and signing happen in the app; only public results come back
+  if (message.type === 'passkey_create') {
+    callAPI('/passkey_create', message.request).then(sendResponse);
     return true;
   }
 
-  if (message.type === 'update_passkey_counter') {
-    callAPI('/update_passkey_counter', {
-      credentialId: message.credentialId,
-      counter: message.counter
-    }).then(sendResponse);
+  if (message.type === 'passkey_assert') {
+    callAPI('/passkey_assert', message.request).then(sendResponse);
     return true;
   }
 
@@ -323,14 +320,15 @@ chrome.runtime.onMessage.addListener((message, sender, sendResponse) => {
         url: data.url
       }).then(sendResponse);
     } else {
-      // For cards and addresses, use save_entry with JSON notes
+      // For cards and addresses, use save_entry with the fields as entry data
+      const { title, url, ...fields } = data;
       const entryData = {
-        title: data.title,
+        title: title,
         username: category === 'bank_cards' ? data.cardNumber : (data.street || ''),
         password: category === 'bank_cards' ? (data.cvv || '') : '',
-        url: data.url || '',
+        url: url || '',
         category: category,
-        notes: JSON.stringify(data)
+        data: { type: category === 'bank_cards' ? 'card' : 'address', ...fields }
       };
       callAPI('/save_entry', entryData).then(sendResponse);
     }
@@ -418,7 +418,7 @@
       width: 100% !important;
       height: 100% !important;
       background: rgba(5, 5, 7, 0.85) !important;
-      backdrop-filter: blur(12px) !important;
+      backdrop-filter: blur(12px) !important;
       z-index: 2147483647 !important;
       display: flex !important;
       align-items: center !important;
@@ -985,8 +985,8 @@
     // Create icon button
     const iconBtn = document.createElement('button');
     iconBtn.className = 'confpass-icon-btn';
-    iconBtn.type = 'button';
-    field.confpassButton = iconBtn;
+    iconBtn.type = 'button';
+    field.confpassButton = iconBtn;
     iconBtn.innerHTML = `
       <svg viewBox="0 0 24 24" fill="none" stroke="currentColor" stroke-width="2.5">
         <rect x="3" y="11" width="18" height="11" rx="2" ry="2"/>
@@ -1034,7 +1034,7 @@
         <span class="confpass-dropdown-title">ConfPass</span>
       </div>
       <div class="confpass-dropdown-list">
-        <div class="confpass-dropdown-empty">Yükleniyor...</div>
+        <div class="confpass-dropdown-empty">Yükleniyor...</div>
       </div>
     `;
 
@@ -1150,9 +1150,9 @@
           <path d="M7 11V7a5 5 0 0 1 10 0v4"/>
         </svg>
         <div class="confpass-dropdown-locked-text">Kasa kilitli</div>
-        <button class="confpass-dropdown-btn" id="confpass-open-app">
-          Uygulamayı Aç
-        </button>
+        <button class="confpass-dropdown-btn" id="confpass-open-app">
+          Uygulamayı Aç
+        </button>
       </div>
     `;
 
@@ -1923,30 +1923,30 @@
     }
 
     // Focus event listener
-    document.addEventListener('focusin', (e) => {
-      if (e.target.tagName === 'INPUT') {
-        const type = e.target.type?.toLowerCase();
-        if (type === 'text' || type === 'email' || type === 'password' || !type) {
-          setTimeout(() => addIconToField(e.target), 50);
-        }
-      }
-    }, true);
-
-    // Keyboard shortcut: Ctrl+Shift+L to open dropdown on focused field
-    document.addEventListener('keydown', (e) => {
-      if (e.ctrlKey && e.shiftKey && e.key && e.key.toLowerCase() === 'l') {
-        const active = document.activeElement;
-        if (active && active.tagName === 'INPUT') {
-          addIconToField(active);
-          if (active.confpassButton) {
-            active.confpassButton.click();
-          } else {
-            showDropdownForField(active);
-          }
-        }
-      }
-    }, true);
-  }
+    document.addEventListener('focusin', (e) => {
+      if (e.target.tagName === 'INPUT') {
+        const type = e.target.type?.toLowerCase();
+        if (type === 'text' || type === 'email' || type === 'password' || !type) {
+          setTimeout(() => addIconToField(e.target), 50);
+        }
+      }
+    }, true);
+
+    // Keyboard shortcut: Ctrl+Shift+L to open dropdown on focused field
+    document.addEventListener('keydown', (e) => {
+      if (e.ctrlKey && e.shiftKey && e.key && e.key.toLowerCase() === 'l') {
+        const active = document.activeElement;
+        if (active && active.tagName === 'INPUT') {
+          addIconToField(active);
+          if (active.confpassButton) {
+            active.confpassButton.click();
+          } else {
+            showDropdownForField(active);
+          }
+        }
+      }
+    }, true);
+  }
 
   // ========== Message Listener ==========
   chrome.runtime.onMessage.addListener((message, sender, sendResponse) => {
@@ -1990,8 +1990,8 @@
       sendResponse({ success: true });
     }
 
-    return true;
-  });
+    return true;
+  });
 
   // ========== Notification Helper ==========
   function showNotification(message) {
@@ -2125,6 +2125,15 @@
   }
 
   // ========== WebAuthn Handler (Passkey) ==========
+  // Ceremonies the user confirmed in a ConfPass dialog. The page can post
+  // create/assert messages by itself, so the app is only asked for approved
+  // ones, each approval being used once.
+  const approvedPasskeyCeremonies = new Set();
+
+  function ceremonyKey(kind, rpId, challenge, credentialId = '') {
+    return [kind, rpId, challenge, credentialId].join('|');
+  }
+
   window.addEventListener('message', async function(event) {
     if (event.source !== window) return;
     if (!event.data || event.data.type !== CONFPASS_MESSAGE_TYPE) return;
@@ -2149,16 +2158,15 @@
           break;
         }
 
-        case 'save_passkey': {
-          const success = await savePasskey(data.passkey);
-          response.success = success;
-          if (!success) response.error = 'Failed to save passkey';
+        case 'create_passkey': {
+          const result = await requestPasskeyCreate(data);
+          response = { ...response, ...result };
           break;
         }
 
-        case 'update_counter': {
-          const success = await updatePasskeyCounter(data.credentialId, data.counter);
-          response.success = success;
+        case 'assert_passkey': {
+          const result = await requestPasskeyAssert(data);
+          response = { ...response, ...result };
           break;
         }
 
@@ -2190,36 +2198,49 @@
     });
   }
 
-  async function savePasskey(passkey) {
+  // The origin comes from the content script, not from the page message,
+  // so a page cannot ask the app to sign for another site
+  function sendPasskeyRequest(type, request) {
     return new Promise((resolve) => {
       chrome.runtime.sendMessage({
-        type: 'save_passkey_to_server',
-        passkey: passkey
+        type: type,
+        request: { ...request, origin: window.location.origin }
       }, (response) => {
         if (chrome.runtime.lastError) {
-          console.error('[ConfPass Content] Error saving passkey:', chrome.runtime.lastError);
-          resolve(false);
+          console.error('[ConfPass Content] Passkey request failed:', chrome.runtime.lastError);
+          resolve({ success: false, error: chrome.runtime.lastError.message });
           return;
         }
-        resolve(response?.success || false);
+        resolve(response || { success: false, error: 'Empty response' });
       });
     });
   }
 
-  async function updatePasskeyCounter(credentialId, counter) {
-    return new Promise((resolve) => {
-      chrome.runtime.sendMessage({
-        type: 'update_passkey_counter',
-        credentialId: credentialId,
-        counter: counter
-      }, (response) => {
-        if (chrome.runtime.lastError) {
-          console.error('[ConfPass Content] Error updating counter:', chrome.runtime.lastError);
-          resolve(false);
-          return;
-        }
-        resolve(response?.success || false);
-      });
+  async function requestPasskeyCreate(data) {
+    if (!approvedPasskeyCeremonies.delete(ceremonyKey('create', data.rpId, data.challenge))) {
+      return { success: false, error: 'Kullanıcı onayı gerekli' };
+    }
+    return sendPasskeyRequest('passkey_create', {
+      rpId: data.rpId,
+      rpName: data.rpName,
+      userId: data.userId,
+      userName: data.userName,
+      userDisplayName: data.userDisplayName,
+      challenge: data.challenge,
+      userPresent: true
+    });
+  }
+
+  async function requestPasskeyAssert(data) {
+    const key = ceremonyKey('get', data.rpId, data.challenge, data.credentialId);
+    if (!approvedPasskeyCeremonies.delete(key)) {
+      return { success: false, error: 'Kullanıcı onayı gerekli' };
+    }
+    return sendPasskeyRequest('passkey_assert', {
+      credentialId: data.credentialId,
+      rpId: data.rpId,
+      challenge: data.challenge,
+      userPresent: true
     });
   }
 
@@ -2233,7 +2254,7 @@
       width: 100%;
       height: 100%;
       background: rgba(5, 5, 7, 0.85);
-      backdrop-filter: blur(12px);
+      backdrop-filter: blur(12px);
       z-index: 2147483647;
       display: flex;
       align-items: center;
@@ -2442,7 +2463,9 @@
 
       document.body.appendChild(overlay);
 
-      document.getElementById('confpass-pk-use').onclick = () => {
+      document.getElementById('confpass-pk-use').onclick = (e) => {
+        if (!e.isTrusted) return;
+        approvedPasskeyCeremonies.add(ceremonyKey('create', data.rpId, data.challenge));
         overlay.remove();
         resolve({ useConfPass: true });
       };
@@ -2532,9 +2555,14 @@
       });
 
       if (hasPasskeys) {
-        document.getElementById('confpass-pk-use').onclick = () => {
+        document.getElementById('confpass-pk-use').onclick = (e) => {
+          if (!e.isTrusted) return;
+          const passkey = passkeys[selectedIndex];
+          approvedPasskeyCeremonies.add(
+            ceremonyKey('get', data.rpId, data.challenge, passkey.credentialId)
+          );
           overlay.remove();
-          resolve({ useConfPass: true, passkey: passkeys[selectedIndex] });
+          resolve({ useConfPass: true, passkey });
         };
       }
 
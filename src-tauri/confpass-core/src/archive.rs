@@ -0,0 +1,472 @@
+//! Full-fidelity encrypted archive (`confpass_encrypted_v2`).
+//!
+//! Unlike the v1 export, which carries only the entries, an archive bundles
+//! everything a restore on another machine needs: entries (trashed ones
+//! included), folders, tags, password history, revisions, passkeys and the
+//! contents of every attachment. The payload is JSON sealed with AES-256-GCM
+//! under a key derived from the archive password with Argon2id; the
+//! cleartext header (format, KDF parameters and salt) is bound to it as
+//! associated data, so it cannot be altered without the open failing.
+//!
+//! [`restore`] goes through the regular import path: folders and tags are
+//! merged by name and entries re-linked to them, duplicates are handled by a
+//! [`MergeStrategy`] and attachments get fresh ids, so restoring into a vault
+//! that already holds some of the data is safe.
+
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+
+use base64::{engine::general_purpose, Engine as _};
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use zeroize::Zeroize;
+
+use crate::attachments;
+use crate::crypto::KdfParams;
+use crate::error::VaultError;
+use crate::importers::{
+    self, ImportAction, ImportPreview, ImportSummary, ImportedFile, MergeStrategy,
+};
+use crate::keys::DataKey;
+use crate::models::{
+    EntryRevision, Folder, PasswordEntry, PasswordHistoryItem, StoredPasskey, Tag, VaultState,
+};
+use crate::passkeys;
+
+pub const ARCHIVE_FORMAT: &str = "confpass_encrypted_v2";
+const SALT_LEN: usize = 32;
+
+/// Cleartext part of an archive file, authenticated together with the payload.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct ArchiveHeader {
+    format: String,
+    kdf: KdfParams,
+    salt: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct ArchiveFile {
+    #[serde(flatten)]
+    header: ArchiveHeader,
+    /// `nonce || AES-256-GCM(payload)`, base64.
+    data: String,
+}
+
+/// Decrypted contents of an archive.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Archive {
+    pub exported_at: i64,
+    pub entries: Vec<PasswordEntry>,
+    #[serde(default)]
+    pub folders: Vec<Folder>,
+    #[serde(default)]
+    pub tags: Vec<Tag>,
+    #[serde(default)]
+    pub password_history: HashMap<String, Vec<PasswordHistoryItem>>,
+    #[serde(default)]
+    pub revisions: HashMap<String, Vec<EntryRevision>>,
+    #[serde(default)]
+    pub passkeys: Vec<StoredPasskey>,
+    #[serde(default)]
+    pub attachments: Vec<ArchivedAttachment>,
+}
+
+/// Decrypted content of an attachment, base64.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ArchivedAttachment {
+    pub id: String,
+    pub data: String,
+}
+
+impl Drop for ArchivedAttachment {
+    fn drop(&mut self) {
+        self.data.zeroize();
+    }
+}
+
+/// Writes the whole vault as an archive sealed under `password`. `kdf` must be
+/// Argon2id; attachments are read from `attachments_dir`.
+pub fn export(
+    state: &VaultState,
+    attachments_dir: &Path,
+    password: &str,
+    kdf: KdfParams,
+    now: i64,
+) -> Result<String, String> {
+    kdf.validate()?;
+    let data_key = &state
+        .keys
+        .as_ref()
+        .ok_or_else(|| VaultError::Locked.to_string())?
+        .data_key;
+
+    let mut attachments = Vec::new();
+    let mut seen = HashSet::new();
+    for attachment in state
+        .entries
+        .values()
+        .flat_map(|entry| entry.attachments.iter().flatten())
+    {
+        if !seen.insert(attachment.id.as_str()) {
+            continue;
+        }
+        let mut content = attachments::read_attachment(attachments_dir, &attachment.id, data_key)
+            .map_err(|e| format!("'{}' eki okunamadı: {}", attachment.filename, e))?;
+        attachments.push(ArchivedAttachment {
+            id: attachment.id.clone(),
+            data: general_purpose::STANDARD.encode(&content),
+        });
+        content.zeroize();
+    }
+
+    let archive = Archive {
+        exported_at: now,
+        entries: state.entries.values().cloned().collect(),
+        folders: state.folders.clone(),
+        tags: state.tags.clone(),
+        password_history: state.password_history.clone(),
+        revisions: state.revisions.clone(),
+        passkeys: passkeys::retained_passkeys(state),
+        attachments,
+    };
+    let mut payload = serde_json::to_vec(&archive).map_err(|e| format!("JSON error: {}", e))?;
+
+    let mut salt = [0u8; SALT_LEN];
+    rand::rngs::OsRng.fill_bytes(&mut salt);
+    let header = ArchiveHeader {
+        format: ARCHIVE_FORMAT.to_string(),
+        kdf,
+        salt: general_purpose::STANDARD.encode(salt),
+    };
+    let sealed =
+        archive_key(&header, password).and_then(|key| key.encrypt(&payload, &aad(&header)?));
+    payload.zeroize();
+
+    serde_json::to_string(&ArchiveFile {
+        header,
+        data: general_purpose::STANDARD.encode(sealed?),
+    })
+    .map_err(|e| format!("JSON error: {}", e))
+}
+
+/// Decrypts an archive written by [`export`].
+pub fn open(file: &str, password: &str) -> Result<Archive, String> {
+    let file: ArchiveFile =
+        serde_json::from_str(file).map_err(|e| format!("Geçersiz yedek dosyası: {}", e))?;
+    if file.header.format != ARCHIVE_FORMAT {
+        return Err("Desteklenmeyen dosya formatı".to_string());
+    }
+    file.header.kdf.validate()?;
+
+    let sealed = general_purpose::STANDARD
+        .decode(&file.data)
+        .map_err(|e| format!("Data decode error: {}", e))?;
+    let mut payload = archive_key(&file.header, password)?
+        .decrypt(&sealed, &aad(&file.header)?)
+        .map_err(|_| "Şifre çözme hatası: Yanlış şifre".to_string())?;
+    let archive =
+        serde_json::from_slice(&payload).map_err(|e| format!("Geçersiz yedek içeriği: {}", e));
+    payload.zeroize();
+    archive
+}
+
+/// Restores `archive` into the vault. Attachment contents are written to
+/// `attachments_dir` under new ids; entries that are added carry their
+/// password history and revisions along, and passkeys come back with their
+/// entries.
+pub fn restore(
+    state: &mut VaultState,
+    mut archive: Archive,
+    strategy: MergeStrategy,
+    attachments_dir: &Path,
+    now: i64,
+) -> Result<ImportSummary, String> {
+    let mut files = Vec::new();
+    let mut attachment_ids = HashMap::new();
+    for attachment in &archive.attachments {
+        let content = general_purpose::STANDARD
+            .decode(&attachment.data)
+            .map_err(|e| format!("Ek çözümlenemedi: {}", e))?;
+        let id = uuid::Uuid::new_v4().to_string();
+        attachment_ids.insert(attachment.id.clone(), id.clone());
+        files.push(ImportedFile {
+            attachment_id: id,
+            content,
+        });
+    }
+    for entry in &mut archive.entries {
+        if let Some(list) = entry.attachments.as_mut() {
+            list.retain_mut(|attachment| match attachment_ids.get(&attachment.id) {
+                Some(id) => {
+                    attachment.id = id.clone();
+                    true
+                }
+                None => false,
+            });
+        }
+    }
+
+    let source_ids: Vec<String> = archive.entries.iter().map(|e| e.id.clone()).collect();
+    let preview = ImportPreview {
+        entries: std::mem::take(&mut archive.entries),
+        folders: parents_first(std::mem::take(&mut archive.folders)),
+        tags: std::mem::take(&mut archive.tags),
+        files,
+        ..Default::default()
+    };
+    let summary = importers::apply_with_files(state, preview, strategy, now, attachments_dir)?;
+
+    for (source_id, change) in source_ids.iter().zip(&summary.changes) {
+        if !matches!(change.action, ImportAction::Added | ImportAction::KeptBoth) {
+            continue;
+        }
+        if let Some(history) = archive.password_history.remove(source_id) {
+            state
+                .password_history
+                .entry(change.id.clone())
+                .or_insert(history);
+        }
+        if let Some(revisions) = archive.revisions.remove(source_id) {
+            state
+                .revisions
+                .entry(change.id.clone())
+                .or_insert(revisions);
+        }
+    }
+
+    let known: HashSet<String> = state
+        .passkeys
+        .iter()
+        .map(|p| p.credential_id.clone())
+        .collect();
+    state.passkeys.extend(
+        archive
+            .passkeys
+            .drain(..)
+            .filter(|p| !known.contains(&p.credential_id)),
+    );
+
+    Ok(summary)
+}
+
+fn archive_key(header: &ArchiveHeader, password: &str) -> Result<DataKey, String> {
+    let salt = general_purpose::STANDARD
+        .decode(&header.salt)
+        .map_err(|e| format!("Salt decode error: {}", e))?;
+    let mut key = header.kdf.derive_key(password, &salt)?;
+    let data_key = DataKey::from_bytes(&key);
+    key.zeroize();
+    data_key
+}
+
+fn aad(header: &ArchiveHeader) -> Result<Vec<u8>, String> {
+    serde_json::to_vec(header).map_err(|e| format!("JSON error: {}", e))
+}
+
+/// Orders folders so every parent comes before its children, as
+/// [`importers::apply`] expects.
+fn parents_first(mut folders: Vec<Folder>) -> Vec<Folder> {
+    folders.sort_by_key(|f| f.order);
+    let ids: HashSet<String> = folders.iter().map(|f| f.id.clone()).collect();
+    let mut placed = HashSet::new();
+    let mut ordered = Vec::with_capacity(folders.len());
+    while !folders.is_empty() {
+        let (ready, rest): (Vec<_>, Vec<_>) = folders.into_iter().partition(|f| {
+            f.parent_id
+                .as_ref()
+                .is_none_or(|id| !ids.contains(id) || placed.contains(id))
+        });
+        if ready.is_empty() {
+            // A parent cycle; the remaining folders end up at the top level
+            ordered.extend(rest);
+            break;
+        }
+        placed.extend(ready.iter().map(|f| f.id.clone()));
+        ordered.extend(ready);
+        folders = rest;
+    }
+    ordered
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::keys::VaultKeys;
+    use crate::models::FileAttachment;
+
+    const TEST_KDF: KdfParams = KdfParams::Argon2id {
+        memory_kib: 19456,
+        iterations: 1,
+        parallelism: 1,
+    };
+
+    fn unlocked_state() -> VaultState {
+        VaultState {
+            keys: Some(
+                VaultKeys::generate("ana-sifre", KdfParams::Pbkdf2Sha256 { iterations: 1000 })
+                    .unwrap(),
+            ),
+            ..VaultState::default()
+        }
+    }
+
+    fn folder(id: &str, name: &str, parent: Option<&str>, order: i32) -> Folder {
+        Folder {
+            id: id.to_string(),
+            name: name.to_string(),
+            color: "#f59e0b".to_string(),
+            icon: "folder".to_string(),
+            parent_id: parent.map(str::to_string),
+            created_at: 1_700_000_000,
+            order,
+        }
+    }
+
+    #[test]
+    fn archive_restores_folders_tags_history_attachments_and_passkeys() {
+        let source_dir = tempfile::tempdir().unwrap();
+        let mut source = unlocked_state();
+        let data_key = &source.keys.as_ref().unwrap().data_key;
+        attachments::write_attachment(source_dir.path(), "att-1", b"gizli belge", data_key)
+            .unwrap();
+
+        // The child comes first, as it may in a vault whose folders were reordered
+        source
+            .folders
+            .push(folder("f-2", "Sunucular", Some("f-1"), 0));
+        source.folders.push(folder("f-1", "İş", None, 1));
+        source.tags.push(Tag {
+            id: "t-1".to_string(),
+            name: "önemli".to_string(),
+            color: "#3b82f6".to_string(),
+        });
+
+        let login = PasswordEntry {
+            id: "e-1".to_string(),
+            title: "Sunucu".to_string(),
+            username: "root".to_string(),
+            password: "yeni".to_string(),
+            url: Some("https://prod.example.com".to_string()),
+            notes: None,
+            created_at: 1_700_000_000,
+            updated_at: 1_700_000_100,
+            category: "accounts".to_string(),
+            folder_id: Some("f-2".to_string()),
+            tags: Some(vec!["t-1".to_string()]),
+            extra_fields: None,
+            attachments: Some(vec![FileAttachment {
+                id: "att-1".to_string(),
+                filename: "belge.txt".to_string(),
+                mime_type: "text/plain".to_string(),
+                size: 11,
+                created_at: 1_700_000_000,
+            }]),
+            data: None,
+            deleted_at: None,
+        };
+        source.entries.insert(login.id.clone(), login);
+        source.password_history.insert(
+            "e-1".to_string(),
+            vec![PasswordHistoryItem {
+                password: "eski".to_string(),
+                changed_at: 1_700_000_050,
+            }],
+        );
+
+        let passkey = StoredPasskey {
+            credential_id: "cred-1".to_string(),
+            private_key: "gizli-anahtar".to_string(),
+            rp_id: "example.com".to_string(),
+            rp_name: "Example".to_string(),
+            user_id: "u-1".to_string(),
+            user_name: "ada".to_string(),
+            user_display_name: "Ada".to_string(),
+            counter: 3,
+            created_at: 1_700_000_000,
+        };
+        let passkey_entry = passkeys::passkey_entry(&passkey, 1_700_000_000);
+        source
+            .entries
+            .insert(passkey_entry.id.clone(), passkey_entry);
+        source.passkeys.push(passkey.clone());
+
+        let file = export(
+            &source,
+            source_dir.path(),
+            "yedek-sifre",
+            TEST_KDF,
+            1_700_001_000,
+        )
+        .unwrap();
+        assert!(!file.contains("gizli"));
+        assert!(open(&file, "yanlis-sifre").is_err());
+        let tampered = file.replace("\"iterations\":1", "\"iterations\":2");
+        assert!(open(&tampered, "yedek-sifre").is_err());
+
+        let target_dir = tempfile::tempdir().unwrap();
+        let mut target = unlocked_state();
+        let archive = open(&file, "yedek-sifre").unwrap();
+        let summary = restore(
+            &mut target,
+            archive,
+            MergeStrategy::Skip,
+            target_dir.path(),
+            1_700_002_000,
+        )
+        .unwrap();
+        assert_eq!(
+            (
+                summary.imported,
+                summary.folders_created,
+                summary.tags_created
+            ),
+            (2, 2, 1)
+        );
+
+        let restored = &target.entries["e-1"];
+        let child = target
+            .folders
+            .iter()
+            .find(|f| Some(&f.id) == restored.folder_id.as_ref())
+            .unwrap();
+        assert_eq!(child.name, "Sunucular");
+        let parent = target
+            .folders
+            .iter()
+            .find(|f| Some(&f.id) == child.parent_id.as_ref())
+            .unwrap();
+        assert_eq!(parent.name, "İş");
+        assert_eq!(target.tags[0].name, "önemli");
+        assert_eq!(
+            restored.tags.as_deref(),
+            Some(&[target.tags[0].id.clone()][..])
+        );
+        assert_eq!(target.password_history["e-1"][0].password, "eski");
+        assert_eq!(target.passkeys, vec![passkey]);
+
+        let attachment = &restored.attachments.as_ref().unwrap()[0];
+        assert_ne!(attachment.id, "att-1");
+        let target_key = &target.keys.as_ref().unwrap().data_key;
+        assert_eq!(
+            attachments::read_attachment(target_dir.path(), &attachment.id, target_key).unwrap(),
+            b"gizli belge"
+        );
+
+        // Restoring again skips the duplicates and leaves no stray files behind
+        let summary = restore(
+            &mut target,
+            open(&file, "yedek-sifre").unwrap(),
+            MergeStrategy::Skip,
+            target_dir.path(),
+            1_700_003_000,
+        )
+        .unwrap();
+        assert_eq!((summary.imported, summary.skipped), (0, 2));
+        assert_eq!(
+            attachments::list_attachment_files(target_dir.path())
+                .unwrap()
+                .len(),
+            1
+        );
+    }
+}
@@ -0,0 +1,718 @@
+//! Encrypted attachment files stored next to `vault.dat` as `attachments/<id>.enc`.
+//!
+//! Files are binary and encrypted with the vault data key in fixed-size
+//! chunks (the STREAM construction), so attachments of any size are written
+//! and read without holding them in memory:
+//!
+//! ```text
+//! "CPAT" | version (1) | chunk size (u32 LE) | nonce prefix (7) | chunk…
+//! ```
+//!
+//! Every chunk is AES-256-GCM with the nonce `prefix || counter (u32 BE) ||
+//! last flag`, and the header plus the attachment id as associated data. The
+//! counter keeps chunks from being reordered, the flag marks the final chunk
+//! so truncation is detected, and the id keeps files from being swapped
+//! between attachments.
+//!
+//! A file belongs to the entries listing its id. It stays on disk while its
+//! entry is in the trash and goes once no entry refers to it any more; see
+//! [`collect_orphans`].
+//!
+//! Older files are converted by [`migrate_legacy_attachments`] on unlock:
+//! JSON `{"version": 2, "data"}` files sealed in one piece with the data key,
+//! and `{"salt", "data"}` files from before the data key existed, keyed by the
+//! master password.
+
+use aes_gcm::aead::{Aead, Payload};
+use aes_gcm::Nonce;
+use base64::{engine::general_purpose, Engine as _};
+use rand::RngCore;
+use serde::Serialize;
+use std::collections::HashSet;
+use std::fs::{self, File};
+use std::io::{BufRead, BufReader, BufWriter, ErrorKind, Read, Write};
+use std::path::{Path, PathBuf};
+use zeroize::Zeroize;
+
+use crate::crypto::decrypt_vault_data;
+use crate::error::VaultError;
+use crate::keys::DataKey;
+use crate::models::{FileAttachment, VaultState};
+
+pub const ATTACHMENTS_DIR_NAME: &str = "attachments";
+pub const ATTACHMENT_EXTENSION: &str = "enc";
+
+/// Size limit for new attachments unless the user has chosen otherwise.
+pub const DEFAULT_MAX_ATTACHMENT_MB: u32 = 100;
+/// Largest size limit the settings accept.
+pub const MAX_ATTACHMENT_MB: u32 = 4096;
+
+const MAGIC: &[u8; 4] = b"CPAT";
+const FILE_VERSION: u8 = 3;
+const LEGACY_JSON_VERSION: u64 = 2;
+const CHUNK_SIZE: usize = 64 * 1024;
+const MAX_CHUNK_SIZE: usize = 16 * 1024 * 1024;
+const NONCE_PREFIX_LEN: usize = 7;
+const HEADER_LEN: usize = MAGIC.len() + 1 + 4 + NONCE_PREFIX_LEN;
+const TAG_LEN: usize = 16;
+
+/// Directory holding the attachments of the vault at `vault_path`.
+pub fn attachments_dir(vault_path: &Path) -> Result<PathBuf, String> {
+    Ok(vault_path
+        .parent()
+        .ok_or_else(|| "Vault path parent bulunamadı".to_string())?
+        .join(ATTACHMENTS_DIR_NAME))
+}
+
+pub fn attachment_path(dir: &Path, attachment_id: &str) -> PathBuf {
+    dir.join(format!("{}.{}", attachment_id, ATTACHMENT_EXTENSION))
+}
+
+fn chunk_nonce(prefix: &[u8], counter: u32, last: bool) -> [u8; 12] {
+    let mut nonce = [0u8; 12];
+    nonce[..NONCE_PREFIX_LEN].copy_from_slice(prefix);
+    nonce[NONCE_PREFIX_LEN..11].copy_from_slice(&counter.to_be_bytes());
+    nonce[11] = last as u8;
+    nonce
+}
+
+/// Fills `buf` from `source` as far as it goes; returns the bytes read, less
+/// than `buf.len()` only at the end of the input.
+fn read_full(source: &mut impl Read, buf: &mut [u8]) -> Result<usize, String> {
+    let mut filled = 0;
+    while filled < buf.len() {
+        match source.read(&mut buf[filled..]) {
+            Ok(0) => break,
+            Ok(n) => filled += n,
+            Err(e) if e.kind() == ErrorKind::Interrupted => {}
+            Err(e) => return Err(format!("Dosya okunamadı: {}", e)),
+        }
+    }
+    Ok(filled)
+}
+
+fn size_limit_error(max_size: u64) -> String {
+    format!(
+        "Dosya boyutu {} MB'dan büyük olamaz",
+        max_size / (1024 * 1024)
+    )
+}
+
+/// Encrypts everything `source` yields into `sink`, bound to `attachment_id`.
+/// Stops with an error once more than `max_size` bytes have been read.
+/// Returns the plaintext size.
+pub fn encrypt_stream(
+    mut source: impl Read,
+    sink: &mut impl Write,
+    attachment_id: &str,
+    key: &DataKey,
+    max_size: Option<u64>,
+) -> Result<u64, String> {
+    let cipher = key.cipher()?;
+
+    let mut header = Vec::with_capacity(HEADER_LEN);
+    header.extend_from_slice(MAGIC);
+    header.push(FILE_VERSION);
+    header.extend_from_slice(&(CHUNK_SIZE as u32).to_le_bytes());
+    let mut prefix = [0u8; NONCE_PREFIX_LEN];
+    rand::rngs::OsRng.fill_bytes(&mut prefix);
+    header.extend_from_slice(&prefix);
+    sink.write_all(&header)
+        .map_err(|e| format!("Dosya yazma hatası: {}", e))?;
+
+    let mut aad = header.clone();
+    aad.extend_from_slice(attachment_id.as_bytes());
+
+    // One chunk of lookahead tells whether the current chunk is the last
+    let mut current = vec![0u8; CHUNK_SIZE];
+    let mut next = vec![0u8; CHUNK_SIZE];
+    let mut current_len = read_full(&mut source, &mut current)?;
+    let mut total = 0u64;
+    let mut counter = 0u32;
+    let result = loop {
+        total += current_len as u64;
+        if let Some(max) = max_size.filter(|&max| total > max) {
+            break Err(size_limit_error(max));
+        }
+        let next_len = match read_full(&mut source, &mut next) {
+            Ok(n) => n,
+            Err(e) => break Err(e),
+        };
+        let last = next_len == 0;
+
+        let nonce = chunk_nonce(&prefix, counter, last);
+        let sealed = cipher.encrypt(
+            Nonce::from_slice(&nonce),
+            Payload {
+                msg: &current[..current_len],
+                aad: &aad,
+            },
+        );
+        let written = match sealed {
+            Ok(sealed) => sink.write_all(&sealed),
+            Err(e) => break Err(format!("Şifreleme hatası: {}", e)),
+        };
+        if let Err(e) = written {
+            break Err(format!("Dosya yazma hatası: {}", e));
+        }
+        if last {
+            break Ok(total);
+        }
+
+        counter = match counter.checked_add(1) {
+            Some(counter) => counter,
+            None => break Err("Dosya çok büyük".to_string()),
+        };
+        std::mem::swap(&mut current, &mut next);
+        current_len = next_len;
+    };
+
+    current.zeroize();
+    next.zeroize();
+    result
+}
+
+/// Decrypts a file written by [`encrypt_stream`] from `source` into `sink`.
+/// Fails if any chunk was altered, reordered or cut off; `sink` may have
+/// received the chunks before the failing one. Returns the plaintext size.
+pub fn decrypt_stream(
+    source: impl Read,
+    sink: &mut impl Write,
+    attachment_id: &str,
+    key: &DataKey,
+) -> Result<u64, String> {
+    decrypt_chunks(source, sink, attachment_id, key, None)
+}
+
+/// [`decrypt_stream`] that stops once `limit` bytes have been written; the
+/// chunks after that are neither read nor verified.
+fn decrypt_chunks(
+    mut source: impl Read,
+    sink: &mut impl Write,
+    attachment_id: &str,
+    key: &DataKey,
+    limit: Option<u64>,
+) -> Result<u64, String> {
+    let corrupt = || "Ek dosyası bozuk veya değiştirilmiş".to_string();
+    let cipher = key.cipher()?;
+
+    let mut header = [0u8; HEADER_LEN];
+    if read_full(&mut source, &mut header)? < HEADER_LEN || &header[..4] != MAGIC {
+        return Err(corrupt());
+    }
+    if header[4] != FILE_VERSION {
+        return Err("Desteklenmeyen ek dosyası sürümü".to_string());
+    }
+    let chunk_size = u32::from_le_bytes([header[5], header[6], header[7], header[8]]) as usize;
+    if chunk_size == 0 || chunk_size > MAX_CHUNK_SIZE {
+        return Err(corrupt());
+    }
+    let prefix = &header[9..];
+
+    let mut aad = header.to_vec();
+    aad.extend_from_slice(attachment_id.as_bytes());
+
+    let mut current = vec![0u8; chunk_size + TAG_LEN];
+    let mut next = vec![0u8; chunk_size + TAG_LEN];
+    let mut current_len = read_full(&mut source, &mut current)?;
+    let mut total = 0u64;
+    let mut counter = 0u32;
+    loop {
+        if current_len < TAG_LEN {
+            return Err(corrupt());
+        }
+        let next_len = read_full(&mut source, &mut next)?;
+        let last = next_len == 0;
+
+        let nonce = chunk_nonce(prefix, counter, last);
+        let mut plaintext = cipher
+            .decrypt(
+                Nonce::from_slice(&nonce),
+                Payload {
+                    msg: &current[..current_len],
+                    aad: &aad,
+                },
+            )
+            .map_err(|_| corrupt())?;
+        let take = limit.map_or(plaintext.len(), |limit| {
+            plaintext.len().min((limit - total) as usize)
+        });
+        let written = sink.write_all(&plaintext[..take]);
+        total += take as u64;
+        plaintext.zeroize();
+        written.map_err(|e| format!("Dosya yazma hatası: {}", e))?;
+        if last || limit == Some(total) {
+            return Ok(total);
+        }
+
+        counter = counter.checked_add(1).ok_or_else(corrupt)?;
+        std::mem::swap(&mut current, &mut next);
+        current_len = next_len;
+    }
+}
+
+/// Writes `path` through a temporary file that replaces it only once `write`
+/// has succeeded, so a failure never leaves a partial file behind.
+fn write_file_atomic(
+    path: &Path,
+    write: impl FnOnce(&mut BufWriter<File>) -> Result<u64, String>,
+) -> Result<u64, String> {
+    let mut tmp_name = path.as_os_str().to_owned();
+    tmp_name.push(".tmp");
+    let tmp_path = PathBuf::from(tmp_name);
+
+    let result = File::create(&tmp_path)
+        .map_err(|e| format!("Geçici dosya oluşturulamadı: {}", e))
+        .and_then(|file| {
+            let mut writer = BufWriter::new(file);
+            let size = write(&mut writer)?;
+            let file = writer
+                .into_inner()
+                .map_err(|e| format!("Dosya yazma hatası: {}", e))?;
+            file.sync_all()
+                .map_err(|e| format!("Dosya senkronizasyon hatası: {}", e))?;
+            Ok(size)
+        })
+        .and_then(|size| {
+            fs::rename(&tmp_path, path)
+                .map_err(|e| format!("Dosya değiştirme (rename) hatası: {}", e))?;
+            Ok(size)
+        });
+
+    if result.is_err() {
+        let _ = fs::remove_file(&tmp_path);
+    }
+    result
+}
+
+/// Encrypts what `source` yields and stores it as the file of
+/// `attachment_id` in `dir`. Returns the plaintext size.
+pub fn write_attachment_from(
+    dir: &Path,
+    attachment_id: &str,
+    source: impl Read,
+    key: &DataKey,
+    max_size: Option<u64>,
+) -> Result<u64, String> {
+    fs::create_dir_all(dir).map_err(|e| format!("Ek dizini oluşturulamadı: {}", e))?;
+    write_file_atomic(&attachment_path(dir, attachment_id), |sink| {
+        encrypt_stream(source, sink, attachment_id, key, max_size)
+    })
+}
+
+/// Encrypts `content` and writes it as the file of `attachment_id` in `dir`.
+pub fn write_attachment(
+    dir: &Path,
+    attachment_id: &str,
+    content: &[u8],
+    key: &DataKey,
+) -> Result<(), String> {
+    write_attachment_from(dir, attachment_id, content, key, None).map(|_| ())
+}
+
+/// Opens the file of `attachment_id`; the flag tells whether it is still in
+/// the single-piece JSON format of older releases.
+fn open_attachment(dir: &Path, attachment_id: &str) -> Result<(BufReader<File>, bool), String> {
+    let file = File::open(attachment_path(dir, attachment_id))
+        .map_err(|e| format!("Ek dosyası okunamadı: {}", e))?;
+    let mut reader = BufReader::new(file);
+    let legacy = reader
+        .fill_buf()
+        .map_err(|e| format!("Ek dosyası okunamadı: {}", e))?
+        .first()
+        == Some(&b'{');
+    Ok((reader, legacy))
+}
+
+/// Reads and decrypts the file of `attachment_id` in `dir`.
+pub fn read_attachment(dir: &Path, attachment_id: &str, key: &DataKey) -> Result<Vec<u8>, String> {
+    let (mut reader, legacy) = open_attachment(dir, attachment_id)?;
+    if legacy {
+        return decrypt_json_attachment(&mut reader, attachment_id, key);
+    }
+
+    let mut content = Vec::new();
+    match decrypt_stream(reader, &mut content, attachment_id, key) {
+        Ok(_) => Ok(content),
+        Err(e) => {
+            content.zeroize();
+            Err(e)
+        }
+    }
+}
+
+/// The first `len` bytes of the attachment, decrypting no more chunks than
+/// needed; for previews.
+pub fn read_attachment_head(
+    dir: &Path,
+    attachment_id: &str,
+    key: &DataKey,
+    len: usize,
+) -> Result<Vec<u8>, String> {
+    let (mut reader, legacy) = open_attachment(dir, attachment_id)?;
+    let mut content = Vec::new();
+    let result = if legacy {
+        decrypt_json_attachment(&mut reader, attachment_id, key).map(|full| {
+            content = full;
+            content.truncate(len);
+        })
+    } else {
+        decrypt_chunks(reader, &mut content, attachment_id, key, Some(len as u64)).map(|_| ())
+    };
+    match result {
+        Ok(()) => Ok(content),
+        Err(e) => {
+            content.zeroize();
+            Err(e)
+        }
+    }
+}
+
+/// Decrypts the file of `attachment_id` in `dir` straight to `destination`,
+/// which is only created once the whole file has been verified. Returns the
+/// plaintext size.
+pub fn save_attachment_to(
+    dir: &Path,
+    attachment_id: &str,
+    key: &DataKey,
+    destination: &Path,
+) -> Result<u64, String> {
+    let (mut reader, legacy) = open_attachment(dir, attachment_id)?;
+    write_file_atomic(destination, |sink| {
+        if !legacy {
+            return decrypt_stream(reader, sink, attachment_id, key);
+        }
+        let mut content = decrypt_json_attachment(&mut reader, attachment_id, key)?;
+        let size = content.len() as u64;
+        let written = sink.write_all(&content);
+        content.zeroize();
+        written.map_err(|e| format!("Dosya yazma hatası: {}", e))?;
+        Ok(size)
+    })
+}
+
+/// Decrypts a JSON attachment file of an older release: `{"version": 2,
+/// "data"}` sealed with the data key. `{"salt", "data"}` files need the
+/// master password and are only read by [`migrate_legacy_attachments`].
+fn decrypt_json_attachment(
+    source: &mut impl Read,
+    attachment_id: &str,
+    key: &DataKey,
+) -> Result<Vec<u8>, String> {
+    let mut contents = String::new();
+    source
+        .read_to_string(&mut contents)
+        .map_err(|e| format!("Ek dosyası okunamadı: {}", e))?;
+    let parsed: serde_json::Value =
+        serde_json::from_str(&contents).map_err(|e| format!("JSON parse error: {}", e))?;
+    if parsed["version"].as_u64() != Some(LEGACY_JSON_VERSION) {
+        return Err("Ek eski formatta; kasa kilidini yeniden açın".to_string());
+    }
+
+    let encrypted_b64 = parsed["data"].as_str().ok_or("Encrypted data bulunamadi")?;
+    let encrypted = general_purpose::STANDARD
+        .decode(encrypted_b64)
+        .map_err(|e| format!("Base64 decode hatası: {}", e))?;
+
+    key.decrypt(&encrypted, attachment_id.as_bytes())
+}
+
+/// Decrypts a pre-data-key attachment (`{"salt", "data"}` keyed by the master password).
+fn decrypt_legacy_attachment(
+    parsed: &serde_json::Value,
+    master_password: &str,
+) -> Result<Vec<u8>, String> {
+    let salt_b64 = parsed["salt"].as_str().ok_or("Salt bulunamadi")?;
+    let salt = general_purpose::STANDARD
+        .decode(salt_b64)
+        .map_err(|e| format!("Salt decode error: {}", e))?;
+
+    let encrypted_data = parsed["data"].as_str().ok_or("Encrypted data bulunamadi")?;
+    let content_b64 = decrypt_vault_data(encrypted_data, master_password, &salt)?;
+
+    general_purpose::STANDARD
+        .decode(content_b64)
+        .map_err(|e| format!("Base64 decode hatası: {}", e))
+}
+
+/// Rewrites every attachment still in a JSON format in the chunked format.
+/// Each file is replaced atomically, so an interrupted run simply resumes on
+/// the next unlock. Files that do not decrypt are left untouched.
+pub fn migrate_legacy_attachments(
+    dir: &Path,
+    master_password: &str,
+    key: &DataKey,
+) -> Result<usize, String> {
+    let mut migrated = 0;
+
+    for (id, path) in list_attachment_files(dir)? {
+        let Ok(contents) = fs::read(&path) else {
+            continue;
+        };
+        if contents.first() != Some(&b'{') {
+            continue;
+        }
+        let Ok(parsed) = serde_json::from_slice::<serde_json::Value>(&contents) else {
+            continue;
+        };
+
+        let content = if parsed.get("salt").is_some() {
+            decrypt_legacy_attachment(&parsed, master_password)
+        } else {
+            decrypt_json_attachment(&mut contents.as_slice(), &id, key)
+        };
+        let Ok(mut content) = content else {
+            continue;
+        };
+        let written = write_attachment(dir, &id, &content, key);
+        content.zeroize();
+
+        written?;
+        migrated += 1;
+    }
+
+    Ok(migrated)
+}
+
+/// The attachment `attachment_id` of the entry `entry_id`; an attachment can
+/// only be read or removed through an entry that lists it.
+pub fn owned_attachment<'a>(
+    state: &'a VaultState,
+    entry_id: &str,
+    attachment_id: &str,
+) -> Result<&'a FileAttachment, String> {
+    state
+        .entries
+        .get(entry_id)
+        .and_then(|entry| entry.attachments.as_ref())
+        .and_then(|list| list.iter().find(|a| a.id == attachment_id))
+        .ok_or_else(|| "Ek bu kayda ait değil".to_string())
+}
+
+/// Attachment files no entry refers to.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct OrphanReport {
+    pub attachment_ids: Vec<String>,
+    /// Combined size of the files on disk, in bytes.
+    pub total_size: u64,
+    pub removed: bool,
+}
+
+/// Finds the files in `dir` that no entry of the vault, trashed ones
+/// included, refers to, and with `remove` deletes them. Only call it on an
+/// unlocked vault, otherwise every file looks unreferenced.
+pub fn collect_orphans(
+    state: &VaultState,
+    dir: &Path,
+    remove: bool,
+) -> Result<OrphanReport, String> {
+    if state.keys.is_none() {
+        return Err(VaultError::Locked.to_string());
+    }
+    let referenced: HashSet<&str> = state
+        .entries
+        .values()
+        .flat_map(|entry| entry.attachments.iter().flatten())
+        .map(|attachment| attachment.id.as_str())
+        .collect();
+
+    let mut report = OrphanReport {
+        removed: remove,
+        ..OrphanReport::default()
+    };
+    let mut files = list_attachment_files(dir)?;
+    files.sort();
+    for (id, path) in files {
+        if referenced.contains(id.as_str()) {
+            continue;
+        }
+        report.total_size += fs::metadata(&path).map(|m| m.len()).unwrap_or(0);
+        if remove {
+            fs::remove_file(&path).map_err(|e| format!("Ek dosyası silinemedi: {}", e))?;
+        }
+        report.attachment_ids.push(id);
+    }
+    Ok(report)
+}
+
+/// Lists the `.enc` files in `dir` as `(attachment id, path)` pairs.
+pub fn list_attachment_files(dir: &Path) -> Result<Vec<(String, PathBuf)>, String> {
+    if !dir.exists() {
+        return Ok(Vec::new());
+    }
+
+    let mut files = Vec::new();
+    for item in fs::read_dir(dir).map_err(|e| format!("Ek dizini okunamadı: {}", e))? {
+        let path = item
+            .map_err(|e| format!("Ek dizini okunamadı: {}", e))?
+            .path();
+        if path.extension().and_then(|e| e.to_str()) != Some(ATTACHMENT_EXTENSION) {
+            continue;
+        }
+        if let Some(id) = path.file_stem().and_then(|s| s.to_str()) {
+            files.push((id.to_string(), path.clone()));
+        }
+    }
+    Ok(files)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crypto::encrypt_vault_data;
+
+    #[test]
+    fn legacy_attachments_are_migrated_to_chunked_format() {
+        let dir = tempfile::tempdir().unwrap();
+        let key = DataKey::generate();
+        let salt = [5u8; 32];
+        let legacy = serde_json::json!({
+            "salt": general_purpose::STANDARD.encode(salt),
+            "data": encrypt_vault_data(
+                &general_purpose::STANDARD.encode(b"ek icerigi"),
+                "ana-sifre-123",
+                &salt,
+            )
+            .unwrap()
+        });
+        fs::write(attachment_path(dir.path(), "att_1"), legacy.to_string()).unwrap();
+        let json = serde_json::json!({
+            "version": 2,
+            "data": general_purpose::STANDARD.encode(key.encrypt(b"ikinci ek", b"att_2").unwrap())
+        });
+        fs::write(attachment_path(dir.path(), "att_2"), json.to_string()).unwrap();
+
+        // Version 2 files stay readable until they are migrated
+        assert_eq!(
+            read_attachment(dir.path(), "att_2", &key).unwrap(),
+            b"ikinci ek"
+        );
+        assert_eq!(
+            migrate_legacy_attachments(dir.path(), "ana-sifre-123", &key).unwrap(),
+            2
+        );
+        assert_eq!(
+            migrate_legacy_attachments(dir.path(), "ana-sifre-123", &key).unwrap(),
+            0
+        );
+
+        assert!(fs::read(attachment_path(dir.path(), "att_1"))
+            .unwrap()
+            .starts_with(MAGIC));
+        assert_eq!(
+            read_attachment(dir.path(), "att_1", &key).unwrap(),
+            b"ek icerigi"
+        );
+        assert_eq!(
+            read_attachment(dir.path(), "att_2", &key).unwrap(),
+            b"ikinci ek"
+        );
+    }
+
+    #[test]
+    fn orphans_are_reported_and_removed_but_trashed_entries_keep_their_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut state = VaultState {
+            keys: Some(
+                crate::keys::VaultKeys::generate(
+                    "ana-sifre",
+                    crate::crypto::KdfParams::Pbkdf2Sha256 { iterations: 1000 },
+                )
+                .unwrap(),
+            ),
+            ..VaultState::default()
+        };
+        let key = state.keys.as_ref().unwrap().data_key.clone();
+        for id in ["att_1", "att_2", "yetim"] {
+            write_attachment(dir.path(), id, b"icerik", &key).unwrap();
+        }
+        for (entry_id, attachment_id, deleted_at) in
+            [("e1", "att_1", None), ("e2", "att_2", Some(10))]
+        {
+            let entry: crate::models::PasswordEntry = serde_json::from_value(serde_json::json!({
+                "id": entry_id, "title": entry_id, "username": "", "password": "",
+                "url": null, "notes": null, "created_at": 0, "updated_at": 0,
+                "category": "documents", "deleted_at": deleted_at,
+                "attachments": [{"id": attachment_id, "filename": "a.txt",
+                    "mime_type": "text/plain", "size": 6, "created_at": 0}]
+            }))
+            .unwrap();
+            state.entries.insert(entry_id.to_string(), entry);
+        }
+
+        assert!(owned_attachment(&state, "e1", "att_1").is_ok());
+        assert!(owned_attachment(&state, "e1", "att_2").is_err());
+        assert!(owned_attachment(&state, "e1", "yetim").is_err());
+
+        let report = collect_orphans(&state, dir.path(), false).unwrap();
+        assert_eq!(report.attachment_ids, vec!["yetim".to_string()]);
+        assert!(report.total_size > 6);
+        assert!(attachment_path(dir.path(), "yetim").exists());
+
+        state.entries.remove("e2");
+        let report = collect_orphans(&state, dir.path(), true).unwrap();
+        assert_eq!(report.attachment_ids, vec!["att_2", "yetim"]);
+        assert!(!attachment_path(dir.path(), "att_2").exists());
+        assert!(attachment_path(dir.path(), "att_1").exists());
+
+        state.keys = None;
+        assert!(collect_orphans(&state, dir.path(), true).is_err());
+    }
+
+    #[test]
+    fn chunked_files_detect_tampering_truncation_and_swaps() {
+        let dir = tempfile::tempdir().unwrap();
+        let key = DataKey::generate();
+
+        for size in [0, 1, CHUNK_SIZE, 2 * CHUNK_SIZE + 17] {
+            let content: Vec<u8> = (0..size).map(|i| (i % 251) as u8).collect();
+            write_attachment(dir.path(), "att_1", &content, &key).unwrap();
+            assert_eq!(read_attachment(dir.path(), "att_1", &key).unwrap(), content);
+
+            let destination = dir.path().join("kopya.bin");
+            assert_eq!(
+                save_attachment_to(dir.path(), "att_1", &key, &destination).unwrap(),
+                size as u64
+            );
+            assert_eq!(fs::read(&destination).unwrap(), content);
+        }
+
+        let content: Vec<u8> = (0..2 * CHUNK_SIZE + 17).map(|i| (i % 251) as u8).collect();
+        for len in [0, 5, CHUNK_SIZE + 3, 3 * CHUNK_SIZE] {
+            assert_eq!(
+                read_attachment_head(dir.path(), "att_1", &key, len).unwrap(),
+                content[..len.min(content.len())]
+            );
+        }
+
+        let path = attachment_path(dir.path(), "att_1");
+        let sealed = fs::read(&path).unwrap();
+
+        // Cut at a chunk boundary: the remaining chunks are intact but none is the last
+        fs::write(&path, &sealed[..HEADER_LEN + CHUNK_SIZE + TAG_LEN]).unwrap();
+        assert!(read_attachment(dir.path(), "att_1", &key).is_err());
+
+        let mut flipped = sealed.clone();
+        flipped[HEADER_LEN + 10] ^= 1;
+        fs::write(&path, &flipped).unwrap();
+        let destination = dir.path().join("bozuk.bin");
+        assert!(save_attachment_to(dir.path(), "att_1", &key, &destination).is_err());
+        assert!(!destination.exists());
+
+        fs::write(attachment_path(dir.path(), "att_2"), &sealed).unwrap();
+        assert!(read_attachment(dir.path(), "att_2", &key).is_err());
+
+        let limit = write_attachment_from(
+            dir.path(),
+            "att_3",
+            &vec![0u8; 3 * CHUNK_SIZE][..],
+            &key,
+            Some(2 * CHUNK_SIZE as u64),
+        );
+        assert!(limit.is_err());
+        assert!(list_attachment_files(dir.path())
+            .unwrap()
+            .iter()
+            .all(|(id, _)| id != "att_3"));
+    }
+}
@@ -0,0 +1,410 @@
+//! Rolling backups of the vault in `backups/<id>/` next to `vault.dat`.
+//!
+//! A backup is a copy of the encrypted vault file, the legacy salt file if one
+//! is still around and the `attachments/` directory, taken after a save. Its id
+//! is the UTC time it was taken (`YYYYMMDD-HHMMSS`). Nothing is decrypted to
+//! make a backup; [`restore_backup`] proves the master password opens the
+//! backup before it replaces the live files.
+
+use chrono::{DateTime, Datelike, NaiveDateTime, TimeZone, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::attachments;
+use crate::container;
+use crate::models::{VaultData, VaultState};
+use crate::storage::{self, SALT_FILE_NAME, VAULT_FILE_NAME};
+
+pub const BACKUPS_DIR_NAME: &str = "backups";
+const ID_FORMAT: &str = "%Y%m%d-%H%M%S";
+const STAGING_SUFFIX: &str = ".tmp";
+
+/// When backups are taken.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum BackupSchedule {
+    /// After every save.
+    #[default]
+    EverySave,
+    /// After the first save of each (UTC) day.
+    Daily,
+    Off,
+}
+
+/// How many backups survive [`prune_backups`]: the newest `recent` ones, plus
+/// the newest backup of each of the last `daily` days and `weekly` weeks that
+/// have one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BackupRetention {
+    pub recent: usize,
+    pub daily: usize,
+    pub weekly: usize,
+}
+
+impl Default for BackupRetention {
+    fn default() -> Self {
+        Self {
+            recent: 10,
+            daily: 7,
+            weekly: 4,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct BackupInfo {
+    pub id: String,
+    pub created_at: i64,
+    pub size: u64,
+    pub attachment_count: usize,
+}
+
+/// Directory holding the backups of the vault at `vault_path`.
+pub fn backups_dir(vault_path: &Path) -> Result<PathBuf, String> {
+    Ok(vault_path
+        .parent()
+        .ok_or_else(|| "Vault path parent bulunamadı".to_string())?
+        .join(BACKUPS_DIR_NAME))
+}
+
+fn backup_id(created_at: i64) -> Result<String, String> {
+    Utc.timestamp_opt(created_at, 0)
+        .single()
+        .map(|time| time.format(ID_FORMAT).to_string())
+        .ok_or_else(|| "Geçersiz yedek zamanı".to_string())
+}
+
+fn parse_backup_id(id: &str) -> Option<i64> {
+    NaiveDateTime::parse_from_str(id, ID_FORMAT)
+        .ok()
+        .map(|time| time.and_utc().timestamp())
+}
+
+fn backup_path(vault_path: &Path, id: &str) -> Result<PathBuf, String> {
+    if parse_backup_id(id).is_none() {
+        return Err("Geçersiz yedek kimliği".to_string());
+    }
+    let path = backups_dir(vault_path)?.join(id);
+    if !path.is_dir() {
+        return Err("Yedek bulunamadı".to_string());
+    }
+    Ok(path)
+}
+
+fn copy_file(from: &Path, to: &Path) -> Result<u64, String> {
+    fs::copy(from, to).map_err(|e| format!("Yedek dosyası kopyalanamadı: {}", e))
+}
+
+/// Copies the vault files into `backups/<id>/`. A backup taken in the same
+/// second as an existing one replaces it.
+pub fn create_backup(vault_path: &Path, now: i64) -> Result<BackupInfo, String> {
+    if !vault_path.exists() {
+        return Err("Vault dosyası bulunamadı".to_string());
+    }
+
+    let id = backup_id(now)?;
+    let dir = backups_dir(vault_path)?;
+    let target = dir.join(&id);
+    let staging = dir.join(format!("{}{}", id, STAGING_SUFFIX));
+    if staging.exists() {
+        fs::remove_dir_all(&staging).map_err(|e| format!("Yedek dizini silinemedi: {}", e))?;
+    }
+    fs::create_dir_all(staging.join(attachments::ATTACHMENTS_DIR_NAME))
+        .map_err(|e| format!("Yedek dizini oluşturulamadı: {}", e))?;
+
+    let mut size = copy_file(vault_path, &staging.join(VAULT_FILE_NAME))?;
+    let salt_path = vault_path.with_file_name(SALT_FILE_NAME);
+    if salt_path.exists() {
+        size += copy_file(&salt_path, &staging.join(SALT_FILE_NAME))?;
+    }
+
+    let files = attachments::list_attachment_files(&attachments::attachments_dir(vault_path)?)?;
+    for (id, path) in &files {
+        let copy =
+            attachments::attachment_path(&staging.join(attachments::ATTACHMENTS_DIR_NAME), id);
+        size += copy_file(path, &copy)?;
+    }
+
+    if target.exists() {
+        fs::remove_dir_all(&target).map_err(|e| format!("Yedek dizini silinemedi: {}", e))?;
+    }
+    fs::rename(&staging, &target).map_err(|e| format!("Yedek kaydedilemedi: {}", e))?;
+
+    Ok(BackupInfo {
+        id,
+        created_at: now,
+        size,
+        attachment_count: files.len(),
+    })
+}
+
+/// Backups of the vault at `vault_path`, newest first. Unfinished backups and
+/// unrelated directories are skipped.
+pub fn list_backups(vault_path: &Path) -> Result<Vec<BackupInfo>, String> {
+    let dir = backups_dir(vault_path)?;
+    if !dir.exists() {
+        return Ok(Vec::new());
+    }
+
+    let mut backups = Vec::new();
+    for item in fs::read_dir(&dir).map_err(|e| format!("Yedek dizini okunamadı: {}", e))? {
+        let path = item
+            .map_err(|e| format!("Yedek dizini okunamadı: {}", e))?
+            .path();
+        let Some(id) = path.file_name().and_then(|n| n.to_str()) else {
+            continue;
+        };
+        let Some(created_at) = parse_backup_id(id) else {
+            continue;
+        };
+        let Ok(vault_meta) = fs::metadata(path.join(VAULT_FILE_NAME)) else {
+            continue;
+        };
+
+        let files =
+            attachments::list_attachment_files(&path.join(attachments::ATTACHMENTS_DIR_NAME))?;
+        let attachments_size: u64 = files
+            .iter()
+            .filter_map(|(_, file)| fs::metadata(file).ok())
+            .map(|meta| meta.len())
+            .sum();
+
+        backups.push(BackupInfo {
+            id: id.to_string(),
+            created_at,
+            size: vault_meta.len() + attachments_size,
+            attachment_count: files.len(),
+        });
+    }
+
+    backups.sort_by_key(|b| std::cmp::Reverse(b.created_at));
+    Ok(backups)
+}
+
+/// Ids of the backups `retention` keeps, given backups newest first.
+fn kept_backups(backups: &[BackupInfo], retention: BackupRetention) -> HashSet<String> {
+    let mut kept: HashSet<String> = backups
+        .iter()
+        .take(retention.recent)
+        .map(|b| b.id.clone())
+        .collect();
+
+    let mut keep_newest_per = |limit: usize, period: fn(DateTime<Utc>) -> (i32, u32)| {
+        let mut seen = HashSet::new();
+        for backup in backups {
+            if seen.len() >= limit {
+                break;
+            }
+            let Some(time) = Utc.timestamp_opt(backup.created_at, 0).single() else {
+                continue;
+            };
+            if seen.insert(period(time)) {
+                kept.insert(backup.id.clone());
+            }
+        }
+    };
+    keep_newest_per(retention.daily, |t| (t.year(), t.ordinal()));
+    keep_newest_per(retention.weekly, |t| {
+        let week = t.iso_week();
+        (week.year(), week.week())
+    });
+
+    kept
+}
+
+/// Deletes the backups `retention` does not keep. Returns the number deleted.
+pub fn prune_backups(vault_path: &Path, retention: BackupRetention) -> Result<usize, String> {
+    let backups = list_backups(vault_path)?;
+    let kept = kept_backups(&backups, retention);
+    let dir = backups_dir(vault_path)?;
+
+    let mut removed = 0;
+    for backup in backups.iter().filter(|b| !kept.contains(&b.id)) {
+        fs::remove_dir_all(dir.join(&backup.id))
+            .map_err(|e| format!("Eski yedek silinemedi: {}", e))?;
+        removed += 1;
+    }
+    Ok(removed)
+}
+
+/// Takes a backup after a save if `schedule` asks for one, then prunes.
+/// Returns the new backup, if any.
+pub fn backup_after_save(
+    vault_path: &Path,
+    schedule: BackupSchedule,
+    retention: BackupRetention,
+    now: i64,
+) -> Result<Option<BackupInfo>, String> {
+    let due = match schedule {
+        BackupSchedule::Off => false,
+        BackupSchedule::EverySave => true,
+        BackupSchedule::Daily => {
+            let today = backup_id(now)?;
+            list_backups(vault_path)?
+                .first()
+                .is_none_or(|newest| newest.id[..8] != today[..8])
+        }
+    };
+    if !due {
+        return Ok(None);
+    }
+
+    let backup = create_backup(vault_path, now)?;
+    prune_backups(vault_path, retention)?;
+    Ok(Some(backup))
+}
+
+/// Checks that `master_password` opens backup `id` and that its contents
+/// parse, without touching the backup or the live vault.
+pub fn verify_backup(vault_path: &Path, id: &str, master_password: &str) -> Result<(), String> {
+    let raw = fs::read(backup_path(vault_path, id)?.join(VAULT_FILE_NAME))
+        .map_err(|e| format!("Yedek okunamadı: {}", e))?;
+    if !container::is_container(&raw) {
+        return Err("Yedek tanınmayan bir formatta".to_string());
+    }
+
+    let opened =
+        container::open(&raw, master_password).map_err(|e| format!("Yedek çözülemedi: {}", e))?;
+    serde_json::from_slice::<VaultData>(&opened.plaintext)
+        .map_err(|e| format!("Yedek bozuk: {}", e))?;
+    Ok(())
+}
+
+/// Replaces the live vault and its attachments with backup `id` once
+/// [`verify_backup`] passes. The current files are backed up first, so the
+/// restore itself can be undone. Returns the restored vault, loaded with
+/// `master_password`.
+pub fn restore_backup(
+    vault_path: &Path,
+    id: &str,
+    master_password: &str,
+    now: i64,
+) -> Result<VaultState, String> {
+    verify_backup(vault_path, id, master_password)?;
+    let backup = backup_path(vault_path, id)?;
+
+    if vault_path.exists() && backup_id(now)? != id {
+        create_backup(vault_path, now).map_err(|e| format!("Mevcut kasa yedeklenemedi: {}", e))?;
+    }
+
+    let read = |path: &Path| fs::read(path).map_err(|e| format!("Yedek okunamadı: {}", e));
+    let salt_path = vault_path.with_file_name(SALT_FILE_NAME);
+    if backup.join(SALT_FILE_NAME).exists() {
+        storage::write_atomic(&salt_path, &read(&backup.join(SALT_FILE_NAME))?)?;
+    } else if salt_path.exists() {
+        fs::remove_file(&salt_path).map_err(|e| format!("Salt dosyası silinemedi: {}", e))?;
+    }
+    storage::write_atomic(vault_path, &read(&backup.join(VAULT_FILE_NAME))?)?;
+
+    let live_dir = attachments::attachments_dir(vault_path)?;
+    let restored =
+        attachments::list_attachment_files(&backup.join(attachments::ATTACHMENTS_DIR_NAME))?;
+    if !restored.is_empty() {
+        fs::create_dir_all(&live_dir).map_err(|e| format!("Ek dizini oluşturulamadı: {}", e))?;
+    }
+    for (attachment_id, path) in &restored {
+        storage::write_atomic(
+            &attachments::attachment_path(&live_dir, attachment_id),
+            &read(path)?,
+        )?;
+    }
+    // Attachments the backup does not know about belong to newer entries; they
+    // are still in the backup taken above.
+    let restored_ids: HashSet<&String> = restored.iter().map(|(id, _)| id).collect();
+    for (attachment_id, path) in attachments::list_attachment_files(&live_dir)? {
+        if !restored_ids.contains(&attachment_id) {
+            fs::remove_file(&path).map_err(|e| format!("Ek silinemedi: {}", e))?;
+        }
+    }
+
+    storage::load_vault_from_disk(vault_path, master_password)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::{init_keys, save_vault_to_disk};
+
+    const DAY: i64 = 24 * 60 * 60;
+
+    fn save_vault(vault_path: &Path, hash: &str) {
+        let mut state = VaultState {
+            master_password_hash: Some(hash.to_string()),
+            ..VaultState::default()
+        };
+        init_keys(&mut state, "ana-sifre-123").unwrap();
+        save_vault_to_disk(vault_path, &state).unwrap();
+    }
+
+    fn info(created_at: i64) -> BackupInfo {
+        BackupInfo {
+            id: backup_id(created_at).unwrap(),
+            created_at,
+            size: 0,
+            attachment_count: 0,
+        }
+    }
+
+    #[test]
+    fn retention_keeps_recent_daily_and_weekly() {
+        // Hourly backups over 60 days, newest first
+        let now = 1_700_000_000;
+        let backups: Vec<_> = (0..60 * 24).map(|h| info(now - h * 3600)).collect();
+        let retention = BackupRetention {
+            recent: 3,
+            daily: 7,
+            weekly: 4,
+        };
+
+        let kept = kept_backups(&backups, retention);
+        assert!(kept.contains(&backups[0].id));
+        assert!(kept.contains(&backups[2].id));
+        // The days overlap with the recent backups and the weeks with the days
+        assert!(kept.len() >= 7 + 3 && kept.len() <= 3 + 7 + 4);
+        assert!(!kept.contains(&info(now - 40 * DAY).id));
+    }
+
+    #[test]
+    fn restore_verifies_and_swaps_in_the_backup() {
+        let dir = tempfile::tempdir().unwrap();
+        let vault_path = dir.path().join(VAULT_FILE_NAME);
+        let now = 1_700_000_000;
+        let live_attachments = attachments::attachments_dir(&vault_path).unwrap();
+
+        save_vault(&vault_path, "eski");
+        fs::create_dir_all(&live_attachments).unwrap();
+        fs::write(attachments::attachment_path(&live_attachments, "a1"), "1").unwrap();
+        let old = backup_after_save(&vault_path, BackupSchedule::Daily, Default::default(), now)
+            .unwrap()
+            .unwrap();
+        assert_eq!(old.attachment_count, 1);
+
+        save_vault(&vault_path, "yeni");
+        fs::write(attachments::attachment_path(&live_attachments, "a2"), "2").unwrap();
+        let daily = backup_after_save(
+            &vault_path,
+            BackupSchedule::Daily,
+            Default::default(),
+            now + 60,
+        );
+        assert_eq!(daily.unwrap(), None);
+
+        assert!(restore_backup(&vault_path, &old.id, "yanlis-sifre", now + 120).is_err());
+        assert!(restore_backup(&vault_path, "../x", "ana-sifre-123", now + 120).is_err());
+        let state = restore_backup(&vault_path, &old.id, "ana-sifre-123", now + 120).unwrap();
+        assert_eq!(state.master_password_hash.as_deref(), Some("eski"));
+        assert!(!attachments::attachment_path(&live_attachments, "a2").exists());
+
+        // The vault as it was before the restore is kept as the newest backup
+        let backups = list_backups(&vault_path).unwrap();
+        assert_eq!(backups.len(), 2);
+        assert_eq!(backups[0].attachment_count, 2);
+        let undone = restore_backup(&vault_path, &backups[0].id, "ana-sifre-123", now + 180);
+        assert_eq!(
+            undone.unwrap().master_password_hash.as_deref(),
+            Some("yeni")
+        );
+    }
+}
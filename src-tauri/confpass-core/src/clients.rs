@@ -0,0 +1,432 @@
+//! Clients of the local HTTP API.
+//!
+//! Every browser extension or native host instance pairs once: it asks for
+//! access, the user approves the request in the app and picks the scopes it
+//! gets, and the client collects a token of its own with the secret it was
+//! handed when it asked. `api_clients.json` keeps only the SHA-256 of each
+//! token, and a client removed from it loses access on its next request.
+//! Pairing requests live in memory and expire after [`PAIRING_TTL_SECS`].
+
+use base64::{engine::general_purpose, Engine as _};
+use rand::rngs::OsRng;
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::path::{Path, PathBuf};
+use subtle::ConstantTimeEq;
+use zeroize::Zeroizing;
+
+use crate::storage::write_atomic;
+
+pub const CLIENTS_FILE_NAME: &str = "api_clients.json";
+pub const PAIRING_TTL_SECS: i64 = 5 * 60;
+/// Pairing requests waiting for the user at once; more are refused.
+pub const MAX_PENDING_PAIRINGS: usize = 5;
+const CLIENT_NAME_MAX_LEN: usize = 100;
+const TOKEN_PREFIX: &str = "cpt_";
+const SECRET_BYTES: usize = 32;
+/// `last_used_at` is only written back once it is this much out of date.
+const LAST_USED_PRECISION_SECS: i64 = 60;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Scope {
+    /// Logins, TOTP codes and duplicate checks for autofill
+    AutofillRead,
+    /// Saving logins and entries
+    Save,
+    /// Listing, creating and using passkeys
+    Passkeys,
+    /// Bank cards and addresses
+    Cards,
+}
+
+impl Scope {
+    pub const ALL: [Scope; 4] = [
+        Scope::AutofillRead,
+        Scope::Save,
+        Scope::Passkeys,
+        Scope::Cards,
+    ];
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ApiClient {
+    pub id: String,
+    pub name: String,
+    pub scopes: Vec<Scope>,
+    pub created_at: i64,
+    #[serde(default)]
+    pub last_used_at: Option<i64>,
+}
+
+impl ApiClient {
+    pub fn allows(&self, scope: Scope) -> bool {
+        self.scopes.contains(&scope)
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct StoredClient {
+    #[serde(flatten)]
+    client: ApiClient,
+    token_hash: String,
+}
+
+#[derive(Serialize, Deserialize)]
+struct ClientsFile {
+    clients: Vec<StoredClient>,
+}
+
+/// A client asking for access, as shown to the user for approval.
+#[derive(Debug, Clone, Serialize)]
+pub struct PairingRequest {
+    pub id: String,
+    pub name: String,
+    pub requested_scopes: Vec<Scope>,
+    pub created_at: i64,
+}
+
+/// What a client learns when it comes back for its pairing request.
+#[derive(Debug, PartialEq, Eq)]
+pub enum PairingStatus {
+    Pending,
+    Approved(Zeroizing<String>),
+    Denied,
+    /// Expired, already collected, or the secret did not match.
+    Unknown,
+}
+
+enum Outcome {
+    Pending,
+    Approved(Zeroizing<String>),
+    Denied,
+}
+
+struct Pairing {
+    request: PairingRequest,
+    secret_hash: [u8; 32],
+    outcome: Outcome,
+}
+
+#[derive(Default)]
+pub struct ClientRegistry {
+    path: PathBuf,
+    clients: Vec<StoredClient>,
+    pairings: Vec<Pairing>,
+}
+
+fn random_secret() -> Zeroizing<String> {
+    let mut bytes = Zeroizing::new([0u8; SECRET_BYTES]);
+    OsRng.fill_bytes(bytes.as_mut());
+    Zeroizing::new(general_purpose::URL_SAFE_NO_PAD.encode(bytes.as_ref()))
+}
+
+fn hash(secret: &str) -> [u8; 32] {
+    Sha256::digest(secret.as_bytes()).into()
+}
+
+fn hash_matches(stored: &[u8], secret: &str) -> bool {
+    stored.ct_eq(&hash(secret)).into()
+}
+
+impl ClientRegistry {
+    /// Reads the registry of `data_dir`; a missing file is an empty one.
+    pub fn load(data_dir: &Path) -> Result<Self, String> {
+        let path = data_dir.join(CLIENTS_FILE_NAME);
+        let clients = match fs::read(&path) {
+            Ok(contents) => {
+                serde_json::from_slice::<ClientsFile>(&contents)
+                    .map_err(|e| format!("İstemci listesi okunamadı: {}", e))?
+                    .clients
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Vec::new(),
+            Err(e) => return Err(format!("İstemci listesi okunamadı: {}", e)),
+        };
+        Ok(Self {
+            path,
+            clients,
+            pairings: Vec::new(),
+        })
+    }
+
+    pub fn save(&self) -> Result<(), String> {
+        let file = ClientsFile {
+            clients: self
+                .clients
+                .iter()
+                .map(|stored| StoredClient {
+                    client: stored.client.clone(),
+                    token_hash: stored.token_hash.clone(),
+                })
+                .collect(),
+        };
+        let json = serde_json::to_vec_pretty(&file)
+            .map_err(|e| format!("JSON serialize hatası: {}", e))?;
+        write_atomic(&self.path, &json)
+    }
+
+    pub fn clients(&self) -> Vec<ApiClient> {
+        self.clients.iter().map(|s| s.client.clone()).collect()
+    }
+
+    /// The client `token` belongs to. Every stored hash is compared in
+    /// constant time.
+    pub fn authenticate(&self, token: &str) -> Option<&ApiClient> {
+        if !token.starts_with(TOKEN_PREFIX) {
+            return None;
+        }
+        let token_hash = hash(token);
+        let mut found = None;
+        for stored in &self.clients {
+            let Ok(expected) = general_purpose::STANDARD.decode(&stored.token_hash) else {
+                continue;
+            };
+            if bool::from(expected.ct_eq(&token_hash)) {
+                found = Some(&stored.client);
+            }
+        }
+        found
+    }
+
+    /// Records a request by `id`; true when the registry should be saved.
+    pub fn touch(&mut self, id: &str, now: i64) -> bool {
+        match self.clients.iter_mut().find(|s| s.client.id == id) {
+            Some(stored) => {
+                let stale = stored
+                    .client
+                    .last_used_at
+                    .is_none_or(|last| now - last >= LAST_USED_PRECISION_SECS);
+                if stale {
+                    stored.client.last_used_at = Some(now);
+                }
+                stale
+            }
+            None => false,
+        }
+    }
+
+    pub fn revoke(&mut self, id: &str) -> bool {
+        let before = self.clients.len();
+        self.clients.retain(|s| s.client.id != id);
+        self.clients.len() != before
+    }
+
+    fn prune(&mut self, now: i64) {
+        self.pairings
+            .retain(|p| now - p.request.created_at < PAIRING_TTL_SECS);
+    }
+
+    /// Starts pairing a client called `name`. Returns the request and the
+    /// secret the client has to show when it collects its token.
+    pub fn request_pairing(
+        &mut self,
+        name: &str,
+        scopes: &[Scope],
+        now: i64,
+    ) -> Result<(PairingRequest, Zeroizing<String>), String> {
+        self.prune(now);
+        let name = name.trim();
+        if name.is_empty()
+            || name.chars().count() > CLIENT_NAME_MAX_LEN
+            || name.chars().any(char::is_control)
+        {
+            return Err("Geçersiz istemci adı".to_string());
+        }
+        if self
+            .pairings
+            .iter()
+            .filter(|p| matches!(p.outcome, Outcome::Pending))
+            .count()
+            >= MAX_PENDING_PAIRINGS
+        {
+            return Err("Onay bekleyen çok fazla eşleştirme isteği var".to_string());
+        }
+
+        let mut requested_scopes = Vec::new();
+        for scope in scopes {
+            if !requested_scopes.contains(scope) {
+                requested_scopes.push(*scope);
+            }
+        }
+        if requested_scopes.is_empty() {
+            return Err("En az bir izin istenmeli".to_string());
+        }
+
+        let secret = random_secret();
+        let request = PairingRequest {
+            id: uuid::Uuid::new_v4().to_string(),
+            name: name.to_string(),
+            requested_scopes,
+            created_at: now,
+        };
+        self.pairings.push(Pairing {
+            request: request.clone(),
+            secret_hash: hash(&secret),
+            outcome: Outcome::Pending,
+        });
+        Ok((request, secret))
+    }
+
+    /// Requests still waiting for the user.
+    pub fn pending_pairings(&mut self, now: i64) -> Vec<PairingRequest> {
+        self.prune(now);
+        self.pairings
+            .iter()
+            .filter(|p| matches!(p.outcome, Outcome::Pending))
+            .map(|p| p.request.clone())
+            .collect()
+    }
+
+    fn pending_mut(&mut self, request_id: &str, now: i64) -> Result<&mut Pairing, String> {
+        self.prune(now);
+        self.pairings
+            .iter_mut()
+            .find(|p| p.request.id == request_id && matches!(p.outcome, Outcome::Pending))
+            .ok_or_else(|| "Eşleştirme isteği bulunamadı veya süresi doldu".to_string())
+    }
+
+    /// Registers the client of `request_id` with `scopes`, which may be
+    /// fewer than it asked for. Its token waits until the client collects it.
+    pub fn approve(
+        &mut self,
+        request_id: &str,
+        scopes: &[Scope],
+        now: i64,
+    ) -> Result<ApiClient, String> {
+        if scopes.is_empty() {
+            return Err("En az bir izin seçilmeli".to_string());
+        }
+        let pairing = self.pending_mut(request_id, now)?;
+        let token = Zeroizing::new(format!("{}{}", TOKEN_PREFIX, random_secret().as_str()));
+        let client = ApiClient {
+            id: uuid::Uuid::new_v4().to_string(),
+            name: pairing.request.name.clone(),
+            scopes: Scope::ALL
+                .into_iter()
+                .filter(|scope| scopes.contains(scope))
+                .collect(),
+            created_at: now,
+            last_used_at: None,
+        };
+        let token_hash = general_purpose::STANDARD.encode(hash(&token));
+        pairing.outcome = Outcome::Approved(token);
+        self.clients.push(StoredClient {
+            client: client.clone(),
+            token_hash,
+        });
+        Ok(client)
+    }
+
+    pub fn deny(&mut self, request_id: &str, now: i64) -> Result<(), String> {
+        self.pending_mut(request_id, now)?.outcome = Outcome::Denied;
+        Ok(())
+    }
+
+    /// Tells the client of `request_id` how its request went. An approved
+    /// token or a denial is handed out once and the request is forgotten.
+    pub fn pairing_status(&mut self, request_id: &str, secret: &str, now: i64) -> PairingStatus {
+        self.prune(now);
+        let Some(index) = self
+            .pairings
+            .iter()
+            .position(|p| p.request.id == request_id && hash_matches(&p.secret_hash, secret))
+        else {
+            return PairingStatus::Unknown;
+        };
+        if matches!(self.pairings[index].outcome, Outcome::Pending) {
+            return PairingStatus::Pending;
+        }
+        match self.pairings.remove(index).outcome {
+            Outcome::Approved(token) => PairingStatus::Approved(token),
+            _ => PairingStatus::Denied,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn paired_clients_get_their_own_scoped_revocable_tokens() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut registry = ClientRegistry::load(dir.path()).unwrap();
+
+        let (request, secret) = registry
+            .request_pairing(" Chrome ", &[Scope::AutofillRead, Scope::Save], 100)
+            .unwrap();
+        assert_eq!(request.name, "Chrome");
+        assert_eq!(registry.pending_pairings(100).len(), 1);
+        assert_eq!(
+            registry.pairing_status(&request.id, &secret, 110),
+            PairingStatus::Pending
+        );
+
+        let client = registry
+            .approve(&request.id, &[Scope::AutofillRead], 120)
+            .unwrap();
+        assert!(registry.pending_pairings(120).is_empty());
+        assert_eq!(
+            registry.pairing_status(&request.id, "wrong", 130),
+            PairingStatus::Unknown
+        );
+        let PairingStatus::Approved(token) = registry.pairing_status(&request.id, &secret, 130)
+        else {
+            panic!("token not handed out");
+        };
+        assert_eq!(
+            registry.pairing_status(&request.id, &secret, 131),
+            PairingStatus::Unknown
+        );
+
+        let (other, other_secret) = registry
+            .request_pairing("Edge", &[Scope::Cards], 140)
+            .unwrap();
+        registry.deny(&other.id, 150).unwrap();
+        assert_eq!(
+            registry.pairing_status(&other.id, &other_secret, 160),
+            PairingStatus::Denied
+        );
+
+        let found = registry.authenticate(&token).unwrap();
+        assert_eq!(found, &client);
+        assert!(found.allows(Scope::AutofillRead) && !found.allows(Scope::Save));
+        assert!(registry.authenticate("cpt_guess").is_none());
+        assert!(registry.touch(&client.id, 200));
+        assert!(!registry.touch(&client.id, 230));
+        registry.save().unwrap();
+
+        let mut reloaded = ClientRegistry::load(dir.path()).unwrap();
+        assert_eq!(
+            reloaded.authenticate(&token).unwrap().last_used_at,
+            Some(200)
+        );
+        assert!(reloaded.revoke(&client.id));
+        assert!(reloaded.authenticate(&token).is_none());
+    }
+
+    #[test]
+    fn pairing_requests_expire_and_are_limited() {
+        let mut registry = ClientRegistry::default();
+        assert!(registry.request_pairing("", &Scope::ALL, 0).is_err());
+        assert!(registry.request_pairing("Chrome", &[], 0).is_err());
+
+        let (request, secret) = registry.request_pairing("Chrome", &Scope::ALL, 0).unwrap();
+        for i in 1..MAX_PENDING_PAIRINGS {
+            registry
+                .request_pairing(&format!("Chrome {}", i), &Scope::ALL, 0)
+                .unwrap();
+        }
+        assert!(registry.request_pairing("Edge", &Scope::ALL, 1).is_err());
+
+        let later = PAIRING_TTL_SECS;
+        assert!(registry.approve(&request.id, &Scope::ALL, later).is_err());
+        assert_eq!(
+            registry.pairing_status(&request.id, &secret, later),
+            PairingStatus::Unknown
+        );
+        assert!(registry.request_pairing("Edge", &Scope::ALL, later).is_ok());
+    }
+}
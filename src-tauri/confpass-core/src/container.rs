@@ -0,0 +1,390 @@
+//! Self-describing `vault.dat` container.
+//!
+//! Layout (integers little-endian):
+//!
+//! ```text
+//! magic "CPVT" | version u8 | kdf id u8 | kdf params 3 x u32 | salt len u8 | salt
+//! | wrapped key len u8 | wrapped key | nonce (12) | AES-256-GCM ciphertext
+//! ```
+//!
+//! The payload is encrypted with the vault's random data key; the header carries
+//! that key wrapped under the master password (see [`crate::keys`]). Everything
+//! before the ciphertext is passed to AES-GCM as associated data, so tampering
+//! with the KDF parameters, salt, wrapped key or version makes decryption fail.
+//!
+//! Version 1 containers have no wrapped key field and encrypt the payload with
+//! the password-derived key directly. Version 0 vaults (base64
+//! `nonce || ciphertext` plus a `vault.salt` side file) have no magic and are
+//! handled by [`crate::storage`].
+
+use aes_gcm::{
+    aead::{Aead, AeadCore, KeyInit, OsRng, Payload},
+    Aes256Gcm, Nonce,
+};
+use zeroize::Zeroize;
+
+use crate::crypto::KdfParams;
+use crate::keys::{DataKey, VaultKeys, WrappedKey, NONCE_LEN};
+
+pub const MAGIC: &[u8; 4] = b"CPVT";
+pub const FORMAT_VERSION: u8 = 2;
+
+const VERSION_PASSWORD_KEY: u8 = 1;
+const KDF_ID_PBKDF2_SHA256: u8 = 1;
+const KDF_ID_ARGON2ID: u8 = 2;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ContainerHeader {
+    pub version: u8,
+    pub kdf: KdfParams,
+    pub salt: Vec<u8>,
+    /// Data key wrapped by the password key; empty in version 1.
+    pub wrapped_key: Vec<u8>,
+    pub nonce: [u8; NONCE_LEN],
+}
+
+impl ContainerHeader {
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let (kdf_id, params) = match self.kdf {
+            KdfParams::Pbkdf2Sha256 { iterations } => (KDF_ID_PBKDF2_SHA256, [0, iterations, 0]),
+            KdfParams::Argon2id {
+                memory_kib,
+                iterations,
+                parallelism,
+            } => (KDF_ID_ARGON2ID, [memory_kib, iterations, parallelism]),
+        };
+
+        let mut out = Vec::with_capacity(
+            4 + 2 + 12 + 1 + self.salt.len() + 1 + self.wrapped_key.len() + NONCE_LEN,
+        );
+        out.extend_from_slice(MAGIC);
+        out.push(self.version);
+        out.push(kdf_id);
+        for p in params {
+            out.extend_from_slice(&p.to_le_bytes());
+        }
+        out.push(self.salt.len() as u8);
+        out.extend_from_slice(&self.salt);
+        if self.version != VERSION_PASSWORD_KEY {
+            out.push(self.wrapped_key.len() as u8);
+            out.extend_from_slice(&self.wrapped_key);
+        }
+        out.extend_from_slice(&self.nonce);
+        out
+    }
+
+    /// Parses the header at the start of `data`, returning it with its length in bytes.
+    pub fn parse(data: &[u8]) -> Result<(Self, usize), String> {
+        let mut reader = Reader { data, pos: 0 };
+
+        if reader.take(4)? != MAGIC {
+            return Err("Geçersiz kasa dosyası: sihirli sayı eşleşmiyor".to_string());
+        }
+        let version = reader.u8()?;
+        if version != VERSION_PASSWORD_KEY && version != FORMAT_VERSION {
+            return Err(format!("Desteklenmeyen kasa formatı sürümü: {}", version));
+        }
+
+        let kdf_id = reader.u8()?;
+        let params = [reader.u32()?, reader.u32()?, reader.u32()?];
+        let kdf = match kdf_id {
+            KDF_ID_PBKDF2_SHA256 => KdfParams::Pbkdf2Sha256 {
+                iterations: params[1],
+            },
+            KDF_ID_ARGON2ID => KdfParams::Argon2id {
+                memory_kib: params[0],
+                iterations: params[1],
+                parallelism: params[2],
+            },
+            other => return Err(format!("Bilinmeyen KDF: {}", other)),
+        };
+
+        let salt_len = reader.u8()? as usize;
+        let salt = reader.take(salt_len)?.to_vec();
+        let wrapped_key = if version == VERSION_PASSWORD_KEY {
+            Vec::new()
+        } else {
+            let wrapped_len = reader.u8()? as usize;
+            reader.take(wrapped_len)?.to_vec()
+        };
+        let mut nonce = [0u8; NONCE_LEN];
+        nonce.copy_from_slice(reader.take(NONCE_LEN)?);
+
+        Ok((
+            Self {
+                version,
+                kdf,
+                salt,
+                wrapped_key,
+                nonce,
+            },
+            reader.pos,
+        ))
+    }
+
+    /// The password slot stored in a version 2 header.
+    pub fn password_slot(&self) -> WrappedKey {
+        WrappedKey {
+            kdf: self.kdf,
+            salt: self.salt.clone(),
+            wrapped: self.wrapped_key.clone(),
+        }
+    }
+}
+
+struct Reader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8], String> {
+        let end = self
+            .pos
+            .checked_add(len)
+            .filter(|end| *end <= self.data.len())
+            .ok_or_else(|| "Kasa başlığı eksik veya bozuk".to_string())?;
+        let slice = &self.data[self.pos..end];
+        self.pos = end;
+        Ok(slice)
+    }
+
+    fn u8(&mut self) -> Result<u8, String> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u32(&mut self) -> Result<u32, String> {
+        let bytes = self.take(4)?;
+        Ok(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+}
+
+pub fn is_container(data: &[u8]) -> bool {
+    data.starts_with(MAGIC)
+}
+
+/// Encrypts `plaintext` with the vault data key, storing its password slot in the header.
+pub fn seal(plaintext: &[u8], keys: &VaultKeys) -> Result<Vec<u8>, String> {
+    let slot = &keys.password_slot;
+    if slot.salt.len() > u8::MAX as usize || slot.wrapped.len() > u8::MAX as usize {
+        return Err("Anahtar yuvası çok uzun".to_string());
+    }
+
+    let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+    let header = ContainerHeader {
+        version: FORMAT_VERSION,
+        kdf: slot.kdf,
+        salt: slot.salt.clone(),
+        wrapped_key: slot.wrapped.clone(),
+        nonce: nonce.into(),
+    };
+    let mut out = header.to_bytes();
+
+    let ciphertext = keys
+        .data_key
+        .cipher()?
+        .encrypt(
+            &nonce,
+            Payload {
+                msg: plaintext,
+                aad: &out,
+            },
+        )
+        .map_err(|e| format!("Şifreleme hatası: {}", e))?;
+
+    out.extend_from_slice(&ciphertext);
+    Ok(out)
+}
+
+/// Result of [`open`]. `keys` is `None` for version 1 containers, which have
+/// no data key yet and must be re-sealed with a freshly generated one.
+pub struct Opened {
+    pub header: ContainerHeader,
+    pub plaintext: Vec<u8>,
+    pub keys: Option<VaultKeys>,
+}
+
+/// Decrypts a container with the master password.
+pub fn open(data: &[u8], password: &str) -> Result<Opened, String> {
+    let (header, header_len) = ContainerHeader::parse(data)?;
+    // The header is not authenticated until after the key is derived
+    header.kdf.validate_stored()?;
+
+    let (cipher, keys) = if header.version == VERSION_PASSWORD_KEY {
+        let mut key = header.kdf.derive_key(password, &header.salt)?;
+        let cipher = Aes256Gcm::new_from_slice(&key);
+        key.zeroize();
+        let cipher = cipher.map_err(|e| format!("Cipher oluşturulamadı: {}", e))?;
+        (cipher, None)
+    } else {
+        let password_slot = header.password_slot();
+        let data_key = password_slot.unwrap(password)?;
+        let keys = VaultKeys {
+            data_key,
+            password_slot,
+        };
+        (keys.data_key.cipher()?, Some(keys))
+    };
+
+    let plaintext = cipher
+        .decrypt(
+            Nonce::from_slice(&header.nonce),
+            Payload {
+                msg: &data[header_len..],
+                aad: &data[..header_len],
+            },
+        )
+        .map_err(|_| "Decrypt hatası: Yanlış şifre veya bozuk veri".to_string())?;
+
+    Ok(Opened {
+        header,
+        plaintext,
+        keys,
+    })
+}
+
+/// Decrypts a version 2 container with an already unwrapped data key, e.g.
+/// one recovered from a slot other than the master password.
+pub fn open_with_key(data: &[u8], data_key: DataKey) -> Result<Opened, String> {
+    let (header, header_len) = ContainerHeader::parse(data)?;
+    if header.version == VERSION_PASSWORD_KEY {
+        return Err("Kasa eski formatta; önce ana şifreyle açılmalı".to_string());
+    }
+
+    let plaintext = data_key
+        .cipher()?
+        .decrypt(
+            Nonce::from_slice(&header.nonce),
+            Payload {
+                msg: &data[header_len..],
+                aad: &data[..header_len],
+            },
+        )
+        .map_err(|_| "Decrypt hatası: Yanlış anahtar veya bozuk veri".to_string())?;
+
+    let keys = VaultKeys {
+        data_key,
+        password_slot: header.password_slot(),
+    };
+    Ok(Opened {
+        header,
+        plaintext,
+        keys: Some(keys),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TEST_KDF: KdfParams = KdfParams::Pbkdf2Sha256 { iterations: 1000 };
+
+    fn test_keys() -> VaultKeys {
+        VaultKeys::generate("ana-sifre-123", TEST_KDF).unwrap()
+    }
+
+    #[test]
+    fn seal_open_roundtrip() {
+        let keys = test_keys();
+        let sealed = seal(b"{\"entries\":[]}", &keys).unwrap();
+        assert!(is_container(&sealed));
+
+        let opened = open(&sealed, "ana-sifre-123").unwrap();
+        assert_eq!(opened.header.kdf, TEST_KDF);
+        assert_eq!(opened.header.salt, keys.password_slot.salt);
+        assert_eq!(opened.plaintext, b"{\"entries\":[]}");
+        assert_eq!(
+            opened.keys.unwrap().data_key.as_bytes(),
+            keys.data_key.as_bytes()
+        );
+    }
+
+    #[test]
+    fn open_with_key_uses_data_key() {
+        let keys = test_keys();
+        let sealed = seal(b"veri", &keys).unwrap();
+
+        let opened = open_with_key(&sealed, keys.data_key.clone()).unwrap();
+        assert_eq!(opened.plaintext, b"veri");
+        assert!(open_with_key(&sealed, DataKey::generate()).is_err());
+    }
+
+    #[test]
+    fn tampered_header_is_rejected() {
+        let mut sealed = seal(b"veri", &test_keys()).unwrap();
+        // Flip a salt byte: the key changes and the AAD no longer matches.
+        sealed[4 + 2 + 12 + 1] ^= 0xff;
+        assert!(open(&sealed, "ana-sifre-123").is_err());
+    }
+
+    #[test]
+    fn oversized_kdf_params_are_rejected_before_deriving() {
+        let mut header = ContainerHeader::parse(&seal(b"veri", &test_keys()).unwrap())
+            .unwrap()
+            .0;
+        header.kdf = KdfParams::Argon2id {
+            memory_kib: u32::MAX,
+            iterations: u32::MAX,
+            parallelism: 1,
+        };
+        let err = open(&header.to_bytes(), "ana-sifre-123").err().unwrap();
+        assert!(err.contains("Argon2"));
+
+        header.kdf = KdfParams::Pbkdf2Sha256 {
+            iterations: u32::MAX,
+        };
+        assert!(open(&header.to_bytes(), "ana-sifre-123").is_err());
+    }
+
+    #[test]
+    fn truncated_header_is_rejected() {
+        let sealed = seal(b"veri", &test_keys()).unwrap();
+        let err = ContainerHeader::parse(&sealed[..10]).unwrap_err();
+        assert!(err.contains("başlığı"));
+    }
+
+    #[test]
+    fn argon2id_params_survive_header_roundtrip() {
+        let header = ContainerHeader {
+            version: FORMAT_VERSION,
+            kdf: KdfParams::default(),
+            salt: vec![1u8; 32],
+            wrapped_key: vec![4u8; 60],
+            nonce: [2u8; NONCE_LEN],
+        };
+        let bytes = header.to_bytes();
+        let (parsed, len) = ContainerHeader::parse(&bytes).unwrap();
+        assert_eq!(parsed, header);
+        assert_eq!(len, bytes.len());
+    }
+
+    #[test]
+    fn version_1_container_opens_with_password_key() {
+        let salt = [3u8; 32];
+        let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+        let header = ContainerHeader {
+            version: VERSION_PASSWORD_KEY,
+            kdf: TEST_KDF,
+            salt: salt.to_vec(),
+            wrapped_key: Vec::new(),
+            nonce: nonce.into(),
+        };
+        let mut data = header.to_bytes();
+        let key = TEST_KDF.derive_key("ana-sifre-123", &salt).unwrap();
+        let ciphertext = Aes256Gcm::new_from_slice(&key)
+            .unwrap()
+            .encrypt(
+                &nonce,
+                Payload {
+                    msg: b"veri",
+                    aad: &data,
+                },
+            )
+            .unwrap();
+        data.extend_from_slice(&ciphertext);
+
+        let opened = open(&data, "ana-sifre-123").unwrap();
+        assert_eq!(opened.plaintext, b"veri");
+        assert!(opened.keys.is_none());
+    }
+}
@@ -0,0 +1,318 @@
+use std::time::{Duration, Instant};
+
+use aes_gcm::{
+    aead::{Aead, AeadCore, KeyInit, OsRng},
+    Aes256Gcm, Nonce,
+};
+use argon2::Argon2;
+use base64::{engine::general_purpose, Engine as _};
+use pbkdf2::pbkdf2_hmac;
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use zeroize::{Zeroize, ZeroizeOnDrop};
+
+#[derive(ZeroizeOnDrop)]
+pub struct SecurePassword(String);
+
+impl SecurePassword {
+    pub fn new(password: String) -> Self {
+        SecurePassword(password)
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Zeroize for SecurePassword {
+    fn zeroize(&mut self) {
+        self.0.zeroize();
+    }
+}
+
+impl Clone for SecurePassword {
+    fn clone(&self) -> Self {
+        SecurePassword(self.0.clone())
+    }
+}
+
+pub const LEGACY_PBKDF2_ITERATIONS: u32 = 100_000;
+
+pub const ARGON2_DEFAULT_MEMORY_KIB: u32 = 65536;
+pub const ARGON2_DEFAULT_ITERATIONS: u32 = 3;
+pub const ARGON2_DEFAULT_PARALLELISM: u32 = 4;
+
+/// Lower bounds accepted for user-supplied Argon2id parameters (OWASP minimum).
+const ARGON2_MIN_MEMORY_KIB: u32 = 19456;
+const ARGON2_MAX_MEMORY_KIB: u32 = 4 * 1024 * 1024;
+const ARGON2_MAX_ITERATIONS: u32 = 64;
+const ARGON2_MAX_PARALLELISM: u32 = 16;
+
+/// Key derivation function and parameters recorded in the vault container header.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "algorithm", rename_all = "snake_case")]
+pub enum KdfParams {
+    Pbkdf2Sha256 {
+        iterations: u32,
+    },
+    Argon2id {
+        memory_kib: u32,
+        iterations: u32,
+        parallelism: u32,
+    },
+}
+
+impl Default for KdfParams {
+    fn default() -> Self {
+        KdfParams::Argon2id {
+            memory_kib: ARGON2_DEFAULT_MEMORY_KIB,
+            iterations: ARGON2_DEFAULT_ITERATIONS,
+            parallelism: ARGON2_DEFAULT_PARALLELISM,
+        }
+    }
+}
+
+impl KdfParams {
+    /// Parameters used by vaults written before the container format existed.
+    pub fn legacy() -> Self {
+        KdfParams::Pbkdf2Sha256 {
+            iterations: LEGACY_PBKDF2_ITERATIONS,
+        }
+    }
+
+    /// Whether a vault using these parameters should be re-keyed on the next unlock.
+    pub fn is_legacy(&self) -> bool {
+        matches!(self, KdfParams::Pbkdf2Sha256 { .. })
+    }
+
+    /// Rejects parameters too weak to protect a vault or too heavy to unlock it.
+    pub fn validate(&self) -> Result<(), String> {
+        match *self {
+            KdfParams::Pbkdf2Sha256 { .. } => {
+                Err("PBKDF2 yeni kasalar için desteklenmiyor".to_string())
+            }
+            KdfParams::Argon2id {
+                memory_kib,
+                iterations,
+                parallelism,
+            } => {
+                if !(ARGON2_MIN_MEMORY_KIB..=ARGON2_MAX_MEMORY_KIB).contains(&memory_kib) {
+                    return Err(format!(
+                        "Argon2 bellek değeri {} - {} KiB arasında olmalıdır",
+                        ARGON2_MIN_MEMORY_KIB, ARGON2_MAX_MEMORY_KIB
+                    ));
+                }
+                if !(1..=ARGON2_MAX_ITERATIONS).contains(&iterations) {
+                    return Err(format!(
+                        "Argon2 iterasyon sayısı 1 - {} arasında olmalıdır",
+                        ARGON2_MAX_ITERATIONS
+                    ));
+                }
+                if !(1..=ARGON2_MAX_PARALLELISM).contains(&parallelism) {
+                    return Err(format!(
+                        "Argon2 paralellik değeri 1 - {} arasında olmalıdır",
+                        ARGON2_MAX_PARALLELISM
+                    ));
+                }
+                Ok(())
+            }
+        }
+    }
+
+    /// Checks parameters read from an existing file before a key is derived with
+    /// them. Legacy PBKDF2 still opens, up to the iteration count it was written with.
+    pub fn validate_stored(&self) -> Result<(), String> {
+        match *self {
+            KdfParams::Pbkdf2Sha256 { iterations }
+                if (1..=LEGACY_PBKDF2_ITERATIONS).contains(&iterations) =>
+            {
+                Ok(())
+            }
+            KdfParams::Pbkdf2Sha256 { .. } => Err("Geçersiz PBKDF2 iterasyon sayısı".to_string()),
+            KdfParams::Argon2id { .. } => self.validate(),
+        }
+    }
+
+    pub fn derive_key(&self, password: &str, salt: &[u8]) -> Result<[u8; 32], String> {
+        match *self {
+            KdfParams::Pbkdf2Sha256 { iterations } => {
+                if iterations == 0 {
+                    return Err("Geçersiz PBKDF2 iterasyon sayısı".to_string());
+                }
+                let mut key = [0u8; 32];
+                pbkdf2_hmac::<Sha256>(password.as_bytes(), salt, iterations, &mut key);
+                Ok(key)
+            }
+            KdfParams::Argon2id {
+                memory_kib,
+                iterations,
+                parallelism,
+            } => {
+                let params = argon2::Params::new(memory_kib, iterations, parallelism, Some(32))
+                    .map_err(|e| format!("Argon2 params error: {}", e))?;
+                let argon2 =
+                    Argon2::new(argon2::Algorithm::Argon2id, argon2::Version::V0x13, params);
+                let mut key = [0u8; 32];
+                argon2
+                    .hash_password_into(password.as_bytes(), salt, &mut key)
+                    .map_err(|e| format!("Anahtar türetilemedi: {}", e))?;
+                Ok(key)
+            }
+        }
+    }
+
+    /// Benchmarks Argon2id on this machine and picks the iteration count that
+    /// takes roughly `target` to derive a key, keeping the default memory cost.
+    pub fn calibrate(target: Duration) -> Result<Self, String> {
+        let probe = KdfParams::Argon2id {
+            memory_kib: ARGON2_DEFAULT_MEMORY_KIB,
+            iterations: 1,
+            parallelism: ARGON2_DEFAULT_PARALLELISM,
+        };
+        let started = Instant::now();
+        let mut key = probe.derive_key("calibration", &[0u8; 32])?;
+        let elapsed = started.elapsed();
+        key.zeroize();
+
+        let per_pass = elapsed.as_secs_f64().max(0.001);
+        let iterations = (target.as_secs_f64() / per_pass).round().clamp(
+            ARGON2_DEFAULT_ITERATIONS as f64,
+            ARGON2_MAX_ITERATIONS as f64,
+        ) as u32;
+
+        Ok(KdfParams::Argon2id {
+            memory_kib: ARGON2_DEFAULT_MEMORY_KIB,
+            iterations,
+            parallelism: ARGON2_DEFAULT_PARALLELISM,
+        })
+    }
+}
+
+pub fn derive_encryption_key(master_password: &str, salt: &[u8]) -> [u8; 32] {
+    let mut key = [0u8; 32];
+    pbkdf2_hmac::<Sha256>(
+        master_password.as_bytes(),
+        salt,
+        LEGACY_PBKDF2_ITERATIONS,
+        &mut key,
+    );
+    key
+}
+
+pub fn encrypt_vault_data(
+    data: &str,
+    master_password: &str,
+    salt: &[u8],
+) -> Result<String, String> {
+    let key = derive_encryption_key(master_password, salt);
+    let cipher =
+        Aes256Gcm::new_from_slice(&key).map_err(|e| format!("Cipher oluşturulamadı: {}", e))?;
+    let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+
+    let ciphertext = cipher
+        .encrypt(&nonce, data.as_bytes())
+        .map_err(|e| format!("Şifreleme hatası: {}", e))?;
+
+    let mut encrypted = nonce.to_vec();
+    encrypted.extend_from_slice(&ciphertext);
+
+    Ok(general_purpose::STANDARD.encode(&encrypted))
+}
+
+pub fn decrypt_vault_data(
+    encrypted_data: &str,
+    master_password: &str,
+    salt: &[u8],
+) -> Result<String, String> {
+    let encrypted_bytes = match general_purpose::STANDARD.decode(encrypted_data) {
+        Ok(bytes) => bytes,
+        Err(e) => return Err(format!("Base64 decode hatası: {}", e)),
+    };
+
+    if encrypted_bytes.len() < 12 {
+        return Err("Geçersiz şifreli veri uzunluğu".to_string());
+    }
+
+    let nonce = Nonce::from_slice(&encrypted_bytes[..12]);
+    let ciphertext = &encrypted_bytes[12..];
+
+    let key = derive_encryption_key(master_password, salt);
+    let cipher = match Aes256Gcm::new_from_slice(&key) {
+        Ok(c) => c,
+        Err(e) => return Err(format!("Cipher oluşturulamadı: {}", e)),
+    };
+
+    let plaintext = match cipher.decrypt(nonce, ciphertext) {
+        Ok(pt) => pt,
+        Err(_) => return Err("Decrypt hatası: Yanlış şifre veya bozuk veri".to_string()),
+    };
+
+    match String::from_utf8(plaintext) {
+        Ok(s) => Ok(s),
+        Err(e) => Err(format!("UTF-8 decode hatası: {}", e)),
+    }
+}
+
+/// Hashes the master password into the Argon2id verifier stored inside the vault.
+pub fn hash_master_password(master_password: &str) -> Result<String, String> {
+    use argon2::password_hash::{PasswordHasher, SaltString};
+
+    let salt = SaltString::generate(&mut OsRng);
+    let params = argon2::Params::new(
+        ARGON2_DEFAULT_MEMORY_KIB,
+        ARGON2_DEFAULT_ITERATIONS,
+        ARGON2_DEFAULT_PARALLELISM,
+        None,
+    )
+    .map_err(|e| format!("Argon2 params error: {}", e))?;
+    let argon2 = Argon2::new(argon2::Algorithm::Argon2id, argon2::Version::V0x13, params);
+    let hash = argon2
+        .hash_password(master_password.as_bytes(), &salt)
+        .map_err(|e| format!("Hash error: {}", e))?;
+
+    Ok(hash.to_string())
+}
+
+pub fn verify_master_password(master_password: &str, stored_hash: &str) -> Result<bool, String> {
+    use argon2::password_hash::{PasswordHash, PasswordVerifier};
+
+    let parsed_hash =
+        PasswordHash::new(stored_hash).map_err(|e| format!("Hash parse hatası: {}", e))?;
+
+    Ok(Argon2::default()
+        .verify_password(master_password.as_bytes(), &parsed_hash)
+        .is_ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encrypt_roundtrip() {
+        let salt = [7u8; 32];
+        let encrypted = encrypt_vault_data("gizli veri", "ana-sifre-123", &salt).unwrap();
+        let decrypted = decrypt_vault_data(&encrypted, "ana-sifre-123", &salt).unwrap();
+        assert_eq!(decrypted, "gizli veri");
+    }
+
+    #[test]
+    fn decrypt_with_wrong_password_fails() {
+        let salt = [7u8; 32];
+        let encrypted = encrypt_vault_data("gizli veri", "ana-sifre-123", &salt).unwrap();
+        let err = decrypt_vault_data(&encrypted, "yanlis-sifre", &salt).unwrap_err();
+        assert!(err.contains("Decrypt hatası"));
+    }
+
+    #[test]
+    fn argon2id_params_are_validated() {
+        assert!(KdfParams::default().validate().is_ok());
+        assert!(KdfParams::legacy().validate().is_err());
+        let weak = KdfParams::Argon2id {
+            memory_kib: 1024,
+            iterations: 1,
+            parallelism: 1,
+        };
+        assert!(weak.validate().is_err());
+    }
+}
@@ -0,0 +1,58 @@
+pub fn extract_domain(url: &str) -> Option<String> {
+    let url_str = url.trim();
+    if url_str.is_empty() {
+        return None;
+    }
+
+    // Try to parse using Url crate
+    // If it fails (e.g. no protocol), try adding https://
+    let parsed_url = url::Url::parse(url_str)
+        .or_else(|_| url::Url::parse(&format!("https://{}", url_str)))
+        .ok();
+
+    if let Some(u) = parsed_url {
+        if let Some(host) = u.host_str() {
+            // Remove www. prefix if present
+            let domain = host.strip_prefix("www.").unwrap_or(host);
+            return Some(domain.to_lowercase());
+        }
+    }
+
+    // Fallback: simple split if everything fails (should be rare with Url crate)
+    let without_protocol = url_str
+        .strip_prefix("http://")
+        .or_else(|| url_str.strip_prefix("https://"))
+        .unwrap_or(url_str);
+
+    let without_www = without_protocol
+        .strip_prefix("www.")
+        .unwrap_or(without_protocol);
+
+    let domain = without_www.split('/').next().unwrap_or(without_www);
+    let domain = domain.split(':').next().unwrap_or(domain);
+    let domain = domain.split('?').next().unwrap_or(domain); // Handle queries
+
+    if domain.is_empty() {
+        None
+    } else {
+        Some(domain.to_lowercase())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn strips_scheme_www_and_path() {
+        assert_eq!(
+            extract_domain("https://www.Example.com/login?next=/"),
+            Some("example.com".to_string())
+        );
+        assert_eq!(
+            extract_domain("accounts.google.com"),
+            Some("accounts.google.com".to_string())
+        );
+        assert_eq!(extract_domain("   "), None);
+    }
+}
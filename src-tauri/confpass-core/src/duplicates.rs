@@ -0,0 +1,159 @@
+//! Duplicate detection, shared by the browser extension's duplicate check and
+//! imports.
+//!
+//! Logins match on domain and username: the same domain or one a subdomain of
+//! the other, and the username compared case-insensitively. Cards match on the
+//! card number without separators, addresses on street and postal code and
+//! authenticators on their secret. Trashed entries never match.
+
+use crate::domain::extract_domain;
+use crate::entry_data::EntryData;
+use crate::models::{PasswordEntry, VaultState};
+use crate::trash::is_trashed;
+
+/// Whether two URLs belong to the same site, subdomains included.
+pub fn same_site(a: &str, b: &str) -> bool {
+    match (
+        extract_domain(&a.to_lowercase()),
+        extract_domain(&b.to_lowercase()),
+    ) {
+        (Some(a), Some(b)) => {
+            a == b || a.ends_with(&format!(".{}", b)) || b.ends_with(&format!(".{}", a))
+        }
+        _ => false,
+    }
+}
+
+pub fn normalize_card_number(number: &str) -> String {
+    number.replace([' ', '-'], "")
+}
+
+fn card_number(entry: &PasswordEntry) -> Option<String> {
+    match &entry.data {
+        Some(EntryData::Card(card)) => card
+            .card_number
+            .as_deref()
+            .map(normalize_card_number)
+            .filter(|n| !n.is_empty()),
+        _ => None,
+    }
+}
+
+fn totp_secret(entry: &PasswordEntry) -> Option<String> {
+    let secret = match &entry.data {
+        Some(EntryData::Totp(item)) if !item.secret.is_empty() => &item.secret,
+        _ => &entry.password,
+    };
+    let secret = secret.replace([' ', '-'], "").to_uppercase();
+    (!secret.is_empty()).then_some(secret)
+}
+
+/// Whether `candidate` is the same account, card, address or authenticator as
+/// the vault entry `existing`.
+pub fn is_duplicate(existing: &PasswordEntry, candidate: &PasswordEntry) -> bool {
+    if is_trashed(existing) || existing.category != candidate.category {
+        return false;
+    }
+
+    match existing.category.as_str() {
+        "accounts" => {
+            existing.username.to_lowercase() == candidate.username.to_lowercase()
+                && match (&existing.url, &candidate.url) {
+                    (Some(a), Some(b)) => same_site(a, b),
+                    _ => false,
+                }
+        }
+        "bank_cards" => card_number(existing).is_some_and(|n| card_number(candidate) == Some(n)),
+        "addresses" => match (&existing.data, &candidate.data) {
+            (Some(EntryData::Address(a)), Some(EntryData::Address(b))) => {
+                let street = |address: &crate::entry_data::AddressItem| {
+                    address
+                        .street
+                        .as_deref()
+                        .unwrap_or("")
+                        .trim()
+                        .to_lowercase()
+                };
+                !street(a).is_empty()
+                    && street(a) == street(b)
+                    && a.postal_code.as_deref().unwrap_or("")
+                        == b.postal_code.as_deref().unwrap_or("")
+            }
+            _ => false,
+        },
+        "authenticator" => totp_secret(existing).is_some_and(|s| totp_secret(candidate) == Some(s)),
+        _ => false,
+    }
+}
+
+/// The vault entry `candidate` duplicates; the most recently changed one when
+/// several match.
+pub fn find_duplicate<'a>(
+    state: &'a VaultState,
+    candidate: &PasswordEntry,
+) -> Option<&'a PasswordEntry> {
+    state
+        .entries
+        .values()
+        .filter(|entry| entry.id != candidate.id && is_duplicate(entry, candidate))
+        .max_by_key(|entry| (entry.updated_at, entry.id.as_str()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::entry_data::CardItem;
+
+    fn entry(id: &str, category: &str) -> PasswordEntry {
+        PasswordEntry {
+            id: id.to_string(),
+            title: id.to_string(),
+            username: String::new(),
+            password: String::new(),
+            url: None,
+            notes: None,
+            created_at: 0,
+            updated_at: 0,
+            category: category.to_string(),
+            folder_id: None,
+            tags: None,
+            extra_fields: None,
+            attachments: None,
+            data: None,
+            deleted_at: None,
+        }
+    }
+
+    #[test]
+    fn matches_logins_by_site_and_username_and_cards_by_number() {
+        let mut login = entry("a", "accounts");
+        login.username = "Ada@example.com".to_string();
+        login.url = Some("https://www.example.com/login".to_string());
+        let mut candidate = entry("b", "accounts");
+        candidate.username = "ada@example.com".to_string();
+        candidate.url = Some("accounts.example.com".to_string());
+        candidate.password = "farklı".to_string();
+        assert!(is_duplicate(&login, &candidate));
+
+        candidate.url = Some("https://example.org".to_string());
+        assert!(!is_duplicate(&login, &candidate));
+
+        login.deleted_at = Some(1);
+        candidate.url = login.url.clone();
+        assert!(!is_duplicate(&login, &candidate));
+
+        let card = |id: &str, number: &str| {
+            let mut card = entry(id, "bank_cards");
+            card.data = Some(EntryData::Card(CardItem {
+                card_number: Some(number.to_string()),
+                ..Default::default()
+            }));
+            card
+        };
+        assert!(is_duplicate(
+            &card("c", "4111 1111 1111 1111"),
+            &card("d", "4111-1111-1111-1111")
+        ));
+        assert!(!is_duplicate(&card("c", ""), &card("d", "")));
+    }
+}
@@ -0,0 +1,451 @@
+//! Typed entry contents.
+//!
+//! Every category keeps its structured fields in [`PasswordEntry::data`] and
+//! leaves `notes` for the user's own text. Older releases, the desktop UI and
+//! the browser extension wrote those fields into `notes` as a JSON object,
+//! optionally followed by a newline and free text; [`normalize`] splits that
+//! form into `data` and `notes` when a vault or an export is loaded. Fields
+//! this version does not know about, or that belong to another category, are
+//! kept in the item's `extra` map.
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+use crate::error::{validate_input, VaultError};
+use crate::models::{PasswordEntry, VaultState};
+use crate::trash;
+
+/// Free-text notes limit once structured fields have moved to `data`.
+pub const NOTES_MAX_LEN: usize = 100_000;
+const DATA_MAX_LEN: usize = 20_000;
+
+type Extra = BTreeMap<String, serde_json::Value>;
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct LoginItem {
+    #[serde(flatten)]
+    pub extra: Extra,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CardItem {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub card_number: Option<String>,
+    /// `MM/YY` or `MM/YYYY`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub expiry: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cvv: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cardholder_name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub card_type: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub card_color: Option<String>,
+    #[serde(flatten)]
+    pub extra: Extra,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AddressItem {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub street: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub city: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub state: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub postal_code: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub country: Option<String>,
+    #[serde(flatten)]
+    pub extra: Extra,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TotpItem {
+    /// Base32 secret. Older entries keep it only in the password field.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub secret: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub issuer: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub account: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub algorithm: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub digits: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub period: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub backup_codes: Option<Vec<String>>,
+    #[serde(flatten)]
+    pub extra: Extra,
+}
+
+/// Public passkey metadata; the private key lives in
+/// [`VaultState::passkeys`](crate::models::VaultState::passkeys).
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PasskeyItem {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub username: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub email: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub domain: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub credential_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rp_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rp_name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub user_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub user_display_name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub counter: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub created_at: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub backup_codes: Option<Vec<String>>,
+    #[serde(flatten)]
+    pub extra: Extra,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SecureNote {
+    #[serde(flatten)]
+    pub extra: Extra,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DocumentItem {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub document_type: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub file_path: Option<String>,
+    #[serde(flatten)]
+    pub extra: Extra,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum EntryData {
+    Login(LoginItem),
+    Card(CardItem),
+    Address(AddressItem),
+    Totp(TotpItem),
+    Passkey(PasskeyItem),
+    SecureNote(SecureNote),
+    Document(DocumentItem),
+}
+
+/// Categories whose structured fields used to be written into `notes`.
+fn stores_fields_in_notes(category: &str) -> bool {
+    !matches!(category, "accounts" | "notes")
+}
+
+impl EntryData {
+    /// Builds the variant for `category` from a JSON object of its fields.
+    fn from_fields(
+        category: &str,
+        fields: serde_json::Value,
+    ) -> Option<Result<Self, serde_json::Error>> {
+        let data = match category {
+            "accounts" => serde_json::from_value(fields).map(Self::Login),
+            "bank_cards" => serde_json::from_value(fields).map(Self::Card),
+            "addresses" => serde_json::from_value(fields).map(Self::Address),
+            "authenticator" => serde_json::from_value(fields).map(Self::Totp),
+            "passkeys" => serde_json::from_value(fields).map(Self::Passkey),
+            "notes" => serde_json::from_value(fields).map(Self::SecureNote),
+            "documents" => serde_json::from_value(fields).map(Self::Document),
+            _ => return None,
+        };
+        Some(data)
+    }
+
+    fn empty_for(category: &str) -> Option<Self> {
+        Self::from_fields(category, serde_json::json!({}))?.ok()
+    }
+
+    fn matches_category(&self, category: &str) -> bool {
+        matches!(
+            (self, category),
+            (Self::Login(_), "accounts")
+                | (Self::Card(_), "bank_cards")
+                | (Self::Address(_), "addresses")
+                | (Self::Totp(_), "authenticator")
+                | (Self::Passkey(_), "passkeys")
+                | (Self::SecureNote(_), "notes")
+                | (Self::Document(_), "documents")
+        )
+    }
+
+    pub fn validate(&self) -> Result<(), VaultError> {
+        let json = serde_json::to_string(self)
+            .map_err(|e| VaultError::InternalError(format!("JSON serialize hatası: {}", e)))?;
+        validate_input(&json, 0, DATA_MAX_LEN, "Kayıt verisi")?;
+
+        let invalid = |msg: &str| Err(VaultError::InvalidInput(msg.to_string()));
+        match self {
+            Self::Card(card) => {
+                if let Some(number) = card.card_number.as_deref().filter(|n| !n.is_empty()) {
+                    let digits: String =
+                        number.chars().filter(|c| *c != ' ' && *c != '-').collect();
+                    if !(12..=19).contains(&digits.len())
+                        || !digits.chars().all(|c| c.is_ascii_digit())
+                    {
+                        return invalid("Kart numarası 12-19 haneli olmalı");
+                    }
+                }
+                if let Some(cvv) = card.cvv.as_deref().filter(|c| !c.is_empty()) {
+                    if !(3..=4).contains(&cvv.len()) || !cvv.chars().all(|c| c.is_ascii_digit()) {
+                        return invalid("CVV 3 veya 4 haneli olmalı");
+                    }
+                }
+                if let Some(expiry) = card.expiry.as_deref().filter(|e| !e.is_empty()) {
+                    let valid = expiry.split_once('/').is_some_and(|(month, year)| {
+                        month.parse::<u8>().is_ok_and(|m| (1..=12).contains(&m))
+                            && (year.len() == 2 || year.len() == 4)
+                            && year.chars().all(|c| c.is_ascii_digit())
+                    });
+                    if !valid {
+                        return invalid("Son kullanma tarihi AA/YY biçiminde olmalı");
+                    }
+                }
+            }
+            Self::Totp(totp) => {
+                if !totp.secret.is_empty() {
+                    let secret: String = totp
+                        .secret
+                        .chars()
+                        .filter(|c| !c.is_whitespace())
+                        .collect::<String>()
+                        .to_uppercase();
+                    let decoded = base32::decode(
+                        base32::Alphabet::RFC4648 { padding: false },
+                        secret.trim_end_matches('='),
+                    );
+                    if !matches!(decoded, Some(bytes) if !bytes.is_empty()) {
+                        return invalid("TOTP anahtarı geçerli bir Base32 değeri değil");
+                    }
+                }
+                if totp.digits.is_some_and(|d| d != 6 && d != 8) {
+                    return invalid("TOTP basamak sayısı 6 veya 8 olmalı");
+                }
+                if totp.period.is_some_and(|p| p == 0 || p > 300) {
+                    return invalid("TOTP süresi 1-300 saniye olmalı");
+                }
+                if let Some(algorithm) = totp.algorithm.as_deref() {
+                    if !matches!(algorithm, "SHA1" | "SHA256" | "SHA512") {
+                        return invalid("TOTP algoritması SHA1, SHA256 veya SHA512 olmalı");
+                    }
+                }
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+/// Splits `{...}\nfree text` into the JSON object and the remaining text.
+fn split_legacy_notes(notes: &str) -> Option<(serde_json::Value, Option<String>)> {
+    let (head, rest) = notes.split_once('\n').unwrap_or((notes, ""));
+    let fields: serde_json::Value = serde_json::from_str(head.trim()).ok()?;
+    if !fields.is_object() {
+        return None;
+    }
+    let rest = rest.trim();
+    Some((fields, (!rest.is_empty()).then(|| rest.to_string())))
+}
+
+/// Brings `entry.data` in line with its category. When the category changed,
+/// fields with the same name carry over and the rest stay in `extra`; data
+/// that does not fit the new category is left as it was and an error is
+/// returned. Returns whether the entry changed.
+fn match_category(entry: &mut PasswordEntry) -> Result<bool, VaultError> {
+    let Some(data) = entry.data.as_ref() else {
+        entry.data = EntryData::empty_for(&entry.category);
+        return Ok(entry.data.is_some());
+    };
+    if data.matches_category(&entry.category) {
+        return Ok(false);
+    }
+    let mut fields = serde_json::to_value(data).unwrap_or_default();
+    if let Some(fields) = fields.as_object_mut() {
+        fields.remove("type");
+    }
+    match EntryData::from_fields(&entry.category, fields) {
+        Some(Ok(data)) => {
+            entry.data = Some(data);
+            Ok(true)
+        }
+        Some(Err(e)) => Err(VaultError::InvalidInput(format!(
+            "Kayıt verisi yeni kategoriye taşınamadı: {}",
+            e
+        ))),
+        None => Ok(false),
+    }
+}
+
+/// Moves JSON fields an older writer left in `notes` into `data`, then
+/// brings `data` in line with the category. Only for entries read from disk
+/// or from an export; entries saved now carry `data` themselves. Returns
+/// whether the entry changed.
+pub fn normalize(entry: &mut PasswordEntry) -> bool {
+    let legacy = entry
+        .notes
+        .as_deref()
+        .filter(|_| entry.data.is_none() && stores_fields_in_notes(&entry.category))
+        .and_then(split_legacy_notes);
+    if let Some((fields, rest)) = legacy {
+        // Fields of the wrong type: keep the notes untouched
+        if let Some(Ok(data)) = EntryData::from_fields(&entry.category, fields) {
+            entry.data = Some(data);
+            entry.notes = rest;
+            return true;
+        }
+    }
+    match_category(entry).unwrap_or(false)
+}
+
+/// Brings `data` in line with the category and validates an entry about to
+/// be stored. `notes` is kept as the user wrote it.
+pub fn prepare_entry(entry: &mut PasswordEntry) -> Result<(), VaultError> {
+    trash::migrate_legacy_category(entry);
+    match_category(entry)?;
+    if let Some(notes) = entry.notes.as_deref() {
+        validate_input(notes, 0, NOTES_MAX_LEN, "Notlar")?;
+    }
+    match entry.data.as_ref() {
+        Some(data) => data.validate(),
+        None => Ok(()),
+    }
+}
+
+/// Converts every entry of a freshly loaded vault. Returns the number of
+/// entries that changed.
+pub fn migrate_entries(state: &mut VaultState) -> usize {
+    state
+        .entries
+        .values_mut()
+        .map(normalize)
+        .filter(|changed| *changed)
+        .count()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(category: &str, notes: Option<&str>) -> PasswordEntry {
+        PasswordEntry {
+            id: "e1".to_string(),
+            title: "Örnek".to_string(),
+            username: String::new(),
+            password: String::new(),
+            url: None,
+            notes: notes.map(|n| n.to_string()),
+            created_at: 0,
+            updated_at: 0,
+            category: category.to_string(),
+            folder_id: None,
+            tags: None,
+            extra_fields: None,
+            attachments: None,
+            data: None,
+            deleted_at: None,
+        }
+    }
+
+    #[test]
+    fn legacy_notes_json_moves_into_data() {
+        let mut address = entry(
+            "addresses",
+            Some("{\"street\":\"Cadde 1\",\"city\":\"İzmir\",\"mahalle\":\"Alsancak\"}\nKapı kodu 42"),
+        );
+        assert!(normalize(&mut address));
+        assert_eq!(address.notes.as_deref(), Some("Kapı kodu 42"));
+        let Some(EntryData::Address(item)) = &address.data else {
+            panic!("address expected");
+        };
+        assert_eq!(item.city.as_deref(), Some("İzmir"));
+        assert_eq!(item.extra["mahalle"], "Alsancak");
+        assert!(!normalize(&mut address));
+
+        let json = serde_json::to_value(&address).unwrap();
+        assert_eq!(json["data"]["type"], "address");
+
+        let mut note = entry("notes", Some("{\"not\": \"json verisi\"}"));
+        normalize(&mut note);
+        assert_eq!(note.notes.as_deref(), Some("{\"not\": \"json verisi\"}"));
+        assert_eq!(
+            note.data,
+            Some(EntryData::SecureNote(SecureNote::default()))
+        );
+    }
+
+    fn card(number: &str) -> PasswordEntry {
+        let mut card = entry("bank_cards", None);
+        card.data = Some(EntryData::Card(CardItem {
+            card_number: Some(number.to_string()),
+            expiry: Some("12/29".to_string()),
+            cvv: Some("123".to_string()),
+            ..Default::default()
+        }));
+        card
+    }
+
+    #[test]
+    fn card_fields_are_validated() {
+        let mut valid = card("4111 1111 1111 1111");
+        assert!(prepare_entry(&mut valid).is_ok());
+
+        let mut bad = card("1234");
+        assert!(prepare_entry(&mut bad).is_err());
+
+        // Notes written now are the user's text, even when they look like JSON
+        let mut json_note = entry("bank_cards", Some("{\"cardNumber\":\"1234\"}"));
+        assert!(prepare_entry(&mut json_note).is_ok());
+        assert_eq!(
+            json_note.notes.as_deref(),
+            Some("{\"cardNumber\":\"1234\"}")
+        );
+
+        let mut long_note = entry("notes", Some(&"a".repeat(20_000)));
+        assert!(prepare_entry(&mut long_note).is_ok());
+    }
+
+    #[test]
+    fn fields_survive_a_round_trip_through_another_category() {
+        let mut moved = card("4111 1111 1111 1111");
+        let original = moved.data.clone();
+
+        moved.category = "accounts".to_string();
+        prepare_entry(&mut moved).unwrap();
+        let Some(EntryData::Login(login)) = &moved.data else {
+            panic!("login expected");
+        };
+        assert_eq!(login.extra["cardNumber"], "4111 1111 1111 1111");
+
+        moved.category = "bank_cards".to_string();
+        prepare_entry(&mut moved).unwrap();
+        assert_eq!(moved.data, original);
+
+        // A field the new category reads with another type refuses the change
+        let mut totp = moved.clone();
+        if let Some(EntryData::Card(card)) = totp.data.as_mut() {
+            card.extra
+                .insert("digits".to_string(), serde_json::json!("altı"));
+        }
+        totp.category = "authenticator".to_string();
+        assert!(prepare_entry(&mut totp).is_err());
+        assert!(matches!(totp.data, Some(EntryData::Card(_))));
+    }
+}
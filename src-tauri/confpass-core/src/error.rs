@@ -0,0 +1,47 @@
+#[derive(Debug)]
+pub enum VaultError {
+    Locked,
+    NotFound,
+    InvalidInput(String),
+    /// Seconds until the next unlock attempt is allowed.
+    RateLimited(u64),
+    InternalError(String),
+}
+
+impl std::fmt::Display for VaultError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            VaultError::Locked => write!(f, "Kasa kilitli"),
+            VaultError::NotFound => write!(f, "Kayıt bulunamadı"),
+            VaultError::InvalidInput(msg) => write!(f, "Geçersiz giriş: {}", msg),
+            VaultError::RateLimited(secs) => {
+                write!(f, "Çok fazla deneme. Lütfen {} saniye bekleyin.", secs)
+            }
+            VaultError::InternalError(msg) => write!(f, "İç hata: {}", msg),
+        }
+    }
+}
+
+impl std::error::Error for VaultError {}
+
+pub fn validate_input(
+    input: &str,
+    min_len: usize,
+    max_len: usize,
+    field_name: &str,
+) -> Result<(), VaultError> {
+    let len = input.len();
+    if len < min_len {
+        return Err(VaultError::InvalidInput(format!(
+            "{} en az {} karakter olmalı",
+            field_name, min_len
+        )));
+    }
+    if len > max_len {
+        return Err(VaultError::InvalidInput(format!(
+            "{} en fazla {} karakter olabilir",
+            field_name, max_len
+        )));
+    }
+    Ok(())
+}
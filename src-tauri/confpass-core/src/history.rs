@@ -0,0 +1,208 @@
+//! Password history.
+//!
+//! Earlier passwords of each entry are kept inside the encrypted vault, at
+//! most [`MAX_HISTORY_PER_ENTRY`] per entry and oldest first. Older releases
+//! wrote them in cleartext to `history.json`; [`migrate_plaintext_history`]
+//! imports that file on unlock and then overwrites and deletes it.
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+use crate::error::VaultError;
+use crate::models::{PasswordEntry, PasswordHistoryItem, VaultState};
+use crate::storage::{save_vault_to_disk, secure_delete};
+
+pub const HISTORY_FILE_NAME: &str = "history.json";
+pub const MAX_HISTORY_PER_ENTRY: usize = 10;
+
+fn trim(history: &mut Vec<PasswordHistoryItem>) {
+    if history.len() > MAX_HISTORY_PER_ENTRY {
+        history.drain(..history.len() - MAX_HISTORY_PER_ENTRY);
+    }
+}
+
+/// Remembers `old_password` as replaced at `changed_at`. Empty passwords are skipped.
+pub fn record_password_change(
+    state: &mut VaultState,
+    entry_id: &str,
+    old_password: String,
+    changed_at: i64,
+) {
+    if old_password.is_empty() {
+        return;
+    }
+
+    let history = state
+        .password_history
+        .entry(entry_id.to_string())
+        .or_default();
+    history.push(PasswordHistoryItem {
+        password: old_password,
+        changed_at,
+    });
+    trim(history);
+}
+
+/// Makes the history item at `index` the entry's password again; the current
+/// password moves into the history in its place.
+pub fn restore_password(
+    state: &mut VaultState,
+    entry_id: &str,
+    index: usize,
+    now: i64,
+) -> Result<PasswordEntry, String> {
+    if !state.entries.contains_key(entry_id) {
+        return Err(VaultError::NotFound.to_string());
+    }
+
+    let history = state
+        .password_history
+        .get_mut(entry_id)
+        .filter(|history| index < history.len())
+        .ok_or_else(|| "Geçmiş kaydı bulunamadı".to_string())?;
+    let restored = history.remove(index);
+
+    let entry = state
+        .entries
+        .get_mut(entry_id)
+        .ok_or_else(|| VaultError::NotFound.to_string())?;
+    let current = std::mem::replace(&mut entry.password, restored.password);
+    entry.updated_at = now;
+    let entry = entry.clone();
+
+    record_password_change(state, entry_id, current, now);
+    Ok(entry)
+}
+
+/// Imports a cleartext `history.json` next to the vault into `state`, saves
+/// the vault and only then overwrites and deletes the file. A file that does
+/// not parse is left in place. Returns the number of imported passwords.
+pub fn migrate_plaintext_history(
+    vault_path: &Path,
+    state: &mut VaultState,
+) -> Result<usize, String> {
+    let path = vault_path
+        .parent()
+        .ok_or_else(|| "Vault path parent bulunamadı".to_string())?
+        .join(HISTORY_FILE_NAME);
+    if !path.exists() {
+        return Ok(0);
+    }
+
+    let content = fs::read_to_string(&path).map_err(|e| format!("History okunamadı: {}", e))?;
+    let Ok(legacy) = serde_json::from_str::<HashMap<String, Vec<serde_json::Value>>>(&content)
+    else {
+        return Ok(0);
+    };
+
+    let mut imported = 0;
+    for (entry_id, items) in legacy {
+        if !state.entries.contains_key(&entry_id) {
+            continue;
+        }
+
+        let history = state.password_history.entry(entry_id).or_default();
+        for item in items {
+            let (Some(password), Some(changed_at)) = (
+                item.get("password").and_then(|v| v.as_str()),
+                item.get("changed_at").and_then(|v| v.as_i64()),
+            ) else {
+                continue;
+            };
+            let item = PasswordHistoryItem {
+                password: password.to_string(),
+                changed_at,
+            };
+            if !password.is_empty() && !history.contains(&item) {
+                history.push(item);
+                imported += 1;
+            }
+        }
+        history.sort_by_key(|item| item.changed_at);
+        trim(history);
+    }
+
+    save_vault_to_disk(vault_path, state)?;
+    secure_delete(&path)?;
+
+    Ok(imported)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::{init_keys, load_vault_from_disk, VAULT_FILE_NAME};
+
+    fn entry(id: &str, password: &str) -> PasswordEntry {
+        PasswordEntry {
+            id: id.to_string(),
+            title: "Örnek".to_string(),
+            username: "kullanici".to_string(),
+            password: password.to_string(),
+            url: None,
+            notes: None,
+            created_at: 0,
+            updated_at: 0,
+            category: "accounts".to_string(),
+            folder_id: None,
+            tags: None,
+            extra_fields: None,
+            attachments: None,
+            data: None,
+            deleted_at: None,
+        }
+    }
+
+    #[test]
+    fn history_is_capped_and_restorable() {
+        let mut state = VaultState::default();
+        state
+            .entries
+            .insert("e1".to_string(), entry("e1", "sifre-12"));
+        for i in 0..12 {
+            record_password_change(&mut state, "e1", format!("sifre-{}", i), i);
+        }
+
+        let history = &state.password_history["e1"];
+        assert_eq!(history.len(), MAX_HISTORY_PER_ENTRY);
+        assert_eq!(history[0].password, "sifre-2");
+
+        let restored = restore_password(&mut state, "e1", 0, 100).unwrap();
+        assert_eq!(restored.password, "sifre-2");
+        let history = &state.password_history["e1"];
+        assert_eq!(history.last().unwrap().password, "sifre-12");
+        assert!(restore_password(&mut state, "e1", 10, 100).is_err());
+    }
+
+    #[test]
+    fn plaintext_history_is_imported_and_deleted() {
+        let dir = tempfile::tempdir().unwrap();
+        let vault_path = dir.path().join(VAULT_FILE_NAME);
+        let mut state = VaultState {
+            master_password_hash: Some("hash".to_string()),
+            ..VaultState::default()
+        };
+        state.entries.insert("e1".to_string(), entry("e1", "yeni"));
+        init_keys(&mut state, "ana-sifre-123").unwrap();
+        save_vault_to_disk(&vault_path, &state).unwrap();
+
+        let legacy = serde_json::json!({
+            "e1": [{"password": "eski", "changed_at": 5}],
+            "silinmis": [{"password": "x", "changed_at": 1}]
+        });
+        let history_path = dir.path().join(HISTORY_FILE_NAME);
+        fs::write(&history_path, legacy.to_string()).unwrap();
+
+        let loaded = load_vault_from_disk(&vault_path, "ana-sifre-123").unwrap();
+        assert!(!history_path.exists());
+        assert_eq!(
+            loaded.password_history["e1"],
+            vec![PasswordHistoryItem {
+                password: "eski".to_string(),
+                changed_at: 5
+            }]
+        );
+        assert!(!loaded.password_history.contains_key("silinmis"));
+    }
+}
@@ -0,0 +1,1901 @@
+//! Imports from ConfPass's own export, other password managers and browsers.
+//!
+//! [`parse`] turns an export file into an [`ImportPreview`]: the new entries,
+//! the folders and tags they refer to, and a warning for every row that was
+//! skipped or only partly understood. Nothing touches the vault until the
+//! preview is handed to [`apply`], which merges folders and tags with the
+//! existing ones by name, so a dry run is simply a `parse` whose result is
+//! shown instead of applied. Entries that duplicate a vault entry, by id or
+//! by content (see [`crate::duplicates`]), are handled by a [`MergeStrategy`].
+//!
+//! TOTP secrets become separate `authenticator` entries named after their
+//! login, the same shape the desktop UI writes. Fields a format has no place
+//! for end up in `extra_fields` rather than being dropped.
+
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::io::Read;
+use std::path::Path;
+
+use base64::Engine as _;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use zeroize::Zeroize;
+
+use crate::attachments;
+use crate::domain::extract_domain;
+use crate::duplicates;
+use crate::entry_data::{normalize, prepare_entry, AddressItem, CardItem, EntryData, TotpItem};
+use crate::error::VaultError;
+use crate::history;
+use crate::kdbx::{CONFPASS_CATEGORY_KEY, CONFPASS_DATA_KEY};
+use crate::mime;
+use crate::models::{
+    FileAttachment, Folder, PasswordEntry, RevisionSource, Tag, VaultState, VALID_CATEGORIES,
+};
+use crate::revisions;
+
+/// Colour and icon of folders and tags created by an import; the first preset
+/// of the folder dialog.
+const DEFAULT_COLOR: &str = "#f59e0b";
+const DEFAULT_FOLDER_ICON: &str = "folder";
+const UNTITLED: &str = "Adsız kayıt";
+
+/// Seconds between 0001-01-01 and the Unix epoch; KDBX 4 stores times as
+/// seconds since the former.
+pub(crate) const KEEPASS_EPOCH_OFFSET: i64 = 62_135_596_800;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ImportFormat {
+    /// ConfPass JSON export: `{"entries": [...]}` or a bare array.
+    Confpass,
+    /// Unencrypted Bitwarden JSON export.
+    BitwardenJson,
+    /// 1Password `.1pux` archive.
+    OnePasswordPux,
+    /// KeePass 2.x XML export (also written by KeePassXC).
+    KeepassXml,
+    LastpassCsv,
+    /// Chrome, Edge, Firefox or Safari password CSV.
+    BrowserCsv,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ImportWarning {
+    /// 1-based position of the row or item in the source file.
+    pub row: usize,
+    pub title: String,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ImportPreview {
+    pub entries: Vec<PasswordEntry>,
+    /// Folders the entries refer to, parents before their children.
+    pub folders: Vec<Folder>,
+    pub tags: Vec<Tag>,
+    pub warnings: Vec<ImportWarning>,
+    /// Rows that produced no entry.
+    pub skipped: usize,
+    /// Attachment contents read from a KDBX file or an archive, for the
+    /// attachments listed in the entries; see [`apply_with_files`].
+    #[serde(skip)]
+    pub files: Vec<ImportedFile>,
+}
+
+#[derive(Debug, Clone)]
+pub struct ImportedFile {
+    pub attachment_id: String,
+    pub content: Vec<u8>,
+}
+
+/// What happens to an imported entry that duplicates a vault entry.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MergeStrategy {
+    /// Adds the imported entry next to the vault entry.
+    KeepBoth,
+    /// Updates the vault entry if the imported one was changed later.
+    PreferNewer,
+    /// Always updates the vault entry.
+    Overwrite,
+    /// Leaves the vault entry as it is.
+    #[default]
+    Skip,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ImportAction {
+    Added,
+    KeptBoth,
+    Updated,
+    Skipped,
+}
+
+/// What the import did with one entry.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ImportChange {
+    /// The entry in the vault: the new one, or the duplicate that was
+    /// updated or kept.
+    pub id: String,
+    pub title: String,
+    pub category: String,
+    pub action: ImportAction,
+    /// The vault entry the imported one duplicated.
+    pub duplicate_of: Option<String>,
+    /// Fields an update changed.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub changed_fields: Vec<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct ImportSummary {
+    /// New entries, duplicates kept next to their vault entry included.
+    pub imported: usize,
+    pub updated: usize,
+    /// Duplicates left out, and updates that would not have changed anything.
+    pub skipped: usize,
+    pub folders_created: usize,
+    pub tags_created: usize,
+    pub changes: Vec<ImportChange>,
+}
+
+/// Parses an export file without touching the vault.
+pub fn parse(format: ImportFormat, data: &[u8], now: i64) -> Result<ImportPreview, String> {
+    let mut builder = Builder::new(now);
+    match format {
+        ImportFormat::Confpass => parse_confpass(&mut builder, data)?,
+        ImportFormat::BitwardenJson => parse_bitwarden(&mut builder, data)?,
+        ImportFormat::OnePasswordPux => parse_1pux(&mut builder, data)?,
+        ImportFormat::KeepassXml => parse_keepass_xml(&mut builder, utf8(data)?)?,
+        ImportFormat::LastpassCsv => parse_lastpass(&mut builder, data)?,
+        ImportFormat::BrowserCsv => parse_browser_csv(&mut builder, data)?,
+    }
+    Ok(builder.preview)
+}
+
+/// Adds a parsed preview to the vault. Folders with the same name under the
+/// same parent and tags with the same name are reused; references to folders
+/// or tags that exist in neither are dropped. Entries with the id of a vault
+/// entry, or duplicating a live one, are merged according to `strategy`;
+/// updates are recorded in the password history and the entry revisions.
+pub fn apply(
+    state: &mut VaultState,
+    preview: ImportPreview,
+    strategy: MergeStrategy,
+    now: i64,
+) -> Result<ImportSummary, String> {
+    let mut summary = ImportSummary::default();
+
+    let mut folder_ids: HashMap<String, String> = HashMap::new();
+    for mut folder in preview.folders {
+        let parent_id = folder
+            .parent_id
+            .as_ref()
+            .and_then(|id| folder_ids.get(id).cloned());
+        let name = folder.name.to_lowercase();
+        let existing = state
+            .folders
+            .iter()
+            .find(|f| f.parent_id == parent_id && f.name.to_lowercase() == name)
+            .map(|f| f.id.clone());
+        let id = match existing {
+            Some(id) => id,
+            None => {
+                folder.parent_id = parent_id;
+                folder.order = state.folders.len() as i32;
+                state.folders.push(folder.clone());
+                summary.folders_created += 1;
+                folder.id.clone()
+            }
+        };
+        folder_ids.insert(folder.id, id);
+    }
+
+    let mut tag_ids: HashMap<String, String> = HashMap::new();
+    for tag in preview.tags {
+        let name = tag.name.to_lowercase();
+        let existing = state
+            .tags
+            .iter()
+            .find(|t| t.name.to_lowercase() == name)
+            .map(|t| t.id.clone());
+        let id = match existing {
+            Some(id) => id,
+            None => {
+                state.tags.push(tag.clone());
+                summary.tags_created += 1;
+                tag.id.clone()
+            }
+        };
+        tag_ids.insert(tag.id, id);
+    }
+
+    for mut entry in preview.entries {
+        entry.folder_id = entry.folder_id.and_then(|id| match folder_ids.get(&id) {
+            Some(mapped) => Some(mapped.clone()),
+            None => state.folders.iter().any(|f| f.id == id).then_some(id),
+        });
+        entry.tags = entry
+            .tags
+            .map(|tags| {
+                tags.into_iter()
+                    .filter_map(|id| match tag_ids.get(&id) {
+                        Some(mapped) => Some(mapped.clone()),
+                        None => state.tags.iter().any(|t| t.id == id).then_some(id),
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .filter(|tags| !tags.is_empty());
+
+        let duplicate = match state.entries.get(&entry.id) {
+            Some(existing) => Some(existing),
+            None => duplicates::find_duplicate(state, &entry),
+        }
+        .cloned();
+        let mut change = ImportChange {
+            id: entry.id.clone(),
+            title: entry.title.clone(),
+            category: entry.category.clone(),
+            action: ImportAction::Added,
+            duplicate_of: duplicate.as_ref().map(|d| d.id.clone()),
+            changed_fields: Vec::new(),
+        };
+
+        match duplicate {
+            None => {
+                state.entries.insert(entry.id.clone(), entry);
+                summary.imported += 1;
+            }
+            Some(_) if strategy == MergeStrategy::KeepBoth => {
+                if state.entries.contains_key(&entry.id) {
+                    entry.id = new_entry_id();
+                    change.id = entry.id.clone();
+                }
+                change.action = ImportAction::KeptBoth;
+                state.entries.insert(entry.id.clone(), entry);
+                summary.imported += 1;
+            }
+            Some(existing)
+                if strategy == MergeStrategy::Overwrite
+                    || (strategy == MergeStrategy::PreferNewer
+                        && entry.updated_at > existing.updated_at) =>
+            {
+                change.id = existing.id.clone();
+                change.changed_fields = merge_entry(state, &existing, entry, now)?;
+                if change.changed_fields.is_empty() {
+                    change.action = ImportAction::Skipped;
+                    summary.skipped += 1;
+                } else {
+                    change.action = ImportAction::Updated;
+                    summary.updated += 1;
+                }
+            }
+            Some(existing) => {
+                change.id = existing.id.clone();
+                change.action = ImportAction::Skipped;
+                summary.skipped += 1;
+            }
+        }
+        summary.changes.push(change);
+    }
+
+    Ok(summary)
+}
+
+/// [`apply`] for a preview that carries attachment contents. The files are
+/// encrypted into `attachments_dir` with the vault data key first; those no
+/// entry refers to afterwards, because their entry was a skipped duplicate,
+/// are removed again.
+pub fn apply_with_files(
+    state: &mut VaultState,
+    mut preview: ImportPreview,
+    strategy: MergeStrategy,
+    now: i64,
+    attachments_dir: &Path,
+) -> Result<ImportSummary, String> {
+    let data_key = &state
+        .keys
+        .as_ref()
+        .ok_or_else(|| VaultError::Locked.to_string())?
+        .data_key;
+
+    let mut written = Vec::new();
+    let mut result = Ok(());
+    for mut file in std::mem::take(&mut preview.files) {
+        if result.is_ok() {
+            result = attachments::write_attachment(
+                attachments_dir,
+                &file.attachment_id,
+                &file.content,
+                data_key,
+            );
+            written.push(file.attachment_id);
+        }
+        file.content.zeroize();
+    }
+    let summary = result.and_then(|_| apply(state, preview, strategy, now));
+
+    let referenced: HashSet<&str> = match summary {
+        Ok(_) => state
+            .entries
+            .values()
+            .flat_map(|entry| entry.attachments.iter().flatten())
+            .map(|attachment| attachment.id.as_str())
+            .collect(),
+        Err(_) => HashSet::new(),
+    };
+    for id in written
+        .iter()
+        .filter(|id| !referenced.contains(id.as_str()))
+    {
+        let _ = fs::remove_file(attachments::attachment_path(attachments_dir, id));
+    }
+    summary
+}
+
+/// Updates the vault entry `existing` with the imported `entry`. The vault
+/// entry keeps its id, creation time and trash state; folder and tags are
+/// kept unless the import has its own, and attachments are combined.
+/// Returns the fields that changed.
+fn merge_entry(
+    state: &mut VaultState,
+    existing: &PasswordEntry,
+    entry: PasswordEntry,
+    now: i64,
+) -> Result<Vec<String>, String> {
+    let mut tags = existing.tags.clone().unwrap_or_default();
+    for tag in entry.tags.into_iter().flatten() {
+        if !tags.contains(&tag) {
+            tags.push(tag);
+        }
+    }
+    let mut attachments = existing.attachments.clone().unwrap_or_default();
+    attachments.extend(entry.attachments.into_iter().flatten());
+
+    let merged = PasswordEntry {
+        id: existing.id.clone(),
+        created_at: existing.created_at,
+        updated_at: now,
+        folder_id: entry.folder_id.or_else(|| existing.folder_id.clone()),
+        tags: (!tags.is_empty()).then_some(tags),
+        attachments: (!attachments.is_empty()).then_some(attachments),
+        deleted_at: existing.deleted_at,
+        ..entry
+    };
+    let mut changed_fields: Vec<String> = revisions::diff_entries(existing, &merged)?
+        .into_iter()
+        .map(|change| change.field)
+        .collect();
+    if merged.attachments.as_ref().map(Vec::len) != existing.attachments.as_ref().map(Vec::len) {
+        changed_fields.push("attachments".to_string());
+    }
+    if changed_fields.is_empty() {
+        return Ok(changed_fields);
+    }
+
+    let id = existing.id.clone();
+    let old_password = (merged.password != existing.password).then(|| existing.password.clone());
+    state.entries.insert(id.clone(), merged);
+    if let Some(old_password) = old_password {
+        history::record_password_change(state, &id, old_password, now);
+    }
+    revisions::record_change(state, &id, existing, RevisionSource::Import, now)?;
+    Ok(changed_fields)
+}
+
+struct Builder {
+    now: i64,
+    preview: ImportPreview,
+    folder_paths: HashMap<Vec<String>, String>,
+    tag_ids: HashMap<String, String>,
+    /// Files of the entry being built; kept only if the entry is.
+    pending_files: Vec<ImportedFile>,
+}
+
+impl Builder {
+    fn new(now: i64) -> Self {
+        Self {
+            now,
+            preview: ImportPreview::default(),
+            folder_paths: HashMap::new(),
+            tag_ids: HashMap::new(),
+            pending_files: Vec::new(),
+        }
+    }
+
+    fn entry(&self, title: &str, category: &str) -> PasswordEntry {
+        PasswordEntry {
+            id: new_entry_id(),
+            title: title.trim().to_string(),
+            username: String::new(),
+            password: String::new(),
+            url: None,
+            notes: None,
+            created_at: self.now,
+            updated_at: self.now,
+            category: category.to_string(),
+            folder_id: None,
+            tags: None,
+            extra_fields: None,
+            attachments: None,
+            data: None,
+            deleted_at: None,
+        }
+    }
+
+    fn warn(&mut self, row: usize, title: &str, message: impl Into<String>) {
+        self.preview.warnings.push(ImportWarning {
+            row,
+            title: title.to_string(),
+            message: message.into(),
+        });
+    }
+
+    fn skip(&mut self, row: usize, title: &str, message: impl Into<String>) {
+        self.warn(row, title, message);
+        self.preview.skipped += 1;
+        self.pending_files.clear();
+    }
+
+    /// Returns the id of the folder at `path`, creating it and any missing
+    /// parents. Empty segments are ignored; an empty path means no folder.
+    fn folder<S: AsRef<str>>(&mut self, path: &[S]) -> Option<String> {
+        let path: Vec<String> = path
+            .iter()
+            .map(|segment| segment.as_ref().trim())
+            .filter(|segment| !segment.is_empty())
+            .map(str::to_string)
+            .collect();
+        let mut parent: Option<String> = None;
+        for depth in 1..=path.len() {
+            let key = path[..depth].to_vec();
+            let id = match self.folder_paths.get(&key) {
+                Some(id) => id.clone(),
+                None => {
+                    let id = format!("folder_{}", uuid::Uuid::new_v4());
+                    self.preview.folders.push(Folder {
+                        id: id.clone(),
+                        name: path[depth - 1].clone(),
+                        color: DEFAULT_COLOR.to_string(),
+                        icon: DEFAULT_FOLDER_ICON.to_string(),
+                        parent_id: parent.clone(),
+                        created_at: self.now,
+                        order: self.preview.folders.len() as i32,
+                    });
+                    self.folder_paths.insert(key, id.clone());
+                    id
+                }
+            };
+            parent = Some(id);
+        }
+        parent
+    }
+
+    fn tags<'a>(&mut self, names: impl IntoIterator<Item = &'a str>) -> Option<Vec<String>> {
+        let mut ids = Vec::new();
+        for name in names.into_iter().map(str::trim).filter(|n| !n.is_empty()) {
+            let tags = &mut self.preview.tags;
+            let id = self
+                .tag_ids
+                .entry(name.to_lowercase())
+                .or_insert_with(|| {
+                    let id = format!("tag_{}", uuid::Uuid::new_v4());
+                    tags.push(Tag {
+                        id: id.clone(),
+                        name: name.to_string(),
+                        color: DEFAULT_COLOR.to_string(),
+                    });
+                    id
+                })
+                .clone();
+            if !ids.contains(&id) {
+                ids.push(id);
+            }
+        }
+        (!ids.is_empty()).then_some(ids)
+    }
+
+    /// Validates an entry and adds it to the preview, followed by an
+    /// authenticator entry when the row carried a TOTP secret or URI.
+    fn push(&mut self, row: usize, mut entry: PasswordEntry, totp: Option<&str>) {
+        if let Some(url) = entry.url.take() {
+            let url = url.trim();
+            match web_url(url) {
+                Some(url) => entry.url = Some(url),
+                None if !url.is_empty() => add_field(&mut entry, "URL", url),
+                None => {}
+            }
+        }
+        if entry.title.trim().is_empty() {
+            entry.title = entry
+                .url
+                .as_deref()
+                .and_then(extract_domain)
+                .unwrap_or_else(|| UNTITLED.to_string());
+        }
+        let title = entry.title.clone();
+        let mut totp = totp.map(str::trim).filter(|t| !t.is_empty());
+
+        // A login with a TOTP but no password is an authenticator on its own
+        if let Some(secret) =
+            totp.filter(|_| entry.category == "accounts" && entry.password.is_empty())
+        {
+            match self.authenticator(&entry, secret) {
+                Ok(mut authenticator) => {
+                    authenticator.url = entry.url;
+                    authenticator.notes = entry.notes;
+                    authenticator.extra_fields = entry.extra_fields;
+                    authenticator.attachments = entry.attachments;
+                    if let Some(authenticator) = self.prepare(row, authenticator) {
+                        self.preview.entries.push(authenticator);
+                        self.preview.files.append(&mut self.pending_files);
+                    }
+                    return;
+                }
+                Err(e) => {
+                    self.warn(row, &title, format!("TOTP anahtarı aktarılamadı: {}", e));
+                    totp = None;
+                }
+            }
+        }
+
+        if entry.category == "accounts" {
+            match (entry.username.is_empty(), entry.password.is_empty()) {
+                (true, true) => {
+                    entry.category = "notes".to_string();
+                    self.warn(
+                        row,
+                        &title,
+                        "Kullanıcı adı ve şifre yok; not olarak aktarıldı",
+                    );
+                }
+                (true, false) => self.warn(row, &title, "Kullanıcı adı boş"),
+                (false, true) => self.warn(row, &title, "Şifre boş"),
+                (false, false) => {}
+            }
+        }
+
+        let Some(entry) = self.prepare(row, entry) else {
+            return;
+        };
+        let authenticator = totp.map(|totp| {
+            let mut authenticator = self.authenticator(&entry, totp)?;
+            prepare_entry(&mut authenticator).map_err(|e| e.to_string())?;
+            Ok::<_, String>(authenticator)
+        });
+        self.preview.entries.push(entry);
+        self.preview.files.append(&mut self.pending_files);
+        match authenticator {
+            Some(Ok(entry)) => self.preview.entries.push(entry),
+            Some(Err(e)) => self.warn(row, &title, format!("TOTP anahtarı aktarılamadı: {}", e)),
+            None => {}
+        }
+    }
+
+    /// Normalizes and validates an entry. A card or address whose fields do
+    /// not validate is kept as a note with those fields as custom fields.
+    fn prepare(&mut self, row: usize, mut entry: PasswordEntry) -> Option<PasswordEntry> {
+        let title = entry.title.clone();
+        let Err(e) = prepare_entry(&mut entry) else {
+            return Some(entry);
+        };
+        match entry.data.take() {
+            Some(data) if entry.category != "notes" => {
+                add_data_fields(&mut entry, &data);
+                entry.category = "notes".to_string();
+                if let Err(e) = prepare_entry(&mut entry) {
+                    self.skip(row, &title, e.to_string());
+                    return None;
+                }
+                self.warn(row, &title, format!("{}; not olarak aktarıldı", e));
+                Some(entry)
+            }
+            _ => {
+                self.skip(row, &title, e.to_string());
+                None
+            }
+        }
+    }
+
+    /// Builds the authenticator entry for a login's TOTP value.
+    fn authenticator(&self, login: &PasswordEntry, totp: &str) -> Result<PasswordEntry, String> {
+        let mut item = parse_totp(totp)?;
+        let issuer = item.issuer.clone().unwrap_or_else(|| login.title.clone());
+        let account = item
+            .account
+            .clone()
+            .unwrap_or_else(|| login.username.clone());
+        item.issuer = Some(issuer.clone());
+        item.account = (!account.is_empty()).then(|| account.clone());
+
+        let mut entry = self.entry(&issuer, "authenticator");
+        entry.username = account;
+        entry.password = item.secret.clone();
+        entry.created_at = login.created_at;
+        entry.updated_at = login.updated_at;
+        entry.folder_id = login.folder_id.clone();
+        entry.tags = login.tags.clone();
+        entry.deleted_at = login.deleted_at;
+        entry.data = Some(EntryData::Totp(item));
+        Ok(entry)
+    }
+}
+
+fn new_entry_id() -> String {
+    format!("entry_{}", uuid::Uuid::new_v4())
+}
+
+fn utf8(data: &[u8]) -> Result<&str, String> {
+    let data = data.strip_prefix(b"\xEF\xBB\xBF").unwrap_or(data);
+    std::str::from_utf8(data).map_err(|_| "Dosya UTF-8 metin değil".to_string())
+}
+
+fn non_empty(value: &str) -> Option<String> {
+    let value = value.trim();
+    (!value.is_empty()).then(|| value.to_string())
+}
+
+/// Returns a URL the entry form accepts, adding `https://` to bare hosts.
+/// Other schemes (Android app links, `ftp://`, ...) are not web URLs.
+fn web_url(url: &str) -> Option<String> {
+    let lower = url.to_ascii_lowercase();
+    if lower.starts_with("http://") || lower.starts_with("https://") {
+        return (url.len() <= 500).then(|| url.to_string());
+    }
+    let bare_host = !url.contains("://")
+        && url.contains('.')
+        && !url.chars().any(char::is_whitespace)
+        && url.len() <= 490;
+    bare_host.then(|| format!("https://{}", url))
+}
+
+/// Adds a custom field, numbering the name when it is already taken.
+fn add_field(entry: &mut PasswordEntry, name: &str, value: &str) {
+    if value.trim().is_empty() {
+        return;
+    }
+    let fields = entry.extra_fields.get_or_insert_with(HashMap::new);
+    let name = match name.trim() {
+        "" => "Alan",
+        name => name,
+    };
+    let mut key = name.to_string();
+    let mut n = 2;
+    while fields.contains_key(&key) {
+        key = format!("{} ({})", name, n);
+        n += 1;
+    }
+    fields.insert(key, value.to_string());
+}
+
+/// Copies the structured fields of `data` into custom fields.
+fn add_data_fields(entry: &mut PasswordEntry, data: &EntryData) {
+    let Ok(Value::Object(fields)) = serde_json::to_value(data) else {
+        return;
+    };
+    for (name, value) in fields {
+        if name == "type" {
+            continue;
+        }
+        match value {
+            Value::String(value) => add_field(entry, &name, &value),
+            Value::Null => {}
+            value => add_field(entry, &name, &value.to_string()),
+        }
+    }
+}
+
+fn json_text<'a>(value: &'a Value, key: &str) -> &'a str {
+    value.get(key).and_then(Value::as_str).unwrap_or("")
+}
+
+fn json_array<'a>(value: &'a Value, key: &str) -> &'a [Value] {
+    value
+        .get(key)
+        .and_then(Value::as_array)
+        .map(Vec::as_slice)
+        .unwrap_or(&[])
+}
+
+fn rfc3339(value: &str) -> Option<i64> {
+    chrono::DateTime::parse_from_rfc3339(value.trim())
+        .ok()
+        .map(|time| time.timestamp())
+}
+
+fn clean_secret(secret: &str) -> String {
+    secret
+        .chars()
+        .filter(|c| !c.is_whitespace() && *c != '-')
+        .collect::<String>()
+        .to_uppercase()
+        .trim_end_matches('=')
+        .to_string()
+}
+
+fn percent_decode(value: &str) -> String {
+    let bytes = value.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = bytes
+            .get(i + 1..i + 3)
+            .and_then(|h| std::str::from_utf8(h).ok())
+            .and_then(|h| u8::from_str_radix(h, 16).ok());
+        match (bytes[i], hex) {
+            (b'%', Some(byte)) => {
+                out.push(byte);
+                i += 3;
+            }
+            (byte, _) => {
+                out.push(byte);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+/// Reads an `otpauth://totp/...` URI or a bare Base32 secret.
+fn parse_totp(value: &str) -> Result<TotpItem, String> {
+    let value = value.trim();
+    let lower = value.to_ascii_lowercase();
+    if lower.starts_with("steam://") {
+        return Err("Steam Guard anahtarları desteklenmiyor".to_string());
+    }
+    if !lower.starts_with("otpauth://") {
+        return Ok(TotpItem {
+            secret: clean_secret(value),
+            ..TotpItem::default()
+        });
+    }
+
+    let url = url::Url::parse(value).map_err(|_| "Geçersiz otpauth adresi".to_string())?;
+    if !url
+        .host_str()
+        .is_some_and(|h| h.eq_ignore_ascii_case("totp"))
+    {
+        return Err("Yalnızca zaman tabanlı (TOTP) kodlar desteklenir".to_string());
+    }
+    let label = percent_decode(url.path().trim_start_matches('/'));
+    let (label_issuer, account) = match label.split_once(':') {
+        Some((issuer, account)) => (non_empty(issuer), account),
+        None => (None, label.as_str()),
+    };
+
+    let mut item = TotpItem {
+        account: non_empty(account),
+        ..TotpItem::default()
+    };
+    for (key, value) in url.query_pairs() {
+        match key.to_ascii_lowercase().as_str() {
+            "secret" => item.secret = clean_secret(&value),
+            "issuer" => item.issuer = non_empty(&value),
+            "algorithm" => item.algorithm = Some(value.to_ascii_uppercase()),
+            "digits" => item.digits = Some(value.parse().unwrap_or(0)),
+            "period" => item.period = Some(value.parse().unwrap_or(0)),
+            _ => {}
+        }
+    }
+    if item.issuer.is_none() {
+        item.issuer = label_issuer;
+    }
+    if item.secret.is_empty() {
+        return Err("otpauth adresinde anahtar yok".to_string());
+    }
+    Ok(item)
+}
+
+fn parse_confpass(b: &mut Builder, data: &[u8]) -> Result<(), String> {
+    let value: Value = serde_json::from_slice(data).map_err(|e| format!("Geçersiz JSON: {}", e))?;
+    let items = match &value {
+        Value::Array(items) => items.as_slice(),
+        Value::Object(_) => match value.get("entries").and_then(Value::as_array) {
+            Some(items) => items.as_slice(),
+            None => return Err("JSON'da 'entries' listesi bulunamadı".to_string()),
+        },
+        _ => return Err("Geçersiz import formatı".to_string()),
+    };
+
+    for (i, item) in items.iter().enumerate() {
+        let row = i + 1;
+        let mut entry: PasswordEntry = match serde_json::from_value(item.clone()) {
+            Ok(entry) => entry,
+            Err(e) => {
+                b.skip(
+                    row,
+                    json_text(item, "title"),
+                    format!("Kayıt okunamadı: {}", e),
+                );
+                continue;
+            }
+        };
+        if entry.id.trim().is_empty() {
+            entry.id = new_entry_id();
+        }
+        if entry.attachments.take().is_some_and(|a| !a.is_empty()) {
+            b.warn(
+                row,
+                &entry.title,
+                "Ek dosyaları dışa aktarımda yok; aktarılmadı",
+            );
+        }
+        // Exports of older releases keep card and address fields in the notes
+        normalize(&mut entry);
+        b.push(row, entry, None);
+    }
+    Ok(())
+}
+
+fn parse_bitwarden(b: &mut Builder, data: &[u8]) -> Result<(), String> {
+    let export: Value =
+        serde_json::from_slice(data).map_err(|e| format!("Bitwarden JSON okunamadı: {}", e))?;
+    if export.get("encrypted").and_then(Value::as_bool) == Some(true) {
+        return Err(
+            "Parola korumalı Bitwarden dışa aktarımları desteklenmiyor; şifresiz JSON olarak dışa aktarın"
+                .to_string(),
+        );
+    }
+    let Some(items) = export.get("items").and_then(Value::as_array) else {
+        return Err("Bitwarden JSON'da 'items' listesi bulunamadı".to_string());
+    };
+    let names = |key: &str| -> HashMap<String, String> {
+        json_array(&export, key)
+            .iter()
+            .map(|f| {
+                (
+                    json_text(f, "id").to_string(),
+                    json_text(f, "name").to_string(),
+                )
+            })
+            .collect()
+    };
+    let folders = names("folders");
+    let collections = names("collections");
+
+    for (i, item) in items.iter().enumerate() {
+        let row = i + 1;
+        let title = json_text(item, "name");
+        let mut entry = b.entry(title, "accounts");
+        entry.notes = non_empty(json_text(item, "notes"));
+        entry.created_at = rfc3339(json_text(item, "creationDate")).unwrap_or(b.now);
+        entry.updated_at = rfc3339(json_text(item, "revisionDate")).unwrap_or(entry.created_at);
+        entry.deleted_at = rfc3339(json_text(item, "deletedDate"));
+        if let Some(folder) = folders.get(json_text(item, "folderId")) {
+            entry.folder_id = b.folder(&folder.split('/').collect::<Vec<_>>());
+        }
+        entry.tags = b.tags(
+            json_array(item, "collectionIds")
+                .iter()
+                .filter_map(Value::as_str)
+                .filter_map(|id| collections.get(id).map(String::as_str)),
+        );
+
+        let mut totp = None;
+        match item.get("type").and_then(Value::as_u64) {
+            Some(1) => {
+                let login = &item["login"];
+                entry.username = json_text(login, "username").trim().to_string();
+                entry.password = json_text(login, "password").to_string();
+                totp = non_empty(json_text(login, "totp"));
+                for (n, uri) in json_array(login, "uris").iter().enumerate() {
+                    let uri = json_text(uri, "uri");
+                    if n == 0 {
+                        entry.url = non_empty(uri);
+                    } else {
+                        add_field(&mut entry, "URL", uri);
+                    }
+                }
+                if !json_array(login, "fido2Credentials").is_empty() {
+                    b.warn(
+                        row,
+                        title,
+                        "Geçiş anahtarları dışa aktarımda yok; aktarılmadı",
+                    );
+                }
+            }
+            Some(2) => entry.category = "notes".to_string(),
+            Some(3) => {
+                let card = &item["card"];
+                let month = json_text(card, "expMonth").trim().parse::<u8>().ok();
+                let year = json_text(card, "expYear").trim();
+                let item = CardItem {
+                    card_number: non_empty(json_text(card, "number")),
+                    expiry: month
+                        .filter(|_| !year.is_empty())
+                        .map(|m| format!("{:02}/{}", m, year)),
+                    cvv: non_empty(json_text(card, "code")),
+                    cardholder_name: non_empty(json_text(card, "cardholderName")),
+                    card_type: non_empty(json_text(card, "brand")),
+                    ..CardItem::default()
+                };
+                set_card(&mut entry, item);
+            }
+            Some(4) => {
+                let identity = &item["identity"];
+                let street: Vec<&str> = ["address1", "address2", "address3"]
+                    .iter()
+                    .map(|key| json_text(identity, key).trim())
+                    .filter(|line| !line.is_empty())
+                    .collect();
+                entry.category = "addresses".to_string();
+                entry.username = json_text(identity, "email").trim().to_string();
+                entry.data = Some(EntryData::Address(AddressItem {
+                    street: non_empty(&street.join("\n")),
+                    city: non_empty(json_text(identity, "city")),
+                    state: non_empty(json_text(identity, "state")),
+                    postal_code: non_empty(json_text(identity, "postalCode")),
+                    country: non_empty(json_text(identity, "country")),
+                    ..AddressItem::default()
+                }));
+                for (key, name) in [
+                    ("title", "Unvan"),
+                    ("firstName", "Ad"),
+                    ("middleName", "İkinci ad"),
+                    ("lastName", "Soyad"),
+                    ("company", "Şirket"),
+                    ("phone", "Telefon"),
+                    ("username", "Kullanıcı adı"),
+                    ("ssn", "Kimlik numarası"),
+                    ("passportNumber", "Pasaport numarası"),
+                    ("licenseNumber", "Ehliyet numarası"),
+                ] {
+                    add_field(&mut entry, name, json_text(identity, key));
+                }
+            }
+            Some(5) => {
+                let key = &item["sshKey"];
+                entry.category = "notes".to_string();
+                add_field(&mut entry, "Özel anahtar", json_text(key, "privateKey"));
+                add_field(&mut entry, "Açık anahtar", json_text(key, "publicKey"));
+                add_field(&mut entry, "Parmak izi", json_text(key, "keyFingerprint"));
+            }
+            _ => {
+                b.skip(row, title, "Desteklenmeyen Bitwarden öğe türü");
+                continue;
+            }
+        }
+
+        for field in json_array(item, "fields") {
+            // Type 3 links to another field of the item and has no value
+            if field.get("type").and_then(Value::as_u64) == Some(3) {
+                continue;
+            }
+            let value = match field.get("value") {
+                Some(Value::String(value)) => value.clone(),
+                Some(Value::Bool(value)) => value.to_string(),
+                _ => continue,
+            };
+            add_field(&mut entry, json_text(field, "name"), &value);
+        }
+        b.push(row, entry, totp.as_deref());
+    }
+    Ok(())
+}
+
+/// Stores card fields the way the card form does: number as username and CVV
+/// as password.
+fn set_card(entry: &mut PasswordEntry, card: CardItem) {
+    entry.category = "bank_cards".to_string();
+    entry.username = card
+        .card_number
+        .as_deref()
+        .unwrap_or("")
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .collect();
+    entry.password = card.cvv.clone().unwrap_or_default();
+    entry.data = Some(EntryData::Card(card));
+}
+
+fn parse_1pux(b: &mut Builder, data: &[u8]) -> Result<(), String> {
+    let mut archive = zip::ZipArchive::new(std::io::Cursor::new(data))
+        .map_err(|e| format!("1PUX arşivi açılamadı: {}", e))?;
+    let mut json = String::new();
+    archive
+        .by_name("export.data")
+        .map_err(|_| "1PUX arşivinde export.data bulunamadı".to_string())?
+        .read_to_string(&mut json)
+        .map_err(|e| format!("1PUX arşivi okunamadı: {}", e))?;
+    let export: Value =
+        serde_json::from_str(&json).map_err(|e| format!("1PUX verisi okunamadı: {}", e))?;
+
+    let mut row = 0;
+    for account in json_array(&export, "accounts") {
+        for vault in json_array(account, "vaults") {
+            let vault_name = vault
+                .get("attrs")
+                .map(|attrs| json_text(attrs, "name"))
+                .unwrap_or("");
+            for item in json_array(vault, "items") {
+                row += 1;
+                onepassword_item(b, row, vault_name, item);
+            }
+        }
+    }
+    Ok(())
+}
+
+fn onepassword_item(b: &mut Builder, row: usize, vault_name: &str, item: &Value) {
+    let overview = &item["overview"];
+    let details = &item["details"];
+    let title = json_text(overview, "title");
+
+    let category = match json_text(item, "categoryUuid") {
+        "001" | "005" => "accounts",
+        "002" => "bank_cards",
+        "003" => "notes",
+        "004" => "addresses",
+        _ => {
+            b.warn(row, title, "Bu 1Password öğe türü not olarak aktarıldı");
+            "notes"
+        }
+    };
+    let mut entry = b.entry(title, category);
+    if let Some(created) = item.get("createdAt").and_then(Value::as_i64) {
+        entry.created_at = created;
+    }
+    entry.updated_at = item
+        .get("updatedAt")
+        .and_then(Value::as_i64)
+        .unwrap_or(entry.created_at);
+    if json_text(item, "state") == "archived" {
+        entry.deleted_at = Some(b.now);
+        b.warn(row, title, "Arşivlenmiş öğe çöp kutusuna aktarıldı");
+    }
+    entry.folder_id = b.folder(&[vault_name]);
+    entry.tags = b.tags(
+        json_array(overview, "tags")
+            .iter()
+            .filter_map(Value::as_str),
+    );
+    entry.url = non_empty(json_text(overview, "url"));
+    for url in json_array(overview, "urls") {
+        let url = json_text(url, "url");
+        if Some(url.trim()) != entry.url.as_deref() {
+            add_field(&mut entry, "URL", url);
+        }
+    }
+    entry.notes = non_empty(json_text(details, "notesPlain"));
+    entry.password = json_text(details, "password").to_string();
+
+    for field in json_array(details, "loginFields") {
+        let value = json_text(field, "value");
+        match json_text(field, "designation") {
+            "username" => entry.username = value.trim().to_string(),
+            "password" => entry.password = value.to_string(),
+            _ => add_field(&mut entry, json_text(field, "name"), value),
+        }
+    }
+
+    let mut totp = None;
+    let mut card = CardItem::default();
+    let mut address = AddressItem::default();
+    for section in json_array(details, "sections") {
+        for field in json_array(section, "fields") {
+            let Some((kind, value)) = field
+                .get("value")
+                .and_then(Value::as_object)
+                .and_then(|v| v.iter().next())
+            else {
+                continue;
+            };
+            let id = json_text(field, "id");
+            let name = match json_text(field, "title") {
+                "" => id,
+                title => title,
+            };
+            let text = value.as_str().unwrap_or("");
+            match (kind.as_str(), id) {
+                ("totp", _) if totp.is_none() => totp = non_empty(text),
+                ("creditCardNumber", _) => card.card_number = non_empty(text),
+                ("creditCardType", _) => card.card_type = non_empty(text),
+                ("monthYear", "expiry") => card.expiry = value.as_i64().map(month_year),
+                (_, "cvv") => card.cvv = non_empty(text),
+                (_, "cardholder") => card.cardholder_name = non_empty(text),
+                ("address", _) => {
+                    address = AddressItem {
+                        street: non_empty(json_text(value, "street")),
+                        city: non_empty(json_text(value, "city")),
+                        state: non_empty(json_text(value, "state")),
+                        postal_code: non_empty(json_text(value, "zip")),
+                        country: non_empty(json_text(value, "country")),
+                        ..AddressItem::default()
+                    }
+                }
+                ("file", _) => b.warn(row, title, format!("'{}' dosyası aktarılmadı", name)),
+                ("email", _) => add_field(&mut entry, name, json_text(value, "email_address")),
+                ("date", _) => {
+                    let date = value
+                        .as_i64()
+                        .and_then(|t| chrono::DateTime::from_timestamp(t, 0))
+                        .map(|t| t.format("%Y-%m-%d").to_string());
+                    add_field(&mut entry, name, date.as_deref().unwrap_or(""));
+                }
+                ("monthYear", _) => add_field(
+                    &mut entry,
+                    name,
+                    &value.as_i64().map(month_year).unwrap_or_default(),
+                ),
+                ("sshKey", _) => add_field(&mut entry, name, json_text(value, "privateKey")),
+                (_, _) => match value {
+                    Value::String(text) => add_field(&mut entry, name, text),
+                    Value::Number(n) => add_field(&mut entry, name, &n.to_string()),
+                    Value::Bool(flag) => add_field(&mut entry, name, &flag.to_string()),
+                    _ => {}
+                },
+            }
+        }
+    }
+    if details.get("documentAttributes").is_some() {
+        b.warn(row, title, "Belge dosyası aktarılmadı");
+    }
+
+    let has_card = card != CardItem::default();
+    let has_address = address != AddressItem::default();
+    match category {
+        "bank_cards" => set_card(&mut entry, card),
+        "addresses" => entry.data = Some(EntryData::Address(address)),
+        _ => {
+            if has_card {
+                add_data_fields(&mut entry, &EntryData::Card(card));
+            }
+            if has_address {
+                add_data_fields(&mut entry, &EntryData::Address(address));
+            }
+        }
+    }
+    b.push(row, entry, totp.as_deref());
+}
+
+/// 1Password stores month/year values as `YYYYMM`.
+fn month_year(value: i64) -> String {
+    format!("{:02}/{}", value % 100, value / 100)
+}
+
+fn xml_child<'a, 'input>(
+    node: roxmltree::Node<'a, 'input>,
+    name: &str,
+) -> Option<roxmltree::Node<'a, 'input>> {
+    node.children().find(|child| child.has_tag_name(name))
+}
+
+fn xml_text<'a>(node: roxmltree::Node<'a, '_>, name: &str) -> Option<&'a str> {
+    xml_child(node, name).and_then(|child| child.text())
+}
+
+/// KeePass times are RFC 3339 in XML exports and base64 seconds since year 1
+/// inside KDBX 4 files.
+fn keepass_time(value: &str) -> Option<i64> {
+    rfc3339(value).or_else(|| {
+        let bytes = base64::engine::general_purpose::STANDARD
+            .decode(value.trim())
+            .ok()?;
+        let seconds = i64::from_le_bytes(bytes.try_into().ok()?);
+        Some(seconds - KEEPASS_EPOCH_OFFSET)
+    })
+}
+
+fn parse_keepass_xml(b: &mut Builder, xml: &str) -> Result<(), String> {
+    parse_keepass(b, xml, None)
+}
+
+/// What a KDBX file adds to its XML: the inner stream that protects values
+/// and the binary pool attachments refer to.
+pub(crate) struct KdbxPayload<'a> {
+    pub unprotect: &'a mut dyn FnMut(&mut [u8]),
+    pub binaries: &'a [Vec<u8>],
+}
+
+/// Parses the XML of a decrypted KDBX database.
+pub(crate) fn parse_kdbx_xml(
+    xml: &str,
+    payload: KdbxPayload,
+    now: i64,
+) -> Result<ImportPreview, String> {
+    let mut builder = Builder::new(now);
+    parse_keepass(&mut builder, xml, Some(payload))?;
+    Ok(builder.preview)
+}
+
+struct KeepassDoc<'a> {
+    recycle_bin: Option<&'a str>,
+    /// Decrypted protected values by node; XML exports store them in clear.
+    protected: HashMap<roxmltree::NodeId, String>,
+    binaries: &'a [Vec<u8>],
+}
+
+impl KeepassDoc<'_> {
+    fn value(&self, string: roxmltree::Node) -> String {
+        let Some(value) = xml_child(string, "Value") else {
+            return String::new();
+        };
+        match self.protected.get(&value.id()) {
+            Some(text) => text.clone(),
+            None => value.text().unwrap_or("").to_string(),
+        }
+    }
+}
+
+fn parse_keepass(b: &mut Builder, xml: &str, payload: Option<KdbxPayload>) -> Result<(), String> {
+    let doc =
+        roxmltree::Document::parse(xml).map_err(|e| format!("KeePass XML okunamadı: {}", e))?;
+    let root = doc.root_element();
+    if !root.has_tag_name("KeePassFile") {
+        return Err("Dosya bir KeePass XML dışa aktarımı değil".to_string());
+    }
+    let Some(top) = xml_child(root, "Root").and_then(|r| xml_child(r, "Group")) else {
+        return Err("KeePass XML'de grup bulunamadı".to_string());
+    };
+
+    let mut keepass = KeepassDoc {
+        recycle_bin: xml_child(root, "Meta").and_then(|meta| xml_text(meta, "RecycleBinUUID")),
+        protected: HashMap::new(),
+        binaries: &[],
+    };
+    if let Some(payload) = payload {
+        // The inner stream runs over every protected value in document
+        // order, history entries included
+        let protected = doc
+            .descendants()
+            .filter(|n| n.has_tag_name("Value") && n.attribute("Protected") == Some("True"));
+        for node in protected {
+            let mut bytes = base64::engine::general_purpose::STANDARD
+                .decode(node.text().unwrap_or("").trim())
+                .map_err(|_| "KDBX korumalı değeri okunamadı".to_string())?;
+            (payload.unprotect)(&mut bytes);
+            keepass
+                .protected
+                .insert(node.id(), String::from_utf8_lossy(&bytes).into_owned());
+        }
+        keepass.binaries = payload.binaries;
+    }
+
+    let mut row = 0;
+    keepass_group(b, &keepass, top, &[], false, &mut row);
+    Ok(())
+}
+
+/// Walks a group. The top-level group is the database itself and does not
+/// become a folder; the recycle bin's entries are imported into the trash.
+fn keepass_group(
+    b: &mut Builder,
+    keepass: &KeepassDoc,
+    group: roxmltree::Node,
+    path: &[String],
+    trashed: bool,
+    row: &mut usize,
+) {
+    for node in group.children().filter(roxmltree::Node::is_element) {
+        match node.tag_name().name() {
+            "Entry" => {
+                *row += 1;
+                keepass_entry(b, keepass, *row, node, path, trashed);
+            }
+            "Group" => {
+                let is_bin =
+                    keepass.recycle_bin.is_some() && xml_text(node, "UUID") == keepass.recycle_bin;
+                let mut path = path.to_vec();
+                if !is_bin {
+                    path.push(xml_text(node, "Name").unwrap_or("").to_string());
+                }
+                keepass_group(b, keepass, node, &path, trashed || is_bin, row);
+            }
+            _ => {}
+        }
+    }
+}
+
+fn keepass_entry(
+    b: &mut Builder,
+    keepass: &KeepassDoc,
+    row: usize,
+    node: roxmltree::Node,
+    path: &[String],
+    trashed: bool,
+) {
+    let mut strings: Vec<(String, String)> = node
+        .children()
+        .filter(|child| child.has_tag_name("String"))
+        .map(|string| {
+            (
+                xml_text(string, "Key").unwrap_or("").to_string(),
+                keepass.value(string),
+            )
+        })
+        .collect();
+    let mut take = |key: &str| {
+        strings
+            .iter()
+            .position(|(k, _)| k == key)
+            .map(|i| strings.remove(i).1)
+            .unwrap_or_default()
+    };
+
+    let title = take("Title");
+    let mut entry = b.entry(&title, "accounts");
+    entry.username = take("UserName").trim().to_string();
+    entry.password = take("Password");
+    entry.url = non_empty(&take("URL"));
+    entry.notes = non_empty(&take("Notes"));
+
+    // KeePassXC writes an otpauth URI, older KeePassXC a seed plus
+    // "period;digits", KeePass 2.47+ separate TimeOtp fields
+    let otp = take("otp");
+    let seed = take("TOTP Seed");
+    let settings = take("TOTP Settings");
+    let time_otp = take("TimeOtp-Secret-Base32");
+    let totp = if !otp.trim().is_empty() {
+        otp
+    } else if !seed.trim().is_empty() {
+        let (period, digits) = settings.split_once(';').unwrap_or(("30", "6"));
+        format!(
+            "otpauth://totp/?secret={}&period={}&digits={}",
+            clean_secret(&seed),
+            period.trim(),
+            digits.trim()
+        )
+    } else if !time_otp.trim().is_empty() {
+        let algorithm = take("TimeOtp-Algorithm")
+            .replace("HMAC-", "")
+            .replace('-', "");
+        let mut uri = format!("otpauth://totp/?secret={}", clean_secret(&time_otp));
+        for (name, value) in [
+            ("digits", take("TimeOtp-Length")),
+            ("period", take("TimeOtp-Period")),
+            ("algorithm", algorithm),
+        ] {
+            if !value.trim().is_empty() {
+                uri.push_str(&format!("&{}={}", name, value.trim()));
+            }
+        }
+        uri
+    } else {
+        String::new()
+    };
+
+    for (key, value) in strings {
+        add_field(&mut entry, &key, &value);
+    }
+    entry.folder_id = b.folder(path);
+    if let Some(tags) = xml_text(node, "Tags") {
+        entry.tags = b.tags(tags.split([';', ',']));
+    }
+    if let Some(times) = xml_child(node, "Times") {
+        if let Some(created) = xml_text(times, "CreationTime").and_then(keepass_time) {
+            entry.created_at = created;
+        }
+        entry.updated_at = xml_text(times, "LastModificationTime")
+            .and_then(keepass_time)
+            .unwrap_or(entry.created_at);
+    }
+    if trashed {
+        entry.deleted_at = Some(b.now);
+    }
+
+    let mut missing_files = false;
+    for binary in node.children().filter(|child| child.has_tag_name("Binary")) {
+        let content = xml_child(binary, "Value")
+            .and_then(|value| value.attribute("Ref"))
+            .and_then(|index| index.parse::<usize>().ok())
+            .and_then(|index| keepass.binaries.get(index));
+        let Some(content) = content else {
+            missing_files = true;
+            continue;
+        };
+        let attachment = FileAttachment {
+            id: uuid::Uuid::new_v4().to_string(),
+            filename: xml_text(binary, "Key").unwrap_or("ek").to_string(),
+            mime_type: mime::detect(content, xml_text(binary, "Key").unwrap_or("")).to_string(),
+            size: content.len() as u64,
+            created_at: b.now,
+        };
+        b.pending_files.push(ImportedFile {
+            attachment_id: attachment.id.clone(),
+            content: content.clone(),
+        });
+        entry
+            .attachments
+            .get_or_insert_with(Vec::new)
+            .push(attachment);
+    }
+    if missing_files {
+        b.warn(row, &title, "Ek dosyaları aktarılmadı");
+    }
+
+    // Our own KDBX export keeps categories KeePass has no notion of here
+    let custom_data = |key: &str| {
+        xml_child(node, "CustomData")?
+            .children()
+            .filter(|item| item.has_tag_name("Item"))
+            .find(|item| xml_text(*item, "Key") == Some(key))
+            .and_then(|item| xml_text(item, "Value"))
+    };
+    let category = custom_data(CONFPASS_CATEGORY_KEY).filter(|c| VALID_CATEGORIES.contains(c));
+    let totp = match category {
+        Some(category) => {
+            entry.category = category.to_string();
+            entry.data = custom_data(CONFPASS_DATA_KEY).and_then(|d| serde_json::from_str(d).ok());
+            if category == "authenticator" && entry.data.is_none() {
+                entry.data = parse_totp(&totp).ok().map(EntryData::Totp);
+            }
+            if let Some(EntryData::Totp(item)) = &entry.data {
+                if entry.password.is_empty() {
+                    entry.password = item.secret.clone();
+                }
+            }
+            None
+        }
+        None => Some(totp.as_str()),
+    };
+    b.push(row, entry, totp);
+}
+
+/// Reads a CSV file into lower-case headers and its records.
+fn read_csv(data: &[u8]) -> Result<(Vec<String>, Vec<csv::StringRecord>), String> {
+    let data = data.strip_prefix(b"\xEF\xBB\xBF").unwrap_or(data);
+    let mut reader = csv::ReaderBuilder::new().flexible(true).from_reader(data);
+    let headers = reader
+        .headers()
+        .map_err(|e| format!("CSV okunamadı: {}", e))?
+        .iter()
+        .map(|header| header.trim().to_lowercase())
+        .collect();
+    let records = reader
+        .records()
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("CSV okunamadı: {}", e))?;
+    Ok((headers, records))
+}
+
+fn csv_column(headers: &[String], names: &[&str]) -> Option<usize> {
+    headers.iter().position(|h| names.contains(&h.as_str()))
+}
+
+fn csv_field(record: &csv::StringRecord, column: Option<usize>) -> &str {
+    column.and_then(|c| record.get(c)).unwrap_or("")
+}
+
+fn parse_lastpass(b: &mut Builder, data: &[u8]) -> Result<(), String> {
+    let (headers, records) = read_csv(data)?;
+    let column = |name: &str| csv_column(&headers, &[name]);
+    let (url, username, password, totp, extra, name, grouping) = (
+        column("url"),
+        column("username"),
+        column("password"),
+        column("totp"),
+        column("extra"),
+        column("name"),
+        column("grouping"),
+    );
+    if url.is_none() || name.is_none() {
+        return Err(
+            "LastPass CSV başlıkları bulunamadı (url, username, password, name)".to_string(),
+        );
+    }
+
+    for (i, record) in records.iter().enumerate() {
+        let row = i + 1;
+        let mut entry = b.entry(csv_field(record, name), "accounts");
+        let folder: Vec<&str> = csv_field(record, grouping).split('\\').collect();
+        entry.folder_id = b.folder(&folder);
+        let url = csv_field(record, url).trim();
+        let extra = csv_field(record, extra);
+        // Secure notes are stored as entries with this placeholder URL
+        if url == "http://sn" {
+            lastpass_note(&mut entry, extra);
+        } else {
+            entry.username = csv_field(record, username).trim().to_string();
+            entry.password = csv_field(record, password).to_string();
+            entry.url = non_empty(url);
+            entry.notes = non_empty(extra);
+        }
+        b.push(row, entry, Some(csv_field(record, totp)));
+    }
+    Ok(())
+}
+
+/// Splits a LastPass secure note. Typed notes start with `NoteType:` followed
+/// by `Name:value` lines; everything after `Notes:` is free text.
+fn lastpass_note(entry: &mut PasswordEntry, extra: &str) {
+    entry.category = "notes".to_string();
+    let Some(rest) = extra.strip_prefix("NoteType:") else {
+        entry.notes = non_empty(extra);
+        return;
+    };
+    let (note_type, rest) = rest.split_once('\n').unwrap_or((rest, ""));
+    let mut fields: Vec<(String, String)> = Vec::new();
+    let mut lines = rest.lines();
+    while let Some(line) = lines.next() {
+        let (name, value) = line.split_once(':').unwrap_or((line, ""));
+        if name == "Notes" {
+            let notes: Vec<&str> = std::iter::once(value).chain(lines.by_ref()).collect();
+            entry.notes = non_empty(&notes.join("\n"));
+            break;
+        }
+        fields.push((name.trim().to_string(), value.trim().to_string()));
+    }
+    let mut take = |name: &str| {
+        fields
+            .iter()
+            .position(|(n, _)| n == name)
+            .and_then(|i| non_empty(&fields.remove(i).1))
+    };
+
+    match note_type.trim() {
+        "Credit Card" => {
+            let expiry = take("Expiration Date");
+            let card = CardItem {
+                card_number: take("Number"),
+                cvv: take("Security Code"),
+                cardholder_name: take("Name on Card"),
+                card_type: take("Type"),
+                expiry: expiry.as_deref().and_then(lastpass_expiry),
+                ..CardItem::default()
+            };
+            if card.expiry.is_none() {
+                if let Some(expiry) = expiry {
+                    fields.push(("Expiration Date".to_string(), expiry));
+                }
+            }
+            set_card(entry, card);
+        }
+        "Address" => {
+            let street: Vec<String> = ["Address 1", "Address 2", "Address 3"]
+                .into_iter()
+                .filter_map(&mut take)
+                .collect();
+            let address = AddressItem {
+                street: non_empty(&street.join("\n")),
+                city: take("City / Town"),
+                state: take("State"),
+                postal_code: take("Zip / Postal Code"),
+                country: take("Country"),
+                ..AddressItem::default()
+            };
+            entry.category = "addresses".to_string();
+            entry.data = Some(EntryData::Address(address));
+        }
+        other => add_field(entry, "Not türü", other),
+    }
+    for (name, value) in fields {
+        add_field(entry, &name, &value);
+    }
+}
+
+/// `January,2025` to `01/2025`.
+fn lastpass_expiry(value: &str) -> Option<String> {
+    const MONTHS: [&str; 12] = [
+        "january",
+        "february",
+        "march",
+        "april",
+        "may",
+        "june",
+        "july",
+        "august",
+        "september",
+        "october",
+        "november",
+        "december",
+    ];
+    let (month, year) = value.split_once(',')?;
+    let month = MONTHS
+        .iter()
+        .position(|m| m.eq_ignore_ascii_case(month.trim()))?;
+    let year = year.trim();
+    (year.len() == 4 && year.chars().all(|c| c.is_ascii_digit()))
+        .then(|| format!("{:02}/{}", month + 1, year))
+}
+
+/// Chrome and Edge write `name,url,username,password,note`, Firefox
+/// `url,username,password,httpRealm,...,timeCreated,...` with times in
+/// milliseconds, Safari `Title,URL,Username,Password,Notes,OTPAuth`.
+fn parse_browser_csv(b: &mut Builder, data: &[u8]) -> Result<(), String> {
+    let (headers, records) = read_csv(data)?;
+    let column = |names: &[&str]| csv_column(&headers, names);
+    let title = column(&["name", "title"]);
+    let url = column(&["url", "origin"]);
+    let username = column(&["username"]);
+    let password = column(&["password"]);
+    let notes = column(&["note", "notes"]);
+    let totp = column(&["otpauth"]);
+    let created = column(&["timecreated"]);
+    let changed = column(&["timepasswordchanged"]);
+    if url.is_none() || password.is_none() {
+        return Err("Tarayıcı CSV başlıkları bulunamadı (url, username, password)".to_string());
+    }
+    let millis = |record: &csv::StringRecord, column| {
+        csv_field(record, column)
+            .trim()
+            .parse::<i64>()
+            .ok()
+            .map(|ms| ms / 1000)
+    };
+
+    for (i, record) in records.iter().enumerate() {
+        let row = i + 1;
+        let mut entry = b.entry(csv_field(record, title), "accounts");
+        entry.url = non_empty(csv_field(record, url));
+        entry.username = csv_field(record, username).trim().to_string();
+        entry.password = csv_field(record, password).to_string();
+        entry.notes = non_empty(csv_field(record, notes));
+        if let Some(created) = millis(record, created) {
+            entry.created_at = created;
+        }
+        entry.updated_at = millis(record, changed).unwrap_or(entry.created_at);
+        b.push(row, entry, Some(csv_field(record, totp)));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    const NOW: i64 = 1_700_000_000;
+
+    fn by_title<'a>(preview: &'a ImportPreview, title: &str, category: &str) -> &'a PasswordEntry {
+        preview
+            .entries
+            .iter()
+            .find(|e| e.title == title && e.category == category)
+            .unwrap_or_else(|| panic!("{} ({}) bulunamadı", title, category))
+    }
+
+    #[test]
+    fn lastpass_and_browser_csv() {
+        let csv = "url,username,password,totp,extra,name,grouping,fav\n\
+            https://mail.example.com,ayse,s3cret,JBSWY3DPEHPK3PXP,,Mail,İş\\Posta,0\n\
+            http://sn,,,,\"NoteType:Credit Card\nLanguage:en-US\nName on Card:Ayşe\nType:Visa\nNumber:4111 1111 1111 1111\nSecurity Code:123\nStart Date:,\nExpiration Date:March,2027\nNotes:ilk satır\nikinci satır\",Kart,,0\n\
+            https://empty.example.com,,,,,Boş,,0\n";
+        let preview = parse(ImportFormat::LastpassCsv, csv.as_bytes(), NOW).unwrap();
+
+        let mail = by_title(&preview, "Mail", "accounts");
+        let folder = preview
+            .folders
+            .iter()
+            .find(|f| Some(&f.id) == mail.folder_id.as_ref());
+        let folder = folder.unwrap();
+        assert_eq!(folder.name, "Posta");
+        let parent = preview
+            .folders
+            .iter()
+            .find(|f| Some(&f.id) == folder.parent_id.as_ref());
+        assert_eq!(parent.unwrap().name, "İş");
+        let auth = by_title(&preview, "Mail", "authenticator");
+        assert_eq!(auth.username, "ayse");
+        assert!(matches!(&auth.data, Some(EntryData::Totp(t)) if t.secret == "JBSWY3DPEHPK3PXP"));
+
+        let card = by_title(&preview, "Kart", "bank_cards");
+        assert_eq!(card.username, "4111111111111111");
+        assert_eq!(card.notes.as_deref(), Some("ilk satır\nikinci satır"));
+        match &card.data {
+            Some(EntryData::Card(c)) => assert_eq!(c.expiry.as_deref(), Some("03/2027")),
+            other => panic!("{:?}", other),
+        }
+        assert_eq!(
+            by_title(&preview, "Boş", "notes").url.as_deref(),
+            Some("https://empty.example.com")
+        );
+        assert_eq!(preview.warnings.len(), 1);
+
+        let firefox = "url,username,password,httpRealm,formActionOrigin,guid,timeCreated,timeLastUsed,timePasswordChanged\n\
+            https://shop.example.com,ali,pw,,,{guid},1600000000000,1600000000000,1650000000000\n";
+        let preview = parse(ImportFormat::BrowserCsv, firefox.as_bytes(), NOW).unwrap();
+        let entry = by_title(&preview, "shop.example.com", "accounts");
+        assert_eq!(
+            (entry.created_at, entry.updated_at),
+            (1_600_000_000, 1_650_000_000)
+        );
+    }
+
+    #[test]
+    fn bitwarden_import_merges_folders_and_tags_with_the_vault() {
+        let json = r#"{
+            "encrypted": false,
+            "folders": [{"id": "f1", "name": "Sosyal/Eski"}],
+            "collections": [{"id": "c1", "name": "Aile"}],
+            "items": [
+                {"type": 1, "name": "Forum", "folderId": "f1", "collectionIds": ["c1"],
+                 "notes": null, "revisionDate": "2024-01-02T00:00:00Z",
+                 "fields": [{"name": "PIN", "value": "1234", "type": 1}],
+                 "login": {"username": "ali", "password": "pw",
+                           "totp": "otpauth://totp/Forum:ali%40example.com?secret=JBSWY3DPEHPK3PXP&digits=8",
+                           "uris": [{"uri": "forum.example.com"}, {"uri": "androidapp://com.forum"}]}},
+                {"type": 3, "name": "Kart", "card": {"number": "12", "code": "1"}},
+                {"type": 9, "name": "Bilinmeyen"}
+            ]
+        }"#;
+        let preview = parse(ImportFormat::BitwardenJson, json.as_bytes(), NOW).unwrap();
+        assert_eq!(preview.skipped, 1);
+
+        let forum = by_title(&preview, "Forum", "accounts");
+        assert_eq!(forum.url.as_deref(), Some("https://forum.example.com"));
+        let fields = forum.extra_fields.as_ref().unwrap();
+        assert_eq!(fields["PIN"], "1234");
+        assert_eq!(fields["URL"], "androidapp://com.forum");
+        assert_eq!(forum.updated_at, 1_704_153_600);
+        match &by_title(&preview, "Forum", "authenticator").data {
+            Some(EntryData::Totp(t)) => {
+                assert_eq!(t.account.as_deref(), Some("ali@example.com"));
+                assert_eq!(t.digits, Some(8));
+            }
+            other => panic!("{:?}", other),
+        }
+        // An invalid card number keeps the card's fields in a note
+        let card = by_title(&preview, "Kart", "notes");
+        assert_eq!(card.extra_fields.as_ref().unwrap()["cardNumber"], "12");
+
+        let mut state = VaultState::default();
+        let first = apply(&mut state, preview, MergeStrategy::Skip, NOW).unwrap();
+        assert_eq!(
+            (first.imported, first.folders_created, first.tags_created),
+            (3, 2, 1)
+        );
+
+        // The login and its authenticator are found again; the note has
+        // nothing to match on
+        let again = parse(ImportFormat::BitwardenJson, json.as_bytes(), NOW).unwrap();
+        let second = apply(&mut state, again, MergeStrategy::Skip, NOW).unwrap();
+        assert_eq!(
+            (
+                second.imported,
+                second.skipped,
+                second.folders_created,
+                second.tags_created
+            ),
+            (1, 2, 0, 0)
+        );
+        assert_eq!(state.folders.len(), 2);
+        let folder_ids: Vec<_> = state
+            .entries
+            .values()
+            .filter_map(|e| e.folder_id.as_ref())
+            .collect();
+        assert!(folder_ids
+            .iter()
+            .all(|id| state.folders.iter().any(|f| &&f.id == id)));
+    }
+
+    #[test]
+    fn merge_strategies_resolve_duplicates() {
+        let csv = |password: &str| {
+            format!(
+                "name,url,username,password\nForum,https://www.forum.example.com/giris,ALI,{}\n",
+                password
+            )
+        };
+        let mut state = VaultState::default();
+        let import = |state: &mut VaultState, password: &str, now: i64, strategy| {
+            let preview = parse(ImportFormat::BrowserCsv, csv(password).as_bytes(), now).unwrap();
+            apply(state, preview, strategy, now).unwrap()
+        };
+        import(&mut state, "eski", NOW, MergeStrategy::Skip);
+        let id = state.entries.keys().next().unwrap().clone();
+
+        let skipped = import(&mut state, "yeni", NOW, MergeStrategy::PreferNewer);
+        assert_eq!(skipped.changes[0].action, ImportAction::Skipped);
+        assert_eq!(skipped.changes[0].duplicate_of.as_ref(), Some(&id));
+
+        let updated = import(&mut state, "yeni", NOW + 60, MergeStrategy::PreferNewer);
+        assert_eq!(
+            (
+                updated.updated,
+                updated.changes[0].action,
+                &updated.changes[0].id
+            ),
+            (1, ImportAction::Updated, &id)
+        );
+        assert_eq!(updated.changes[0].changed_fields, ["password"]);
+        assert_eq!(state.entries[&id].password, "yeni");
+        assert_eq!(state.password_history[&id][0].password, "eski");
+        assert_eq!(state.revisions[&id][0].source, RevisionSource::Import);
+
+        let kept = import(&mut state, "yeni", NOW, MergeStrategy::KeepBoth);
+        assert_eq!(kept.changes[0].action, ImportAction::KeptBoth);
+        assert_eq!(state.entries.len(), 2);
+    }
+
+    #[test]
+    fn keepass_xml_groups_totp_and_recycle_bin() {
+        let xml = r#"<?xml version="1.0" encoding="utf-8"?>
+            <KeePassFile>
+              <Meta><RecycleBinUUID>BIN</RecycleBinUUID></Meta>
+              <Root><Group><UUID>ROOT</UUID><Name>Veritabanı</Name>
+                <Group><UUID>G1</UUID><Name>İnternet</Name>
+                  <Entry>
+                    <Tags>web;kişisel</Tags>
+                    <Times><CreationTime>2020-05-01T10:00:00Z</CreationTime></Times>
+                    <String><Key>Title</Key><Value>Site</Value></String>
+                    <String><Key>UserName</Key><Value>veli</Value></String>
+                    <String><Key>Password</Key><Value Protected="True">pw</Value></String>
+                    <String><Key>otp</Key><Value>otpauth://totp/Site:veli?secret=JBSWY3DPEHPK3PXP&amp;issuer=Site</Value></String>
+                    <String><Key>Güvenlik sorusu</Key><Value>kedi</Value></String>
+                    <History><Entry><String><Key>Title</Key><Value>Eski</Value></String></Entry></History>
+                  </Entry>
+                </Group>
+                <Group><UUID>BIN</UUID><Name>Geri Dönüşüm Kutusu</Name>
+                  <Entry>
+                    <String><Key>Title</Key><Value>Silinen</Value></String>
+                    <String><Key>UserName</Key><Value>x</Value></String>
+                    <String><Key>Password</Key><Value>y</Value></String>
+                  </Entry>
+                </Group>
+              </Group></Root>
+            </KeePassFile>"#;
+        let preview = parse(ImportFormat::KeepassXml, xml.as_bytes(), NOW).unwrap();
+        assert_eq!(preview.entries.len(), 3);
+        assert_eq!(preview.folders.len(), 1);
+        assert_eq!(preview.tags.len(), 2);
+
+        let site = by_title(&preview, "Site", "accounts");
+        assert_eq!(site.created_at, 1_588_327_200);
+        assert_eq!(
+            site.extra_fields.as_ref().unwrap()["Güvenlik sorusu"],
+            "kedi"
+        );
+        assert_eq!(site.folder_id.as_ref(), Some(&preview.folders[0].id));
+        let auth = by_title(&preview, "Site", "authenticator");
+        assert_eq!(auth.tags, site.tags);
+
+        let deleted = by_title(&preview, "Silinen", "accounts");
+        assert_eq!(
+            (deleted.deleted_at, deleted.folder_id.as_ref()),
+            (Some(NOW), None)
+        );
+        assert_eq!(keepass_time("AAAAAAAAAAA="), Some(-KEEPASS_EPOCH_OFFSET));
+    }
+
+    #[test]
+    fn onepassword_pux_reads_vaults_and_sections() {
+        let data = serde_json::json!({"accounts": [{"vaults": [{
+            "attrs": {"name": "Kişisel"},
+            "items": [
+                {"categoryUuid": "002", "createdAt": 1600000000, "updatedAt": 1600000100,
+                 "overview": {"title": "Banka", "tags": ["finans"]},
+                 "details": {"sections": [{"fields": [
+                    {"id": "ccnum", "title": "Kart numarası", "value": {"creditCardNumber": "5555555555554444"}},
+                    {"id": "cvv", "title": "CVV", "value": {"concealed": "321"}},
+                    {"id": "expiry", "title": "Son kullanma", "value": {"monthYear": 202811}},
+                    {"id": "pin", "title": "PIN", "value": {"concealed": "0000"}}
+                 ]}]}},
+                {"categoryUuid": "001", "state": "archived",
+                 "overview": {"title": "Eski hesap", "url": "https://old.example.com"},
+                 "details": {"loginFields": [
+                    {"designation": "username", "value": "zeynep"},
+                    {"designation": "password", "value": "pw"}
+                 ]}}
+            ]
+        }]}]});
+        let mut archive = Vec::new();
+        {
+            let mut zip = zip::ZipWriter::new(std::io::Cursor::new(&mut archive));
+            let options = zip::write::SimpleFileOptions::default()
+                .compression_method(zip::CompressionMethod::Stored);
+            zip.start_file("export.data", options).unwrap();
+            zip.write_all(data.to_string().as_bytes()).unwrap();
+            zip.finish().unwrap();
+        }
+
+        let preview = parse(ImportFormat::OnePasswordPux, &archive, NOW).unwrap();
+        assert_eq!(preview.folders.len(), 1);
+        let card = by_title(&preview, "Banka", "bank_cards");
+        assert_eq!(
+            (card.username.as_str(), card.password.as_str()),
+            ("5555555555554444", "321")
+        );
+        assert_eq!(card.extra_fields.as_ref().unwrap()["PIN"], "0000");
+        match &card.data {
+            Some(EntryData::Card(c)) => assert_eq!(c.expiry.as_deref(), Some("11/2028")),
+            other => panic!("{:?}", other),
+        }
+        let archived = by_title(&preview, "Eski hesap", "accounts");
+        assert_eq!(archived.deleted_at, Some(NOW));
+        assert!(parse(ImportFormat::OnePasswordPux, b"not a zip", NOW).is_err());
+    }
+}
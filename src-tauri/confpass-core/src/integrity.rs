@@ -0,0 +1,534 @@
+//! Integrity check and repair of the vault on disk.
+//!
+//! [`verify_vault`] reads the vault without changing anything: the container
+//! header (or the salt file of a v0 vault), decryption, the JSON schema and the
+//! references between entries, folders, attachment files and passkeys. Each
+//! problem becomes an [`IntegrityIssue`]. [`repair_vault`] backs the vault up,
+//! fixes the issues marked repairable and checks again; a vault that cannot be
+//! decrypted or parsed can only be restored from a backup.
+
+use serde::Serialize;
+use std::collections::HashSet;
+use std::fs;
+use std::path::Path;
+
+use crate::attachments;
+use crate::backups;
+use crate::container;
+use crate::models::{VaultData, VaultState};
+use crate::passkeys::{self, PASSKEYS_FILE_NAME, PASSKEY_CATEGORY};
+use crate::storage;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum IssueKind {
+    MissingVaultFile,
+    InvalidHeader,
+    MissingSalt,
+    DecryptFailed,
+    InvalidJson,
+    SchemaMismatch,
+    MissingFolder,
+    MissingParentFolder,
+    MissingAttachmentFile,
+    OrphanAttachmentFile,
+    OrphanHistory,
+    PlaintextPasskeys,
+    PasskeyWithoutEntry,
+    PasskeyEntryWithoutKey,
+}
+
+impl IssueKind {
+    /// Whether the vault could not be read at all; nothing else is checked then.
+    pub fn is_fatal(self) -> bool {
+        matches!(
+            self,
+            IssueKind::MissingVaultFile
+                | IssueKind::InvalidHeader
+                | IssueKind::MissingSalt
+                | IssueKind::DecryptFailed
+                | IssueKind::InvalidJson
+                | IssueKind::SchemaMismatch
+        )
+    }
+
+    /// Whether [`repair_vault`] can fix the issue without losing data.
+    pub fn is_repairable(self) -> bool {
+        matches!(
+            self,
+            IssueKind::MissingFolder
+                | IssueKind::MissingParentFolder
+                | IssueKind::MissingAttachmentFile
+                | IssueKind::OrphanAttachmentFile
+                | IssueKind::OrphanHistory
+                | IssueKind::PlaintextPasskeys
+                | IssueKind::PasskeyWithoutEntry
+        )
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct IntegrityIssue {
+    pub kind: IssueKind,
+    pub message: String,
+    /// Entry, folder, attachment or credential the issue is about.
+    pub subject: Option<String>,
+    pub repairable: bool,
+}
+
+impl IntegrityIssue {
+    fn new(kind: IssueKind, message: impl Into<String>, subject: Option<&str>) -> Self {
+        Self {
+            kind,
+            message: message.into(),
+            subject: subject.map(str::to_string),
+            repairable: kind.is_repairable(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct IntegrityReport {
+    /// Problems found, after repairs when the report comes from [`repair_vault`].
+    pub issues: Vec<IntegrityIssue>,
+    /// Problems [`repair_vault`] fixed.
+    pub repaired: Vec<IntegrityIssue>,
+    /// Backup taken before repairing.
+    pub backup_id: Option<String>,
+}
+
+impl IntegrityReport {
+    pub fn is_healthy(&self) -> bool {
+        self.issues.is_empty()
+    }
+
+    pub fn is_fatal(&self) -> bool {
+        self.issues.iter().any(|issue| issue.kind.is_fatal())
+    }
+
+    fn fatal(kind: IssueKind, message: impl Into<String>) -> Self {
+        Self {
+            issues: vec![IntegrityIssue::new(kind, message, None)],
+            ..Self::default()
+        }
+    }
+}
+
+/// Decrypts and parses the vault at `vault_path` without migrating or saving
+/// it, so a damaged file is left exactly as it was.
+fn read_vault_data(vault_path: &Path, master_password: &str) -> Result<VaultData, IntegrityReport> {
+    if !vault_path.exists() {
+        return Err(IntegrityReport::fatal(
+            IssueKind::MissingVaultFile,
+            "Vault dosyası bulunamadı",
+        ));
+    }
+    let raw = fs::read(vault_path).map_err(|e| {
+        IntegrityReport::fatal(
+            IssueKind::MissingVaultFile,
+            format!("Dosya okuma hatası: {}", e),
+        )
+    })?;
+
+    let plaintext = if container::is_container(&raw) {
+        if let Err(e) = container::ContainerHeader::parse(&raw) {
+            return Err(IntegrityReport::fatal(IssueKind::InvalidHeader, e));
+        }
+        container::open(&raw, master_password)
+            .map_err(|e| {
+                IntegrityReport::fatal(
+                    IssueKind::DecryptFailed,
+                    format!(
+                        "Şifre çözülemedi (ana şifre yanlış veya dosya bozuk): {}",
+                        e
+                    ),
+                )
+            })?
+            .plaintext
+    } else {
+        if !vault_path.with_file_name(storage::SALT_FILE_NAME).exists() {
+            return Err(IntegrityReport::fatal(
+                IssueKind::MissingSalt,
+                "Eski formattaki kasanın salt dosyası bulunamadı",
+            ));
+        }
+        storage::decrypt_legacy_vault(vault_path, &raw, master_password)
+            .map_err(|e| IntegrityReport::fatal(IssueKind::DecryptFailed, e))?
+            .into_bytes()
+    };
+
+    let value: serde_json::Value = serde_json::from_slice(&plaintext).map_err(|e| {
+        IntegrityReport::fatal(IssueKind::InvalidJson, format!("JSON parse hatası: {}", e))
+    })?;
+    serde_json::from_value(value).map_err(|e| {
+        IntegrityReport::fatal(
+            IssueKind::SchemaMismatch,
+            format!("Kasa verisi beklenen yapıda değil: {}", e),
+        )
+    })
+}
+
+/// Reference problems in a decrypted vault.
+pub fn check_state(vault_path: &Path, state: &VaultState) -> Result<Vec<IntegrityIssue>, String> {
+    let mut issues = Vec::new();
+
+    let folder_ids: HashSet<&str> = state.folders.iter().map(|f| f.id.as_str()).collect();
+    for folder in &state.folders {
+        if let Some(parent) = folder.parent_id.as_deref() {
+            if !folder_ids.contains(parent) {
+                issues.push(IntegrityIssue::new(
+                    IssueKind::MissingParentFolder,
+                    format!("\"{}\" klasörünün üst klasörü yok", folder.name),
+                    Some(&folder.id),
+                ));
+            }
+        }
+    }
+
+    let attachments_dir = attachments::attachments_dir(vault_path)?;
+    let mut referenced = HashSet::new();
+    let mut entries: Vec<_> = state.entries.values().collect();
+    entries.sort_by(|a, b| a.id.cmp(&b.id));
+    for entry in entries {
+        if let Some(folder) = entry.folder_id.as_deref() {
+            if !folder_ids.contains(folder) {
+                issues.push(IntegrityIssue::new(
+                    IssueKind::MissingFolder,
+                    format!("\"{}\" kaydının klasörü yok", entry.title),
+                    Some(&entry.id),
+                ));
+            }
+        }
+        for attachment in entry.attachments.iter().flatten() {
+            referenced.insert(attachment.id.clone());
+            if !attachments::attachment_path(&attachments_dir, &attachment.id).exists() {
+                issues.push(IntegrityIssue::new(
+                    IssueKind::MissingAttachmentFile,
+                    format!(
+                        "\"{}\" kaydının \"{}\" eki diskte yok",
+                        entry.title, attachment.filename
+                    ),
+                    Some(&attachment.id),
+                ));
+            }
+        }
+        if entry.category == PASSKEY_CATEGORY {
+            if let Some(id) = passkeys::credential_id(entry) {
+                if !state.passkeys.iter().any(|p| p.credential_id == id) {
+                    issues.push(IntegrityIssue::new(
+                        IssueKind::PasskeyEntryWithoutKey,
+                        format!("\"{}\" geçiş anahtarının özel anahtarı yok", entry.title),
+                        Some(&entry.id),
+                    ));
+                }
+            }
+        }
+    }
+
+    for (id, _) in attachments::list_attachment_files(&attachments_dir)? {
+        if !referenced.contains(&id) {
+            issues.push(IntegrityIssue::new(
+                IssueKind::OrphanAttachmentFile,
+                "Hiçbir kayda ait olmayan ek dosyası",
+                Some(&id),
+            ));
+        }
+    }
+
+    let orphan_history = state
+        .password_history
+        .keys()
+        .chain(state.revisions.keys())
+        .filter(|id| !state.entries.contains_key(*id))
+        .collect::<HashSet<_>>();
+    let mut orphan_history: Vec<_> = orphan_history.into_iter().collect();
+    orphan_history.sort();
+    for id in orphan_history {
+        issues.push(IntegrityIssue::new(
+            IssueKind::OrphanHistory,
+            "Silinmiş bir kayda ait geçmiş",
+            Some(id),
+        ));
+    }
+
+    let retained: HashSet<String> = passkeys::retained_passkeys(state)
+        .into_iter()
+        .map(|p| p.credential_id)
+        .collect();
+    for passkey in state
+        .passkeys
+        .iter()
+        .filter(|p| !retained.contains(&p.credential_id))
+    {
+        issues.push(IntegrityIssue::new(
+            IssueKind::PasskeyWithoutEntry,
+            format!("{} geçiş anahtarının kaydı yok", passkey.rp_id),
+            Some(&passkey.credential_id),
+        ));
+    }
+
+    if vault_path.with_file_name(PASSKEYS_FILE_NAME).exists() {
+        issues.push(IntegrityIssue::new(
+            IssueKind::PlaintextPasskeys,
+            "Şifrelenmemiş passkeys.json dosyası kasaya taşınmamış",
+            None,
+        ));
+    }
+
+    Ok(issues)
+}
+
+/// Checks the vault at `vault_path` without changing it.
+pub fn verify_vault(vault_path: &Path, master_password: &str) -> Result<IntegrityReport, String> {
+    let vault_data = match read_vault_data(vault_path, master_password) {
+        Ok(vault_data) => vault_data,
+        Err(report) => return Ok(report),
+    };
+
+    Ok(IntegrityReport {
+        issues: check_state(vault_path, &storage::vault_state(vault_data))?,
+        ..IntegrityReport::default()
+    })
+}
+
+/// Fixes the repairable issues in `state` that live inside the vault. Returns
+/// whether the state changed.
+fn repair_state(state: &mut VaultState, issues: &[IntegrityIssue], now: i64) -> bool {
+    let mut changed = false;
+    let folder_ids: HashSet<String> = state.folders.iter().map(|f| f.id.clone()).collect();
+    let missing_files: HashSet<&str> = issues
+        .iter()
+        .filter(|issue| issue.kind == IssueKind::MissingAttachmentFile)
+        .filter_map(|issue| issue.subject.as_deref())
+        .collect();
+
+    for folder in &mut state.folders {
+        if folder
+            .parent_id
+            .as_ref()
+            .is_some_and(|parent| !folder_ids.contains(parent))
+        {
+            folder.parent_id = None;
+            changed = true;
+        }
+    }
+
+    for entry in state.entries.values_mut() {
+        if entry
+            .folder_id
+            .as_ref()
+            .is_some_and(|folder| !folder_ids.contains(folder))
+        {
+            entry.folder_id = None;
+            changed = true;
+        }
+        if let Some(list) = entry.attachments.as_mut() {
+            let before = list.len();
+            list.retain(|a| !missing_files.contains(a.id.as_str()));
+            changed |= list.len() != before;
+            if list.is_empty() {
+                entry.attachments = None;
+            }
+        }
+    }
+
+    for issue in issues {
+        if issue.kind != IssueKind::PasskeyWithoutEntry {
+            continue;
+        }
+        let passkey = state
+            .passkeys
+            .iter()
+            .find(|p| issue.subject.as_deref() == Some(p.credential_id.as_str()));
+        if let Some(passkey) = passkey {
+            let entry = passkeys::passkey_entry(passkey, now);
+            state.entries.insert(entry.id.clone(), entry);
+            changed = true;
+        }
+    }
+
+    // Orphaned history and revisions are left out by the next save
+    changed || issues.iter().any(|i| i.kind == IssueKind::OrphanHistory)
+}
+
+/// Backs up the vault, fixes every repairable issue and checks it again.
+/// A vault with a fatal issue is not touched; see [`backups::restore_backup`].
+pub fn repair_vault(
+    vault_path: &Path,
+    master_password: &str,
+    now: i64,
+) -> Result<IntegrityReport, String> {
+    let before = verify_vault(vault_path, master_password)?;
+    if before.is_fatal() || !before.issues.iter().any(|issue| issue.repairable) {
+        return Ok(before);
+    }
+
+    let backup = backups::create_backup(vault_path, now)
+        .map_err(|e| format!("Onarımdan önce yedek alınamadı: {}", e))?;
+
+    let mut state = storage::load_vault_from_disk(vault_path, master_password)?;
+    if repair_state(&mut state, &before.issues, now) {
+        storage::save_vault_to_disk(vault_path, &state)?;
+    }
+
+    let attachments_dir = attachments::attachments_dir(vault_path)?;
+    for issue in &before.issues {
+        match (issue.kind, issue.subject.as_deref()) {
+            (IssueKind::OrphanAttachmentFile, Some(id)) => {
+                fs::remove_file(attachments::attachment_path(&attachments_dir, id))
+                    .map_err(|e| format!("Ek silinemedi: {}", e))?;
+            }
+            (IssueKind::PlaintextPasskeys, _) => {
+                passkeys::migrate_plaintext_passkeys(
+                    &vault_path.with_file_name(PASSKEYS_FILE_NAME),
+                    vault_path,
+                    &mut state,
+                    now,
+                )?;
+            }
+            _ => {}
+        }
+    }
+
+    let after = verify_vault(vault_path, master_password)?;
+    let repaired = before
+        .issues
+        .into_iter()
+        .filter(|issue| issue.repairable && !after.issues.contains(issue))
+        .collect();
+
+    Ok(IntegrityReport {
+        issues: after.issues,
+        repaired,
+        backup_id: Some(backup.id),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{FileAttachment, Folder, PasswordEntry, PasswordHistoryItem};
+    use crate::storage::{init_keys, save_vault_to_disk, VAULT_FILE_NAME};
+
+    const PASSWORD: &str = "ana-sifre-123";
+
+    fn entry(id: &str) -> PasswordEntry {
+        PasswordEntry {
+            id: id.to_string(),
+            title: "Örnek".to_string(),
+            username: "kullanici".to_string(),
+            password: "parola123".to_string(),
+            url: None,
+            notes: None,
+            created_at: 0,
+            updated_at: 0,
+            category: "accounts".to_string(),
+            folder_id: None,
+            tags: None,
+            extra_fields: None,
+            attachments: None,
+            data: None,
+            deleted_at: None,
+        }
+    }
+
+    fn attachment(id: &str) -> FileAttachment {
+        FileAttachment {
+            id: id.to_string(),
+            filename: format!("{}.txt", id),
+            mime_type: "text/plain".to_string(),
+            size: 1,
+            created_at: 0,
+        }
+    }
+
+    fn kinds(report: &IntegrityReport) -> Vec<IssueKind> {
+        report.issues.iter().map(|issue| issue.kind).collect()
+    }
+
+    #[test]
+    fn unreadable_vaults_are_reported_without_changes() {
+        let dir = tempfile::tempdir().unwrap();
+        let vault_path = dir.path().join(VAULT_FILE_NAME);
+
+        let report = verify_vault(&vault_path, PASSWORD).unwrap();
+        assert_eq!(kinds(&report), vec![IssueKind::MissingVaultFile]);
+
+        // A v0 vault whose salt file is gone must not load as an empty vault
+        fs::write(&vault_path, "bGVnYWN5").unwrap();
+        let report = repair_vault(&vault_path, PASSWORD, 0).unwrap();
+        assert_eq!(kinds(&report), vec![IssueKind::MissingSalt]);
+        assert!(storage::load_vault_from_disk(&vault_path, PASSWORD).is_err());
+        assert_eq!(fs::read(&vault_path).unwrap(), b"bGVnYWN5");
+
+        let mut state = VaultState {
+            master_password_hash: Some("hash".to_string()),
+            ..VaultState::default()
+        };
+        init_keys(&mut state, PASSWORD).unwrap();
+        save_vault_to_disk(&vault_path, &state).unwrap();
+        let report = verify_vault(&vault_path, "yanlis-sifre").unwrap();
+        assert_eq!(kinds(&report), vec![IssueKind::DecryptFailed]);
+        assert!(verify_vault(&vault_path, PASSWORD).unwrap().is_healthy());
+    }
+
+    #[test]
+    fn dangling_references_are_repaired() {
+        let dir = tempfile::tempdir().unwrap();
+        let vault_path = dir.path().join(VAULT_FILE_NAME);
+        let attachments_dir = attachments::attachments_dir(&vault_path).unwrap();
+        fs::create_dir_all(&attachments_dir).unwrap();
+
+        let mut state = VaultState {
+            master_password_hash: Some("hash".to_string()),
+            ..VaultState::default()
+        };
+        init_keys(&mut state, PASSWORD).unwrap();
+        let mut e1 = entry("e1");
+        e1.folder_id = Some("yok".to_string());
+        e1.attachments = Some(vec![attachment("a1"), attachment("a2")]);
+        state.entries.insert("e1".to_string(), e1);
+        state.folders.push(Folder {
+            id: "f1".to_string(),
+            name: "İş".to_string(),
+            color: String::new(),
+            icon: String::new(),
+            parent_id: Some("yok".to_string()),
+            created_at: 0,
+            order: 0,
+        });
+        state.password_history.insert(
+            "silinmis".to_string(),
+            vec![PasswordHistoryItem {
+                password: "eski".to_string(),
+                changed_at: 0,
+            }],
+        );
+        save_vault_to_disk(&vault_path, &state).unwrap();
+        fs::write(attachments::attachment_path(&attachments_dir, "a1"), "1").unwrap();
+        fs::write(attachments::attachment_path(&attachments_dir, "yetim"), "2").unwrap();
+
+        let report = verify_vault(&vault_path, PASSWORD).unwrap();
+        assert_eq!(
+            kinds(&report),
+            vec![
+                IssueKind::MissingParentFolder,
+                IssueKind::MissingFolder,
+                IssueKind::MissingAttachmentFile,
+                IssueKind::OrphanAttachmentFile,
+            ]
+        );
+
+        let report = repair_vault(&vault_path, PASSWORD, 100).unwrap();
+        assert!(report.is_healthy(), "{:?}", report.issues);
+        assert_eq!(report.repaired.len(), 4);
+        assert!(report.backup_id.is_some());
+
+        let repaired = storage::load_vault_from_disk(&vault_path, PASSWORD).unwrap();
+        let e1 = &repaired.entries["e1"];
+        assert_eq!(e1.folder_id, None);
+        assert_eq!(e1.attachments.as_ref().unwrap().len(), 1);
+        assert_eq!(repaired.folders[0].parent_id, None);
+        assert!(!attachments::attachment_path(&attachments_dir, "yetim").exists());
+    }
+}
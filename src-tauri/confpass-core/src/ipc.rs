@@ -0,0 +1,137 @@
+//! Local channel between the native messaging host and the app.
+//!
+//! The app listens on a Unix domain socket in the data directory, or on a
+//! named pipe on Windows, and serves only processes running as the same user.
+//! Any user can create a pipe under a given name, so on Windows the native
+//! host also checks that the server runs as its user before sending a token.
+//! Messages are framed like native messaging itself: a little-endian `u32`
+//! length, then that many bytes of JSON. A request names one of the routes of
+//! the local HTTP API and the reply carries the status and body that route
+//! returned, so both transports share one set of handlers.
+
+use serde::{Deserialize, Serialize};
+use std::io::{ErrorKind, Read, Write};
+use std::path::{Path, PathBuf};
+
+pub const SOCKET_FILE_NAME: &str = "confpass.sock";
+/// Largest frame either side accepts.
+pub const MAX_FRAME_LEN: usize = 16 * 1024 * 1024;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct IpcRequest {
+    /// Route name without the leading slash, e.g. `get_password`
+    pub endpoint: String,
+    #[serde(default)]
+    pub token: Option<String>,
+    #[serde(default)]
+    pub body: serde_json::Value,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct IpcResponse {
+    pub status: u16,
+    pub body: serde_json::Value,
+}
+
+/// Where the app listens: `confpass.sock` in `data_dir`, or on Windows a
+/// pipe named after the user, since pipes do not live in the file system.
+pub fn channel_path(data_dir: &Path) -> Result<PathBuf, String> {
+    if cfg!(windows) {
+        let user: String = std::env::var("USERNAME")
+            .unwrap_or_default()
+            .chars()
+            .filter(|c| c.is_ascii_alphanumeric() || *c == '-' || *c == '_')
+            .collect();
+        // Every user without a name would share one pipe
+        if user.is_empty() {
+            return Err("IPC kanalı için kullanıcı adı bulunamadı".to_string());
+        }
+        Ok(PathBuf::from(format!(r"\\.\pipe\confpass-{}", user)))
+    } else {
+        Ok(data_dir.join(SOCKET_FILE_NAME))
+    }
+}
+
+/// Whether `endpoint` can be a route name; anything else is refused before
+/// it is turned into a request path.
+pub fn is_valid_endpoint(endpoint: &str) -> bool {
+    !endpoint.is_empty()
+        && endpoint.len() <= 64
+        && endpoint
+            .bytes()
+            .all(|b| b.is_ascii_lowercase() || b.is_ascii_digit() || b == b'_')
+}
+
+/// Reads one frame. `None` when the peer closed the channel between frames.
+pub fn read_frame(reader: &mut impl Read) -> Result<Option<Vec<u8>>, String> {
+    let mut len = [0u8; 4];
+    match reader.read_exact(&mut len) {
+        Ok(()) => {}
+        Err(e) if e.kind() == ErrorKind::UnexpectedEof => return Ok(None),
+        Err(e) => return Err(format!("IPC okuma hatası: {}", e)),
+    }
+    let len = u32::from_le_bytes(len) as usize;
+    if len > MAX_FRAME_LEN {
+        return Err(format!("IPC mesajı çok büyük: {} bayt", len));
+    }
+    let mut frame = vec![0u8; len];
+    reader
+        .read_exact(&mut frame)
+        .map_err(|e| format!("IPC okuma hatası: {}", e))?;
+    Ok(Some(frame))
+}
+
+pub fn write_frame(writer: &mut impl Write, frame: &[u8]) -> Result<(), String> {
+    if frame.len() > MAX_FRAME_LEN {
+        return Err(format!("IPC mesajı çok büyük: {} bayt", frame.len()));
+    }
+    writer
+        .write_all(&(frame.len() as u32).to_le_bytes())
+        .and_then(|_| writer.write_all(frame))
+        .and_then(|_| writer.flush())
+        .map_err(|e| format!("IPC yazma hatası: {}", e))
+}
+
+/// Sends `request` over `stream` and waits for the reply.
+pub fn call(stream: &mut (impl Read + Write), request: &IpcRequest) -> Result<IpcResponse, String> {
+    let frame = serde_json::to_vec(request).map_err(|e| format!("JSON serialize hatası: {}", e))?;
+    write_frame(stream, &frame)?;
+    let reply = read_frame(stream)?.ok_or_else(|| "IPC bağlantısı kapandı".to_string())?;
+    serde_json::from_slice(&reply).map_err(|e| format!("IPC yanıtı okunamadı: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    #[test]
+    fn frames_round_trip_and_oversized_ones_are_refused() {
+        let request = IpcRequest {
+            endpoint: "get_password".to_string(),
+            token: Some("cpt_x".to_string()),
+            body: serde_json::json!({ "url": "https://example.com" }),
+        };
+        let mut buffer = Vec::new();
+        write_frame(&mut buffer, &serde_json::to_vec(&request).unwrap()).unwrap();
+        write_frame(&mut buffer, b"{}").unwrap();
+
+        let mut reader = Cursor::new(buffer);
+        let frame = read_frame(&mut reader).unwrap().unwrap();
+        assert_eq!(
+            serde_json::from_slice::<IpcRequest>(&frame).unwrap(),
+            request
+        );
+        assert_eq!(read_frame(&mut reader).unwrap().unwrap(), b"{}");
+        assert_eq!(read_frame(&mut reader).unwrap(), None);
+
+        let mut truncated = Cursor::new(vec![10, 0, 0, 0, b'{']);
+        assert!(read_frame(&mut truncated).is_err());
+        let mut oversized = Cursor::new(((MAX_FRAME_LEN + 1) as u32).to_le_bytes().to_vec());
+        assert!(read_frame(&mut oversized).is_err());
+
+        assert!(is_valid_endpoint("pair_status"));
+        assert!(!is_valid_endpoint("../get_password"));
+        assert!(!is_valid_endpoint("get_password?x=1"));
+    }
+}
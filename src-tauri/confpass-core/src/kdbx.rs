@@ -0,0 +1,1136 @@
+//! KeePass KDBX 4 databases.
+//!
+//! [`read`] decrypts a database and hands its XML to the KeePass importer, so
+//! groups, tags, TOTP and custom fields map exactly as for an XML export and
+//! binaries come back as attachments. [`write`] produces a KDBX 4.0 file that
+//! KeePass and KeePassXC open directly: folders become groups, trashed entries
+//! go to the recycle bin, authenticators carry an `otp` field and attachments
+//! are stored as binaries. Categories KeePass has no notion of keep their
+//! typed fields in the entry's custom data, so a round trip restores them.
+//!
+//! Reading accepts AES-256 and ChaCha20 payloads keyed with Argon2d, Argon2id
+//! or AES-KDF; writing uses AES-256 with Argon2id. Passkeys are not written,
+//! their private keys have no KeePass counterpart.
+
+use aes::cipher::block_padding::Pkcs7;
+use aes::cipher::{
+    BlockDecryptMut, BlockEncrypt, BlockEncryptMut, KeyInit, KeyIvInit, StreamCipher,
+};
+use base64::{engine::general_purpose, Engine as _};
+use hmac::{Hmac, Mac};
+use rand::RngCore;
+use sha2::{Digest, Sha256, Sha512};
+use std::collections::{HashMap, HashSet};
+use std::io::{Read, Write};
+use std::path::Path;
+use zeroize::{Zeroize, ZeroizeOnDrop};
+
+use crate::attachments;
+use crate::crypto::KdfParams;
+use crate::entry_data::{EntryData, TotpItem};
+use crate::error::VaultError;
+use crate::importers::{self, ImportPreview, KdbxPayload, KEEPASS_EPOCH_OFFSET};
+use crate::keys::DataKey;
+use crate::models::{Folder, PasswordEntry, VaultState};
+use crate::passkeys::PASSKEY_CATEGORY;
+use crate::trash::is_trashed;
+
+/// Custom data items our export adds to entries that are not plain logins.
+pub(crate) const CONFPASS_CATEGORY_KEY: &str = "ConfPass.Category";
+pub(crate) const CONFPASS_DATA_KEY: &str = "ConfPass.Data";
+
+const SIGNATURE_1: u32 = 0x9AA2_D903;
+const SIGNATURE_2: u32 = 0xB54B_FB67;
+const MAJOR_VERSION: u16 = 4;
+
+const CIPHER_AES256: [u8; 16] = [
+    0x31, 0xc1, 0xf2, 0xe6, 0xbf, 0x71, 0x43, 0x50, 0xbe, 0x58, 0x05, 0x21, 0x6a, 0xfc, 0x5a, 0xff,
+];
+const CIPHER_CHACHA20: [u8; 16] = [
+    0xd6, 0x03, 0x8a, 0x2b, 0x8b, 0x6f, 0x4c, 0xb5, 0xa5, 0x24, 0x33, 0x9a, 0x31, 0xdb, 0xb5, 0x9a,
+];
+const KDF_AES: [u8; 16] = [
+    0xc9, 0xd9, 0xf3, 0x9a, 0x62, 0x8a, 0x44, 0x60, 0xbf, 0x74, 0x0d, 0x08, 0xc1, 0x8a, 0x4f, 0xea,
+];
+const KDF_ARGON2D: [u8; 16] = [
+    0xef, 0x63, 0x6d, 0xdf, 0x8c, 0x29, 0x44, 0x4b, 0x91, 0xf7, 0xa9, 0xa4, 0x03, 0xe3, 0x0a, 0x0c,
+];
+const KDF_ARGON2ID: [u8; 16] = [
+    0x9e, 0x29, 0x8b, 0x19, 0x56, 0xdb, 0x47, 0x73, 0xb2, 0x3d, 0xfc, 0x3e, 0xc6, 0xf0, 0xa1, 0xe6,
+];
+
+// Upper limits for the KDF parameters of a file being read. KeePass
+// defaults sit well below the vault's own minimums, so only work is bounded.
+const MAX_AES_ROUNDS: u64 = 100_000_000;
+const MAX_ARGON2_MEMORY_KIB: u32 = 4 * 1024 * 1024;
+const MAX_ARGON2_ITERATIONS: u32 = 1_000;
+const MAX_ARGON2_PARALLELISM: u32 = 64;
+
+const FIELD_END: u8 = 0;
+const HEADER_CIPHER: u8 = 2;
+const HEADER_COMPRESSION: u8 = 3;
+const HEADER_MASTER_SEED: u8 = 4;
+const HEADER_IV: u8 = 7;
+const HEADER_KDF: u8 = 11;
+const INNER_STREAM_ID: u8 = 1;
+const INNER_STREAM_KEY: u8 = 2;
+const INNER_BINARY: u8 = 3;
+
+const COMPRESSION_GZIP: u32 = 1;
+const STREAM_CHACHA20: u32 = 3;
+const BLOCK_SIZE: usize = 1 << 20;
+const ROOT_GROUP_NAME: &str = "ConfPass";
+const RECYCLE_BIN_NAME: &str = "Geri Dönüşüm Kutusu";
+/// Keys KeePass gives its standard strings; custom fields must not reuse them.
+const RESERVED_KEYS: [&str; 6] = ["Title", "UserName", "Password", "URL", "Notes", "otp"];
+
+const CORRUPT: &str = "KDBX dosyası bozuk veya eksik";
+const KDF_TOO_COSTLY: &str = "KDBX anahtar türetme parametreleri desteklenen sınırları aşıyor";
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// Decrypts a KDBX 4 database with its password and optional key file.
+pub fn read(
+    data: &[u8],
+    password: &str,
+    keyfile: Option<&[u8]>,
+    now: i64,
+) -> Result<ImportPreview, String> {
+    let mut r = Reader { data, pos: 0 };
+    if r.u32().ok() != Some(SIGNATURE_1) || r.u32().ok() != Some(SIGNATURE_2) {
+        return Err("Dosya bir KeePass veritabanı değil".to_string());
+    }
+    let _minor = r.u16()?;
+    if r.u16()? != MAJOR_VERSION {
+        return Err(
+            "Yalnızca KDBX 4 veritabanları destekleniyor; dosyayı KeePass'te KDBX 4 olarak kaydedin"
+                .to_string(),
+        );
+    }
+    let fields = read_fields(&mut r)?;
+    let header = &data[..r.pos];
+    if Sha256::digest(header).as_slice() != r.take(32)? {
+        return Err(CORRUPT.to_string());
+    }
+    let header_mac = r.take(32)?;
+
+    let kdf = read_variants(field(&fields, HEADER_KDF).ok_or(CORRUPT)?)?;
+    let seed = field(&fields, HEADER_MASTER_SEED).ok_or(CORRUPT)?;
+    let keys = derive_keys(password, keyfile, seed, &kdf)?;
+    if !verify_mac(&keys.block_key(u64::MAX), &[header], header_mac) {
+        return Err("Şifre veya anahtar dosyası yanlış".to_string());
+    }
+
+    let mut payload = Vec::new();
+    for index in 0u64.. {
+        let mac = r.take(32)?;
+        let length = r.take(4)?;
+        let block = r.take(u32::from_le_bytes(length.try_into().unwrap()) as usize)?;
+        if !verify_mac(
+            &keys.block_key(index),
+            &[&index.to_le_bytes(), length, block],
+            mac,
+        ) {
+            return Err(CORRUPT.to_string());
+        }
+        if block.is_empty() {
+            break;
+        }
+        payload.extend_from_slice(block);
+    }
+
+    let iv = field(&fields, HEADER_IV).ok_or(CORRUPT)?;
+    let mut plain = match field(&fields, HEADER_CIPHER) {
+        Some(cipher) if cipher == CIPHER_AES256.as_slice() => {
+            cbc::Decryptor::<aes::Aes256>::new_from_slices(&keys.cipher, iv)
+                .map_err(|_| CORRUPT)?
+                .decrypt_padded_vec_mut::<Pkcs7>(&payload)
+                .map_err(|_| CORRUPT)?
+        }
+        Some(cipher) if cipher == CIPHER_CHACHA20.as_slice() => {
+            chacha20::ChaCha20::new_from_slices(&keys.cipher, iv)
+                .map_err(|_| CORRUPT)?
+                .apply_keystream(&mut payload);
+            payload
+        }
+        _ => return Err("Desteklenmeyen KDBX şifreleme algoritması".to_string()),
+    };
+    if field(&fields, HEADER_COMPRESSION) == Some(COMPRESSION_GZIP.to_le_bytes().as_slice()) {
+        let mut inflated = Vec::new();
+        flate2::read::GzDecoder::new(plain.as_slice())
+            .read_to_end(&mut inflated)
+            .map_err(|_| CORRUPT)?;
+        plain.zeroize();
+        plain = inflated;
+    }
+
+    let mut r = Reader {
+        data: &plain,
+        pos: 0,
+    };
+    let inner = read_fields(&mut r)?;
+    if field(&inner, INNER_STREAM_ID) != Some(STREAM_CHACHA20.to_le_bytes().as_slice()) {
+        return Err("Desteklenmeyen KDBX iç akış şifrelemesi".to_string());
+    }
+    let mut stream = inner_stream(field(&inner, INNER_STREAM_KEY).ok_or(CORRUPT)?);
+    // Each binary starts with a flags byte
+    let binaries: Vec<Vec<u8>> = inner
+        .iter()
+        .filter(|(id, _)| *id == INNER_BINARY)
+        .map(|(_, data)| data.get(1..).unwrap_or_default().to_vec())
+        .collect();
+    let xml = std::str::from_utf8(&plain[r.pos..]).map_err(|_| CORRUPT)?;
+
+    let preview = importers::parse_kdbx_xml(
+        xml,
+        KdbxPayload {
+            unprotect: &mut |bytes| stream.apply_keystream(bytes),
+            binaries: &binaries,
+        },
+        now,
+    );
+    plain.zeroize();
+    preview
+}
+
+/// Writes the vault as a KDBX 4 database protected by `password` and the
+/// optional key file. Attachment files are read from `attachments_dir` with
+/// the vault's data key. The Argon2id cost comes from `kdf`; a PBKDF2 vault
+/// uses the default cost instead.
+pub fn write(
+    state: &VaultState,
+    attachments_dir: &Path,
+    password: &str,
+    keyfile: Option<&[u8]>,
+    kdf: KdfParams,
+    now: i64,
+) -> Result<Vec<u8>, String> {
+    let data_key = &state
+        .keys
+        .as_ref()
+        .ok_or_else(|| VaultError::Locked.to_string())?
+        .data_key;
+    let kdf = if kdf.is_legacy() {
+        KdfParams::default()
+    } else {
+        kdf
+    };
+    let KdfParams::Argon2id {
+        memory_kib,
+        iterations,
+        parallelism,
+    } = kdf
+    else {
+        return Err("Desteklenmeyen KDF".to_string());
+    };
+
+    let mut rng = rand::rngs::OsRng;
+    let mut master_seed = [0u8; 32];
+    let mut iv = [0u8; 16];
+    let mut salt = [0u8; 32];
+    let mut stream_key = [0u8; 64];
+    rng.fill_bytes(&mut master_seed);
+    rng.fill_bytes(&mut iv);
+    rng.fill_bytes(&mut salt);
+    rng.fill_bytes(&mut stream_key);
+
+    let kdf_params = write_variants(&[
+        ("$UUID", Variant::Bytes(KDF_ARGON2ID.to_vec())),
+        ("S", Variant::Bytes(salt.to_vec())),
+        ("P", Variant::U32(parallelism)),
+        ("M", Variant::U64(u64::from(memory_kib) * 1024)),
+        ("I", Variant::U64(u64::from(iterations))),
+        ("V", Variant::U32(0x13)),
+    ]);
+    let keys = derive_keys(
+        password,
+        keyfile,
+        &master_seed,
+        &read_variants(&kdf_params)?,
+    )?;
+
+    let mut header = Vec::new();
+    header.extend_from_slice(&SIGNATURE_1.to_le_bytes());
+    header.extend_from_slice(&SIGNATURE_2.to_le_bytes());
+    header.extend_from_slice(&0u16.to_le_bytes());
+    header.extend_from_slice(&MAJOR_VERSION.to_le_bytes());
+    write_field(&mut header, HEADER_CIPHER, &CIPHER_AES256);
+    write_field(
+        &mut header,
+        HEADER_COMPRESSION,
+        &COMPRESSION_GZIP.to_le_bytes(),
+    );
+    write_field(&mut header, HEADER_MASTER_SEED, &master_seed);
+    write_field(&mut header, HEADER_IV, &iv);
+    write_field(&mut header, HEADER_KDF, &kdf_params);
+    write_field(&mut header, FIELD_END, b"\r\n\r\n");
+
+    let mut xml = XmlWriter {
+        state,
+        attachments_dir,
+        data_key,
+        out: String::new(),
+        stream: inner_stream(&stream_key),
+        binaries: Vec::new(),
+        now,
+    };
+    xml.document()?;
+
+    let mut inner = Vec::new();
+    write_field(&mut inner, INNER_STREAM_ID, &STREAM_CHACHA20.to_le_bytes());
+    write_field(&mut inner, INNER_STREAM_KEY, &stream_key);
+    for binary in &xml.binaries {
+        let mut data = vec![0u8];
+        data.extend_from_slice(binary);
+        write_field(&mut inner, INNER_BINARY, &data);
+        data.zeroize();
+    }
+    write_field(&mut inner, FIELD_END, &[]);
+    inner.extend_from_slice(xml.out.as_bytes());
+    xml.out.zeroize();
+    xml.binaries.iter_mut().for_each(|binary| binary.zeroize());
+
+    let mut gzip = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+    gzip.write_all(&inner)
+        .map_err(|e| format!("KDBX sıkıştırılamadı: {}", e))?;
+    inner.zeroize();
+    let mut compressed = gzip
+        .finish()
+        .map_err(|e| format!("KDBX sıkıştırılamadı: {}", e))?;
+    let encrypted = cbc::Encryptor::<aes::Aes256>::new_from_slices(&keys.cipher, &iv)
+        .map_err(|_| "KDBX şifrelenemedi".to_string())?
+        .encrypt_padded_vec_mut::<Pkcs7>(&compressed);
+    compressed.zeroize();
+
+    let mut out = header.clone();
+    out.extend_from_slice(&Sha256::digest(&header));
+    out.extend_from_slice(&mac(&keys.block_key(u64::MAX), &[&header]));
+    let blocks = encrypted.chunks(BLOCK_SIZE).chain([[].as_slice()]);
+    for (index, block) in (0u64..).zip(blocks) {
+        let length = (block.len() as u32).to_le_bytes();
+        out.extend_from_slice(&mac(
+            &keys.block_key(index),
+            &[&index.to_le_bytes(), &length, block],
+        ));
+        out.extend_from_slice(&length);
+        out.extend_from_slice(block);
+    }
+    Ok(out)
+}
+
+struct Reader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8], String> {
+        let end = self
+            .pos
+            .checked_add(len)
+            .filter(|end| *end <= self.data.len())
+            .ok_or(CORRUPT)?;
+        let bytes = &self.data[self.pos..end];
+        self.pos = end;
+        Ok(bytes)
+    }
+
+    fn u8(&mut self) -> Result<u8, String> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u16(&mut self) -> Result<u16, String> {
+        Ok(u16::from_le_bytes(self.take(2)?.try_into().unwrap()))
+    }
+
+    fn u32(&mut self) -> Result<u32, String> {
+        Ok(u32::from_le_bytes(self.take(4)?.try_into().unwrap()))
+    }
+}
+
+/// Reads `(id, length, data)` header fields up to the end marker.
+fn read_fields<'a>(r: &mut Reader<'a>) -> Result<Vec<(u8, &'a [u8])>, String> {
+    let mut fields = Vec::new();
+    loop {
+        let id = r.u8()?;
+        let len = r.u32()? as usize;
+        let data = r.take(len)?;
+        if id == FIELD_END {
+            return Ok(fields);
+        }
+        fields.push((id, data));
+    }
+}
+
+fn field<'a>(fields: &[(u8, &'a [u8])], id: u8) -> Option<&'a [u8]> {
+    fields.iter().find(|(i, _)| *i == id).map(|(_, data)| *data)
+}
+
+fn write_field(out: &mut Vec<u8>, id: u8, data: &[u8]) {
+    out.push(id);
+    out.extend_from_slice(&(data.len() as u32).to_le_bytes());
+    out.extend_from_slice(data);
+}
+
+/// Values of a KDBX variant dictionary, as far as KDF parameters use them.
+enum Variant {
+    U32(u32),
+    U64(u64),
+    Bytes(Vec<u8>),
+    Other,
+}
+
+impl Variant {
+    const TYPE_U32: u8 = 0x04;
+    const TYPE_U64: u8 = 0x05;
+    const TYPE_BYTES: u8 = 0x42;
+    const DICTIONARY_VERSION: u16 = 0x0100;
+}
+
+fn read_variants(data: &[u8]) -> Result<HashMap<String, Variant>, String> {
+    let mut r = Reader { data, pos: 0 };
+    if r.u16()? >> 8 != Variant::DICTIONARY_VERSION >> 8 {
+        return Err("Desteklenmeyen KDF parametreleri".to_string());
+    }
+    let mut variants = HashMap::new();
+    loop {
+        let kind = r.u8()?;
+        if kind == 0 {
+            return Ok(variants);
+        }
+        let name_len = r.u32()? as usize;
+        let name = String::from_utf8_lossy(r.take(name_len)?).into_owned();
+        let value_len = r.u32()? as usize;
+        let value = r.take(value_len)?;
+        let variant = match kind {
+            Variant::TYPE_U32 if value.len() == 4 => {
+                Variant::U32(u32::from_le_bytes(value.try_into().unwrap()))
+            }
+            Variant::TYPE_U64 if value.len() == 8 => {
+                Variant::U64(u64::from_le_bytes(value.try_into().unwrap()))
+            }
+            Variant::TYPE_BYTES => Variant::Bytes(value.to_vec()),
+            _ => Variant::Other,
+        };
+        variants.insert(name, variant);
+    }
+}
+
+fn write_variants(items: &[(&str, Variant)]) -> Vec<u8> {
+    let mut out = Variant::DICTIONARY_VERSION.to_le_bytes().to_vec();
+    for (name, variant) in items {
+        let (kind, value) = match variant {
+            Variant::U32(v) => (Variant::TYPE_U32, v.to_le_bytes().to_vec()),
+            Variant::U64(v) => (Variant::TYPE_U64, v.to_le_bytes().to_vec()),
+            Variant::Bytes(v) => (Variant::TYPE_BYTES, v.clone()),
+            Variant::Other => continue,
+        };
+        out.push(kind);
+        out.extend_from_slice(&(name.len() as u32).to_le_bytes());
+        out.extend_from_slice(name.as_bytes());
+        out.extend_from_slice(&(value.len() as u32).to_le_bytes());
+        out.extend_from_slice(&value);
+    }
+    out.push(0);
+    out
+}
+
+#[derive(Zeroize, ZeroizeOnDrop)]
+struct Keys {
+    cipher: [u8; 32],
+    hmac: [u8; 64],
+}
+
+impl Keys {
+    fn block_key(&self, index: u64) -> [u8; 64] {
+        Sha512::new()
+            .chain_update(index.to_le_bytes())
+            .chain_update(self.hmac)
+            .finalize()
+            .into()
+    }
+}
+
+fn derive_keys(
+    password: &str,
+    keyfile: Option<&[u8]>,
+    seed: &[u8],
+    kdf: &HashMap<String, Variant>,
+) -> Result<Keys, String> {
+    if password.is_empty() && keyfile.is_none() {
+        return Err("Şifre veya anahtar dosyası gerekli".to_string());
+    }
+    // An empty password means the database has only a key file
+    let mut composite = Sha256::new();
+    if !password.is_empty() {
+        composite.update(Sha256::digest(password.as_bytes()));
+    }
+    if let Some(keyfile) = keyfile {
+        composite.update(keyfile_key(keyfile)?);
+    }
+    let mut composite: [u8; 32] = composite.finalize().into();
+    let transformed = transform_key(&composite, kdf);
+    composite.zeroize();
+    let mut transformed = transformed?;
+
+    let keys = Keys {
+        cipher: Sha256::new()
+            .chain_update(seed)
+            .chain_update(transformed)
+            .finalize()
+            .into(),
+        hmac: Sha512::new()
+            .chain_update(seed)
+            .chain_update(transformed)
+            .chain_update([1u8])
+            .finalize()
+            .into(),
+    };
+    transformed.zeroize();
+    Ok(keys)
+}
+
+fn transform_key(composite: &[u8; 32], kdf: &HashMap<String, Variant>) -> Result<[u8; 32], String> {
+    let bytes = |name: &str| match kdf.get(name) {
+        Some(Variant::Bytes(value)) => Ok(value.as_slice()),
+        _ => Err(CORRUPT.to_string()),
+    };
+    let number = |name: &str| match kdf.get(name) {
+        Some(Variant::U32(value)) => Ok(u64::from(*value)),
+        Some(Variant::U64(value)) => Ok(*value),
+        _ => Err(CORRUPT.to_string()),
+    };
+    let u32_param = |name: &str| u32::try_from(number(name)?).map_err(|_| CORRUPT.to_string());
+
+    let uuid = bytes("$UUID")?;
+    if uuid == KDF_AES.as_slice() {
+        let cipher = aes::Aes256::new_from_slice(bytes("S")?).map_err(|_| CORRUPT)?;
+        let rounds = number("R")?;
+        if rounds > MAX_AES_ROUNDS {
+            return Err(KDF_TOO_COSTLY.to_string());
+        }
+        let mut key = *composite;
+        for _ in 0..rounds {
+            for half in key.chunks_exact_mut(16) {
+                cipher.encrypt_block(half.into());
+            }
+        }
+        let transformed = Sha256::digest(key).into();
+        key.zeroize();
+        return Ok(transformed);
+    }
+
+    let algorithm = if uuid == KDF_ARGON2D.as_slice() {
+        argon2::Algorithm::Argon2d
+    } else if uuid == KDF_ARGON2ID.as_slice() {
+        argon2::Algorithm::Argon2id
+    } else {
+        return Err("Desteklenmeyen KDBX anahtar türetme algoritması".to_string());
+    };
+    let version = match number("V").unwrap_or(0x13) {
+        0x10 => argon2::Version::V0x10,
+        _ => argon2::Version::V0x13,
+    };
+    let memory_kib = u32::try_from(number("M")? / 1024).map_err(|_| CORRUPT)?;
+    let iterations = u32_param("I")?;
+    let parallelism = u32_param("P")?;
+    if memory_kib > MAX_ARGON2_MEMORY_KIB
+        || iterations > MAX_ARGON2_ITERATIONS
+        || parallelism > MAX_ARGON2_PARALLELISM
+    {
+        return Err(KDF_TOO_COSTLY.to_string());
+    }
+    let params = argon2::Params::new(memory_kib, iterations, parallelism, Some(32))
+        .map_err(|e| format!("Argon2 parametreleri geçersiz: {}", e))?;
+    let mut transformed = [0u8; 32];
+    argon2::Argon2::new(algorithm, version, params)
+        .hash_password_into(composite, bytes("S")?, &mut transformed)
+        .map_err(|e| format!("Argon2 hatası: {}", e))?;
+    Ok(transformed)
+}
+
+/// KeePass key files: XML (versions 1.0 and 2.0), 32 raw bytes, 64 hex
+/// characters, or any other file, which is hashed.
+fn keyfile_key(data: &[u8]) -> Result<[u8; 32], String> {
+    let invalid = || "Anahtar dosyası bozuk".to_string();
+
+    if let Some(doc) = std::str::from_utf8(data)
+        .ok()
+        .and_then(|text| roxmltree::Document::parse(text.trim_start_matches('\u{feff}')).ok())
+        .filter(|doc| doc.root_element().has_tag_name("KeyFile"))
+    {
+        let version = doc
+            .descendants()
+            .find(|n| n.has_tag_name("Version"))
+            .and_then(|n| n.text())
+            .unwrap_or("1.0");
+        let data = doc
+            .descendants()
+            .find(|n| n.has_tag_name("Data"))
+            .ok_or_else(invalid)?;
+        let text: String = data
+            .text()
+            .unwrap_or("")
+            .chars()
+            .filter(|c| !c.is_whitespace())
+            .collect();
+
+        let key = if version.starts_with('2') {
+            let key = hex_decode(&text).ok_or_else(invalid)?;
+            if let Some(hash) = data.attribute("Hash") {
+                let expected = hex_decode(hash).ok_or_else(invalid)?;
+                if Sha256::digest(&key)[..4] != expected[..] {
+                    return Err(invalid());
+                }
+            }
+            key
+        } else {
+            general_purpose::STANDARD
+                .decode(&text)
+                .map_err(|_| invalid())?
+        };
+        return key.try_into().map_err(|_| invalid());
+    }
+
+    if let Ok(key) = <[u8; 32]>::try_from(data) {
+        return Ok(key);
+    }
+    if data.len() == 64 {
+        if let Some(key) = std::str::from_utf8(data).ok().and_then(hex_decode) {
+            return key.try_into().map_err(|_| invalid());
+        }
+    }
+    Ok(Sha256::digest(data).into())
+}
+
+fn hex_decode(text: &str) -> Option<Vec<u8>> {
+    if !text.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..text.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(text.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+fn mac(key: &[u8], parts: &[&[u8]]) -> [u8; 32] {
+    let mut mac = <HmacSha256 as Mac>::new_from_slice(key).expect("HMAC accepts any key length");
+    for part in parts {
+        mac.update(part);
+    }
+    mac.finalize().into_bytes().into()
+}
+
+fn verify_mac(key: &[u8], parts: &[&[u8]], expected: &[u8]) -> bool {
+    let mut mac = <HmacSha256 as Mac>::new_from_slice(key).expect("HMAC accepts any key length");
+    for part in parts {
+        mac.update(part);
+    }
+    mac.verify_slice(expected).is_ok()
+}
+
+/// The ChaCha20 stream protecting values inside the XML.
+fn inner_stream(key: &[u8]) -> chacha20::ChaCha20 {
+    let hash = Sha512::digest(key);
+    chacha20::ChaCha20::new(hash[..32].into(), hash[32..44].into())
+}
+
+/// Stable KeePass UUID for a ConfPass id, so repeated exports update the
+/// same entries when merged in KeePass.
+fn keepass_uuid(id: &str) -> String {
+    general_purpose::STANDARD.encode(&Sha256::digest(id.as_bytes())[..16])
+}
+
+fn keepass_time(timestamp: i64) -> String {
+    general_purpose::STANDARD.encode((timestamp + KEEPASS_EPOCH_OFFSET).to_le_bytes())
+}
+
+/// Escapes text for XML, dropping the control characters XML 1.0 forbids.
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            '\t' | '\n' | '\r' => escaped.push(c),
+            c if c.is_control() => {}
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// `otpauth://` URI of an authenticator, the form KeePassXC reads from `otp`.
+fn otpauth_uri(item: &TotpItem, entry: &PasswordEntry) -> String {
+    let secret = if item.secret.is_empty() {
+        &entry.password
+    } else {
+        &item.secret
+    };
+    let issuer = item.issuer.as_deref().unwrap_or(&entry.title);
+    let account = item.account.as_deref().unwrap_or(&entry.username);
+
+    let mut uri = url::Url::parse("otpauth://totp/").expect("valid base URI");
+    uri.set_path(&format!("{}:{}", issuer, account));
+    uri.query_pairs_mut()
+        .append_pair("secret", &secret.replace(' ', "").to_uppercase())
+        .append_pair("issuer", issuer)
+        .append_pair("period", &item.period.unwrap_or(30).to_string())
+        .append_pair("digits", &item.digits.unwrap_or(6).to_string())
+        .append_pair("algorithm", item.algorithm.as_deref().unwrap_or("SHA1"));
+    uri.to_string()
+}
+
+struct XmlWriter<'a> {
+    state: &'a VaultState,
+    attachments_dir: &'a Path,
+    data_key: &'a DataKey,
+    out: String,
+    stream: chacha20::ChaCha20,
+    binaries: Vec<Vec<u8>>,
+    now: i64,
+}
+
+impl XmlWriter<'_> {
+    fn document(&mut self) -> Result<(), String> {
+        let state = self.state;
+        let mut exported: Vec<&PasswordEntry> = state
+            .entries
+            .values()
+            .filter(|entry| entry.category != PASSKEY_CATEGORY)
+            .collect();
+        exported.sort_by_key(|entry| (entry.title.to_lowercase(), entry.id.as_str()));
+        let (trashed, live): (Vec<_>, Vec<_>) =
+            exported.into_iter().partition(|entry| is_trashed(entry));
+
+        // Folders reachable from the top; entries of anything else (a
+        // dangling or cyclic parent) land in the root group
+        let mut reachable = HashSet::new();
+        let mut queue: Vec<Option<&str>> = vec![None];
+        while let Some(parent) = queue.pop() {
+            for folder in child_folders(state, parent) {
+                if reachable.insert(folder.id.as_str()) {
+                    queue.push(Some(folder.id.as_str()));
+                }
+            }
+        }
+        let mut by_folder: HashMap<Option<&str>, Vec<&PasswordEntry>> = HashMap::new();
+        for entry in live {
+            let folder = entry
+                .folder_id
+                .as_deref()
+                .filter(|id| reachable.contains(id));
+            by_folder.entry(folder).or_default().push(entry);
+        }
+
+        let recycle_bin = keepass_uuid("confpass-recycle-bin");
+        let now = keepass_time(self.now);
+        self.out
+            .push_str("<?xml version=\"1.0\" encoding=\"utf-8\" standalone=\"yes\"?>\n");
+        self.out.push_str("<KeePassFile><Meta>");
+        self.out.push_str("<Generator>ConfPass</Generator>");
+        self.out.push_str(&format!(
+            "<DatabaseName>{0}</DatabaseName><DatabaseNameChanged>{1}</DatabaseNameChanged>",
+            ROOT_GROUP_NAME, now
+        ));
+        self.out.push_str(
+            "<MemoryProtection><ProtectTitle>False</ProtectTitle>\
+             <ProtectUserName>False</ProtectUserName><ProtectPassword>True</ProtectPassword>\
+             <ProtectURL>False</ProtectURL><ProtectNotes>False</ProtectNotes></MemoryProtection>",
+        );
+        if trashed.is_empty() {
+            self.out.push_str(
+                "<RecycleBinEnabled>True</RecycleBinEnabled>\
+                 <RecycleBinUUID>AAAAAAAAAAAAAAAAAAAAAA==</RecycleBinUUID>",
+            );
+        } else {
+            self.out.push_str(&format!(
+                "<RecycleBinEnabled>True</RecycleBinEnabled><RecycleBinUUID>{}</RecycleBinUUID>",
+                recycle_bin
+            ));
+        }
+        self.out.push_str(&format!(
+            "<RecycleBinChanged>{}</RecycleBinChanged></Meta><Root>",
+            now
+        ));
+
+        self.open_group(&keepass_uuid("confpass-root"), ROOT_GROUP_NAME, self.now);
+        self.group_contents(None, &by_folder)?;
+        if !trashed.is_empty() {
+            self.open_group(&recycle_bin, RECYCLE_BIN_NAME, self.now);
+            for entry in trashed {
+                self.entry(entry)?;
+            }
+            self.out.push_str("</Group>");
+        }
+        self.out.push_str("</Group></Root></KeePassFile>");
+        Ok(())
+    }
+
+    fn group_contents(
+        &mut self,
+        folder: Option<&str>,
+        by_folder: &HashMap<Option<&str>, Vec<&PasswordEntry>>,
+    ) -> Result<(), String> {
+        for entry in by_folder.get(&folder).into_iter().flatten() {
+            self.entry(entry)?;
+        }
+        for child in child_folders(self.state, folder) {
+            self.open_group(&keepass_uuid(&child.id), &child.name, child.created_at);
+            self.group_contents(Some(child.id.as_str()), by_folder)?;
+            self.out.push_str("</Group>");
+        }
+        Ok(())
+    }
+
+    fn open_group(&mut self, uuid: &str, name: &str, created_at: i64) {
+        self.out.push_str(&format!(
+            "<Group><UUID>{}</UUID><Name>{}</Name>",
+            uuid,
+            escape(name)
+        ));
+        self.times(created_at, created_at);
+        self.out.push_str("<IsExpanded>True</IsExpanded>");
+    }
+
+    fn times(&mut self, created_at: i64, updated_at: i64) {
+        let (created, modified) = (keepass_time(created_at), keepass_time(updated_at));
+        self.out.push_str(&format!(
+            "<Times><CreationTime>{0}</CreationTime>\
+             <LastModificationTime>{1}</LastModificationTime>\
+             <LastAccessTime>{1}</LastAccessTime><ExpiryTime>{1}</ExpiryTime>\
+             <Expires>False</Expires><UsageCount>0</UsageCount>\
+             <LocationChanged>{1}</LocationChanged></Times>",
+            created, modified
+        ));
+    }
+
+    fn string(&mut self, key: &str, value: &str, protected: bool) {
+        if protected {
+            let mut bytes = value.as_bytes().to_vec();
+            self.stream.apply_keystream(&mut bytes);
+            self.out.push_str(&format!(
+                "<String><Key>{}</Key><Value Protected=\"True\">{}</Value></String>",
+                escape(key),
+                general_purpose::STANDARD.encode(&bytes)
+            ));
+        } else {
+            self.out.push_str(&format!(
+                "<String><Key>{}</Key><Value>{}</Value></String>",
+                escape(key),
+                escape(value)
+            ));
+        }
+    }
+
+    fn entry(&mut self, entry: &PasswordEntry) -> Result<(), String> {
+        self.out
+            .push_str(&format!("<Entry><UUID>{}</UUID>", keepass_uuid(&entry.id)));
+        let tags: Vec<&str> = entry
+            .tags
+            .iter()
+            .flatten()
+            .filter_map(|id| self.state.tags.iter().find(|tag| &tag.id == id))
+            .map(|tag| tag.name.as_str())
+            .collect();
+        if !tags.is_empty() {
+            self.out
+                .push_str(&format!("<Tags>{}</Tags>", escape(&tags.join(";"))));
+        }
+        self.times(entry.created_at, entry.updated_at);
+
+        let totp = match &entry.data {
+            Some(EntryData::Totp(item)) if entry.category == "authenticator" => Some(item),
+            _ => None,
+        };
+        self.string("Title", &entry.title, false);
+        self.string("UserName", &entry.username, false);
+        // An authenticator's password is its secret, which goes into `otp`
+        let password = if totp.is_some() { "" } else { &entry.password };
+        self.string("Password", password, true);
+        self.string("URL", entry.url.as_deref().unwrap_or(""), false);
+        self.string("Notes", entry.notes.as_deref().unwrap_or(""), false);
+        if let Some(item) = totp {
+            self.string("otp", &otpauth_uri(item, entry), true);
+        }
+
+        let mut used: HashSet<String> = RESERVED_KEYS.iter().map(|key| key.to_string()).collect();
+        let mut fields: Vec<_> = entry.extra_fields.iter().flatten().collect();
+        fields.sort();
+        for (key, value) in fields {
+            let key = unique_key(&mut used, key);
+            self.string(&key, value, false);
+        }
+
+        let mut names = HashSet::new();
+        for attachment in entry.attachments.iter().flatten() {
+            let content =
+                attachments::read_attachment(self.attachments_dir, &attachment.id, self.data_key)
+                    .map_err(|e| format!("'{}' eki okunamadı: {}", attachment.filename, e))?;
+            let name = unique_key(&mut names, &attachment.filename);
+            self.out.push_str(&format!(
+                "<Binary><Key>{}</Key><Value Ref=\"{}\"/></Binary>",
+                escape(&name),
+                self.binaries.len()
+            ));
+            self.binaries.push(content);
+        }
+
+        if entry.category != "accounts" {
+            self.out.push_str("<CustomData>");
+            self.custom_data(CONFPASS_CATEGORY_KEY, &entry.category);
+            if let Some(data) = &entry.data {
+                let json = serde_json::to_string(data).map_err(|e| e.to_string())?;
+                self.custom_data(CONFPASS_DATA_KEY, &json);
+            }
+            self.out.push_str("</CustomData>");
+        }
+        self.out.push_str("</Entry>");
+        Ok(())
+    }
+
+    fn custom_data(&mut self, key: &str, value: &str) {
+        self.out.push_str(&format!(
+            "<Item><Key>{}</Key><Value>{}</Value></Item>",
+            escape(key),
+            escape(value)
+        ));
+    }
+}
+
+/// Subfolders of `parent` (or top-level folders) in display order.
+fn child_folders<'a>(state: &'a VaultState, parent: Option<&str>) -> Vec<&'a Folder> {
+    let mut folders: Vec<_> = state
+        .folders
+        .iter()
+        .filter(|folder| folder.parent_id.as_deref() == parent)
+        .collect();
+    folders.sort_by_key(|folder| (folder.order, folder.name.to_lowercase()));
+    folders
+}
+
+/// `key`, or `key (2)`, `key (3)`… when it is already taken.
+fn unique_key(used: &mut HashSet<String>, key: &str) -> String {
+    let mut unique = key.to_string();
+    let mut n = 2;
+    while used.contains(&unique) {
+        unique = format!("{} ({})", key, n);
+        n += 1;
+    }
+    used.insert(unique.clone());
+    unique
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::entry_data::CardItem;
+    use crate::keys::VaultKeys;
+    use crate::models::{FileAttachment, Tag};
+
+    const TEST_KDF: KdfParams = KdfParams::Argon2id {
+        memory_kib: 1024,
+        iterations: 1,
+        parallelism: 1,
+    };
+
+    fn entry(id: &str, title: &str, category: &str) -> PasswordEntry {
+        PasswordEntry {
+            id: id.to_string(),
+            title: title.to_string(),
+            username: String::new(),
+            password: String::new(),
+            url: None,
+            notes: None,
+            created_at: 1_700_000_000,
+            updated_at: 1_700_000_100,
+            category: category.to_string(),
+            folder_id: None,
+            tags: None,
+            extra_fields: None,
+            attachments: None,
+            data: None,
+            deleted_at: None,
+        }
+    }
+
+    #[test]
+    fn vault_round_trips_through_kdbx() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut state = VaultState {
+            keys: Some(
+                VaultKeys::generate("ana-sifre", KdfParams::Pbkdf2Sha256 { iterations: 1000 })
+                    .unwrap(),
+            ),
+            ..VaultState::default()
+        };
+        let data_key = &state.keys.as_ref().unwrap().data_key;
+        attachments::write_attachment(dir.path(), "att-1", b"gizli belge", data_key).unwrap();
+
+        for (id, name, parent) in [("f-1", "İş", None), ("f-2", "Sunucular", Some("f-1"))] {
+            state.folders.push(Folder {
+                id: id.to_string(),
+                name: name.to_string(),
+                color: "#f59e0b".to_string(),
+                icon: "folder".to_string(),
+                parent_id: parent.map(str::to_string),
+                created_at: 1_700_000_000,
+                order: 0,
+            });
+        }
+        state.tags.push(Tag {
+            id: "t-1".to_string(),
+            name: "önemli".to_string(),
+            color: "#3b82f6".to_string(),
+        });
+
+        let mut login = entry("e-1", "Sunucu <prod>", "accounts");
+        login.username = "root".to_string();
+        login.password = "p@ss & \"word\"".to_string();
+        login.url = Some("https://prod.example.com".to_string());
+        login.folder_id = Some("f-2".to_string());
+        login.tags = Some(vec!["t-1".to_string()]);
+        login.extra_fields = Some(HashMap::from([("Notes".to_string(), "ek".to_string())]));
+        login.attachments = Some(vec![FileAttachment {
+            id: "att-1".to_string(),
+            filename: "belge.txt".to_string(),
+            mime_type: "text/plain".to_string(),
+            size: 11,
+            created_at: 1_700_000_000,
+        }]);
+        let mut card = entry("e-2", "Kart", "bank_cards");
+        card.data = Some(EntryData::Card(CardItem {
+            cardholder_name: Some("Ada".to_string()),
+            ..Default::default()
+        }));
+        let mut otp = entry("e-3", "GitHub", "authenticator");
+        otp.password = "JBSWY3DPEHPK3PXP".to_string();
+        otp.data = Some(EntryData::Totp(TotpItem {
+            secret: "JBSWY3DPEHPK3PXP".to_string(),
+            issuer: Some("GitHub".to_string()),
+            account: Some("ada".to_string()),
+            ..Default::default()
+        }));
+        let mut old = entry("e-4", "Eski", "accounts");
+        old.password = "eski".to_string();
+        old.deleted_at = Some(1_700_000_500);
+        for entry in [login, card, otp, old] {
+            state.entries.insert(entry.id.clone(), entry);
+        }
+
+        let keyfile = b"anahtar dosyasi icerigi";
+        let data = write(&state, dir.path(), "kdbx-sifre", Some(keyfile), TEST_KDF, 1).unwrap();
+        assert_eq!(
+            read(&data, "kdbx-sifre", None, 2).unwrap_err(),
+            "Şifre veya anahtar dosyası yanlış"
+        );
+
+        let preview = read(&data, "kdbx-sifre", Some(keyfile), 2).unwrap();
+        assert_eq!(preview.entries.len(), 4);
+        let by_title = |title: &str| {
+            preview
+                .entries
+                .iter()
+                .find(|entry| entry.title == title)
+                .unwrap()
+        };
+
+        let login = by_title("Sunucu <prod>");
+        assert_eq!(login.password, "p@ss & \"word\"");
+        assert_eq!(login.extra_fields.as_ref().unwrap()["Notes (2)"], "ek");
+        let folder = preview
+            .folders
+            .iter()
+            .find(|f| Some(&f.id) == login.folder_id.as_ref())
+            .unwrap();
+        assert_eq!(folder.name, "Sunucular");
+        let parent = preview
+            .folders
+            .iter()
+            .find(|f| Some(&f.id) == folder.parent_id.as_ref())
+            .unwrap();
+        assert_eq!(
+            (parent.name.as_str(), parent.parent_id.is_some()),
+            ("İş", false)
+        );
+        assert_eq!(preview.tags[0].name, "önemli");
+        let attachment = &login.attachments.as_ref().unwrap()[0];
+        assert_eq!(attachment.filename, "belge.txt");
+        assert_eq!(preview.files[0].attachment_id, attachment.id);
+        assert_eq!(preview.files[0].content, b"gizli belge");
+
+        let card = by_title("Kart");
+        assert_eq!(card.category, "bank_cards");
+        assert!(matches!(
+            &card.data,
+            Some(EntryData::Card(item)) if item.cardholder_name.as_deref() == Some("Ada")
+        ));
+        let otp = by_title("GitHub");
+        assert_eq!(
+            (otp.category.as_str(), otp.password.as_str()),
+            ("authenticator", "JBSWY3DPEHPK3PXP")
+        );
+        assert_eq!(by_title("Eski").deleted_at, Some(2));
+        assert!(login.deleted_at.is_none());
+    }
+
+    #[test]
+    fn keyfile_formats() {
+        let key: Vec<u8> = (0..32).collect();
+        let hex: String = key.iter().map(|b| format!("{:02X}", b)).collect();
+        let hash: String = Sha256::digest(&key)[..4]
+            .iter()
+            .map(|b| format!("{:02X}", b))
+            .collect();
+
+        let v2 = format!(
+            "<?xml version=\"1.0\"?><KeyFile><Meta><Version>2.0</Version></Meta>\
+             <Key><Data Hash=\"{}\">{} {}</Data></Key></KeyFile>",
+            hash,
+            &hex[..32],
+            &hex[32..]
+        );
+        let v1 = format!(
+            "<KeyFile><Meta><Version>1.00</Version></Meta><Key><Data>{}</Data></Key></KeyFile>",
+            general_purpose::STANDARD.encode(&key)
+        );
+        for file in [v2.as_bytes(), v1.as_bytes(), key.as_slice(), hex.as_bytes()] {
+            assert_eq!(keyfile_key(file).unwrap().as_slice(), key.as_slice());
+        }
+        assert_eq!(
+            keyfile_key(b"herhangi bir dosya").unwrap(),
+            <[u8; 32]>::from(Sha256::digest(b"herhangi bir dosya"))
+        );
+        assert!(keyfile_key(v2.replace(&hash, "00000000").as_bytes()).is_err());
+    }
+
+    #[test]
+    fn header_kdf_parameters_are_bounded() {
+        let kdf = |memory: u64, iterations: u64| {
+            HashMap::from([
+                ("$UUID".to_string(), Variant::Bytes(KDF_ARGON2ID.to_vec())),
+                ("S".to_string(), Variant::Bytes(vec![0; 32])),
+                ("P".to_string(), Variant::U32(1)),
+                ("M".to_string(), Variant::U64(memory)),
+                ("I".to_string(), Variant::U64(iterations)),
+            ])
+        };
+        assert!(transform_key(&[0; 32], &kdf(64 << 30, 1)).is_err());
+        assert!(transform_key(&[0; 32], &kdf(64 << 20, 1_000_000)).is_err());
+        // KeePass's default of 1 MiB is below the vault's own minimum
+        assert!(transform_key(&[0; 32], &kdf(1 << 20, 2)).is_ok());
+
+        let aes = |rounds: u64| {
+            HashMap::from([
+                ("$UUID".to_string(), Variant::Bytes(KDF_AES.to_vec())),
+                ("S".to_string(), Variant::Bytes(vec![0; 32])),
+                ("R".to_string(), Variant::U64(rounds)),
+            ])
+        };
+        assert!(transform_key(&[0; 32], &aes(u64::MAX)).is_err());
+        assert!(transform_key(&[0; 32], &aes(60_000)).is_ok());
+    }
+}
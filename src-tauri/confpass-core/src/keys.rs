@@ -0,0 +1,173 @@
+//! Two-level key hierarchy.
+//!
+//! A random [`DataKey`] encrypts the vault and its attachments. It never
+//! touches disk in the clear: each secret that can open the vault (today the
+//! master password) stores its own [`WrappedKey`] copy of it. Changing the
+//! password or the KDF only rewraps these 32 bytes, and reading an attachment
+//! costs one AES-GCM operation instead of a full key derivation.
+
+use aes_gcm::{
+    aead::{Aead, AeadCore, KeyInit, OsRng, Payload},
+    Aes256Gcm, Nonce,
+};
+use rand::RngCore;
+use zeroize::{Zeroize, ZeroizeOnDrop};
+
+use crate::crypto::KdfParams;
+
+pub const KEY_LEN: usize = 32;
+pub const NONCE_LEN: usize = 12;
+const SALT_LEN: usize = 32;
+
+#[derive(Clone, Zeroize, ZeroizeOnDrop)]
+pub struct DataKey([u8; KEY_LEN]);
+
+impl std::fmt::Debug for DataKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.write_str("DataKey(..)")
+    }
+}
+
+impl DataKey {
+    pub fn generate() -> Self {
+        let mut key = [0u8; KEY_LEN];
+        OsRng.fill_bytes(&mut key);
+        DataKey(key)
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, String> {
+        let key: [u8; KEY_LEN] = bytes
+            .try_into()
+            .map_err(|_| "Geçersiz anahtar uzunluğu".to_string())?;
+        Ok(DataKey(key))
+    }
+
+    pub fn as_bytes(&self) -> &[u8; KEY_LEN] {
+        &self.0
+    }
+
+    pub(crate) fn cipher(&self) -> Result<Aes256Gcm, String> {
+        Aes256Gcm::new_from_slice(&self.0).map_err(|e| format!("Cipher oluşturulamadı: {}", e))
+    }
+
+    /// Encrypts `plaintext` bound to `aad`, returning `nonce || ciphertext`.
+    pub fn encrypt(&self, plaintext: &[u8], aad: &[u8]) -> Result<Vec<u8>, String> {
+        let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+        let ciphertext = self
+            .cipher()?
+            .encrypt(
+                &nonce,
+                Payload {
+                    msg: plaintext,
+                    aad,
+                },
+            )
+            .map_err(|e| format!("Şifreleme hatası: {}", e))?;
+
+        let mut out = nonce.to_vec();
+        out.extend_from_slice(&ciphertext);
+        Ok(out)
+    }
+
+    /// Decrypts `nonce || ciphertext` produced by [`DataKey::encrypt`].
+    pub fn decrypt(&self, data: &[u8], aad: &[u8]) -> Result<Vec<u8>, String> {
+        if data.len() < NONCE_LEN {
+            return Err("Geçersiz şifreli veri uzunluğu".to_string());
+        }
+        let (nonce, ciphertext) = data.split_at(NONCE_LEN);
+        self.cipher()?
+            .decrypt(
+                Nonce::from_slice(nonce),
+                Payload {
+                    msg: ciphertext,
+                    aad,
+                },
+            )
+            .map_err(|_| "Decrypt hatası: Yanlış anahtar veya bozuk veri".to_string())
+    }
+}
+
+/// A [`DataKey`] encrypted under a key derived from a secret with `kdf`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WrappedKey {
+    pub kdf: KdfParams,
+    pub salt: Vec<u8>,
+    /// `nonce || AES-256-GCM(data key)`.
+    pub wrapped: Vec<u8>,
+}
+
+impl WrappedKey {
+    /// Wraps `key` under `secret` with a fresh salt.
+    pub fn wrap(key: &DataKey, secret: &str, kdf: KdfParams) -> Result<Self, String> {
+        let mut salt = vec![0u8; SALT_LEN];
+        OsRng.fill_bytes(&mut salt);
+
+        let kek = DataKey(kdf.derive_key(secret, &salt)?);
+        let wrapped = kek.encrypt(key.as_bytes(), b"confpass-key-wrap")?;
+
+        Ok(Self { kdf, salt, wrapped })
+    }
+
+    pub fn unwrap(&self, secret: &str) -> Result<DataKey, String> {
+        let kek = DataKey(self.kdf.derive_key(secret, &self.salt)?);
+        let mut bytes = kek
+            .decrypt(&self.wrapped, b"confpass-key-wrap")
+            .map_err(|_| "Decrypt hatası: Yanlış şifre veya bozuk veri".to_string())?;
+        let key = DataKey::from_bytes(&bytes);
+        bytes.zeroize();
+        key
+    }
+}
+
+/// The unwrapped data key together with the slots it is stored under.
+#[derive(Debug, Clone)]
+pub struct VaultKeys {
+    pub data_key: DataKey,
+    pub password_slot: WrappedKey,
+}
+
+impl VaultKeys {
+    /// Generates a new data key and wraps it under the master password.
+    pub fn generate(master_password: &str, kdf: KdfParams) -> Result<Self, String> {
+        let data_key = DataKey::generate();
+        let password_slot = WrappedKey::wrap(&data_key, master_password, kdf)?;
+        Ok(Self {
+            data_key,
+            password_slot,
+        })
+    }
+
+    /// Rewraps the same data key under a new password and/or KDF.
+    pub fn rewrap_password(&mut self, master_password: &str, kdf: KdfParams) -> Result<(), String> {
+        self.password_slot = WrappedKey::wrap(&self.data_key, master_password, kdf)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TEST_KDF: KdfParams = KdfParams::Pbkdf2Sha256 { iterations: 1000 };
+
+    #[test]
+    fn wrap_unwrap_roundtrip() {
+        let key = DataKey::generate();
+        let slot = WrappedKey::wrap(&key, "ana-sifre-123", TEST_KDF).unwrap();
+
+        assert_eq!(
+            slot.unwrap("ana-sifre-123").unwrap().as_bytes(),
+            key.as_bytes()
+        );
+        assert!(slot.unwrap("yanlis-sifre").is_err());
+    }
+
+    #[test]
+    fn data_key_binds_associated_data() {
+        let key = DataKey::generate();
+        let sealed = key.encrypt(b"ek icerigi", b"att_1").unwrap();
+
+        assert_eq!(key.decrypt(&sealed, b"att_1").unwrap(), b"ek icerigi");
+        assert!(key.decrypt(&sealed, b"att_2").is_err());
+    }
+}
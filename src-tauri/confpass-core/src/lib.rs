@@ -0,0 +1,40 @@
+//! Headless ConfPass vault: data model, encryption and on-disk storage shared
+//! by the desktop app, the native messaging host and command-line tooling.
+//! Nothing in this crate depends on Tauri or platform UI libraries.
+
+pub mod archive;
+pub mod attachments;
+pub mod backups;
+pub mod clients;
+pub mod container;
+pub mod crypto;
+pub mod domain;
+pub mod duplicates;
+pub mod entry_data;
+pub mod error;
+pub mod history;
+pub mod importers;
+pub mod integrity;
+pub mod ipc;
+pub mod kdbx;
+pub mod keys;
+pub mod mime;
+pub mod models;
+pub mod passkeys;
+pub mod password_change;
+pub mod rate_limit;
+pub mod recovery;
+pub mod revisions;
+pub mod storage;
+pub mod totp;
+pub mod trash;
+pub mod vault;
+pub mod webauthn;
+
+pub use domain::extract_domain;
+pub use error::VaultError;
+pub use models::{
+    EntryRevision, FieldChange, FileAttachment, Folder, PasswordEntry, PasswordHistoryItem,
+    RevisionSource, StoredPasskey, Tag, VaultState,
+};
+pub use vault::Vault;
@@ -0,0 +1,316 @@
+//! Content type of attachments, detected from their first bytes.
+//!
+//! The magic number decides; the file extension only tells apart formats
+//! that share a container (Office and OpenDocument files are ZIP archives,
+//! legacy Office files OLE2 compound files) and names the kind of a text
+//! file. Content that matches nothing and is not text is
+//! `application/octet-stream`, whatever its name.
+
+/// Bytes of a file [`detect`] looks at.
+pub const SNIFF_LEN: usize = 8192;
+
+pub const OCTET_STREAM: &str = "application/octet-stream";
+
+const ZIP_TYPES: &[(&str, &str)] = &[
+    (
+        "docx",
+        "application/vnd.openxmlformats-officedocument.wordprocessingml.document",
+    ),
+    (
+        "xlsx",
+        "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet",
+    ),
+    (
+        "pptx",
+        "application/vnd.openxmlformats-officedocument.presentationml.presentation",
+    ),
+    ("odt", "application/vnd.oasis.opendocument.text"),
+    ("ods", "application/vnd.oasis.opendocument.spreadsheet"),
+    ("odp", "application/vnd.oasis.opendocument.presentation"),
+    ("epub", "application/epub+zip"),
+    ("jar", "application/java-archive"),
+    ("apk", "application/vnd.android.package-archive"),
+];
+
+const OLE_TYPES: &[(&str, &str)] = &[
+    ("doc", "application/msword"),
+    ("xls", "application/vnd.ms-excel"),
+    ("ppt", "application/vnd.ms-powerpoint"),
+    ("msg", "application/vnd.ms-outlook"),
+];
+
+const TEXT_TYPES: &[(&str, &str)] = &[
+    ("csv", "text/csv"),
+    ("htm", "text/html"),
+    ("html", "text/html"),
+    ("json", "application/json"),
+    ("md", "text/markdown"),
+    ("xml", "application/xml"),
+    ("yaml", "application/yaml"),
+    ("yml", "application/yaml"),
+];
+
+fn extension(filename: &str) -> String {
+    filename
+        .rsplit_once('.')
+        .map(|(_, ext)| ext.to_ascii_lowercase())
+        .unwrap_or_default()
+}
+
+fn by_extension(table: &[(&str, &'static str)], ext: &str) -> Option<&'static str> {
+    table.iter().find(|(e, _)| *e == ext).map(|(_, mime)| *mime)
+}
+
+/// ZIP-based formats name their content in the first entries; ODF and EPUB
+/// start with an uncompressed `mimetype` entry.
+fn zip_type(head: &[u8], ext: &str) -> &'static str {
+    let contains = |needle: &[u8]| head.windows(needle.len()).any(|w| w == needle);
+    let by_content = if contains(b"mimetypeapplication/vnd.oasis.opendocument.text") {
+        Some("odt")
+    } else if contains(b"mimetypeapplication/vnd.oasis.opendocument.spreadsheet") {
+        Some("ods")
+    } else if contains(b"mimetypeapplication/vnd.oasis.opendocument.presentation") {
+        Some("odp")
+    } else if contains(b"mimetypeapplication/epub+zip") {
+        Some("epub")
+    } else if contains(b"word/") {
+        Some("docx")
+    } else if contains(b"xl/") {
+        Some("xlsx")
+    } else if contains(b"ppt/") {
+        Some("pptx")
+    } else {
+        None
+    };
+    by_content
+        .or(Some(ext))
+        .and_then(|ext| by_extension(ZIP_TYPES, ext))
+        .unwrap_or("application/zip")
+}
+
+/// Whether `head` looks like text: valid UTF-8, a character cut off at the
+/// end aside, without control characters other than whitespace.
+fn is_text(head: &[u8]) -> bool {
+    let valid = match std::str::from_utf8(head) {
+        Ok(text) => text,
+        // A multi-byte character cut off by the sniffing window
+        Err(e) if e.error_len().is_none() && head.len() - e.valid_up_to() < 4 => {
+            match std::str::from_utf8(&head[..e.valid_up_to()]) {
+                Ok(text) => text,
+                Err(_) => return false,
+            }
+        }
+        Err(_) => return false,
+    };
+    valid
+        .chars()
+        .all(|c| !c.is_control() || matches!(c, '\n' | '\r' | '\t' | '\x0c'))
+}
+
+fn text_type(head: &[u8], ext: &str) -> &'static str {
+    let start = String::from_utf8_lossy(&head[..head.len().min(512)]).to_ascii_lowercase();
+    let start = start.trim_start_matches('\u{feff}').trim_start();
+    if start.contains("<svg") {
+        "image/svg+xml"
+    } else if start.starts_with("<!doctype html") || start.starts_with("<html") {
+        "text/html"
+    } else if let Some(mime) = by_extension(TEXT_TYPES, ext) {
+        mime
+    } else if start.starts_with("<?xml") {
+        "application/xml"
+    } else if start.starts_with("-----begin ") {
+        "application/x-pem-file"
+    } else {
+        "text/plain"
+    }
+}
+
+/// Content type of a file starting with `head`, at most [`SNIFF_LEN`] bytes
+/// of it.
+pub fn detect(head: &[u8], filename: &str) -> &'static str {
+    let ext = extension(filename);
+    let at = |offset: usize, magic: &[u8]| head.get(offset..offset + magic.len()) == Some(magic);
+
+    if at(0, b"%PDF-") {
+        "application/pdf"
+    } else if at(0, b"\x89PNG\r\n\x1a\n") {
+        "image/png"
+    } else if at(0, b"\xff\xd8\xff") {
+        "image/jpeg"
+    } else if at(0, b"GIF87a") || at(0, b"GIF89a") {
+        "image/gif"
+    } else if at(0, b"RIFF") && at(8, b"WEBP") {
+        "image/webp"
+    } else if at(0, b"RIFF") && at(8, b"WAVE") {
+        "audio/wav"
+    } else if at(0, b"II*\0") || at(0, b"MM\0*") {
+        "image/tiff"
+    } else if at(0, b"BM") && head.len() >= 26 && at(6, b"\0\0\0\0") {
+        "image/bmp"
+    } else if at(0, b"\0\0\x01\0") && ext == "ico" {
+        "image/x-icon"
+    } else if at(4, b"ftypavif") {
+        "image/avif"
+    } else if at(4, b"ftypheic") || at(4, b"ftypheix") || at(4, b"ftypmif1") {
+        "image/heic"
+    } else if at(4, b"ftypM4A ") {
+        "audio/mp4"
+    } else if at(4, b"ftypqt  ") {
+        "video/quicktime"
+    } else if at(4, b"ftyp") {
+        "video/mp4"
+    } else if at(0, b"\x1a\x45\xdf\xa3") {
+        if ext == "mkv" {
+            "video/x-matroska"
+        } else {
+            "video/webm"
+        }
+    } else if at(0, b"ID3") || at(0, b"\xff\xfb") || at(0, b"\xff\xf3") {
+        "audio/mpeg"
+    } else if at(0, b"OggS") {
+        "audio/ogg"
+    } else if at(0, b"fLaC") {
+        "audio/flac"
+    } else if at(0, b"PK\x03\x04") || at(0, b"PK\x05\x06") {
+        zip_type(head, &ext)
+    } else if at(0, b"\xd0\xcf\x11\xe0\xa1\xb1\x1a\xe1") {
+        by_extension(OLE_TYPES, &ext).unwrap_or("application/x-ole-storage")
+    } else if at(0, b"\x1f\x8b") {
+        "application/gzip"
+    } else if at(0, b"7z\xbc\xaf\x27\x1c") {
+        "application/x-7z-compressed"
+    } else if at(0, b"Rar!\x1a\x07") {
+        "application/vnd.rar"
+    } else if at(0, b"BZh") {
+        "application/x-bzip2"
+    } else if at(0, b"\xfd7zXZ\0") {
+        "application/x-xz"
+    } else if at(257, b"ustar") {
+        "application/x-tar"
+    } else if at(0, b"{\\rtf") {
+        "application/rtf"
+    } else if at(0, b"SQLite format 3\0") {
+        "application/vnd.sqlite3"
+    } else if at(0, b"\x03\xd9\xa2\x9a\x67\xfb\x4b\xb5") {
+        "application/x-keepass2"
+    } else if !head.is_empty() && is_text(head) {
+        text_type(head, &ext)
+    } else {
+        OCTET_STREAM
+    }
+}
+
+/// Whether attachments of `mime_type` get an image thumbnail as preview.
+pub fn is_previewable_image(mime_type: &str) -> bool {
+    matches!(
+        mime_type,
+        "image/png" | "image/jpeg" | "image/gif" | "image/webp" | "image/bmp" | "image/tiff"
+    )
+}
+
+/// Whether attachments of `mime_type` are shown as text in a preview.
+pub fn is_text_type(mime_type: &str) -> bool {
+    mime_type.starts_with("text/")
+        || matches!(
+            mime_type,
+            "application/json"
+                | "application/xml"
+                | "application/yaml"
+                | "application/x-pem-file"
+                | "image/svg+xml"
+        )
+}
+
+/// The first `max_lines` lines of `content`, at most `max_bytes` of it, cut
+/// at a character boundary. The flag tells whether anything was left out.
+pub fn text_excerpt(content: &[u8], max_bytes: usize, max_lines: usize) -> (String, bool) {
+    let mut end = content.len().min(max_bytes);
+    if let Some(newline) = content[..end]
+        .iter()
+        .enumerate()
+        .filter(|(_, b)| **b == b'\n')
+        .nth(max_lines.saturating_sub(1))
+        .map(|(i, _)| i)
+    {
+        end = newline;
+    }
+    let text = match std::str::from_utf8(&content[..end]) {
+        Ok(text) => text.to_string(),
+        Err(e) if e.error_len().is_none() => {
+            String::from_utf8_lossy(&content[..e.valid_up_to()]).into_owned()
+        }
+        Err(_) => String::from_utf8_lossy(&content[..end]).into_owned(),
+    };
+    let truncated = text.len() < content.len();
+    (text.trim_start_matches('\u{feff}').to_string(), truncated)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detects_by_content_and_uses_the_name_only_for_containers() {
+        assert_eq!(detect(b"%PDF-1.7\n...", "belge.txt"), "application/pdf");
+        assert_eq!(
+            detect(b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR", "foto"),
+            "image/png"
+        );
+        assert_eq!(detect(b"\xff\xd8\xff\xe0\0\x10JFIF", "a.png"), "image/jpeg");
+        assert_eq!(detect(b"\0\0\0\x20ftypheic", "IMG_1.HEIC"), "image/heic");
+
+        let docx = b"PK\x03\x04\x14\0\x06\0[Content_Types].xml....PK\x03\x04word/document.xml";
+        assert_eq!(
+            detect(docx, "rapor.zip"),
+            "application/vnd.openxmlformats-officedocument.wordprocessingml.document"
+        );
+        assert_eq!(
+            detect(b"PK\x03\x04\x14\0\0\0[Content_Types].xml", "tablo.XLSX"),
+            "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet"
+        );
+        assert_eq!(
+            detect(
+                b"PK\x03\x04\0\0mimetypeapplication/vnd.oasis.opendocument.text",
+                "x"
+            ),
+            "application/vnd.oasis.opendocument.text"
+        );
+        assert_eq!(
+            detect(b"PK\x03\x04\x14\0\0\0a.txt", "arsiv.zip"),
+            "application/zip"
+        );
+        assert_eq!(
+            detect(b"\xd0\xcf\x11\xe0\xa1\xb1\x1a\xe1\0\0", "eski.xls"),
+            "application/vnd.ms-excel"
+        );
+
+        assert_eq!(detect("Merhaba dünya\n".as_bytes(), "not"), "text/plain");
+        assert_eq!(detect(b"{\"a\": 1}", "veri.json"), "application/json");
+        assert_eq!(
+            detect(b"<?xml version=\"1.0\"?><svg xmlns=\"\">", "x.xml"),
+            "image/svg+xml"
+        );
+        // A two-byte character cut off by the sniffing window is still text
+        assert_eq!(detect(&"aaç".as_bytes()[..3], "a"), "text/plain");
+        assert_eq!(detect(b"\0\x01\x02binary", "resim.png"), OCTET_STREAM);
+        assert_eq!(detect(b"", "bos.txt"), OCTET_STREAM);
+    }
+
+    #[test]
+    fn text_excerpt_stops_at_lines_bytes_and_char_boundaries() {
+        let content = "bir\niki\nüç\ndört\n".as_bytes();
+        assert_eq!(
+            text_excerpt(content, 100, 2),
+            ("bir\niki".to_string(), true)
+        );
+        assert_eq!(
+            text_excerpt(content, 100, 10),
+            (String::from_utf8(content.to_vec()).unwrap(), false)
+        );
+        // "ü" is two bytes; cutting inside it drops the whole character
+        assert_eq!(
+            text_excerpt(content, 9, 10),
+            ("bir\niki\n".to_string(), true)
+        );
+    }
+}
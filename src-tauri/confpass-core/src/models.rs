@@ -0,0 +1,184 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use crate::entry_data::EntryData;
+use crate::keys::VaultKeys;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FileAttachment {
+    pub id: String,
+    pub filename: String,
+    pub mime_type: String,
+    pub size: u64,
+    pub created_at: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct PasswordEntry {
+    pub id: String,
+    pub title: String,
+    pub username: String,
+    pub password: String,
+    pub url: Option<String>,
+    pub notes: Option<String>,
+    pub created_at: i64,
+    pub updated_at: i64,
+    pub category: String,
+    #[serde(default)]
+    pub folder_id: Option<String>,
+    #[serde(default)]
+    pub tags: Option<Vec<String>>,
+    #[serde(default)]
+    pub extra_fields: Option<HashMap<String, String>>,
+    #[serde(default)]
+    pub attachments: Option<Vec<FileAttachment>>,
+    /// Typed fields of the entry's category; see [`crate::entry_data`].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub data: Option<EntryData>,
+    /// When the entry was moved to the trash; see [`crate::trash`].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub deleted_at: Option<i64>,
+}
+
+/// A password an entry used before, kept encrypted inside the vault.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PasswordHistoryItem {
+    pub password: String,
+    pub changed_at: i64,
+}
+
+/// Where an entry change came from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RevisionSource {
+    Ui,
+    Extension,
+    Import,
+}
+
+/// One field of an entry as it was before and after a change. Values are the
+/// field's JSON form; `null` stands for an empty optional field.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FieldChange {
+    pub field: String,
+    pub old_value: serde_json::Value,
+    pub new_value: serde_json::Value,
+}
+
+/// A recorded change to an entry; see [`crate::revisions`].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EntryRevision {
+    pub changed_at: i64,
+    pub source: RevisionSource,
+    pub changes: Vec<FieldChange>,
+}
+
+/// WebAuthn credential created through the browser extension. It is stored
+/// only inside the encrypted vault; the matching `passkeys` entry shows it in
+/// the UI.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StoredPasskey {
+    pub credential_id: String,
+    pub private_key: String,
+    pub rp_id: String,
+    pub rp_name: String,
+    pub user_id: String,
+    pub user_name: String,
+    pub user_display_name: String,
+    pub counter: u32,
+    pub created_at: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Folder {
+    pub id: String,
+    pub name: String,
+    pub color: String,
+    pub icon: String,
+    #[serde(default)]
+    pub parent_id: Option<String>,
+    pub created_at: i64,
+    #[serde(default)]
+    pub order: i32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Tag {
+    pub id: String,
+    pub name: String,
+    pub color: String,
+}
+
+/// Decrypted on-disk representation of the vault.
+#[derive(Deserialize, Serialize)]
+pub struct VaultData {
+    pub entries: Vec<PasswordEntry>,
+    pub master_password_hash: String,
+    pub encryption_salt: String,
+    #[serde(default)]
+    pub folders: Vec<Folder>,
+    #[serde(default)]
+    pub tags: Vec<Tag>,
+    /// Previous passwords by entry id, oldest first.
+    #[serde(default)]
+    pub password_history: HashMap<String, Vec<PasswordHistoryItem>>,
+    #[serde(default)]
+    pub passkeys: Vec<StoredPasskey>,
+    /// Changes by entry id, oldest first.
+    #[serde(default)]
+    pub revisions: HashMap<String, Vec<EntryRevision>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VaultState {
+    pub entries: HashMap<String, PasswordEntry>,
+    pub master_password_hash: Option<String>,
+    pub vault_locked: bool,
+    pub auto_lock_timeout: Option<u64>,
+    pub encryption_salt: Option<String>,
+    /// Unwrapped data key and its slots; present only while unlocked.
+    #[serde(skip)]
+    pub keys: Option<VaultKeys>,
+    pub folders: Vec<Folder>,
+    pub tags: Vec<Tag>,
+    pub password_history: HashMap<String, Vec<PasswordHistoryItem>>,
+    pub passkeys: Vec<StoredPasskey>,
+    pub revisions: HashMap<String, Vec<EntryRevision>>,
+}
+
+impl Default for VaultState {
+    fn default() -> Self {
+        Self {
+            entries: HashMap::new(),
+            master_password_hash: None,
+            vault_locked: true,
+            auto_lock_timeout: Some(300),
+            encryption_salt: None,
+            keys: None,
+            folders: Vec::new(),
+            tags: Vec::new(),
+            password_history: HashMap::new(),
+            passkeys: Vec::new(),
+            revisions: HashMap::new(),
+        }
+    }
+}
+
+pub const VALID_CATEGORIES: &[&str] = &[
+    "accounts",
+    "bank_cards",
+    "documents",
+    "addresses",
+    "notes",
+    "passkeys",
+    "authenticator",
+];
+
+/// Categories that require a username and password.
+pub fn requires_credentials(category: &str) -> bool {
+    category != "notes"
+        && category != "passkeys"
+        && category != "authenticator"
+        && category != "addresses"
+        && category != "documents"
+}
@@ -0,0 +1,327 @@
+//! Passkey storage.
+//!
+//! Passkeys saved by the browser extension are kept in the encrypted vault as
+//! [`StoredPasskey`] records, next to a `passkeys` entry whose [`PasskeyItem`]
+//! carries the public metadata for the UI. A passkey is served only while its
+//! entry is live; moving the entry to the trash hides it and deleting the
+//! entry drops it on the next save. Older releases wrote the
+//! private keys in cleartext to `passkeys.json` and into the entry notes;
+//! [`migrate_plaintext_passkeys`] moves both into the vault.
+
+use std::collections::HashSet;
+use std::fs;
+use std::path::Path;
+
+use crate::entry_data::{self, EntryData, PasskeyItem};
+use crate::models::{PasswordEntry, StoredPasskey, VaultState};
+use crate::storage::{save_vault_to_disk, secure_delete};
+use crate::trash;
+
+pub const PASSKEYS_FILE_NAME: &str = "passkeys.json";
+pub const PASSKEY_CATEGORY: &str = "passkeys";
+
+/// Credential id recorded in a passkey entry.
+pub fn credential_id(entry: &PasswordEntry) -> Option<String> {
+    match entry.data.as_ref()? {
+        EntryData::Passkey(item) => item.credential_id.clone(),
+        _ => None,
+    }
+}
+
+fn entry_credential_ids(state: &VaultState, include_trashed: bool) -> HashSet<String> {
+    state
+        .entries
+        .values()
+        .filter(|e| e.category == PASSKEY_CATEGORY)
+        .filter(|e| include_trashed || !trash::is_trashed(e))
+        .filter_map(credential_id)
+        .collect()
+}
+
+/// Passkeys that still have an entry, in the list or in the trash.
+pub fn retained_passkeys(state: &VaultState) -> Vec<StoredPasskey> {
+    let ids = entry_credential_ids(state, true);
+    state
+        .passkeys
+        .iter()
+        .filter(|p| ids.contains(&p.credential_id))
+        .cloned()
+        .collect()
+}
+
+/// Passkeys that may be handed to the browser for `rp_id`; an empty `rp_id`
+/// matches none.
+pub fn active_passkeys<'a>(state: &'a VaultState, rp_id: &str) -> Vec<&'a StoredPasskey> {
+    if rp_id.is_empty() {
+        return Vec::new();
+    }
+    let ids = entry_credential_ids(state, false);
+    state
+        .passkeys
+        .iter()
+        .filter(|p| ids.contains(&p.credential_id))
+        .filter(|p| p.rp_id == rp_id)
+        .collect()
+}
+
+/// Entry shown in the UI for `passkey`. It carries everything but the
+/// private key.
+pub fn passkey_entry(passkey: &StoredPasskey, now: i64) -> PasswordEntry {
+    let item = PasskeyItem {
+        username: Some(passkey.user_name.clone()),
+        email: Some(passkey.user_display_name.clone()).filter(|name| name.contains('@')),
+        domain: Some(passkey.rp_id.clone()),
+        credential_id: Some(passkey.credential_id.clone()),
+        rp_id: Some(passkey.rp_id.clone()),
+        rp_name: Some(passkey.rp_name.clone()),
+        user_id: Some(passkey.user_id.clone()),
+        user_display_name: Some(passkey.user_display_name.clone()),
+        counter: Some(passkey.counter),
+        created_at: Some(passkey.created_at),
+        ..PasskeyItem::default()
+    };
+
+    let url = if passkey.rp_id.starts_with("http://") || passkey.rp_id.starts_with("https://") {
+        passkey.rp_id.clone()
+    } else {
+        format!("https://{}", passkey.rp_id)
+    };
+    let title = if !passkey.rp_name.is_empty() {
+        passkey.rp_name.clone()
+    } else {
+        passkey
+            .rp_id
+            .split('.')
+            .next()
+            .unwrap_or(&passkey.rp_id)
+            .to_string()
+    };
+
+    PasswordEntry {
+        id: format!("entry_{}", uuid::Uuid::new_v4()),
+        title,
+        username: passkey.user_name.clone(),
+        password: String::new(), // Passkeys don't have passwords
+        url: Some(url),
+        notes: None,
+        created_at: now,
+        updated_at: now,
+        category: PASSKEY_CATEGORY.to_string(),
+        folder_id: None,
+        tags: None,
+        extra_fields: None,
+        attachments: None,
+        data: Some(EntryData::Passkey(item)),
+        deleted_at: None,
+    }
+}
+
+/// Stores a new passkey and its entry, returning the entry.
+pub fn add_passkey(
+    state: &mut VaultState,
+    passkey: StoredPasskey,
+    now: i64,
+) -> Result<PasswordEntry, String> {
+    if passkey.credential_id.is_empty() || passkey.private_key.is_empty() {
+        return Err("Geçiş anahtarı bilgileri eksik".to_string());
+    }
+    if state
+        .passkeys
+        .iter()
+        .any(|p| p.credential_id == passkey.credential_id)
+    {
+        return Err("Bu geçiş anahtarı zaten kayıtlı".to_string());
+    }
+
+    let entry = passkey_entry(&passkey, now);
+    state.entries.insert(entry.id.clone(), entry.clone());
+    state.passkeys.push(passkey);
+    Ok(entry)
+}
+
+/// Moves private keys that older releases copied into entry notes over to
+/// [`VaultState::passkeys`] and drops them from the entry. Returns the number
+/// of entries changed.
+fn strip_private_keys(state: &mut VaultState) -> usize {
+    let mut changed = 0;
+    for entry in state.entries.values_mut() {
+        let Some(EntryData::Passkey(item)) = entry.data.as_mut() else {
+            continue;
+        };
+        let Some(private_key) = item.extra.remove("privateKey") else {
+            continue;
+        };
+        changed += 1;
+
+        let (Some(credential_id), Some(private_key)) =
+            (item.credential_id.clone(), private_key.as_str())
+        else {
+            continue;
+        };
+        if state
+            .passkeys
+            .iter()
+            .any(|p| p.credential_id == credential_id)
+        {
+            continue;
+        }
+
+        let user_name = item.username.clone().unwrap_or_default();
+        state.passkeys.push(StoredPasskey {
+            credential_id,
+            private_key: private_key.to_string(),
+            rp_id: item
+                .rp_id
+                .clone()
+                .or_else(|| item.domain.clone())
+                .unwrap_or_default(),
+            rp_name: item.rp_name.clone().unwrap_or_default(),
+            user_id: item.user_id.clone().unwrap_or_default(),
+            user_display_name: item
+                .user_display_name
+                .clone()
+                .unwrap_or_else(|| user_name.clone()),
+            user_name,
+            counter: item.counter.unwrap_or(0),
+            created_at: item.created_at.unwrap_or(entry.created_at),
+        });
+    }
+    changed
+}
+
+/// Moves passkeys from a cleartext `passkeys.json` and from entry notes into
+/// the vault, saves it and only then overwrites and deletes the file. Passkeys
+/// saved while the vault was locked get their entry here. A file that does not
+/// parse is left in place. Returns the number of passkeys taken from the file.
+pub fn migrate_plaintext_passkeys(
+    passkeys_path: &Path,
+    vault_path: &Path,
+    state: &mut VaultState,
+    now: i64,
+) -> Result<usize, String> {
+    let mut changed = entry_data::migrate_entries(state) > 0;
+    changed |= strip_private_keys(state) > 0;
+
+    let legacy: Option<Vec<StoredPasskey>> = if passkeys_path.exists() {
+        let content = fs::read_to_string(passkeys_path)
+            .map_err(|e| format!("Geçiş anahtarı dosyası okunamadı: {}", e))?;
+        serde_json::from_str(&content).ok()
+    } else {
+        None
+    };
+
+    let mut imported = 0;
+    if let Some(legacy) = legacy.as_ref() {
+        let with_entry = entry_credential_ids(state, true);
+        for passkey in legacy {
+            if passkey.credential_id.is_empty() || passkey.private_key.is_empty() {
+                continue;
+            }
+            if !with_entry.contains(&passkey.credential_id) {
+                let entry = passkey_entry(passkey, now);
+                state.entries.insert(entry.id.clone(), entry);
+            }
+            if !state
+                .passkeys
+                .iter()
+                .any(|p| p.credential_id == passkey.credential_id)
+            {
+                state.passkeys.push(passkey.clone());
+                imported += 1;
+            }
+        }
+        changed = true;
+    }
+
+    if changed {
+        save_vault_to_disk(vault_path, state)?;
+    }
+    if legacy.is_some() {
+        secure_delete(passkeys_path)?;
+    }
+
+    Ok(imported)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::{init_keys, load_vault_from_disk, VAULT_FILE_NAME};
+
+    fn passkey(credential_id: &str, rp_id: &str) -> StoredPasskey {
+        StoredPasskey {
+            credential_id: credential_id.to_string(),
+            private_key: format!("gizli-{}", credential_id),
+            rp_id: rp_id.to_string(),
+            rp_name: String::new(),
+            user_id: "u1".to_string(),
+            user_name: "kullanici".to_string(),
+            user_display_name: "kullanici@example.com".to_string(),
+            counter: 0,
+            created_at: 1,
+        }
+    }
+
+    #[test]
+    fn trashed_and_deleted_passkeys_are_not_served() {
+        let mut state = VaultState::default();
+        let kept = add_passkey(&mut state, passkey("c1", "example.com"), 10).unwrap();
+        let trashed = add_passkey(&mut state, passkey("c2", "example.com"), 10).unwrap();
+        add_passkey(&mut state, passkey("c3", "other.com"), 10).unwrap();
+        assert!(add_passkey(&mut state, passkey("c1", "example.com"), 10).is_err());
+        assert!(!serde_json::to_string(&kept).unwrap().contains("gizli"));
+
+        trash::trash_entry(&mut state, &trashed.id, 20).unwrap();
+        let served: Vec<_> = active_passkeys(&state, "example.com")
+            .iter()
+            .map(|p| p.credential_id.clone())
+            .collect();
+        assert_eq!(served, vec!["c1".to_string()]);
+        assert!(active_passkeys(&state, "").is_empty());
+
+        state.entries.remove(&kept.id);
+        let retained: Vec<_> = retained_passkeys(&state)
+            .into_iter()
+            .map(|p| p.credential_id)
+            .collect();
+        assert_eq!(retained, vec!["c2".to_string(), "c3".to_string()]);
+    }
+
+    #[test]
+    fn plaintext_passkeys_are_moved_into_vault() {
+        let dir = tempfile::tempdir().unwrap();
+        let vault_path = dir.path().join(VAULT_FILE_NAME);
+        let mut state = VaultState {
+            master_password_hash: Some("hash".to_string()),
+            ..VaultState::default()
+        };
+        let mut old_entry = passkey_entry(&passkey("c1", "example.com"), 5);
+        old_entry.data = None;
+        old_entry.notes = Some(
+            serde_json::json!({"credentialId": "c1", "privateKey": "gizli-c1", "rpId": "example.com"})
+                .to_string(),
+        );
+        state
+            .entries
+            .insert(old_entry.id.clone(), old_entry.clone());
+        init_keys(&mut state, "ana-sifre-123").unwrap();
+        save_vault_to_disk(&vault_path, &state).unwrap();
+
+        let passkeys_path = dir.path().join(PASSKEYS_FILE_NAME);
+        let legacy = vec![passkey("c1", "example.com"), passkey("c2", "other.com")];
+        fs::write(&passkeys_path, serde_json::to_string(&legacy).unwrap()).unwrap();
+
+        let imported =
+            migrate_plaintext_passkeys(&passkeys_path, &vault_path, &mut state, 20).unwrap();
+        assert_eq!(imported, 1);
+        assert!(!passkeys_path.exists());
+
+        let loaded = load_vault_from_disk(&vault_path, "ana-sifre-123").unwrap();
+        assert_eq!(loaded.passkeys.len(), 2);
+        assert_eq!(loaded.entries.len(), 2);
+        assert!(!serde_json::to_string(&loaded.entries)
+            .unwrap()
+            .contains("gizli"));
+        assert_eq!(active_passkeys(&loaded, "other.com").len(), 1);
+    }
+}
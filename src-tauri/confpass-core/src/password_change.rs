@@ -0,0 +1,112 @@
+//! Changing the master password.
+//!
+//! Vault content and attachments are encrypted with the data key, which does
+//! not change; only its password slot in the `vault.dat` header is rewrapped.
+//! Replacing `vault.dat` is a single atomic rename, so an interrupted change
+//! leaves the vault under either the old or the new password.
+
+use std::path::Path;
+
+use crate::attachments::{attachments_dir, migrate_legacy_attachments};
+use crate::crypto::{hash_master_password, verify_master_password, KdfParams};
+use crate::error::validate_input;
+use crate::models::VaultState;
+use crate::storage::{rewrap_password, save_vault_to_disk};
+
+/// Changes the master password of the vault at `vault_path`, returning the
+/// rewrapped state to keep in memory. `state` must be the unlocked state.
+pub fn change_master_password(
+    vault_path: &Path,
+    state: &VaultState,
+    old_password: &str,
+    new_password: &str,
+) -> Result<VaultState, String> {
+    validate_input(new_password, 8, 128, "Yeni ana şifre").map_err(|e| e.to_string())?;
+
+    let stored_hash = state
+        .master_password_hash
+        .as_deref()
+        .ok_or_else(|| "Hash bulunamadı".to_string())?;
+    if !verify_master_password(old_password, stored_hash)? {
+        return Err("Mevcut ana şifre yanlış".to_string());
+    }
+    if old_password == new_password {
+        return Err("Yeni ana şifre mevcut şifreyle aynı olamaz".to_string());
+    }
+
+    let keys = state
+        .keys
+        .as_ref()
+        .ok_or_else(|| "Kasa anahtarı yüklenmedi".to_string())?;
+
+    // Attachments still keyed by the old password would be lost after the change.
+    migrate_legacy_attachments(&attachments_dir(vault_path)?, old_password, &keys.data_key)?;
+
+    let kdf = if keys.password_slot.kdf.is_legacy() {
+        KdfParams::default()
+    } else {
+        keys.password_slot.kdf
+    };
+
+    let mut new_state = state.clone();
+    new_state.master_password_hash = Some(hash_master_password(new_password)?);
+    rewrap_password(&mut new_state, new_password, kdf)?;
+
+    save_vault_to_disk(vault_path, &new_state)
+        .map_err(|e| format!("Kasa yeni şifreyle kaydedilemedi: {}", e))?;
+
+    Ok(new_state)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::attachments::{read_attachment, write_attachment};
+    use crate::storage::{init_keys, load_vault_from_disk, VAULT_FILE_NAME};
+
+    fn setup(dir: &Path) -> (std::path::PathBuf, VaultState) {
+        let vault_path = dir.join(VAULT_FILE_NAME);
+        let mut state = VaultState {
+            master_password_hash: Some(hash_master_password("eski-sifre-123").unwrap()),
+            vault_locked: false,
+            ..VaultState::default()
+        };
+        init_keys(&mut state, "eski-sifre-123").unwrap();
+        save_vault_to_disk(&vault_path, &state).unwrap();
+
+        let att_dir = attachments_dir(&vault_path).unwrap();
+        let key = &state.keys.as_ref().unwrap().data_key;
+        write_attachment(&att_dir, "att_1", b"ek icerigi", key).unwrap();
+
+        (vault_path, state)
+    }
+
+    #[test]
+    fn change_rewraps_key_and_keeps_attachments_readable() {
+        let dir = tempfile::tempdir().unwrap();
+        let (vault_path, state) = setup(dir.path());
+
+        change_master_password(&vault_path, &state, "eski-sifre-123", "yeni-sifre-456").unwrap();
+
+        assert!(load_vault_from_disk(&vault_path, "eski-sifre-123").is_err());
+        let loaded = load_vault_from_disk(&vault_path, "yeni-sifre-456").unwrap();
+
+        let att_dir = attachments_dir(&vault_path).unwrap();
+        let key = &loaded.keys.as_ref().unwrap().data_key;
+        assert_eq!(
+            read_attachment(&att_dir, "att_1", key).unwrap(),
+            b"ek icerigi"
+        );
+    }
+
+    #[test]
+    fn change_rejects_wrong_old_password() {
+        let dir = tempfile::tempdir().unwrap();
+        let (vault_path, state) = setup(dir.path());
+
+        let err = change_master_password(&vault_path, &state, "yanlis-sifre", "yeni-sifre-456")
+            .unwrap_err();
+        assert_eq!(err, "Mevcut ana şifre yanlış");
+        assert!(load_vault_from_disk(&vault_path, "eski-sifre-123").is_ok());
+    }
+}
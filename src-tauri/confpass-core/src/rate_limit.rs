@@ -0,0 +1,268 @@
+//! Persistent unlock rate limiting.
+//!
+//! Failed unlock attempts are counted in `unlock_attempts.json` next to
+//! `vault.dat`, so restarting the app no longer resets them. The file carries
+//! an HMAC-SHA256 under a key supplied by the caller; a file that fails
+//! verification, or is missing while the vault exists, is treated as if the
+//! free attempts had been used up rather than as a fresh start. After
+//! [`FREE_ATTEMPTS`] failures every further failure doubles the lockout,
+//! starting at 30 seconds and capped at an hour.
+
+use base64::{engine::general_purpose, Engine as _};
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::error::VaultError;
+use crate::storage::write_atomic;
+
+pub const ATTEMPTS_FILE_NAME: &str = "unlock_attempts.json";
+pub const FREE_ATTEMPTS: u32 = 5;
+const BASE_LOCKOUT_SECS: u64 = 30;
+const MAX_LOCKOUT_SECS: u64 = 60 * 60;
+const MAC_CONTEXT: &[u8] = b"confpass-unlock-attempts-v1";
+
+type HmacSha256 = Hmac<Sha256>;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AttemptState {
+    pub failed_attempts: u32,
+    /// Unix time of the last failure, in seconds.
+    pub last_failure: u64,
+}
+
+impl AttemptState {
+    /// Lockout that follows the current number of failures.
+    pub fn lockout_secs(&self) -> u64 {
+        if self.failed_attempts < FREE_ATTEMPTS {
+            return 0;
+        }
+        let doublings = (self.failed_attempts - FREE_ATTEMPTS).min(16);
+        (BASE_LOCKOUT_SECS << doublings).min(MAX_LOCKOUT_SECS)
+    }
+
+    /// Seconds left before the next attempt is allowed. A clock set back
+    /// before the last failure restarts the full lockout.
+    pub fn remaining_lockout(&self, now: u64) -> u64 {
+        let elapsed = now.saturating_sub(self.last_failure);
+        self.lockout_secs().saturating_sub(elapsed)
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct AttemptsFile {
+    failed_attempts: u32,
+    last_failure: u64,
+    mac: String,
+}
+
+pub struct AttemptStore {
+    path: PathBuf,
+    vault_path: PathBuf,
+    mac_key: Vec<u8>,
+}
+
+impl AttemptStore {
+    pub fn new(vault_path: &Path, mac_key: &[u8]) -> Result<Self, String> {
+        let path = vault_path
+            .parent()
+            .ok_or_else(|| "Vault path parent bulunamadı".to_string())?
+            .join(ATTEMPTS_FILE_NAME);
+        Ok(Self {
+            path,
+            vault_path: vault_path.to_path_buf(),
+            mac_key: mac_key.to_vec(),
+        })
+    }
+
+    fn mac(&self, state: &AttemptState) -> Result<HmacSha256, String> {
+        let mut mac = HmacSha256::new_from_slice(&self.mac_key)
+            .map_err(|e| format!("HMAC oluşturulamadı: {}", e))?;
+        mac.update(MAC_CONTEXT);
+        mac.update(&state.failed_attempts.to_le_bytes());
+        mac.update(&state.last_failure.to_le_bytes());
+        Ok(mac)
+    }
+
+    fn save(&self, state: &AttemptState) -> Result<(), String> {
+        let file = AttemptsFile {
+            failed_attempts: state.failed_attempts,
+            last_failure: state.last_failure,
+            mac: general_purpose::STANDARD.encode(self.mac(state)?.finalize().into_bytes()),
+        };
+        let json =
+            serde_json::to_vec(&file).map_err(|e| format!("JSON serialize hatası: {}", e))?;
+        write_atomic(&self.path, &json)
+    }
+
+    fn verify(&self, contents: &[u8]) -> Option<AttemptState> {
+        let file: AttemptsFile = serde_json::from_slice(contents).ok()?;
+        let state = AttemptState {
+            failed_attempts: file.failed_attempts,
+            last_failure: file.last_failure,
+        };
+        let tag = general_purpose::STANDARD.decode(file.mac).ok()?;
+        self.mac(&state).ok()?.verify_slice(&tag).ok()?;
+        Some(state)
+    }
+
+    /// Reads the counter. A tampered file, or one deleted while the vault
+    /// exists, is replaced by one that has just used up the free attempts, so
+    /// editing or removing it never grants extra tries.
+    pub fn load(&self, now: u64) -> Result<AttemptState, String> {
+        let verified = match fs::read(&self.path) {
+            Ok(contents) => self.verify(&contents),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                if !self.vault_path.exists() {
+                    return Ok(AttemptState::default());
+                }
+                None
+            }
+            Err(e) => return Err(format!("Deneme sayacı okunamadı: {}", e)),
+        };
+
+        match verified {
+            Some(state) => Ok(state),
+            None => self.use_up_free_attempts(now),
+        }
+    }
+
+    fn use_up_free_attempts(&self, now: u64) -> Result<AttemptState, String> {
+        let state = AttemptState {
+            failed_attempts: FREE_ATTEMPTS,
+            last_failure: now,
+        };
+        self.save(&state)?;
+        Ok(state)
+    }
+
+    /// Starts the counter under a MAC key that was just created. Only a first
+    /// run, with neither a vault nor a counter on disk, starts from zero. If
+    /// either exists the previous key was removed, and the counter is treated
+    /// like one that fails verification.
+    pub fn adopt_new_key(&self, now: u64) -> Result<(), String> {
+        if self.vault_path.exists() || self.path.exists() {
+            self.use_up_free_attempts(now).map(|_| ())
+        } else {
+            self.reset()
+        }
+    }
+
+    /// Fails with [`VaultError::RateLimited`] while a lockout is running.
+    pub fn check(&self, now: u64) -> Result<AttemptState, VaultError> {
+        let state = self.load(now).map_err(VaultError::InternalError)?;
+        match state.remaining_lockout(now) {
+            0 => Ok(state),
+            remaining => Err(VaultError::RateLimited(remaining)),
+        }
+    }
+
+    pub fn record_failure(&self, now: u64) -> Result<AttemptState, String> {
+        let mut state = self.load(now)?;
+        state.failed_attempts = state.failed_attempts.saturating_add(1);
+        state.last_failure = now;
+        self.save(&state)?;
+        Ok(state)
+    }
+
+    /// Starts the counter over. The file is rewritten rather than removed,
+    /// since a missing file counts as tampering.
+    pub fn reset(&self) -> Result<(), String> {
+        self.save(&AttemptState::default())
+            .map_err(|e| format!("Deneme sayacı sıfırlanamadı: {}", e))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::VAULT_FILE_NAME;
+
+    #[test]
+    fn lockout_doubles_after_free_attempts_and_persists() {
+        let dir = tempfile::tempdir().unwrap();
+        let vault_path = dir.path().join(VAULT_FILE_NAME);
+        let store = AttemptStore::new(&vault_path, b"anahtar").unwrap();
+
+        for _ in 0..FREE_ATTEMPTS - 1 {
+            store.record_failure(1000).unwrap();
+        }
+        assert!(store.check(1000).is_ok());
+
+        store.record_failure(1000).unwrap();
+        let reopened = AttemptStore::new(&vault_path, b"anahtar").unwrap();
+        assert!(matches!(
+            reopened.check(1010),
+            Err(VaultError::RateLimited(20))
+        ));
+        assert!(reopened.check(1030).is_ok());
+
+        let state = reopened.record_failure(1030).unwrap();
+        assert_eq!(state.remaining_lockout(1030), 60);
+
+        reopened.reset().unwrap();
+        assert_eq!(store.load(1030).unwrap(), AttemptState::default());
+    }
+
+    #[test]
+    fn tampered_counter_does_not_reset_attempts() {
+        let dir = tempfile::tempdir().unwrap();
+        let vault_path = dir.path().join(VAULT_FILE_NAME);
+        let store = AttemptStore::new(&vault_path, b"anahtar").unwrap();
+        store.record_failure(1000).unwrap();
+
+        let path = dir.path().join(ATTEMPTS_FILE_NAME);
+        let edited = fs::read_to_string(&path)
+            .unwrap()
+            .replace("\"failed_attempts\":1", "\"failed_attempts\":0");
+        fs::write(&path, edited).unwrap();
+
+        assert!(matches!(
+            store.check(2000),
+            Err(VaultError::RateLimited(30))
+        ));
+        assert_eq!(store.load(2000).unwrap().failed_attempts, FREE_ATTEMPTS);
+    }
+
+    #[test]
+    fn deleted_counter_counts_as_tampering_once_a_vault_exists() {
+        let dir = tempfile::tempdir().unwrap();
+        let vault_path = dir.path().join(VAULT_FILE_NAME);
+        let store = AttemptStore::new(&vault_path, b"anahtar").unwrap();
+        assert_eq!(store.load(1000).unwrap(), AttemptState::default());
+
+        fs::write(&vault_path, b"kasa").unwrap();
+        store.reset().unwrap();
+        assert_eq!(store.load(1000).unwrap(), AttemptState::default());
+
+        fs::remove_file(dir.path().join(ATTEMPTS_FILE_NAME)).unwrap();
+        assert!(matches!(
+            store.check(1000),
+            Err(VaultError::RateLimited(30))
+        ));
+    }
+
+    #[test]
+    fn new_key_only_starts_from_zero_on_a_first_run() {
+        let dir = tempfile::tempdir().unwrap();
+        let vault_path = dir.path().join(VAULT_FILE_NAME);
+        let store = AttemptStore::new(&vault_path, b"anahtar").unwrap();
+        store.adopt_new_key(1000).unwrap();
+        assert_eq!(store.load(1000).unwrap(), AttemptState::default());
+
+        store.record_failure(1000).unwrap();
+        let rekeyed = AttemptStore::new(&vault_path, b"yeni anahtar").unwrap();
+        rekeyed.adopt_new_key(2000).unwrap();
+        assert!(matches!(
+            rekeyed.check(2000),
+            Err(VaultError::RateLimited(30))
+        ));
+
+        fs::remove_file(dir.path().join(ATTEMPTS_FILE_NAME)).unwrap();
+        fs::write(&vault_path, b"kasa").unwrap();
+        rekeyed.adopt_new_key(3000).unwrap();
+        assert_eq!(rekeyed.load(3000).unwrap().failed_attempts, FREE_ATTEMPTS);
+    }
+}
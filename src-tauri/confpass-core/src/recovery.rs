@@ -0,0 +1,211 @@
+//! Offline recovery key.
+//!
+//! A random code, shown to the user once to print or scan, wraps the vault
+//! data key a second time in `recovery.key` next to `vault.dat`. Because it
+//! wraps the data key rather than the password, the code stays valid across
+//! master password changes. Opening the vault with it always sets a new
+//! master password.
+
+use base64::{engine::general_purpose, Engine as _};
+use rand::{rngs::OsRng, RngCore};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::crypto::{hash_master_password, KdfParams};
+use crate::error::validate_input;
+use crate::keys::{DataKey, VaultKeys, WrappedKey};
+use crate::models::VaultState;
+use crate::storage::{load_vault_with_key, rewrap_password, save_vault_to_disk, write_atomic};
+
+pub const RECOVERY_FILE_NAME: &str = "recovery.key";
+const FILE_VERSION: u32 = 1;
+/// 160 bits, printed as 32 base32 characters.
+const CODE_BYTES: usize = 20;
+const GROUP_LEN: usize = 4;
+/// The code already carries full entropy, so a light KDF is enough.
+const RECOVERY_KDF: KdfParams = KdfParams::Argon2id {
+    memory_kib: 19456,
+    iterations: 2,
+    parallelism: 1,
+};
+
+#[derive(Serialize, Deserialize)]
+struct RecoveryFile {
+    version: u32,
+    kdf: KdfParams,
+    salt: String,
+    wrapped: String,
+}
+
+pub fn recovery_path(vault_path: &Path) -> Result<PathBuf, String> {
+    Ok(vault_path
+        .parent()
+        .ok_or_else(|| "Vault path parent bulunamadı".to_string())?
+        .join(RECOVERY_FILE_NAME))
+}
+
+pub fn has_recovery_key(vault_path: &Path) -> bool {
+    recovery_path(vault_path)
+        .map(|p| p.exists())
+        .unwrap_or(false)
+}
+
+/// Generates a printable code such as `ABCD-EFGH-...` (8 groups of 4).
+pub fn generate_recovery_code() -> String {
+    let mut bytes = [0u8; CODE_BYTES];
+    OsRng.fill_bytes(&mut bytes);
+    let encoded = base32::encode(base32::Alphabet::RFC4648 { padding: false }, &bytes);
+
+    encoded
+        .as_bytes()
+        .chunks(GROUP_LEN)
+        .map(|chunk| String::from_utf8_lossy(chunk).into_owned())
+        .collect::<Vec<_>>()
+        .join("-")
+}
+
+/// Accepts the code as typed: case, dashes and spaces are ignored.
+pub fn normalize_recovery_code(code: &str) -> String {
+    code.chars()
+        .filter(|c| c.is_ascii_alphanumeric())
+        .map(|c| c.to_ascii_uppercase())
+        .collect()
+}
+
+/// Creates a new recovery code for the unlocked vault, replacing any previous
+/// one. The code is returned once and never stored.
+pub fn create_recovery_key(vault_path: &Path, keys: &VaultKeys) -> Result<String, String> {
+    let code = generate_recovery_code();
+    let slot = WrappedKey::wrap(
+        &keys.data_key,
+        &normalize_recovery_code(&code),
+        RECOVERY_KDF,
+    )?;
+
+    let file = RecoveryFile {
+        version: FILE_VERSION,
+        kdf: slot.kdf,
+        salt: general_purpose::STANDARD.encode(&slot.salt),
+        wrapped: general_purpose::STANDARD.encode(&slot.wrapped),
+    };
+    let json = serde_json::to_vec(&file).map_err(|e| format!("JSON serialize hatası: {}", e))?;
+    write_atomic(&recovery_path(vault_path)?, &json)?;
+
+    Ok(code)
+}
+
+fn read_recovery_slot(vault_path: &Path) -> Result<WrappedKey, String> {
+    let path = recovery_path(vault_path)?;
+    if !path.exists() {
+        return Err("Bu kasa için kurtarma anahtarı oluşturulmamış".to_string());
+    }
+
+    let contents = fs::read(&path).map_err(|e| format!("Kurtarma dosyası okunamadı: {}", e))?;
+    let file: RecoveryFile =
+        serde_json::from_slice(&contents).map_err(|e| format!("Kurtarma dosyası bozuk: {}", e))?;
+    if file.version != FILE_VERSION {
+        return Err(format!(
+            "Desteklenmeyen kurtarma dosyası sürümü: {}",
+            file.version
+        ));
+    }
+
+    let decode = |value: &str| {
+        general_purpose::STANDARD
+            .decode(value)
+            .map_err(|e| format!("Kurtarma dosyası bozuk: {}", e))
+    };
+    Ok(WrappedKey {
+        kdf: file.kdf,
+        salt: decode(&file.salt)?,
+        wrapped: decode(&file.wrapped)?,
+    })
+}
+
+fn unwrap_with_code(vault_path: &Path, code: &str) -> Result<DataKey, String> {
+    read_recovery_slot(vault_path)?
+        .unwrap(&normalize_recovery_code(code))
+        .map_err(|_| "Kurtarma anahtarı geçersiz".to_string())
+}
+
+/// Opens the vault with a recovery code and replaces the master password with
+/// `new_password`, returning the unlocked state.
+pub fn unlock_with_recovery_code(
+    vault_path: &Path,
+    code: &str,
+    new_password: &str,
+) -> Result<VaultState, String> {
+    validate_input(new_password, 8, 128, "Yeni ana şifre").map_err(|e| e.to_string())?;
+
+    let data_key = unwrap_with_code(vault_path, code)?;
+    let mut state = load_vault_with_key(vault_path, data_key)?;
+
+    let kdf = match state.keys.as_ref() {
+        Some(keys) if !keys.password_slot.kdf.is_legacy() => keys.password_slot.kdf,
+        _ => KdfParams::default(),
+    };
+    state.master_password_hash = Some(hash_master_password(new_password)?);
+    rewrap_password(&mut state, new_password, kdf)?;
+
+    save_vault_to_disk(vault_path, &state)
+        .map_err(|e| format!("Kasa yeni şifreyle kaydedilemedi: {}", e))?;
+
+    Ok(state)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crypto::verify_master_password;
+    use crate::storage::{init_keys, load_vault_from_disk, VAULT_FILE_NAME};
+
+    fn setup(dir: &Path) -> (PathBuf, VaultState) {
+        let vault_path = dir.join(VAULT_FILE_NAME);
+        let mut state = VaultState {
+            master_password_hash: Some(hash_master_password("eski-sifre-123").unwrap()),
+            ..VaultState::default()
+        };
+        init_keys(&mut state, "eski-sifre-123").unwrap();
+        save_vault_to_disk(&vault_path, &state).unwrap();
+        (vault_path, state)
+    }
+
+    #[test]
+    fn recovery_code_sets_new_master_password() {
+        let dir = tempfile::tempdir().unwrap();
+        let (vault_path, state) = setup(dir.path());
+        let code = create_recovery_key(&vault_path, state.keys.as_ref().unwrap()).unwrap();
+        assert_eq!(code.len(), 39);
+
+        let typed = code.to_lowercase().replace('-', " ");
+        let recovered = unlock_with_recovery_code(&vault_path, &typed, "yeni-sifre-456").unwrap();
+        assert!(verify_master_password(
+            "yeni-sifre-456",
+            recovered.master_password_hash.as_deref().unwrap()
+        )
+        .unwrap());
+
+        assert!(load_vault_from_disk(&vault_path, "eski-sifre-123").is_err());
+        let loaded = load_vault_from_disk(&vault_path, "yeni-sifre-456").unwrap();
+        assert_eq!(
+            loaded.keys.unwrap().data_key.as_bytes(),
+            state.keys.unwrap().data_key.as_bytes()
+        );
+    }
+
+    #[test]
+    fn wrong_recovery_code_is_rejected() {
+        let dir = tempfile::tempdir().unwrap();
+        let (vault_path, state) = setup(dir.path());
+
+        let err = unlock_with_recovery_code(&vault_path, "yanlis", "yeni-sifre-456").unwrap_err();
+        assert_eq!(err, "Bu kasa için kurtarma anahtarı oluşturulmamış");
+
+        create_recovery_key(&vault_path, state.keys.as_ref().unwrap()).unwrap();
+        let other = generate_recovery_code();
+        let err = unlock_with_recovery_code(&vault_path, &other, "yeni-sifre-456").unwrap_err();
+        assert_eq!(err, "Kurtarma anahtarı geçersiz");
+        assert!(load_vault_from_disk(&vault_path, "eski-sifre-123").is_ok());
+    }
+}
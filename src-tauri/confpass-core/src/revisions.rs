@@ -0,0 +1,246 @@
+//! Entry revisions.
+//!
+//! Every change to an entry's fields is kept inside the encrypted vault as an
+//! [`EntryRevision`] listing each changed field with its old and new value, at
+//! most [`MAX_REVISIONS_PER_ENTRY`] per entry and oldest first. Version `n` of
+//! an entry is the entry after its first `n` revisions, so the live entry is
+//! version `revisions.len()`; older versions are rebuilt by undoing the newer
+//! revisions one by one. Bookkeeping fields (id, timestamps, attachments and
+//! the trash marker) are not tracked.
+
+use serde_json::{Map, Value};
+
+use crate::error::VaultError;
+use crate::history;
+use crate::models::{EntryRevision, FieldChange, PasswordEntry, RevisionSource, VaultState};
+
+pub const MAX_REVISIONS_PER_ENTRY: usize = 50;
+
+const UNTRACKED_FIELDS: &[&str] = &[
+    "id",
+    "created_at",
+    "updated_at",
+    "attachments",
+    "deleted_at",
+];
+
+fn tracked_fields(entry: &PasswordEntry) -> Result<Map<String, Value>, String> {
+    let mut fields = match serde_json::to_value(entry) {
+        Ok(Value::Object(fields)) => fields,
+        Ok(_) => return Err("Kayıt JSON nesnesi değil".to_string()),
+        Err(e) => return Err(format!("JSON serialize hatası: {}", e)),
+    };
+    for field in UNTRACKED_FIELDS {
+        fields.remove(*field);
+    }
+    Ok(fields)
+}
+
+/// Fields that differ between `old` and `new`, in field name order.
+pub fn diff_entries(old: &PasswordEntry, new: &PasswordEntry) -> Result<Vec<FieldChange>, String> {
+    let old = tracked_fields(old)?;
+    let new = tracked_fields(new)?;
+
+    let mut names: Vec<&String> = old.keys().chain(new.keys()).collect();
+    names.sort();
+    names.dedup();
+
+    Ok(names
+        .into_iter()
+        .filter_map(|name| {
+            let old_value = old.get(name).cloned().unwrap_or(Value::Null);
+            let new_value = new.get(name).cloned().unwrap_or(Value::Null);
+            (old_value != new_value).then(|| FieldChange {
+                field: name.clone(),
+                old_value,
+                new_value,
+            })
+        })
+        .collect())
+}
+
+/// Records how entry `id` differs from `before`. Nothing is recorded when no
+/// tracked field changed. Returns whether a revision was added.
+pub fn record_change(
+    state: &mut VaultState,
+    id: &str,
+    before: &PasswordEntry,
+    source: RevisionSource,
+    changed_at: i64,
+) -> Result<bool, String> {
+    let entry = state
+        .entries
+        .get(id)
+        .ok_or_else(|| VaultError::NotFound.to_string())?;
+    let changes = diff_entries(before, entry)?;
+    if changes.is_empty() {
+        return Ok(false);
+    }
+
+    let revisions = state.revisions.entry(id.to_string()).or_default();
+    revisions.push(EntryRevision {
+        changed_at,
+        source,
+        changes,
+    });
+    if revisions.len() > MAX_REVISIONS_PER_ENTRY {
+        revisions.drain(..revisions.len() - MAX_REVISIONS_PER_ENTRY);
+    }
+    Ok(true)
+}
+
+/// Revisions of entry `id`, oldest first.
+pub fn revisions<'a>(state: &'a VaultState, id: &str) -> &'a [EntryRevision] {
+    state
+        .revisions
+        .get(id)
+        .map(Vec::as_slice)
+        .unwrap_or_default()
+}
+
+/// Entry `id` as it was at `version`; see the module docs.
+pub fn entry_at(state: &VaultState, id: &str, version: usize) -> Result<PasswordEntry, String> {
+    let entry = state
+        .entries
+        .get(id)
+        .ok_or_else(|| VaultError::NotFound.to_string())?;
+    let revisions = revisions(state, id);
+    if version > revisions.len() {
+        return Err("Sürüm bulunamadı".to_string());
+    }
+
+    let mut fields = match serde_json::to_value(entry) {
+        Ok(Value::Object(fields)) => fields,
+        _ => return Err("Kayıt JSON nesnesi değil".to_string()),
+    };
+    for revision in revisions[version..].iter().rev() {
+        for change in &revision.changes {
+            fields.insert(change.field.clone(), change.old_value.clone());
+        }
+    }
+    serde_json::from_value(Value::Object(fields))
+        .map_err(|e| format!("Sürüm oluşturulamadı: {}", e))
+}
+
+/// Field changes that lead from version `from` to version `to` of entry `id`.
+pub fn diff_versions(
+    state: &VaultState,
+    id: &str,
+    from: usize,
+    to: usize,
+) -> Result<Vec<FieldChange>, String> {
+    diff_entries(&entry_at(state, id, from)?, &entry_at(state, id, to)?)
+}
+
+/// Puts entry `id` back to `version`. The rollback is itself recorded as a
+/// revision, so it can be undone the same way, and a replaced password goes
+/// to the password history.
+pub fn rollback(
+    state: &mut VaultState,
+    id: &str,
+    version: usize,
+    source: RevisionSource,
+    now: i64,
+) -> Result<PasswordEntry, String> {
+    let target = entry_at(state, id, version)?;
+    let entry = state
+        .entries
+        .get_mut(id)
+        .ok_or_else(|| VaultError::NotFound.to_string())?;
+    let before = entry.clone();
+
+    *entry = PasswordEntry {
+        id: before.id.clone(),
+        created_at: before.created_at,
+        updated_at: now,
+        attachments: before.attachments.clone(),
+        deleted_at: before.deleted_at,
+        ..target
+    };
+    let restored = entry.clone();
+
+    if restored.password != before.password {
+        history::record_password_change(state, id, before.password.clone(), now);
+    }
+    record_change(state, id, &before, source, now)?;
+    Ok(restored)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(id: &str) -> PasswordEntry {
+        PasswordEntry {
+            id: id.to_string(),
+            title: "Örnek".to_string(),
+            username: "kullanici".to_string(),
+            password: "parola-1".to_string(),
+            url: None,
+            notes: None,
+            created_at: 0,
+            updated_at: 0,
+            category: "accounts".to_string(),
+            folder_id: None,
+            tags: None,
+            extra_fields: None,
+            attachments: None,
+            data: None,
+            deleted_at: None,
+        }
+    }
+
+    fn edit(state: &mut VaultState, source: RevisionSource, f: impl FnOnce(&mut PasswordEntry)) {
+        let before = state.entries["e1"].clone();
+        f(state.entries.get_mut("e1").unwrap());
+        record_change(state, "e1", &before, source, 10).unwrap();
+    }
+
+    #[test]
+    fn changes_are_recorded_per_field() {
+        let mut state = VaultState::default();
+        state.entries.insert("e1".to_string(), entry("e1"));
+
+        edit(&mut state, RevisionSource::Ui, |e| e.updated_at = 5);
+        assert!(revisions(&state, "e1").is_empty());
+
+        edit(&mut state, RevisionSource::Extension, |e| {
+            e.password = "parola-2".to_string();
+            e.url = Some("https://example.com".to_string());
+        });
+        let revision = &revisions(&state, "e1")[0];
+        assert_eq!(revision.source, RevisionSource::Extension);
+        let fields: Vec<_> = revision.changes.iter().map(|c| c.field.as_str()).collect();
+        assert_eq!(fields, vec!["password", "url"]);
+        assert_eq!(revision.changes[1].old_value, Value::Null);
+    }
+
+    #[test]
+    fn rollback_restores_an_earlier_version() {
+        let mut state = VaultState::default();
+        state.entries.insert("e1".to_string(), entry("e1"));
+        edit(&mut state, RevisionSource::Ui, |e| {
+            e.notes = Some("not".to_string())
+        });
+        edit(&mut state, RevisionSource::Extension, |e| {
+            e.title = "Bozuk".to_string();
+            e.password = "parola-2".to_string();
+        });
+
+        assert_eq!(entry_at(&state, "e1", 1).unwrap().title, "Örnek");
+        assert!(entry_at(&state, "e1", 3).is_err());
+        let diff = diff_versions(&state, "e1", 0, 2).unwrap();
+        assert_eq!(diff.len(), 3);
+
+        let restored = rollback(&mut state, "e1", 1, RevisionSource::Ui, 20).unwrap();
+        assert_eq!(restored.title, "Örnek");
+        assert_eq!(restored.password, "parola-1");
+        assert_eq!(restored.notes.as_deref(), Some("not"));
+        assert_eq!(revisions(&state, "e1").len(), 3);
+        assert_eq!(state.password_history["e1"][0].password, "parola-2");
+
+        // The rollback itself can be undone
+        let undone = rollback(&mut state, "e1", 2, RevisionSource::Ui, 30).unwrap();
+        assert_eq!(undone.title, "Bozuk");
+    }
+}
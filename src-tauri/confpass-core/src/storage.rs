@@ -0,0 +1,444 @@
+use base64::{engine::general_purpose, Engine as _};
+use std::collections::HashMap;
+use std::env;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use crate::attachments;
+use crate::container;
+use crate::crypto::{decrypt_vault_data, KdfParams};
+use crate::entry_data;
+use crate::history;
+use crate::keys::{DataKey, VaultKeys};
+use crate::models::{PasswordEntry, VaultData, VaultState};
+use crate::passkeys;
+use crate::trash;
+
+pub const VAULT_FILE_NAME: &str = "vault.dat";
+pub const SALT_FILE_NAME: &str = "vault.salt";
+
+/// Platform data directory shared by the desktop app and the native host.
+pub fn default_data_dir() -> Result<PathBuf, String> {
+    if cfg!(windows) {
+        env::var("APPDATA")
+            .map(PathBuf::from)
+            .map_err(|_| "APPDATA environment variable bulunamadı".to_string())
+            .map(|p| p.join("ConfPass"))
+    } else if cfg!(target_os = "macos") {
+        let home =
+            env::var("HOME").map_err(|_| "HOME environment variable bulunamadı".to_string())?;
+        Ok(PathBuf::from(home)
+            .join("Library")
+            .join("Application Support")
+            .join("ConfPass"))
+    } else {
+        let home =
+            env::var("HOME").map_err(|_| "HOME environment variable bulunamadı".to_string())?;
+        Ok(PathBuf::from(home).join(".config").join("confpass"))
+    }
+}
+
+fn vault_dir(vault_path: &Path) -> Result<&Path, String> {
+    vault_path
+        .parent()
+        .ok_or_else(|| "Vault path parent bulunamadı".to_string())
+}
+
+/// Writes `contents` next to `path` and renames it into place so readers
+/// never observe a half-written file.
+pub fn write_atomic(path: &Path, contents: &[u8]) -> Result<(), String> {
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    write_atomic_with(path, contents, &options)
+}
+
+/// Like [`write_atomic`], for secrets such as client tokens: on Unix the
+/// file is readable by its owner only.
+pub fn write_private(path: &Path, contents: &[u8]) -> Result<(), String> {
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+    write_atomic_with(path, contents, &options)
+}
+
+fn write_atomic_with(
+    path: &Path,
+    contents: &[u8],
+    options: &fs::OpenOptions,
+) -> Result<(), String> {
+    let mut tmp_name = path.as_os_str().to_owned();
+    tmp_name.push(".tmp");
+    let tmp_path = PathBuf::from(tmp_name);
+
+    // A leftover from an interrupted write would keep its old permissions
+    let _ = fs::remove_file(&tmp_path);
+    let mut file = options
+        .open(&tmp_path)
+        .map_err(|e| format!("Geçici dosya oluşturulamadı: {} (Path: {:?})", e, tmp_path))?;
+    file.write_all(contents)
+        .map_err(|e| format!("Dosya yazma hatası: {}", e))?;
+    file.sync_all()
+        .map_err(|e| format!("Dosya senkronizasyon hatası: {}", e))?;
+    drop(file); // Dosya handle'ını kapat
+
+    fs::rename(&tmp_path, path).map_err(|e| format!("Dosya değiştirme (rename) hatası: {}", e))
+}
+
+/// Overwrites `path` with zeros before deleting it. Best effort only:
+/// journaling filesystems and SSDs may keep older copies of the blocks.
+pub fn secure_delete(path: &Path) -> Result<(), String> {
+    let len = fs::metadata(path)
+        .map_err(|e| format!("Dosya okunamadı: {}", e))?
+        .len();
+
+    let mut file = fs::OpenOptions::new()
+        .write(true)
+        .open(path)
+        .map_err(|e| format!("Dosya açılamadı: {}", e))?;
+    file.write_all(&vec![0u8; len as usize])
+        .map_err(|e| format!("Dosya yazma hatası: {}", e))?;
+    file.sync_all()
+        .map_err(|e| format!("Dosya senkronizasyon hatası: {}", e))?;
+    drop(file);
+
+    fs::remove_file(path).map_err(|e| format!("Dosya silinemedi: {}", e))
+}
+
+pub fn save_vault_to_disk(vault_path: &Path, state: &VaultState) -> Result<(), String> {
+    let keys = state
+        .keys
+        .as_ref()
+        .ok_or_else(|| "Kasa anahtarı yüklenmedi".to_string())?;
+
+    let vault_dir = vault_dir(vault_path)?;
+
+    fs::create_dir_all(vault_dir).map_err(|e| format!("Vault dizini oluşturulamadı: {}", e))?;
+
+    let entries_vec: Vec<PasswordEntry> = state.entries.values().cloned().collect();
+    let vault_data = VaultData {
+        entries: entries_vec,
+        master_password_hash: state
+            .master_password_hash
+            .clone()
+            .ok_or_else(|| "Master password hash bulunamadı".to_string())?,
+        encryption_salt: general_purpose::STANDARD.encode(&keys.password_slot.salt),
+        folders: state.folders.clone(),
+        tags: state.tags.clone(),
+        // History of deleted entries goes with them
+        password_history: state
+            .password_history
+            .iter()
+            .filter(|(id, _)| state.entries.contains_key(*id))
+            .map(|(id, history)| (id.clone(), history.clone()))
+            .collect(),
+        passkeys: passkeys::retained_passkeys(state),
+        revisions: state
+            .revisions
+            .iter()
+            .filter(|(id, _)| state.entries.contains_key(*id))
+            .map(|(id, revisions)| (id.clone(), revisions.clone()))
+            .collect(),
+    };
+
+    let json_data =
+        serde_json::to_string(&vault_data).map_err(|e| format!("JSON serialize hatası: {}", e))?;
+
+    let sealed = container::seal(json_data.as_bytes(), keys)?;
+
+    write_atomic(vault_path, &sealed)?;
+
+    // The salt now lives in the container header; drop the v0 side file.
+    let legacy_salt_path = vault_dir.join(SALT_FILE_NAME);
+    if legacy_salt_path.exists() {
+        fs::remove_file(&legacy_salt_path)
+            .map_err(|e| format!("Eski salt dosyası silinemedi: {}", e))?;
+    }
+
+    Ok(())
+}
+
+fn set_keys(state: &mut VaultState, keys: VaultKeys) {
+    state.encryption_salt = Some(general_purpose::STANDARD.encode(&keys.password_slot.salt));
+    state.keys = Some(keys);
+}
+
+/// Gives a new vault a random data key wrapped under `master_password`.
+pub fn init_keys(state: &mut VaultState, master_password: &str) -> Result<(), String> {
+    set_keys(
+        state,
+        VaultKeys::generate(master_password, KdfParams::default())?,
+    );
+    Ok(())
+}
+
+/// Rewraps the data key under `master_password` with `kdf`. The vault file
+/// only changes once the caller saves it again.
+pub fn rewrap_password(
+    state: &mut VaultState,
+    master_password: &str,
+    kdf: KdfParams,
+) -> Result<(), String> {
+    let mut keys = state
+        .keys
+        .clone()
+        .ok_or_else(|| "Kasa anahtarı yüklenmedi".to_string())?;
+    keys.rewrap_password(master_password, kdf)?;
+    set_keys(state, keys);
+    Ok(())
+}
+
+/// KDF for vaults migrated from a format without a data key: PBKDF2 vaults
+/// are moved to Argon2id at the same time.
+fn migration_kdf(kdf: KdfParams) -> KdfParams {
+    if kdf.is_legacy() {
+        KdfParams::default()
+    } else {
+        kdf
+    }
+}
+
+fn state_from_vault_data(vault_data: VaultData, keys: VaultKeys) -> VaultState {
+    let mut state = vault_state(vault_data);
+    set_keys(&mut state, keys);
+    state
+}
+
+/// Builds the in-memory state of a decrypted vault, without keys.
+pub(crate) fn vault_state(vault_data: VaultData) -> VaultState {
+    let mut entries = HashMap::with_capacity(vault_data.entries.len());
+    for entry in vault_data.entries {
+        entries.insert(entry.id.clone(), entry);
+    }
+
+    let mut state = VaultState {
+        entries,
+        master_password_hash: Some(vault_data.master_password_hash),
+        folders: vault_data.folders,
+        tags: vault_data.tags,
+        password_history: vault_data.password_history,
+        passkeys: vault_data.passkeys,
+        revisions: vault_data.revisions,
+        ..VaultState::default()
+    };
+    // Older releases marked trashed entries by category and kept typed fields in notes
+    trash::migrate_entries(&mut state);
+    entry_data::migrate_entries(&mut state);
+    state
+}
+
+pub fn load_vault_from_disk(
+    vault_path: &Path,
+    master_password: &str,
+) -> Result<VaultState, String> {
+    if !vault_path.exists() {
+        return Err("Vault dosyası bulunamadı".to_string());
+    }
+
+    let raw = match fs::read(vault_path) {
+        Ok(d) => d,
+        Err(e) => return Err(format!("Dosya okuma hatası: {}", e)),
+    };
+
+    let mut state = if container::is_container(&raw) {
+        load_container_vault(vault_path, &raw, master_password)?
+    } else {
+        load_legacy_vault(vault_path, &raw, master_password)?
+    };
+
+    // Attachments written before the data key existed are still encrypted
+    // with the master password, later ones in one JSON piece; move them
+    // over to the chunked format while we have the password.
+    if let Some(keys) = state.keys.as_ref() {
+        attachments::migrate_legacy_attachments(
+            &attachments::attachments_dir(vault_path)?,
+            master_password,
+            &keys.data_key,
+        )?;
+    }
+    history::migrate_plaintext_history(vault_path, &mut state)?;
+
+    Ok(state)
+}
+
+fn load_container_vault(
+    vault_path: &Path,
+    raw: &[u8],
+    master_password: &str,
+) -> Result<VaultState, String> {
+    let opened = match container::open(raw, master_password) {
+        Ok(opened) => opened,
+        Err(e) => return Err(format!("Decrypt hatası: {}", e)),
+    };
+
+    let vault_data: VaultData = match serde_json::from_slice(&opened.plaintext) {
+        Ok(d) => d,
+        Err(e) => return Err(format!("JSON parse hatası: {}", e)),
+    };
+
+    match opened.keys {
+        Some(keys) => Ok(state_from_vault_data(vault_data, keys)),
+        None => {
+            let keys = VaultKeys::generate(master_password, migration_kdf(opened.header.kdf))?;
+            let state = state_from_vault_data(vault_data, keys);
+            save_vault_to_disk(vault_path, &state)
+                .map_err(|e| format!("Kasa yeni formata taşınamadı: {}", e))?;
+            Ok(state)
+        }
+    }
+}
+
+/// Loads the vault with a data key unwrapped from a non-password slot.
+pub fn load_vault_with_key(vault_path: &Path, data_key: DataKey) -> Result<VaultState, String> {
+    let raw = fs::read(vault_path).map_err(|e| format!("Dosya okuma hatası: {}", e))?;
+    let opened = container::open_with_key(&raw, data_key)?;
+
+    let vault_data: VaultData = serde_json::from_slice(&opened.plaintext)
+        .map_err(|e| format!("JSON parse hatası: {}", e))?;
+    let keys = opened
+        .keys
+        .ok_or_else(|| "Kasa anahtarı yüklenmedi".to_string())?;
+
+    Ok(state_from_vault_data(vault_data, keys))
+}
+
+/// Loads a v0 vault (base64 blob plus `vault.salt`) and rewrites it as a
+/// container once the password has been proven by a successful decrypt.
+fn load_legacy_vault(
+    vault_path: &Path,
+    raw: &[u8],
+    master_password: &str,
+) -> Result<VaultState, String> {
+    let decrypted_json = decrypt_legacy_vault(vault_path, raw, master_password)?;
+
+    let vault_data: VaultData = match serde_json::from_str(&decrypted_json) {
+        Ok(d) => d,
+        Err(e) => return Err(format!("JSON parse hatası: {}", e)),
+    };
+
+    let keys = VaultKeys::generate(master_password, migration_kdf(KdfParams::legacy()))?;
+    let state = state_from_vault_data(vault_data, keys);
+
+    save_vault_to_disk(vault_path, &state)
+        .map_err(|e| format!("Kasa yeni formata taşınamadı: {}", e))?;
+
+    Ok(state)
+}
+
+/// Decrypts a v0 vault with the salt from `vault.salt`, returning its JSON.
+pub(crate) fn decrypt_legacy_vault(
+    vault_path: &Path,
+    raw: &[u8],
+    master_password: &str,
+) -> Result<String, String> {
+    let salt_path = vault_dir(vault_path)?.join(SALT_FILE_NAME);
+
+    let salt = if salt_path.exists() {
+        let salt_b64 = match fs::read_to_string(&salt_path) {
+            Ok(s) => s,
+            Err(e) => return Err(format!("Salt dosyası okunamadı: {}", e)),
+        };
+        match general_purpose::STANDARD.decode(salt_b64.trim()) {
+            Ok(s) => s,
+            Err(e) => return Err(format!("Salt decode hatası: {}", e)),
+        }
+    } else {
+        // Without the salt the vault cannot be decrypted; an empty vault here
+        // would be saved over the real one.
+        return Err("Salt dosyası bulunamadı".to_string());
+    };
+
+    if salt.len() != 32 {
+        return Err("Geçersiz salt uzunluğu".to_string());
+    }
+
+    let encrypted_data = String::from_utf8_lossy(raw);
+
+    if encrypted_data.trim().is_empty() {
+        return Err("Vault dosyası boş".to_string());
+    }
+
+    decrypt_vault_data(encrypted_data.trim(), master_password, &salt)
+        .map_err(|e| format!("Decrypt hatası: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crypto::encrypt_vault_data;
+
+    #[test]
+    fn legacy_vault_is_migrated_on_load() {
+        let dir = tempfile::tempdir().unwrap();
+        let vault_path = dir.path().join(VAULT_FILE_NAME);
+        let salt = [9u8; 32];
+
+        let legacy_json = serde_json::json!({
+            "entries": [],
+            "master_password_hash": "hash",
+            "encryption_salt": general_purpose::STANDARD.encode(salt),
+        })
+        .to_string();
+        let blob = encrypt_vault_data(&legacy_json, "ana-sifre-123", &salt).unwrap();
+        fs::write(&vault_path, blob).unwrap();
+        fs::write(
+            dir.path().join(SALT_FILE_NAME),
+            general_purpose::STANDARD.encode(salt),
+        )
+        .unwrap();
+
+        let state = load_vault_from_disk(&vault_path, "ana-sifre-123").unwrap();
+        assert_eq!(state.master_password_hash.as_deref(), Some("hash"));
+
+        assert!(container::is_container(&fs::read(&vault_path).unwrap()));
+        assert!(!dir.path().join(SALT_FILE_NAME).exists());
+
+        let reloaded = load_vault_from_disk(&vault_path, "ana-sifre-123").unwrap();
+        assert_eq!(reloaded.encryption_salt, state.encryption_salt);
+        assert_eq!(
+            reloaded.keys.unwrap().password_slot.kdf,
+            KdfParams::default()
+        );
+    }
+
+    #[test]
+    fn password_rewrap_keeps_data_key() {
+        let dir = tempfile::tempdir().unwrap();
+        let vault_path = dir.path().join(VAULT_FILE_NAME);
+
+        let mut state = VaultState {
+            master_password_hash: Some("hash".to_string()),
+            ..VaultState::default()
+        };
+        init_keys(&mut state, "ana-sifre-123").unwrap();
+        save_vault_to_disk(&vault_path, &state).unwrap();
+        let data_key = state.keys.as_ref().unwrap().data_key.clone();
+
+        rewrap_password(&mut state, "yeni-sifre-456", KdfParams::default()).unwrap();
+        save_vault_to_disk(&vault_path, &state).unwrap();
+
+        assert!(load_vault_from_disk(&vault_path, "ana-sifre-123").is_err());
+        let loaded = load_vault_from_disk(&vault_path, "yeni-sifre-456").unwrap();
+        assert_eq!(
+            loaded.keys.unwrap().data_key.as_bytes(),
+            data_key.as_bytes()
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn private_files_are_readable_by_their_owner_only() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("client.token");
+        fs::write(path.with_extension("token.tmp"), b"eski").unwrap();
+        write_private(&path, b"cpt_gizli").unwrap();
+        write_private(&path, b"cpt_yeni").unwrap();
+
+        assert_eq!(fs::read(&path).unwrap(), b"cpt_yeni");
+        let mode = fs::metadata(&path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+    }
+}
@@ -0,0 +1,27 @@
+use base64::Engine as _;
+
+pub fn generate_totp_code(secret: &str) -> Result<String, String> {
+    use totp_lite::{totp_custom, Sha1};
+
+    let cleaned_secret = secret.replace(" ", "").replace("-", "").to_uppercase();
+
+    let secret_bytes = match base32::decode(
+        base32::Alphabet::RFC4648 { padding: false },
+        &cleaned_secret,
+    ) {
+        Some(bytes) if !bytes.is_empty() => bytes,
+        _ => match base64::engine::general_purpose::STANDARD.decode(secret) {
+            Ok(bytes) if !bytes.is_empty() => bytes,
+            _ => return Err("Geçersiz TOTP secret formatı".to_string()),
+        },
+    };
+
+    let timestamp = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_err(|e| format!("Zaman hatası: {}", e))?
+        .as_secs();
+
+    let code = totp_custom::<Sha1>(30, 6, secret_bytes.as_slice(), timestamp);
+
+    Ok(code)
+}
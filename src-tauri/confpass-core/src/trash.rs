@@ -0,0 +1,186 @@
+//! Trash for every entry category.
+//!
+//! Deleting an entry only stamps [`PasswordEntry::deleted_at`]; the entry keeps
+//! its category and can be restored until it is removed for good, either by
+//! hand or by [`purge_expired`] once the retention period has passed. Older
+//! releases had a trash only for authenticators and passkeys and marked it by
+//! renaming the category to `authenticator_trash` / `passkeys_trash`.
+
+use crate::error::VaultError;
+use crate::models::{PasswordEntry, VaultState};
+
+/// Days a trashed entry is kept when the user has not chosen otherwise.
+pub const DEFAULT_RETENTION_DAYS: u32 = 30;
+/// Longest retention the settings accept.
+pub const MAX_RETENTION_DAYS: u32 = 365;
+
+const LEGACY_TRASH_SUFFIX: &str = "_trash";
+
+pub fn is_trashed(entry: &PasswordEntry) -> bool {
+    entry.deleted_at.is_some()
+}
+
+/// Turns an `authenticator_trash` / `passkeys_trash` entry into a trashed
+/// entry of the base category. Returns whether the entry changed.
+pub fn migrate_legacy_category(entry: &mut PasswordEntry) -> bool {
+    let Some(category) = entry.category.strip_suffix(LEGACY_TRASH_SUFFIX) else {
+        return false;
+    };
+    entry.category = category.to_string();
+    entry.deleted_at.get_or_insert(entry.updated_at);
+    true
+}
+
+/// Converts the legacy trash categories of a freshly loaded vault. Returns the
+/// number of entries that changed.
+pub fn migrate_entries(state: &mut VaultState) -> usize {
+    state
+        .entries
+        .values_mut()
+        .map(migrate_legacy_category)
+        .filter(|changed| *changed)
+        .count()
+}
+
+pub fn trash_entry(state: &mut VaultState, id: &str, now: i64) -> Result<(), String> {
+    let entry = state
+        .entries
+        .get_mut(id)
+        .ok_or_else(|| VaultError::NotFound.to_string())?;
+    if is_trashed(entry) {
+        return Err("Kayıt zaten çöp kutusunda".to_string());
+    }
+    entry.deleted_at = Some(now);
+    Ok(())
+}
+
+pub fn restore_entry(state: &mut VaultState, id: &str) -> Result<(), String> {
+    let entry = state
+        .entries
+        .get_mut(id)
+        .ok_or_else(|| VaultError::NotFound.to_string())?;
+    if entry.deleted_at.take().is_none() {
+        return Err("Bu giriş çöp kutusunda değil".to_string());
+    }
+    Ok(())
+}
+
+/// Removes a trashed entry for good. Live entries have to go through the
+/// trash first.
+pub fn delete_permanently(state: &mut VaultState, id: &str) -> Result<PasswordEntry, String> {
+    let entry = state
+        .entries
+        .get(id)
+        .ok_or_else(|| VaultError::NotFound.to_string())?;
+    if !is_trashed(entry) {
+        return Err("Kalıcı silme sadece çöp kutusundan yapılabilir".to_string());
+    }
+    state.password_history.remove(id);
+    state.revisions.remove(id);
+    state
+        .entries
+        .remove(id)
+        .ok_or_else(|| VaultError::NotFound.to_string())
+}
+
+fn remove_trashed_where(state: &mut VaultState, expired: impl Fn(i64) -> bool) -> usize {
+    let ids: Vec<String> = state
+        .entries
+        .values()
+        .filter(|e| e.deleted_at.is_some_and(&expired))
+        .map(|e| e.id.clone())
+        .collect();
+    for id in &ids {
+        state.entries.remove(id);
+        state.password_history.remove(id);
+        state.revisions.remove(id);
+    }
+    ids.len()
+}
+
+/// Removes every trashed entry. Returns the number removed.
+pub fn empty_trash(state: &mut VaultState) -> usize {
+    remove_trashed_where(state, |_| true)
+}
+
+/// Removes entries trashed more than `retention_days` ago; `0` keeps them
+/// until the trash is emptied by hand. Returns the number removed.
+pub fn purge_expired(state: &mut VaultState, retention_days: u32, now: i64) -> usize {
+    if retention_days == 0 {
+        return 0;
+    }
+    let cutoff = now - i64::from(retention_days) * 24 * 60 * 60;
+    remove_trashed_where(state, |deleted_at| deleted_at <= cutoff)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(id: &str, category: &str) -> PasswordEntry {
+        PasswordEntry {
+            id: id.to_string(),
+            title: "Örnek".to_string(),
+            username: "kullanici".to_string(),
+            password: "parola123".to_string(),
+            url: None,
+            notes: None,
+            created_at: 0,
+            updated_at: 100,
+            category: category.to_string(),
+            folder_id: None,
+            tags: None,
+            extra_fields: None,
+            attachments: None,
+            data: None,
+            deleted_at: None,
+        }
+    }
+
+    #[test]
+    fn trashed_entries_can_be_restored_or_deleted() {
+        let mut state = VaultState::default();
+        for id in ["a", "b"] {
+            state
+                .entries
+                .insert(id.to_string(), entry(id, "bank_cards"));
+        }
+
+        assert!(delete_permanently(&mut state, "a").is_err());
+        trash_entry(&mut state, "a", 10).unwrap();
+        assert!(trash_entry(&mut state, "a", 11).is_err());
+        restore_entry(&mut state, "a").unwrap();
+        assert!(!is_trashed(&state.entries["a"]));
+        assert!(restore_entry(&mut state, "a").is_err());
+
+        trash_entry(&mut state, "b", 10).unwrap();
+        delete_permanently(&mut state, "b").unwrap();
+        assert_eq!(state.entries.len(), 1);
+    }
+
+    #[test]
+    fn expired_and_legacy_trash_is_purged() {
+        let day = 24 * 60 * 60;
+        let mut state = VaultState::default();
+        state
+            .entries
+            .insert("old".to_string(), entry("old", "authenticator_trash"));
+        state
+            .entries
+            .insert("new".to_string(), entry("new", "notes"));
+        state
+            .entries
+            .insert("live".to_string(), entry("live", "notes"));
+
+        assert_eq!(migrate_entries(&mut state), 1);
+        assert_eq!(state.entries["old"].category, "authenticator");
+        assert_eq!(state.entries["old"].deleted_at, Some(100));
+        trash_entry(&mut state, "new", 100 + 20 * day).unwrap();
+
+        assert_eq!(purge_expired(&mut state, 0, 100 + 40 * day), 0);
+        assert_eq!(purge_expired(&mut state, 30, 100 + 40 * day), 1);
+        assert!(!state.entries.contains_key("old"));
+        assert_eq!(empty_trash(&mut state), 1);
+        assert_eq!(state.entries.len(), 1);
+    }
+}
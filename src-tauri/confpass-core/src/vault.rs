@@ -0,0 +1,405 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use zeroize::Zeroize;
+
+use crate::crypto::{hash_master_password, verify_master_password, KdfParams, SecurePassword};
+use crate::error::VaultError;
+use crate::history;
+use crate::models::{Folder, PasswordEntry, PasswordHistoryItem, RevisionSource, Tag, VaultState};
+use crate::password_change::change_master_password;
+use crate::recovery::unlock_with_recovery_code;
+use crate::revisions;
+use crate::storage::{
+    init_keys, load_vault_from_disk, rewrap_password, save_vault_to_disk, VAULT_FILE_NAME,
+};
+
+/// Headless vault handle: owns the decrypted state and the master password
+/// while unlocked, and persists every mutation to `vault.dat`.
+pub struct Vault {
+    vault_path: PathBuf,
+    state: VaultState,
+    master_password: Option<SecurePassword>,
+}
+
+impl Vault {
+    /// Opens the vault stored in `dir` without unlocking it.
+    pub fn open(dir: impl AsRef<Path>) -> Result<Self, String> {
+        let dir = dir.as_ref();
+        fs::create_dir_all(dir).map_err(|e| format!("Directory oluşturulamadı: {}", e))?;
+
+        Ok(Self {
+            vault_path: dir.join(VAULT_FILE_NAME),
+            state: VaultState::default(),
+            master_password: None,
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.vault_path
+    }
+
+    pub fn exists(&self) -> bool {
+        self.vault_path.exists()
+    }
+
+    pub fn is_locked(&self) -> bool {
+        self.master_password.is_none() || self.state.vault_locked
+    }
+
+    /// Initializes a new, empty vault protected by `master_password`.
+    pub fn create(&mut self, master_password: &str) -> Result<(), String> {
+        if self.exists() {
+            return Err("Kasa zaten mevcut".to_string());
+        }
+
+        let mut state = VaultState {
+            master_password_hash: Some(hash_master_password(master_password)?),
+            vault_locked: false,
+            ..VaultState::default()
+        };
+        init_keys(&mut state, master_password)?;
+
+        save_vault_to_disk(&self.vault_path, &state)?;
+
+        self.replace_state(state);
+        self.master_password = Some(SecurePassword::new(master_password.to_string()));
+        Ok(())
+    }
+
+    pub fn unlock(&mut self, master_password: &str) -> Result<(), String> {
+        let mut loaded = self.load(master_password)?;
+        loaded.vault_locked = false;
+        self.replace_state(loaded);
+        self.master_password = Some(SecurePassword::new(master_password.to_string()));
+        Ok(())
+    }
+
+    /// Checks `master_password` against the vault on disk without unlocking it.
+    pub fn verify_password(&self, master_password: &str) -> Result<(), String> {
+        self.load(master_password).map(|_| ())
+    }
+
+    /// Unlocks the vault with a recovery code and protects it with `new_password`.
+    pub fn unlock_with_recovery_code(
+        &mut self,
+        code: &str,
+        new_password: &str,
+    ) -> Result<(), String> {
+        let mut loaded = unlock_with_recovery_code(&self.vault_path, code, new_password)?;
+        loaded.vault_locked = false;
+        self.replace_state(loaded);
+        if let Some(mut pwd) = self.master_password.take() {
+            pwd.zeroize();
+        }
+        self.master_password = Some(SecurePassword::new(new_password.to_string()));
+        Ok(())
+    }
+
+    fn load(&self, master_password: &str) -> Result<VaultState, String> {
+        let loaded = load_vault_from_disk(&self.vault_path, master_password).map_err(|e| {
+            if e.contains("Decrypt hatası") {
+                "Yanlış ana şifre".to_string()
+            } else {
+                format!("Vault yüklenemedi: {}", e)
+            }
+        })?;
+
+        let stored_hash = loaded
+            .master_password_hash
+            .as_deref()
+            .ok_or_else(|| "Hash bulunamadı".to_string())?;
+        if !verify_master_password(master_password, stored_hash)? {
+            return Err("Yanlış ana şifre".to_string());
+        }
+        Ok(loaded)
+    }
+
+    /// Swaps in `state`, keeping the auto-lock timeout, which is a session
+    /// setting and not stored in the vault file.
+    fn replace_state(&mut self, mut state: VaultState) {
+        state.auto_lock_timeout = self.state.auto_lock_timeout;
+        self.state = state;
+    }
+
+    /// Rewraps the vault data key under `new_password`.
+    pub fn change_master_password(
+        &mut self,
+        old_password: &str,
+        new_password: &str,
+    ) -> Result<(), String> {
+        if self.is_locked() {
+            return Err(VaultError::Locked.to_string());
+        }
+        let state =
+            change_master_password(&self.vault_path, &self.state, old_password, new_password)?;
+        self.replace_state(state);
+        if let Some(mut pwd) = self.master_password.take() {
+            pwd.zeroize();
+        }
+        self.master_password = Some(SecurePassword::new(new_password.to_string()));
+        Ok(())
+    }
+
+    /// Rewraps the vault data key with new key derivation parameters and a fresh salt.
+    pub fn set_kdf(&mut self, kdf: KdfParams) -> Result<(), String> {
+        kdf.validate()?;
+        if self.is_locked() {
+            return Err(VaultError::Locked.to_string());
+        }
+        let pwd = self
+            .master_password
+            .as_ref()
+            .ok_or_else(|| VaultError::Locked.to_string())?;
+
+        // Rewrap a copy so a failed save leaves the key in memory matching the file
+        let mut rewrapped = self.state.clone();
+        rewrap_password(&mut rewrapped, pwd.as_str(), kdf)?;
+        save_vault_to_disk(&self.vault_path, &rewrapped)?;
+        self.state = rewrapped;
+        Ok(())
+    }
+
+    /// Saves pending changes and wipes the decrypted state and password from memory.
+    pub fn lock(&mut self) -> Result<(), String> {
+        if !self.is_locked() {
+            self.save()
+                .map_err(|e| format!("Kilitlenmeden önce kaydetme hatası: {}", e))?;
+        }
+        self.discard();
+        Ok(())
+    }
+
+    /// Wipes the decrypted state and password from memory without saving,
+    /// e.g. after the vault files were deleted.
+    pub fn discard(&mut self) {
+        self.replace_state(VaultState::default());
+        if let Some(mut pwd) = self.master_password.take() {
+            pwd.zeroize();
+        }
+    }
+
+    pub fn master_password(&self) -> Option<&SecurePassword> {
+        self.master_password.as_ref()
+    }
+
+    pub fn save(&self) -> Result<(), String> {
+        if self.is_locked() {
+            return Err(VaultError::Locked.to_string());
+        }
+        save_vault_to_disk(&self.vault_path, &self.state)
+    }
+
+    pub fn state(&self) -> Result<&VaultState, String> {
+        if self.is_locked() {
+            return Err(VaultError::Locked.to_string());
+        }
+        Ok(&self.state)
+    }
+
+    /// The in-memory state without the lock check, for callers that check
+    /// `vault_locked` themselves. A locked vault holds only the defaults.
+    pub fn state_unchecked(&self) -> &VaultState {
+        &self.state
+    }
+
+    pub fn state_unchecked_mut(&mut self) -> &mut VaultState {
+        &mut self.state
+    }
+
+    /// Applies `f` to the unlocked state and persists the result.
+    pub fn mutate<T>(
+        &mut self,
+        f: impl FnOnce(&mut VaultState) -> Result<T, String>,
+    ) -> Result<T, String> {
+        if self.is_locked() {
+            return Err(VaultError::Locked.to_string());
+        }
+        let result = f(&mut self.state)?;
+        self.save()?;
+        Ok(result)
+    }
+
+    pub fn entries(&self) -> Result<Vec<&PasswordEntry>, String> {
+        let mut entries: Vec<&PasswordEntry> = self.state()?.entries.values().collect();
+        entries.sort_unstable_by_key(|e| std::cmp::Reverse(e.updated_at));
+        Ok(entries)
+    }
+
+    pub fn entry(&self, id: &str) -> Result<&PasswordEntry, String> {
+        self.state()?
+            .entries
+            .get(id)
+            .ok_or_else(|| VaultError::NotFound.to_string())
+    }
+
+    pub fn add_entry(&mut self, entry: PasswordEntry) -> Result<(), String> {
+        self.mutate(|state| {
+            state.entries.insert(entry.id.clone(), entry);
+            Ok(())
+        })
+    }
+
+    /// Applies `f` to a copy of the entry and stores it, recording password
+    /// history and a revision. If `f` fails the stored entry is left untouched.
+    pub fn update_entry(
+        &mut self,
+        id: &str,
+        f: impl FnOnce(&mut PasswordEntry) -> Result<(), String>,
+    ) -> Result<PasswordEntry, String> {
+        self.mutate(|state| {
+            let before = state
+                .entries
+                .get(id)
+                .cloned()
+                .ok_or_else(|| VaultError::NotFound.to_string())?;
+            let mut updated = before.clone();
+            f(&mut updated)?;
+            updated.updated_at = chrono::Utc::now().timestamp();
+            state.entries.insert(id.to_string(), updated.clone());
+
+            if updated.password != before.password {
+                history::record_password_change(
+                    state,
+                    id,
+                    before.password.clone(),
+                    updated.updated_at,
+                );
+            }
+            revisions::record_change(state, id, &before, RevisionSource::Ui, updated.updated_at)?;
+            Ok(updated)
+        })
+    }
+
+    pub fn remove_entry(&mut self, id: &str) -> Result<PasswordEntry, String> {
+        self.mutate(|state| {
+            state
+                .entries
+                .remove(id)
+                .ok_or_else(|| VaultError::NotFound.to_string())
+        })
+    }
+
+    /// Previous passwords of an entry, oldest first.
+    pub fn password_history(&self, id: &str) -> Result<&[PasswordHistoryItem], String> {
+        Ok(self
+            .state()?
+            .password_history
+            .get(id)
+            .map(Vec::as_slice)
+            .unwrap_or_default())
+    }
+
+    pub fn restore_password(&mut self, id: &str, index: usize) -> Result<PasswordEntry, String> {
+        self.mutate(|state| {
+            history::restore_password(state, id, index, chrono::Utc::now().timestamp())
+        })
+    }
+
+    pub fn folders(&self) -> Result<&[Folder], String> {
+        Ok(&self.state()?.folders)
+    }
+
+    pub fn tags(&self) -> Result<&[Tag], String> {
+        Ok(&self.state()?.tags)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_entry(id: &str) -> PasswordEntry {
+        PasswordEntry {
+            id: id.to_string(),
+            title: "Örnek".to_string(),
+            username: "kullanici".to_string(),
+            password: "parola123".to_string(),
+            url: Some("https://example.com".to_string()),
+            notes: None,
+            created_at: 0,
+            updated_at: 0,
+            category: "accounts".to_string(),
+            folder_id: None,
+            tags: None,
+            extra_fields: None,
+            attachments: None,
+            data: None,
+            deleted_at: None,
+        }
+    }
+
+    #[test]
+    fn create_lock_unlock_roundtrip() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut vault = Vault::open(dir.path()).unwrap();
+        vault.create("ana-sifre-123").unwrap();
+        vault.add_entry(sample_entry("entry_1")).unwrap();
+        vault.lock().unwrap();
+
+        assert!(vault.is_locked());
+        assert!(vault.entries().is_err());
+
+        let mut reopened = Vault::open(dir.path()).unwrap();
+        reopened.unlock("ana-sifre-123").unwrap();
+        assert_eq!(reopened.entry("entry_1").unwrap().username, "kullanici");
+    }
+
+    #[test]
+    fn password_update_is_kept_in_history() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut vault = Vault::open(dir.path()).unwrap();
+        vault.create("ana-sifre-123").unwrap();
+        vault.add_entry(sample_entry("entry_1")).unwrap();
+        let original = vault.entry("entry_1").unwrap().password.clone();
+
+        vault
+            .update_entry("entry_1", |e| {
+                e.password = "yeni-sifre".to_string();
+                Ok(())
+            })
+            .unwrap();
+        vault
+            .update_entry("entry_1", |e| {
+                e.title = "Başlık".to_string();
+                Ok(())
+            })
+            .unwrap();
+        assert!(vault
+            .update_entry("entry_1", |e| {
+                e.title = "Yarım".to_string();
+                Err("Geçersiz".to_string())
+            })
+            .is_err());
+        assert_eq!(vault.entry("entry_1").unwrap().title, "Başlık");
+        vault.lock().unwrap();
+
+        let mut reopened = Vault::open(dir.path()).unwrap();
+        reopened.unlock("ana-sifre-123").unwrap();
+        let history = reopened.password_history("entry_1").unwrap();
+        assert_eq!(history.len(), 1);
+        assert_eq!(history[0].password, original);
+        assert_eq!(
+            revisions::revisions(reopened.state().unwrap(), "entry_1").len(),
+            2
+        );
+
+        let restored = reopened.restore_password("entry_1", 0).unwrap();
+        assert_eq!(restored.password, original);
+    }
+
+    #[test]
+    fn unlock_rejects_wrong_password() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut vault = Vault::open(dir.path()).unwrap();
+        vault.create("ana-sifre-123").unwrap();
+        vault.lock().unwrap();
+
+        let err = vault.unlock("yanlis-sifre").unwrap_err();
+        assert_eq!(err, "Yanlış ana şifre");
+        assert!(vault.is_locked());
+        assert!(vault.verify_password("yanlis-sifre").is_err());
+        vault.verify_password("ana-sifre-123").unwrap();
+        assert!(vault.is_locked());
+    }
+}
@@ -0,0 +1,411 @@
+//! Software WebAuthn authenticator.
+//!
+//! Passkey private keys never leave the vault: the extension sends the
+//! ceremony parameters, keys are generated and used here, and only the public
+//! results (authenticator data, attestation object, signature) are returned.
+//! The client data JSON is built here from the origin reported by the
+//! extension, so a page cannot obtain a signature for another site's rpId.
+//!
+//! The extension asks the user before every ceremony and reports it as
+//! `userPresent`; without it nothing is created or signed. There is no PIN
+//! or biometric check here, so the user verified (UV) flag is never set.
+
+use base64::{engine::general_purpose, Engine as _};
+use p256::ecdsa::signature::Signer;
+use p256::ecdsa::{Signature, SigningKey};
+use p256::pkcs8::EncodePublicKey;
+use rand::{rngs::OsRng, RngCore};
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+
+use crate::models::{PasswordEntry, StoredPasskey, VaultState};
+use crate::passkeys::{active_passkeys, add_passkey};
+
+const FLAG_USER_PRESENT: u8 = 0x01;
+const FLAG_ATTESTED_DATA: u8 = 0x40;
+const CREDENTIAL_ID_BYTES: usize = 32;
+/// WebAuthn limits the user handle to 64 bytes.
+const MAX_USER_ID_BYTES: usize = 64;
+/// All zeros: a software authenticator without attestation.
+const AAGUID: [u8; 16] = [0; 16];
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateRequest {
+    pub rp_id: String,
+    #[serde(default)]
+    pub rp_name: String,
+    /// User handle, base64url.
+    #[serde(default)]
+    pub user_id: String,
+    #[serde(default)]
+    pub user_name: String,
+    #[serde(default)]
+    pub user_display_name: String,
+    pub origin: String,
+    /// Challenge from the relying party, base64url.
+    pub challenge: String,
+    /// Whether the user confirmed this ceremony in the extension's dialog.
+    #[serde(default)]
+    pub user_present: bool,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AssertRequest {
+    pub credential_id: String,
+    pub rp_id: String,
+    pub origin: String,
+    /// Challenge from the relying party, base64url.
+    pub challenge: String,
+    /// Whether the user confirmed this ceremony in the extension's dialog.
+    #[serde(default)]
+    pub user_present: bool,
+}
+
+pub struct CreatedCredential {
+    pub credential_id: String,
+    pub client_data_json: Vec<u8>,
+    pub authenticator_data: Vec<u8>,
+    pub attestation_object: Vec<u8>,
+    /// SubjectPublicKeyInfo DER, as returned by `getPublicKey()`.
+    pub public_key: Vec<u8>,
+    pub entry: PasswordEntry,
+}
+
+pub struct Assertion {
+    pub credential_id: String,
+    pub client_data_json: Vec<u8>,
+    pub authenticator_data: Vec<u8>,
+    /// ASN.1 DER encoded ECDSA signature.
+    pub signature: Vec<u8>,
+    pub user_handle: Option<Vec<u8>>,
+}
+
+fn b64url_decode(value: &str, field: &str) -> Result<Vec<u8>, String> {
+    general_purpose::URL_SAFE_NO_PAD
+        .decode(value.trim_end_matches('='))
+        .map_err(|_| format!("{} geçersiz", field))
+}
+
+/// Accepts `rp_id` only if it is the origin's host or a parent domain of it,
+/// and not a public suffix such as `com` or `github.io`.
+fn check_rp_id(rp_id: &str, origin: &str) -> Result<(), String> {
+    let url = url::Url::parse(origin).map_err(|_| "Origin geçersiz".to_string())?;
+    let host = url
+        .host_str()
+        .ok_or_else(|| "Origin geçersiz".to_string())?;
+    if url.scheme() != "https" && host != "localhost" {
+        return Err("Geçiş anahtarları yalnızca HTTPS sitelerde kullanılabilir".to_string());
+    }
+    if rp_id.is_empty() || (host != rp_id && !host.ends_with(&format!(".{}", rp_id))) {
+        return Err("rpId bu siteye ait değil".to_string());
+    }
+    if psl::suffix(rp_id.as_bytes())
+        .is_some_and(|suffix| suffix.is_known() && suffix.as_bytes() == rp_id.as_bytes())
+    {
+        return Err("rpId bir alan adı uzantısı olamaz".to_string());
+    }
+    Ok(())
+}
+
+fn check_user_present(user_present: bool) -> Result<(), String> {
+    if !user_present {
+        return Err("Geçiş anahtarı için kullanıcı onayı alınmadı".to_string());
+    }
+    Ok(())
+}
+
+fn client_data_json(kind: &str, challenge: &str, origin: &str) -> Result<Vec<u8>, String> {
+    b64url_decode(challenge, "Challenge")?;
+    let text = |value: &str| serde_json::Value::String(value.to_string()).to_string();
+    Ok(format!(
+        r#"{{"type":{},"challenge":{},"origin":{},"crossOrigin":false}}"#,
+        text(kind),
+        text(challenge),
+        text(origin)
+    )
+    .into_bytes())
+}
+
+fn authenticator_data(rp_id: &str, flags: u8, counter: u32, attested: &[u8]) -> Vec<u8> {
+    let mut data = Vec::with_capacity(37 + attested.len());
+    data.extend_from_slice(&Sha256::digest(rp_id.as_bytes()));
+    data.push(flags);
+    data.extend_from_slice(&counter.to_be_bytes());
+    data.extend_from_slice(attested);
+    data
+}
+
+/// COSE_Key for ES256: `{1: 2, 3: -7, -1: 1, -2: x, -3: y}`.
+fn cose_public_key(key: &SigningKey) -> Vec<u8> {
+    let point = key.verifying_key().to_encoded_point(false);
+    let mut cose = vec![0xa5, 0x01, 0x02, 0x03, 0x26, 0x20, 0x01, 0x21, 0x58, 0x20];
+    cose.extend_from_slice(point.x().expect("uncompressed point"));
+    cose.extend_from_slice(&[0x22, 0x58, 0x20]);
+    cose.extend_from_slice(point.y().expect("uncompressed point"));
+    cose
+}
+
+/// CBOR `{"fmt": "none", "attStmt": {}, "authData": auth_data}`.
+fn attestation_object(auth_data: &[u8]) -> Vec<u8> {
+    let mut cbor = vec![0xa3, 0x63];
+    cbor.extend_from_slice(b"fmt");
+    cbor.push(0x64);
+    cbor.extend_from_slice(b"none");
+    cbor.push(0x67);
+    cbor.extend_from_slice(b"attStmt");
+    cbor.extend_from_slice(&[0xa0, 0x68]);
+    cbor.extend_from_slice(b"authData");
+    match auth_data.len() {
+        len if len < 24 => cbor.push(0x40 | len as u8),
+        len if len < 256 => cbor.extend_from_slice(&[0x58, len as u8]),
+        len => {
+            cbor.push(0x59);
+            cbor.extend_from_slice(&(len as u16).to_be_bytes());
+        }
+    }
+    cbor.extend_from_slice(auth_data);
+    cbor
+}
+
+/// Private keys are kept as a P-256 JWK, the format older releases received
+/// from WebCrypto.
+fn encode_private_key(key: &SigningKey) -> String {
+    let point = key.verifying_key().to_encoded_point(false);
+    let b64 = |bytes: &[u8]| general_purpose::URL_SAFE_NO_PAD.encode(bytes);
+    serde_json::json!({
+        "kty": "EC",
+        "crv": "P-256",
+        "x": b64(point.x().expect("uncompressed point")),
+        "y": b64(point.y().expect("uncompressed point")),
+        "d": b64(&key.to_bytes()),
+        "ext": true,
+        "key_ops": ["sign"]
+    })
+    .to_string()
+}
+
+fn decode_private_key(private_key: &str) -> Result<SigningKey, String> {
+    let jwk: serde_json::Value =
+        serde_json::from_str(private_key).map_err(|_| "Geçiş anahtarı bozuk".to_string())?;
+    let d = jwk
+        .get("d")
+        .and_then(|v| v.as_str())
+        .ok_or_else(|| "Geçiş anahtarı bozuk".to_string())?;
+    SigningKey::from_slice(&b64url_decode(d, "Geçiş anahtarı")?)
+        .map_err(|_| "Geçiş anahtarı bozuk".to_string())
+}
+
+/// Creates a passkey for `request.rp_id` and stores it in the vault. The
+/// caller saves the vault.
+pub fn create_credential(
+    state: &mut VaultState,
+    request: &CreateRequest,
+    now: i64,
+) -> Result<CreatedCredential, String> {
+    check_rp_id(&request.rp_id, &request.origin)?;
+    let user_id = b64url_decode(&request.user_id, "Kullanıcı kimliği")?;
+    if user_id.is_empty() || user_id.len() > MAX_USER_ID_BYTES {
+        return Err("Kullanıcı kimliği geçersiz".to_string());
+    }
+    check_user_present(request.user_present)?;
+    let client_data_json =
+        client_data_json("webauthn.create", &request.challenge, &request.origin)?;
+
+    let key = SigningKey::random(&mut OsRng);
+    let mut credential_id = [0u8; CREDENTIAL_ID_BYTES];
+    OsRng.fill_bytes(&mut credential_id);
+
+    let mut attested = Vec::with_capacity(16 + 2 + CREDENTIAL_ID_BYTES + 77);
+    attested.extend_from_slice(&AAGUID);
+    attested.extend_from_slice(&(CREDENTIAL_ID_BYTES as u16).to_be_bytes());
+    attested.extend_from_slice(&credential_id);
+    attested.extend_from_slice(&cose_public_key(&key));
+    let authenticator_data = authenticator_data(
+        &request.rp_id,
+        FLAG_USER_PRESENT | FLAG_ATTESTED_DATA,
+        0,
+        &attested,
+    );
+
+    let public_key = key
+        .verifying_key()
+        .to_public_key_der()
+        .map_err(|e| format!("Açık anahtar oluşturulamadı: {}", e))?
+        .into_vec();
+
+    let credential_id = general_purpose::URL_SAFE_NO_PAD.encode(credential_id);
+    let entry = add_passkey(
+        state,
+        StoredPasskey {
+            credential_id: credential_id.clone(),
+            private_key: encode_private_key(&key),
+            rp_id: request.rp_id.clone(),
+            rp_name: if request.rp_name.is_empty() {
+                request.rp_id.clone()
+            } else {
+                request.rp_name.clone()
+            },
+            user_id: request.user_id.clone(),
+            user_name: request.user_name.clone(),
+            user_display_name: request.user_display_name.clone(),
+            counter: 0,
+            created_at: now,
+        },
+        now,
+    )?;
+
+    Ok(CreatedCredential {
+        credential_id,
+        client_data_json,
+        attestation_object: attestation_object(&authenticator_data),
+        authenticator_data,
+        public_key,
+        entry,
+    })
+}
+
+/// Signs a login challenge with a stored passkey and bumps its signature
+/// counter. Only passkeys whose entry is not in the trash can be used. The
+/// caller saves the vault.
+pub fn assert_credential(
+    state: &mut VaultState,
+    request: &AssertRequest,
+) -> Result<Assertion, String> {
+    check_rp_id(&request.rp_id, &request.origin)?;
+    check_user_present(request.user_present)?;
+    let client_data_json = client_data_json("webauthn.get", &request.challenge, &request.origin)?;
+
+    let passkey = active_passkeys(state, &request.rp_id)
+        .into_iter()
+        .find(|p| p.credential_id == request.credential_id)
+        .ok_or_else(|| "Geçiş anahtarı bulunamadı".to_string())?;
+    let key = decode_private_key(&passkey.private_key)?;
+    let user_handle = match passkey.user_id.as_str() {
+        "" => None,
+        user_id => Some(b64url_decode(user_id, "Kullanıcı kimliği")?),
+    };
+
+    let passkey = state
+        .passkeys
+        .iter_mut()
+        .find(|p| p.credential_id == request.credential_id)
+        .ok_or_else(|| "Geçiş anahtarı bulunamadı".to_string())?;
+    passkey.counter = passkey.counter.saturating_add(1);
+
+    let authenticator_data =
+        authenticator_data(&request.rp_id, FLAG_USER_PRESENT, passkey.counter, &[]);
+    let mut signed = authenticator_data.clone();
+    signed.extend_from_slice(&Sha256::digest(&client_data_json));
+    let signature: Signature = key.sign(&signed);
+
+    Ok(Assertion {
+        credential_id: request.credential_id.clone(),
+        client_data_json,
+        authenticator_data,
+        signature: signature.to_der().as_bytes().to_vec(),
+        user_handle,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use p256::ecdsa::signature::Verifier;
+    use p256::ecdsa::VerifyingKey;
+    use p256::pkcs8::DecodePublicKey;
+
+    fn create(state: &mut VaultState) -> CreatedCredential {
+        let request = CreateRequest {
+            rp_id: "example.com".to_string(),
+            rp_name: "Example".to_string(),
+            user_id: "dXNlcg".to_string(),
+            user_name: "kullanici".to_string(),
+            user_display_name: "Kullanıcı".to_string(),
+            origin: "https://login.example.com".to_string(),
+            challenge: "Y2hhbGxlbmdl".to_string(),
+            user_present: true,
+        };
+        create_credential(state, &request, 10).unwrap()
+    }
+
+    #[test]
+    fn assertion_verifies_with_created_public_key() {
+        let mut state = VaultState::default();
+        let created = create(&mut state);
+        assert_eq!(created.authenticator_data.len(), 37 + 16 + 2 + 32 + 77);
+        assert!(!serde_json::to_string(&created.entry)
+            .unwrap()
+            .contains("\"d\""));
+
+        let request = AssertRequest {
+            credential_id: created.credential_id.clone(),
+            rp_id: "example.com".to_string(),
+            origin: "https://example.com".to_string(),
+            challenge: "b3RoZXI".to_string(),
+            user_present: true,
+        };
+        let assertion = assert_credential(&mut state, &request).unwrap();
+        assert_eq!(assertion.authenticator_data[32], FLAG_USER_PRESENT);
+        assert_eq!(&assertion.authenticator_data[33..37], &1u32.to_be_bytes());
+        assert_eq!(assertion.user_handle.as_deref(), Some(&b"user"[..]));
+        assert_eq!(state.passkeys[0].counter, 1);
+
+        let verifying_key = VerifyingKey::from_public_key_der(&created.public_key).unwrap();
+        let mut signed = assertion.authenticator_data.clone();
+        signed.extend_from_slice(&Sha256::digest(&assertion.client_data_json));
+        let signature = Signature::from_der(&assertion.signature).unwrap();
+        assert!(verifying_key.verify(&signed, &signature).is_ok());
+    }
+
+    #[test]
+    fn foreign_origin_cannot_use_passkey() {
+        let mut state = VaultState::default();
+        let created = create(&mut state);
+
+        let request = AssertRequest {
+            credential_id: created.credential_id,
+            rp_id: "example.com".to_string(),
+            origin: "https://evil-example.com".to_string(),
+            challenge: "b3RoZXI".to_string(),
+            user_present: true,
+        };
+        assert_eq!(
+            assert_credential(&mut state, &request).err().unwrap(),
+            "rpId bu siteye ait değil"
+        );
+
+        let unconfirmed = AssertRequest {
+            origin: "https://example.com".to_string(),
+            user_present: false,
+            ..request
+        };
+        assert!(assert_credential(&mut state, &unconfirmed).is_err());
+        assert_eq!(state.passkeys[0].counter, 0);
+    }
+
+    #[test]
+    fn public_suffixes_and_bad_user_handles_are_refused() {
+        assert!(check_rp_id("example.com", "https://login.example.com").is_ok());
+        assert!(check_rp_id("localhost", "http://localhost:3000").is_ok());
+        assert!(check_rp_id("com", "https://example.com").is_err());
+        assert!(check_rp_id("github.io", "https://user.github.io").is_err());
+
+        let mut state = VaultState::default();
+        let mut request = CreateRequest {
+            rp_id: "example.com".to_string(),
+            rp_name: String::new(),
+            user_id: "not base64!".to_string(),
+            user_name: "kullanici".to_string(),
+            user_display_name: String::new(),
+            origin: "https://example.com".to_string(),
+            challenge: "Y2hhbGxlbmdl".to_string(),
+            user_present: true,
+        };
+        assert!(create_credential(&mut state, &request, 10).is_err());
+        request.user_id = String::new();
+        assert!(create_credential(&mut state, &request, 10).is_err());
+        assert!(state.passkeys.is_empty());
+    }
+}
@@ -1,22 +1,13 @@
-use std::env;
 use std::fs;
 use std::io::{self, Read, Write};
 use std::path::PathBuf;
-use std::time::{SystemTime, UNIX_EPOCH};
 
-// DEBUG LOGGER
+use confpass_core::ipc::{self, IpcRequest};
+
+// The browser shows a native host's stderr in its own log; stdout carries
+// the messages
 fn log_native(msg: &str) {
-    if let Ok(mut file) = fs::OpenOptions::new()
-        .create(true)
-        .append(true)
-        .open("C:\\Users\\Public\\confpass_native.txt")
-    {
-        let time = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .unwrap_or_default()
-            .as_secs();
-        let _ = writeln!(file, "[{}] {}", time, msg);
-    }
+    eprintln!("[ConfPass Native] {}", msg);
 }
 
 // Unused struct removed to fix compiler warning
@@ -45,37 +36,294 @@ fn send_message(message: &serde_json::Value) -> Result<(), Box<dyn std::error::E
     Ok(())
 }
 
-fn get_app_data_dir() -> Result<PathBuf, String> {
-    if cfg!(windows) {
-        env::var("APPDATA")
-            .map(PathBuf::from)
-            .map_err(|_| "APPDATA environment variable bulunamadı".to_string())
-            .map(|p| p.join("ConfPass"))
-    } else if cfg!(target_os = "macos") {
-        let home =
-            env::var("HOME").map_err(|_| "HOME environment variable bulunamadı".to_string())?;
-        Ok(PathBuf::from(home)
-            .join("Library")
-            .join("Application Support")
-            .join("ConfPass"))
+const API_BASE: &str = "http://127.0.0.1:1421";
+
+// Each extension pairs on its own; its token and any pairing request that is
+// waiting for approval are kept under native_clients/, named by its origin.
+fn client_file(extension: &str) -> Option<PathBuf> {
+    let origin = std::env::args().nth(1).unwrap_or_default();
+    let key: String = origin
+        .chars()
+        .filter(|c| c.is_ascii_alphanumeric())
+        .collect();
+    let key = if key.is_empty() {
+        "default".to_string()
     } else {
-        let home =
-            env::var("HOME").map_err(|_| "HOME environment variable bulunamadı".to_string())?;
-        Ok(PathBuf::from(home).join(".config").join("confpass"))
+        key
+    };
+    let dir = confpass_core::storage::default_data_dir()
+        .ok()?
+        .join("native_clients");
+    fs::create_dir_all(&dir).ok()?;
+    Some(dir.join(format!("{}.{}", key, extension)))
+}
+
+fn client_name() -> String {
+    match std::env::args().nth(1) {
+        Some(origin) if !origin.is_empty() => {
+            format!("Tarayıcı eklentisi ({})", origin.trim_end_matches('/'))
+        }
+        _ => "Tarayıcı eklentisi".to_string(),
     }
 }
 
 fn get_auth_token() -> Option<String> {
-    let app_dir = get_app_data_dir().ok()?;
-    let token_path = app_dir.join("native_auth_token");
+    let token = fs::read_to_string(client_file("token")?).ok()?;
+    let token = token.trim();
+    (!token.is_empty()).then(|| token.to_string())
+}
+
+fn forget_auth_token() {
+    if let Some(path) = client_file("token") {
+        let _ = fs::remove_file(path);
+    }
+}
+
+fn pairing_pending() -> serde_json::Value {
+    serde_json::json!({
+        "success": false,
+        "pairing": "pending",
+        "error": "Bu tarayıcının erişim isteğini ConfPass uygulamasında onaylayın."
+    })
+}
+
+// The app's local channel: a Unix domain socket, or a named pipe on Windows
+#[cfg(unix)]
+fn open_channel() -> io::Result<std::os::unix::net::UnixStream> {
+    let stream = std::os::unix::net::UnixStream::connect(channel_path()?)?;
+    stream.set_read_timeout(Some(std::time::Duration::from_secs(5)))?;
+    Ok(stream)
+}
+
+// Any user can create a pipe with this name before the app does, so the
+// server may only identify this process, not act as it, and the token is only
+// sent once the server turns out to run as this user
+#[cfg(windows)]
+fn open_channel() -> io::Result<fs::File> {
+    use std::os::windows::fs::OpenOptionsExt;
+
+    const SECURITY_IDENTIFICATION: u32 = 1 << 16;
+
+    let pipe = fs::OpenOptions::new()
+        .read(true)
+        .write(true)
+        .security_qos_flags(SECURITY_IDENTIFICATION)
+        .open(channel_path()?)?;
+    if !server_is_current_user(&pipe) {
+        return Err(io::Error::new(
+            io::ErrorKind::PermissionDenied,
+            "IPC sunucusu başka bir kullanıcı adına çalışıyor",
+        ));
+    }
+    Ok(pipe)
+}
+
+/// Whether the process serving `pipe` runs as the same user as this one.
+/// Errors count as another user.
+#[cfg(windows)]
+fn server_is_current_user(pipe: &fs::File) -> bool {
+    use std::os::windows::io::AsRawHandle;
+    use windows::Win32::Foundation::{CloseHandle, HANDLE};
+    use windows::Win32::Security::{
+        EqualSid, GetTokenInformation, TokenUser, TOKEN_QUERY, TOKEN_USER,
+    };
+    use windows::Win32::System::Pipes::GetNamedPipeServerProcessId;
+    use windows::Win32::System::Threading::{
+        GetCurrentProcess, OpenProcess, OpenProcessToken, PROCESS_QUERY_LIMITED_INFORMATION,
+    };
+
+    // TOKEN_USER of `process`, in a buffer that also holds the SID it points to
+    unsafe fn token_user(process: HANDLE) -> Option<Vec<u64>> {
+        let mut token = HANDLE::default();
+        OpenProcessToken(process, TOKEN_QUERY, &mut token).ok()?;
+        let mut len = 0u32;
+        let _ = GetTokenInformation(token, TokenUser, None, 0, &mut len);
+        let mut buffer = vec![0u64; (len as usize).div_ceil(8)];
+        let result = GetTokenInformation(
+            token,
+            TokenUser,
+            Some(buffer.as_mut_ptr().cast()),
+            len,
+            &mut len,
+        );
+        let _ = CloseHandle(token);
+        result.ok()?;
+        Some(buffer)
+    }
+
+    unsafe {
+        let mut pid = 0u32;
+        if GetNamedPipeServerProcessId(HANDLE(pipe.as_raw_handle()), &mut pid).is_err() {
+            return false;
+        }
+        let Ok(process) = OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, false, pid) else {
+            return false;
+        };
+        let server_user = token_user(process);
+        let _ = CloseHandle(process);
+
+        match (server_user, token_user(GetCurrentProcess())) {
+            (Some(server_user), Some(own_user)) => {
+                let server_sid = (*(server_user.as_ptr() as *const TOKEN_USER)).User.Sid;
+                let own_sid = (*(own_user.as_ptr() as *const TOKEN_USER)).User.Sid;
+                EqualSid(server_sid, own_sid).is_ok()
+            }
+            _ => false,
+        }
+    }
+}
+
+fn channel_path() -> io::Result<PathBuf> {
+    let data_dir = confpass_core::storage::default_data_dir()
+        .map_err(|e| io::Error::new(io::ErrorKind::NotFound, e))?;
+    ipc::channel_path(&data_dir).map_err(|e| io::Error::new(io::ErrorKind::NotFound, e))
+}
+
+// The app's TCP setting, read from its settings.json in the data directory
+fn tcp_api_enabled() -> bool {
+    confpass_core::storage::default_data_dir()
+        .ok()
+        .and_then(|dir| fs::read(dir.join("settings.json")).ok())
+        .and_then(|bytes| serde_json::from_slice::<serde_json::Value>(&bytes).ok())
+        .and_then(|settings| settings.get("tcp_api_enabled")?.as_bool())
+        .unwrap_or(false)
+}
+
+// Any local process can listen on the TCP port, so the token only goes there
+// when the user turned TCP on and there is no channel at all. A socket that
+// exists but refuses the connection, or a pipe served by another user, must
+// not send the host to the port instead.
+fn may_use_tcp(channel_error: &io::Error, tcp_enabled: bool) -> bool {
+    tcp_enabled && channel_error.kind() == io::ErrorKind::NotFound
+}
+
+// Sends a request to the app over the local channel, or over TCP if there is
+// no channel and the app has its TCP fallback turned on. Returns the HTTP
+// status and body of the route.
+fn call(
+    endpoint: &str,
+    token: Option<&str>,
+    body: &serde_json::Value,
+) -> Result<(u16, serde_json::Value), String> {
+    match open_channel() {
+        Ok(mut stream) => {
+            let request = IpcRequest {
+                endpoint: endpoint.to_string(),
+                token: token.map(str::to_string),
+                body: body.clone(),
+            };
+            let response = ipc::call(&mut stream, &request)?;
+            return Ok((response.status, response.body));
+        }
+        Err(e) if may_use_tcp(&e, tcp_api_enabled()) => {
+            log_native(&format!("Local channel unavailable ({}), trying TCP", e))
+        }
+        Err(e) => {
+            return Err(format!(
+                "Connection to App failed: {}. Make sure ConfPass is running.",
+                e
+            ))
+        }
+    }
 
-    if token_path.exists() {
-        fs::read_to_string(token_path).ok()
+    let client = reqwest::blocking::Client::builder()
+        .timeout(std::time::Duration::from_secs(5))
+        .build()
+        .unwrap_or_else(|_| reqwest::blocking::Client::new());
+    let mut request = client.post(format!("{}/{}", API_BASE, endpoint)).json(body);
+    if let Some(token) = token {
+        request = request.header("Authorization", token);
+    }
+    let response = request.send().map_err(|e| {
+        format!(
+            "Connection to App failed: {}. Make sure ConfPass is running.",
+            e
+        )
+    })?;
+    let status = response.status().as_u16();
+    let text = response.text().unwrap_or_default();
+    let body = if text.is_empty() {
+        serde_json::Value::Null
     } else {
-        None
+        serde_json::from_str(&text).unwrap_or(serde_json::Value::String(text))
+    };
+    Ok((status, body))
+}
+
+fn post_json(endpoint: &str, body: &serde_json::Value) -> Result<serde_json::Value, String> {
+    match call(endpoint, None, body)? {
+        (status, body) if (200..300).contains(&status) => Ok(body),
+        (status, _) => Err(format!("HTTP Error: {}", status)),
     }
 }
 
+// Without a token, asks the app to pair this extension or checks on the
+// request made earlier. Returns the token once the user has approved it,
+// otherwise the response to send back to the extension.
+fn pair() -> Result<String, serde_json::Value> {
+    let pairing_path = client_file("pairing").ok_or_else(
+        || serde_json::json!({ "success": false, "error": "Data directory not found" }),
+    )?;
+    let failed = |e: String| {
+        log_native(&e);
+        serde_json::json!({ "success": false, "error": e })
+    };
+
+    if let Some(pairing) = fs::read(&pairing_path)
+        .ok()
+        .and_then(|bytes| serde_json::from_slice::<serde_json::Value>(&bytes).ok())
+    {
+        let status = post_json("pair_status", &pairing).map_err(failed)?;
+        match status.get("status").and_then(|v| v.as_str()) {
+            Some("pending") => return Err(pairing_pending()),
+            Some("approved") => {
+                let _ = fs::remove_file(&pairing_path);
+                let token = status
+                    .get("token")
+                    .and_then(|v| v.as_str())
+                    .unwrap_or_default()
+                    .to_string();
+                let token_path = client_file("token").ok_or_else(
+                    || serde_json::json!({ "success": false, "error": "Data directory not found" }),
+                )?;
+                confpass_core::storage::write_private(&token_path, token.as_bytes())
+                    .map_err(failed)?;
+                log_native("Pairing approved");
+                return Ok(token);
+            }
+            Some("denied") => {
+                let _ = fs::remove_file(&pairing_path);
+                log_native("Pairing denied");
+                return Err(serde_json::json!({
+                    "success": false,
+                    "error": "Erişim isteği ConfPass uygulamasında reddedildi."
+                }));
+            }
+            // Expired or unknown: ask again
+            _ => {
+                let _ = fs::remove_file(&pairing_path);
+            }
+        }
+    }
+
+    let response = post_json(
+        "pair_request",
+        &serde_json::json!({ "name": client_name() }),
+    )
+    .map_err(failed)?;
+    if response.get("success").and_then(|v| v.as_bool()) != Some(true) {
+        return Err(response);
+    }
+    let pairing = serde_json::json!({
+        "request_id": response.get("request_id"),
+        "secret": response.get("secret"),
+    });
+    confpass_core::storage::write_private(&pairing_path, pairing.to_string().as_bytes())
+        .map_err(failed)?;
+    log_native("Pairing requested");
+    Err(pairing_pending())
+}
+
 fn proxy_to_server(message: &serde_json::Value) -> serde_json::Value {
     let message_type = message
         .get("type")
@@ -91,8 +339,8 @@ fn proxy_to_server(message: &serde_json::Value) -> serde_json::Value {
         "ping" => "ping",
         "get_passwords_for_site" => "get_passwords_for_site",
         "get_passkeys" => "get_passkeys",
-        "save_passkey" => "save_passkey",
-        "update_passkey_counter" => "update_passkey_counter",
+        "passkey_create" => "passkey_create",
+        "passkey_assert" => "passkey_assert",
         "get_totp_code" => "get_totp_code",
         "passkey_detected" => "passkey_detected",
         "open_app" => "focus_window",
@@ -103,50 +351,47 @@ fn proxy_to_server(message: &serde_json::Value) -> serde_json::Value {
         _ => message_type,
     };
 
-    let url = format!("http://127.0.0.1:1421/{}", endpoint);
-    let token = get_auth_token();
-
-    if token.is_none() {
-        log_native("AUTH ERROR: Token not found in proxy_to_server");
-        // If ping, we might just return false instead of error
-        if endpoint == "ping" {
-            return serde_json::json!({ "success": false, "error": "Auth token not found" });
-        }
-        return serde_json::json!({ "success": false, "error": "Authorization token not found. Please open the App first." });
-    }
+    let token = match get_auth_token() {
+        Some(token) => token,
+        None => match pair() {
+            Ok(token) => token,
+            Err(response) => return response,
+        },
+    };
 
-    log_native(&format!("Proxying to: {}", url));
-    // log_native(&format!("Using token: {:?}", token)); // Mask in production if needed
+    log_native(&format!("Proxying to: {}", endpoint));
 
-    let client = reqwest::blocking::Client::builder()
-        .timeout(std::time::Duration::from_secs(5))
-        .build()
-        .unwrap_or_else(|_| reqwest::blocking::Client::new());
-
-    let response = client
-        .post(&url)
-        .header("Authorization", token.unwrap())
-        .json(message)
-        .send();
-
-    match response {
-        Ok(resp) => {
-            log_native(&format!("Backend response status: {}", resp.status()));
-            if resp.status().is_success() {
-                resp.json::<serde_json::Value>()
-                    .unwrap_or_else(|_| serde_json::json!({ "success": true }))
+    match call(endpoint, Some(&token), message) {
+        Ok((status, body)) => {
+            log_native(&format!("Backend response status: {}", status));
+            if status == 401 {
+                // Revoked in the app; pair again
+                forget_auth_token();
+                return match pair() {
+                    Ok(_) => serde_json::json!({ "success": false, "error": "Tekrar deneyin" }),
+                    Err(response) => response,
+                };
+            }
+            if status == 403 {
+                let error = match body.as_str() {
+                    Some(reason) if !reason.is_empty() => reason.to_string(),
+                    _ => "Bu işlem için ConfPass uygulamasında izin verilmedi.".to_string(),
+                };
+                return serde_json::json!({ "success": false, "error": error });
+            }
+            if (200..300).contains(&status) {
+                if body.is_null() {
+                    serde_json::json!({ "success": true })
+                } else {
+                    body
+                }
             } else {
-                let status = resp.status();
                 let err_msg = format!("HTTP Error: {}", status);
                 log_native(&err_msg);
                 serde_json::json!({ "success": false, "error": err_msg })
             }
         }
-        Err(e) => {
-            let err_msg = format!(
-                "Connection to App failed: {}. Make sure ConfPass is running.",
-                e
-            );
+        Err(err_msg) => {
             log_native(&err_msg);
             serde_json::json!({ "success": false, "error": err_msg })
         }
@@ -177,3 +422,31 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tcp_is_only_used_when_enabled_and_no_channel_exists() {
+        let missing = io::Error::from(io::ErrorKind::NotFound);
+        assert!(may_use_tcp(&missing, true));
+        assert!(!may_use_tcp(&missing, false));
+
+        let other_user = io::Error::from(io::ErrorKind::PermissionDenied);
+        assert!(!may_use_tcp(&other_user, true));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn a_socket_nobody_answers_on_does_not_fall_back() {
+        let dir = std::env::temp_dir().join(format!("confpass-nh-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(ipc::SOCKET_FILE_NAME);
+        drop(std::os::unix::net::UnixListener::bind(&path).unwrap());
+
+        let refused = std::os::unix::net::UnixStream::connect(&path).unwrap_err();
+        assert!(!may_use_tcp(&refused, true));
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
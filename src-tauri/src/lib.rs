@@ -1,30 +1,46 @@
-use aes_gcm::{
-    aead::{Aead, AeadCore, KeyInit, OsRng},
-    Aes256Gcm, Nonce,
-};
 use axum::{
+    extract::Extension,
     http::StatusCode,
     response::Json,
     routing::{get, post},
     Router,
 };
 use base64::{engine::general_purpose, Engine as _};
+use confpass_core::archive;
+use confpass_core::attachments::{OrphanReport, DEFAULT_MAX_ATTACHMENT_MB, MAX_ATTACHMENT_MB};
+use confpass_core::backups::{BackupInfo, BackupRetention, BackupSchedule};
+use confpass_core::clients::{ApiClient, ClientRegistry, PairingRequest, PairingStatus, Scope};
+use confpass_core::crypto::{decrypt_vault_data, KdfParams};
+use confpass_core::duplicates;
+use confpass_core::entry_data::{prepare_entry, EntryData, TotpItem, NOTES_MAX_LEN};
+use confpass_core::error::validate_input;
+use confpass_core::importers::{self, ImportFormat, ImportSummary, MergeStrategy};
+use confpass_core::integrity::{IntegrityReport, IssueKind};
+use confpass_core::ipc::{self, IpcRequest, IpcResponse};
+use confpass_core::mime;
+use confpass_core::models::{requires_credentials, VALID_CATEGORIES};
+use confpass_core::passkeys::PASSKEY_CATEGORY;
+use confpass_core::rate_limit::{AttemptStore, FREE_ATTEMPTS};
+use confpass_core::trash::{is_trashed, DEFAULT_RETENTION_DAYS, MAX_RETENTION_DAYS};
+use confpass_core::{
+    extract_domain, EntryRevision, FieldChange, FileAttachment, Folder, PasswordEntry,
+    PasswordHistoryItem, RevisionSource, Tag, Vault, VaultError, VaultState,
+};
 use once_cell::sync::Lazy;
-use pbkdf2::pbkdf2_hmac;
+use rand::rngs::OsRng;
 use rand::RngCore;
 use serde::{Deserialize, Serialize};
 use serde_json::json;
-use sha2::Sha256;
 use std::collections::HashMap;
 use std::env;
 use std::fs;
-use std::io::Write;
-use std::path::PathBuf;
+use std::io::{Read, Write};
+use std::ops::{Deref, DerefMut};
+use std::path::{Path, PathBuf};
 use std::sync::{Mutex, MutexGuard};
 use std::time::{Duration, SystemTime, UNIX_EPOCH};
 use tauri::{Emitter, Manager};
-use tower_http::cors::{Any, CorsLayer};
-use zeroize::{Zeroize, ZeroizeOnDrop};
+use zeroize::Zeroize;
 // Auto-Type Dependencies
 use active_win_pos_rs::get_active_window;
 // use enigo::{Enigo, KeyboardControllable};
@@ -57,222 +73,171 @@ fn log_to_file(_msg: &str) {
     // Release build'de log yazma - güvenlik için devre dışı
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct FileAttachment {
-    pub id: String,
-    pub filename: String,
-    pub mime_type: String,
-    pub size: u64,
-    pub created_at: i64,
-}
-
-#[derive(Debug, Serialize, Deserialize, Clone)]
-pub struct PasswordEntry {
-    pub id: String,
-    pub title: String,
-    pub username: String,
-    pub password: String,
-    pub url: Option<String>,
-    pub notes: Option<String>,
-    pub created_at: i64,
-    pub updated_at: i64,
-    pub category: String,
-    #[serde(default)]
-    pub folder_id: Option<String>,
-    #[serde(default)]
-    pub tags: Option<Vec<String>>,
-    #[serde(default)]
-    pub extra_fields: Option<HashMap<String, String>>,
-    #[serde(default)]
-    pub attachments: Option<Vec<FileAttachment>>,
-}
+/// Lock on the app's vault. Dereferences to its in-memory state, so commands
+/// check `vault_locked` and edit entries directly; unlocking, locking and
+/// password changes go through `vault()`/`vault_mut()`.
+struct StateGuard(MutexGuard<'static, Option<Vault>>);
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct VaultState {
-    entries: HashMap<String, PasswordEntry>,
-    master_password_hash: Option<String>,
-    vault_locked: bool,
-    auto_lock_timeout: Option<u64>,
-    failed_attempts: u32,
-    last_attempt_time: Option<SystemTime>,
-    rate_limit_window: Duration,
-    encryption_salt: Option<String>,
-    folders: Vec<Folder>,
-    tags: Vec<Tag>,
-}
-
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct Folder {
-    pub id: String,
-    pub name: String,
-    pub color: String,
-    pub icon: String,
-    #[serde(default)]
-    pub parent_id: Option<String>,
-    pub created_at: i64,
-    #[serde(default)]
-    pub order: i32,
-}
+impl StateGuard {
+    fn vault(&self) -> &Vault {
+        self.0
+            .as_ref()
+            .expect("vault is opened before the guard is handed out")
+    }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct Tag {
-    pub id: String,
-    pub name: String,
-    pub color: String,
+    fn vault_mut(&mut self) -> &mut Vault {
+        self.0
+            .as_mut()
+            .expect("vault is opened before the guard is handed out")
+    }
 }
 
-#[derive(Deserialize, Serialize)]
-struct VaultData {
-    entries: Vec<PasswordEntry>,
-    master_password_hash: String,
-    encryption_salt: String,
-    #[serde(default)]
-    folders: Vec<Folder>,
-    #[serde(default)]
-    tags: Vec<Tag>,
-}
-
-#[derive(Debug)]
-enum VaultError {
-    Locked,
-    NotFound,
-    InvalidInput(String),
-    RateLimited,
-    InternalError(String),
-}
-
-impl std::fmt::Display for VaultError {
-    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
-        match self {
-            VaultError::Locked => write!(f, "Kasa kilitli"),
-            VaultError::NotFound => write!(f, "Kayıt bulunamadı"),
-            VaultError::InvalidInput(msg) => write!(f, "Geçersiz giriş: {}", msg),
-            VaultError::RateLimited => write!(f, "Çok fazla deneme. Lütfen bekleyin."),
-            VaultError::InternalError(msg) => write!(f, "İç hata: {}", msg),
-        }
+impl Deref for StateGuard {
+    type Target = VaultState;
+
+    fn deref(&self) -> &VaultState {
+        self.vault().state_unchecked()
     }
 }
 
-fn get_state() -> Result<MutexGuard<'static, VaultState>, VaultError> {
-    VAULT_STATE
-        .lock()
-        .map_err(|_| VaultError::InternalError("Mutex lock failed".to_string()))
+impl DerefMut for StateGuard {
+    fn deref_mut(&mut self) -> &mut VaultState {
+        self.vault_mut().state_unchecked_mut()
+    }
 }
 
-fn get_state_mut() -> Result<MutexGuard<'static, VaultState>, VaultError> {
-    VAULT_STATE
+fn get_state() -> Result<StateGuard, VaultError> {
+    let mut vault = VAULT
         .lock()
-        .map_err(|_| VaultError::InternalError("Mutex lock failed".to_string()))
+        .map_err(|_| VaultError::InternalError("Mutex lock failed".to_string()))?;
+    if vault.is_none() {
+        let dir = confpass_core::storage::default_data_dir().map_err(VaultError::InternalError)?;
+        *vault = Some(Vault::open(dir).map_err(VaultError::InternalError)?);
+    }
+    Ok(StateGuard(vault))
 }
 
-fn get_master_password() -> Result<String, String> {
-    let master_pwd = MASTER_PASSWORD
-        .lock()
-        .map_err(|_| "Master password lock hatası".to_string())?;
-    let pwd = master_pwd
-        .as_ref()
-        .ok_or_else(|| "Master password bulunamadı. Lütfen kasa kilidini açın.".to_string())?;
-    Ok(pwd.as_str().to_string())
-}
-
-fn validate_input(
-    input: &str,
-    min_len: usize,
-    max_len: usize,
-    field_name: &str,
-) -> Result<(), VaultError> {
-    let len = input.len();
-    if len < min_len {
-        return Err(VaultError::InvalidInput(format!(
-            "{} en az {} karakter olmalı",
-            field_name, min_len
-        )));
-    }
-    if len > max_len {
-        return Err(VaultError::InvalidInput(format!(
-            "{} en fazla {} karakter olabilir",
-            field_name, max_len
-        )));
-    }
-    Ok(())
+fn get_state_mut() -> Result<StateGuard, VaultError> {
+    get_state()
 }
 
-fn check_rate_limit(state: &mut VaultState) -> Result<(), VaultError> {
-    const MAX_ATTEMPTS: u32 = 5;
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
 
-    let now = SystemTime::now();
+/// Seconds since the epoch in the signed form the client registry stores.
+fn unix_timestamp() -> i64 {
+    unix_now() as i64
+}
 
-    if let Some(last_attempt) = state.last_attempt_time {
-        if let Ok(elapsed) = now.duration_since(last_attempt) {
-            if elapsed > state.rate_limit_window {
-                state.failed_attempts = 0;
-                state.last_attempt_time = None;
-            } else if state.failed_attempts >= MAX_ATTEMPTS {
-                return Err(VaultError::RateLimited);
-            }
+// Deneme sayacının MAC anahtarı işletim sisteminin anahtar deposunda saklanır.
+// İkinci değer anahtarın bu çağrıda oluşturulduğunu bildirir. Depo okunamazsa
+// başka bir anahtara geçilmez; sayaç o anahtarla doğrulanamaz.
+fn attempt_mac_key() -> Result<(Vec<u8>, bool), String> {
+    let entry = keyring::Entry::new("ConfPass", "unlock_attempts_key")
+        .map_err(|e| format!("Deneme anahtarına erişilemedi: {}", e))?;
+    match entry.get_password() {
+        Ok(encoded) => general_purpose::STANDARD
+            .decode(encoded)
+            .map(|key| (key, false))
+            .map_err(|_| "Deneme anahtarı bozuk".to_string()),
+        Err(keyring::Error::NoEntry) => {
+            let mut key = vec![0u8; 32];
+            OsRng.fill_bytes(&mut key);
+            entry
+                .set_password(&general_purpose::STANDARD.encode(&key))
+                .map_err(|e| format!("Deneme anahtarı kaydedilemedi: {}", e))?;
+            Ok((key, true))
         }
-    } else if state.failed_attempts >= MAX_ATTEMPTS {
-        return Err(VaultError::RateLimited);
+        Err(e) => Err(format!("Deneme anahtarı okunamadı: {}", e)),
     }
-
-    Ok(())
 }
 
-impl Default for VaultState {
-    fn default() -> Self {
-        Self {
-            entries: HashMap::new(),
-            master_password_hash: None,
-            vault_locked: true,
-            auto_lock_timeout: Some(300),
-            failed_attempts: 0,
-            last_attempt_time: None,
-            rate_limit_window: Duration::from_secs(300),
-            encryption_salt: None,
-            folders: Vec::new(),
-            tags: Vec::new(),
-        }
+fn attempt_store() -> Result<AttemptStore, String> {
+    let vault_path = get_vault_path()?;
+    let (mac_key, created) = attempt_mac_key()?;
+    let store = AttemptStore::new(&vault_path, &mac_key)?;
+    // Önceki sayaç yeni anahtarla doğrulanamaz; yalnızca ilk çalıştırmada sıfırdan başlar
+    if created {
+        store.adopt_new_key(unix_now())?;
     }
+    Ok(store)
 }
 
-static VAULT_STATE: Lazy<Mutex<VaultState>> = Lazy::new(|| Mutex::new(VaultState::default()));
+fn check_rate_limit() -> Result<(), String> {
+    attempt_store()?
+        .check(unix_now())
+        .map(|_| ())
+        .map_err(|e| e.to_string())
+}
 
-#[derive(ZeroizeOnDrop)]
-struct SecurePassword(String);
+/// Başarısız denemeyi kalıcı sayaca işler ve kullanıcıya gösterilecek hatayı döndürür.
+/// Ayarlarda silme eşiği tanımlıysa ve aşıldıysa kasa silinir. Çağıran durum kilidini
+/// tutmamalıdır.
+fn record_failed_attempt(error: String) -> String {
+    let attempts = match attempt_store().and_then(|store| store.record_failure(unix_now())) {
+        Ok(attempts) => attempts,
+        Err(e) => {
+            log_to_file(&format!("Deneme sayacı güncellenemedi: {}", e));
+            return error;
+        }
+    };
 
-impl SecurePassword {
-    fn new(password: String) -> Self {
-        SecurePassword(password)
+    let wipe_after = get_settings().map(|s| s.wipe_after_failures).unwrap_or(0);
+    if wipe_after > 0 && attempts.failed_attempts >= wipe_after {
+        return match wipe_vault_after_failures() {
+            Ok(()) => "Çok fazla hatalı deneme: kasa güvenlik nedeniyle silindi".to_string(),
+            Err(e) => format!("{} (Kasa silinemedi: {})", error, e),
+        };
     }
 
-    fn as_str(&self) -> &str {
-        &self.0
-    }
+    error
 }
 
-impl Zeroize for SecurePassword {
-    fn zeroize(&mut self) {
-        self.0.zeroize();
+fn reset_failed_attempts() {
+    if let Err(e) = attempt_store().and_then(|store| store.reset()) {
+        log_to_file(&format!("Deneme sayacı sıfırlanamadı: {}", e));
     }
 }
 
-impl Clone for SecurePassword {
-    fn clone(&self) -> Self {
-        SecurePassword(self.0.clone())
+fn wipe_vault_after_failures() -> Result<(), String> {
+    reset_vault()?;
+
+    let attachments_dir = confpass_core::attachments::attachments_dir(&get_vault_path()?)?;
+    if attachments_dir.exists() {
+        fs::remove_dir_all(&attachments_dir).map_err(|e| format!("Ek dizini silinemedi: {}", e))?;
     }
+
+    let backups_dir = confpass_core::backups::backups_dir(&get_vault_path()?)?;
+    if backups_dir.exists() {
+        fs::remove_dir_all(&backups_dir).map_err(|e| format!("Yedek dizini silinemedi: {}", e))?;
+    }
+
+    log_to_file("Self-wipe: Kasa hatalı deneme eşiği nedeniyle silindi");
+    Ok(())
 }
 
-static MASTER_PASSWORD: Lazy<Mutex<Option<SecurePassword>>> = Lazy::new(|| Mutex::new(None));
+// Opened from the data directory on first use; see get_state()
+static VAULT: Lazy<Mutex<Option<Vault>>> = Lazy::new(|| Mutex::new(None));
 
 // Master Password Rotation - Bellek güvenliği için
 static MASTER_PASSWORD_SET_TIME: Lazy<Mutex<Option<SystemTime>>> = Lazy::new(|| Mutex::new(None));
 static PASSWORD_ROTATION_SECONDS: Lazy<Mutex<u64>> = Lazy::new(|| Mutex::new(0)); // 0 = devre dışı
 
+// Otomatik kilit - son kullanıcı etkinliği (Tauri komutları ve HTTP istekleri)
+static LAST_ACTIVITY: Lazy<Mutex<SystemTime>> = Lazy::new(|| Mutex::new(SystemTime::now()));
+static LOCK_ON_SLEEP: Lazy<Mutex<bool>> = Lazy::new(|| Mutex::new(true));
+static LOCK_ON_SCREEN_LOCK: Lazy<Mutex<bool>> = Lazy::new(|| Mutex::new(true));
+
 // Global AppHandle for HTTP server to emit events
 static APP_HANDLE: Lazy<Mutex<Option<tauri::AppHandle>>> = Lazy::new(|| Mutex::new(None));
 
-// Auth Token for HTTP server
-static AUTH_TOKEN: Lazy<Mutex<Option<String>>> = Lazy::new(|| Mutex::new(None));
+// Paired clients of the HTTP server
+static API_CLIENTS: Lazy<Mutex<ClientRegistry>> =
+    Lazy::new(|| Mutex::new(ClientRegistry::default()));
 
 fn set_app_handle(handle: tauri::AppHandle) {
     if let Ok(mut app_handle) = APP_HANDLE.lock() {
@@ -285,428 +250,314 @@ fn get_app_handle() -> Option<tauri::AppHandle> {
 }
 
 fn get_vault_path() -> Result<PathBuf, String> {
-    let app_data_dir = if cfg!(windows) {
-        env::var("APPDATA")
-            .map(PathBuf::from)
-            .map_err(|_| "APPDATA environment variable bulunamadı".to_string())?
-            .join("ConfPass")
-    } else if cfg!(target_os = "macos") {
-        let home =
-            env::var("HOME").map_err(|_| "HOME environment variable bulunamadı".to_string())?;
-        PathBuf::from(home)
-            .join("Library")
-            .join("Application Support")
-            .join("ConfPass")
-    } else {
-        let home =
-            env::var("HOME").map_err(|_| "HOME environment variable bulunamadı".to_string())?;
-        PathBuf::from(home).join(".config").join("confpass")
-    };
+    let app_data_dir = confpass_core::storage::default_data_dir()?;
 
     fs::create_dir_all(&app_data_dir).map_err(|e| format!("Directory oluşturulamadı: {}", e))?;
-    Ok(app_data_dir.join("vault.dat"))
+    Ok(app_data_dir.join(confpass_core::storage::VAULT_FILE_NAME))
 }
 
-fn derive_encryption_key(master_password: &str, salt: &[u8]) -> [u8; 32] {
-    let mut key = [0u8; 32];
-    pbkdf2_hmac::<Sha256>(master_password.as_bytes(), salt, 100000, &mut key);
-    key
+fn save_vault_to_disk(state: &VaultState) -> Result<(), String> {
+    let vault_path = get_vault_path()?;
+    confpass_core::storage::save_vault_to_disk(&vault_path, state)?;
+    backup_after_save(&vault_path);
+    Ok(())
 }
 
-fn encrypt_vault_data(data: &str, master_password: &str, salt: &[u8]) -> Result<String, String> {
-    let key = derive_encryption_key(master_password, salt);
-    let cipher =
-        Aes256Gcm::new_from_slice(&key).map_err(|e| format!("Cipher oluşturulamadı: {}", e))?;
-    let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
-
-    let ciphertext = cipher
-        .encrypt(&nonce, data.as_bytes())
-        .map_err(|e| format!("Şifreleme hatası: {}", e))?;
-
-    let mut encrypted = nonce.to_vec();
-    encrypted.extend_from_slice(&ciphertext);
-
-    Ok(general_purpose::STANDARD.encode(&encrypted))
+/// Takes the scheduled backup after the vault file was written. A failed backup
+/// must not fail the save that triggered it.
+fn backup_after_save(vault_path: &Path) {
+    let settings = get_settings().ok();
+    if let Err(e) = confpass_core::backups::backup_after_save(
+        vault_path,
+        settings
+            .as_ref()
+            .map(|s| s.backup_schedule)
+            .unwrap_or_default(),
+        settings.map(|s| s.backup_retention).unwrap_or_default(),
+        chrono::Utc::now().timestamp(),
+    ) {
+        eprintln!("[Backup] Yedek alınamadı: {}", e);
+    }
 }
 
-fn decrypt_vault_data(
-    encrypted_data: &str,
-    master_password: &str,
-    salt: &[u8],
-) -> Result<String, String> {
-    let encrypted_bytes = match general_purpose::STANDARD.decode(encrypted_data) {
-        Ok(bytes) => bytes,
-        Err(e) => return Err(format!("Base64 decode hatası: {}", e)),
-    };
+#[tauri::command]
+fn unlock_vault(mut master_password: String) -> Result<bool, String> {
+    validate_input(&master_password, 8, 128, "Ana şifre").map_err(|e| e.to_string())?;
 
-    if encrypted_bytes.len() < 12 {
-        return Err("Geçersiz şifreli veri uzunluğu".to_string());
-    }
+    let mut state = get_state_mut().map_err(|e| e.to_string())?;
 
-    let nonce = Nonce::from_slice(&encrypted_bytes[..12]);
-    let ciphertext = &encrypted_bytes[12..];
+    if !state.vault().exists() {
+        let result = state.vault_mut().create(&master_password);
+        drop(state);
+        if let Err(e) = result {
+            master_password.zeroize();
+            return Err(e);
+        }
 
-    let key = derive_encryption_key(master_password, salt);
-    let cipher = match Aes256Gcm::new_from_slice(&key) {
-        Ok(c) => c,
-        Err(e) => return Err(format!("Cipher oluşturulamadı: {}", e)),
-    };
+        // Set password rotation time
+        if let Ok(mut set_time) = MASTER_PASSWORD_SET_TIME.lock() {
+            *set_time = Some(SystemTime::now());
+        }
 
-    let plaintext = match cipher.decrypt(nonce, ciphertext) {
-        Ok(pt) => pt,
-        Err(_) => return Err("Decrypt hatası: Yanlış şifre veya bozuk veri".to_string()),
-    };
+        // [SÜPER YAMA] İlk kurulumda da şifreyi kaydet
+        #[cfg(windows)]
+        {
+            if let Ok(entry) = keyring::Entry::new("ConfPass", "master_password") {
+                let _ = entry.set_password(&master_password);
+            }
+        }
 
-    match String::from_utf8(plaintext) {
-        Ok(s) => Ok(s),
-        Err(e) => Err(format!("UTF-8 decode hatası: {}", e)),
+        master_password.zeroize();
+        return Ok(true);
     }
-}
+    drop(state);
 
-fn save_vault_to_disk(state: &VaultState, master_password: &str) -> Result<(), String> {
-    let vault_path = get_vault_path()?;
-    let vault_dir = vault_path
-        .parent()
-        .ok_or_else(|| "Vault path parent bulunamadı".to_string())?;
+    // Kilitlenme süresi dolmadan şifre denenmez
+    check_rate_limit()?;
 
-    fs::create_dir_all(&vault_dir).map_err(|e| format!("Vault dizini oluşturulamadı: {}", e))?;
+    let result = get_state_mut()
+        .map_err(|e| e.to_string())?
+        .vault_mut()
+        .unlock(&master_password);
+    if let Err(e) = result {
+        master_password.zeroize();
+        if e == "Yanlış ana şifre" {
+            return Err(record_failed_attempt(e));
+        }
+        return Err(e);
+    }
 
-    let salt_path = vault_dir.join("vault.salt");
+    reset_failed_attempts();
 
-    let salt = if let Some(ref stored_salt) = state.encryption_salt {
-        general_purpose::STANDARD
-            .decode(stored_salt)
-            .map_err(|e| format!("Salt decode hatası: {}", e))?
-    } else {
-        let mut new_salt = [0u8; 32];
-        rand::thread_rng().fill_bytes(&mut new_salt);
-        let salt_b64 = general_purpose::STANDARD.encode(&new_salt);
+    // [SÜPER YAMA] Şifre doğrulandığı an Windows Kasasına yaz
+    #[cfg(windows)]
+    {
+        match keyring::Entry::new("ConfPass", "master_password") {
+            Ok(entry) => {
+                println!("[DEBUG] Windows Kasasına yazılıyor...");
+                if let Err(e) = entry.set_password(&master_password) {
+                    eprintln!("[ERROR] Windows Kasasına yazılamadı: {}", e);
+                } else {
+                    println!("[SUCCESS] Windows Kasasına yazıldı!");
+                }
+            }
+            Err(e) => eprintln!("[ERROR] Keyring girişi oluşturulamadı: {}", e),
+        }
+    }
 
-        fs::write(&salt_path, &salt_b64).map_err(|e| format!("Salt dosyası yazılamadı: {}", e))?;
+    // Set password rotation time
+    if let Ok(mut set_time) = MASTER_PASSWORD_SET_TIME.lock() {
+        *set_time = Some(SystemTime::now());
+    }
 
-        new_salt.to_vec()
-    };
+    master_password.zeroize();
 
-    if !salt_path.exists() {
-        let salt_b64 = general_purpose::STANDARD.encode(&salt);
-        fs::write(&salt_path, &salt_b64).map_err(|e| format!("Salt dosyası yazılamadı: {}", e))?;
-    }
-
-    let entries_vec: Vec<PasswordEntry> = state.entries.values().cloned().collect();
-    let vault_data = VaultData {
-        entries: entries_vec,
-        master_password_hash: state
-            .master_password_hash
-            .clone()
-            .ok_or_else(|| "Master password hash bulunamadı".to_string())?,
-        encryption_salt: general_purpose::STANDARD.encode(&salt),
-        folders: state.folders.clone(),
-        tags: state.tags.clone(),
-    };
+    // Move passkeys from the old plaintext passkeys.json into the vault
+    migrate_plaintext_passkeys();
+    purge_expired_trash();
 
-    let json_data =
-        serde_json::to_string(&vault_data).map_err(|e| format!("JSON serialize hatası: {}", e))?;
+    Ok(true)
+}
 
-    let encrypted = encrypt_vault_data(&json_data, master_password, &salt)?;
+#[tauri::command]
+fn lock_vault() -> Result<(), String> {
+    lock_vault_state()
+}
 
-    let tmp_path = vault_path.with_extension("dat.tmp");
-    use std::fs::File;
-    use std::io::Write;
-    let mut file = File::create(&tmp_path)
-        .map_err(|e| format!("Geçici dosya oluşturulamadı: {} (Path: {:?})", e, tmp_path))?;
-    file.write_all(encrypted.as_bytes())
-        .map_err(|e| format!("Dosya yazma hatası: {}", e))?;
-    file.sync_all()
-        .map_err(|e| format!("Dosya senkronizasyon hatası: {}", e))?;
-    drop(file); // Dosya handle'ını kapat
+/// Saves and locks the vault, clearing entries, keys and the master password from memory.
+fn lock_vault_state() -> Result<(), String> {
+    close_vault_session(true)
+}
 
-    fs::rename(&tmp_path, &vault_path)
-        .map_err(|e| format!("Dosya değiştirme (rename) hatası: {}", e))?;
+/// Clears the unlocked vault from memory: decrypted state, keys, the master
+/// password and its rotation timer. With `save` pending changes are written
+/// first; the reset paths skip that because the vault files are already gone.
+fn close_vault_session(save: bool) -> Result<(), String> {
+    let mut state = get_state_mut().map_err(|e| e.to_string())?;
+    let was_unlocked = !state.vault().is_locked();
+    if save {
+        state.vault_mut().lock()?;
+    } else {
+        state.vault_mut().discard();
+    }
+    drop(state);
 
+    if let Ok(mut set_time) = MASTER_PASSWORD_SET_TIME.lock() {
+        *set_time = None;
+    }
+    if save && was_unlocked {
+        backup_after_save(&get_vault_path()?);
+    }
     Ok(())
 }
 
-fn load_vault_from_disk(master_password: &str) -> Result<VaultState, String> {
-    let vault_path = match get_vault_path() {
-        Ok(p) => p,
-        Err(e) => return Err(format!("Vault path hatası: {}", e)),
-    };
+#[tauri::command]
+fn is_vault_locked() -> Result<bool, String> {
+    let state = get_state().map_err(|e| e.to_string())?;
+    Ok(!state.vault().exists() || state.vault().is_locked())
+}
 
-    if !vault_path.exists() {
-        return Err("Vault dosyası bulunamadı".to_string());
+#[tauri::command]
+fn change_master_password(
+    mut old_password: String,
+    mut new_password: String,
+) -> Result<(), String> {
+    check_rate_limit()?;
+
+    let mut state = get_state_mut().map_err(|e| e.to_string())?;
+    if state.vault_locked {
+        return Err(VaultError::Locked.to_string());
     }
 
-    let salt_path = match vault_path.parent() {
-        Some(p) => p.join("vault.salt"),
-        None => return Err("Vault path parent bulunamadı".to_string()),
-    };
+    // Durum kilidi boyunca tutulur; anahtar yeniden sarılırken başka değişiklik kaybolmaz
+    let result = state
+        .vault_mut()
+        .change_master_password(&old_password, &new_password);
+    old_password.zeroize();
+    drop(state);
 
-    let salt = if salt_path.exists() {
-        let salt_b64 = match fs::read_to_string(&salt_path) {
-            Ok(s) => s,
-            Err(e) => return Err(format!("Salt dosyası okunamadı: {}", e)),
-        };
-        match general_purpose::STANDARD.decode(&salt_b64.trim()) {
-            Ok(s) => s,
-            Err(e) => return Err(format!("Salt decode hatası: {}", e)),
+    if let Err(e) = result {
+        new_password.zeroize();
+        if e == "Mevcut ana şifre yanlış" {
+            return Err(record_failed_attempt(e));
         }
-    } else {
-        return Ok(VaultState::default());
-    };
-
-    if salt.len() != 32 {
-        return Err("Geçersiz salt uzunluğu".to_string());
+        return Err(e);
     }
+    reset_failed_attempts();
 
-    let encrypted_data = match fs::read_to_string(&vault_path) {
-        Ok(d) => d,
-        Err(e) => return Err(format!("Dosya okuma hatası: {}", e)),
-    };
-
-    if encrypted_data.trim().is_empty() {
-        return Err("Vault dosyası boş".to_string());
+    if let Ok(mut set_time) = MASTER_PASSWORD_SET_TIME.lock() {
+        *set_time = Some(SystemTime::now());
     }
 
-    let decrypted_json = match decrypt_vault_data(&encrypted_data, master_password, &salt) {
-        Ok(d) => d,
-        Err(e) => return Err(format!("Decrypt hatası: {}", e)),
-    };
+    #[cfg(windows)]
+    {
+        if let Ok(entry) = keyring::Entry::new("ConfPass", "master_password") {
+            if let Err(e) = entry.set_password(&new_password) {
+                eprintln!("[ERROR] Windows Kasası güncellenemedi: {}", e);
+            }
+        }
+    }
 
-    let vault_data: VaultData = match serde_json::from_str(&decrypted_json) {
-        Ok(d) => d,
-        Err(e) => return Err(format!("JSON parse hatası: {}", e)),
-    };
+    new_password.zeroize();
+    Ok(())
+}
 
-    let mut entries = HashMap::with_capacity(vault_data.entries.len());
-    for entry in vault_data.entries {
-        entries.insert(entry.id.clone(), entry);
-    }
-
-    Ok(VaultState {
-        entries,
-        master_password_hash: Some(vault_data.master_password_hash),
-        vault_locked: true,
-        auto_lock_timeout: Some(300),
-        failed_attempts: 0,
-        last_attempt_time: None,
-        rate_limit_window: Duration::from_secs(300),
-        encryption_salt: Some(general_purpose::STANDARD.encode(&salt)),
-        folders: vault_data.folders,
-        tags: vault_data.tags,
-    })
+#[derive(Serialize)]
+struct RecoveryKitResponse {
+    code: String,
+    qr_code: String,
 }
 
 #[tauri::command]
-fn unlock_vault(mut master_password: String) -> Result<bool, String> {
-    validate_input(&master_password, 8, 128, "Ana şifre").map_err(|e| e.to_string())?;
-
-    let vault_path = get_vault_path()?;
-    let vault_exists = vault_path.exists();
-
-    if !vault_exists {
-        let mut state = get_state_mut().map_err(|e| e.to_string())?;
-
-        check_rate_limit(&mut state).map_err(|e| e.to_string())?;
-
-        use argon2::password_hash::{PasswordHasher, SaltString};
-        use argon2::Argon2;
-        use rand::rngs::OsRng;
+fn generate_recovery_key() -> Result<RecoveryKitResponse, String> {
+    let state = get_state().map_err(|e| e.to_string())?;
+    if state.vault_locked {
+        return Err(VaultError::Locked.to_string());
+    }
+    let keys = state
+        .keys
+        .as_ref()
+        .ok_or_else(|| "Kasa anahtarı yüklenmedi".to_string())?;
 
-        let salt = SaltString::generate(&mut OsRng);
-        let params = argon2::Params::new(65536, 3, 4, None)
-            .map_err(|e| format!("Argon2 params error: {}", e))?;
-        let argon2 = Argon2::new(argon2::Algorithm::Argon2id, argon2::Version::V0x13, params);
-        let hash = argon2
-            .hash_password(master_password.as_bytes(), &salt)
-            .map_err(|e| format!("Hash error: {}", e))?;
+    // Eski kod geçersiz olur; yeni kod yalnızca bu yanıtta gösterilir
+    let code = confpass_core::recovery::create_recovery_key(&get_vault_path()?, keys)?;
+    let qr_code = render_qr_png_base64(code.as_bytes())?;
 
-        state.master_password_hash = Some(hash.to_string());
-        state.vault_locked = false;
-        state.failed_attempts = 0;
-        state.last_attempt_time = None;
+    Ok(RecoveryKitResponse { code, qr_code })
+}
 
-        {
-            let mut master_pwd = MASTER_PASSWORD
-                .lock()
-                .map_err(|_| "Master password kilidi alinamadi".to_string())?;
-            *master_pwd = Some(SecurePassword::new(master_password.clone()));
-        }
+#[tauri::command]
+fn has_recovery_key() -> Result<bool, String> {
+    Ok(confpass_core::recovery::has_recovery_key(&get_vault_path()?))
+}
 
-        // Set password rotation time
-        if let Ok(mut set_time) = MASTER_PASSWORD_SET_TIME.lock() {
-            *set_time = Some(SystemTime::now());
-        }
+#[tauri::command]
+fn unlock_with_recovery_key(
+    mut recovery_code: String,
+    mut new_master_password: String,
+) -> Result<bool, String> {
+    let mut state = get_state_mut().map_err(|e| e.to_string())?;
+    if !state.vault().exists() {
+        return Err("Kasa bulunamadı".to_string());
+    }
+    drop(state);
 
-        let state_snapshot = VaultState {
-            entries: state.entries.clone(),
-            master_password_hash: state.master_password_hash.clone(),
-            vault_locked: state.vault_locked,
-            auto_lock_timeout: state.auto_lock_timeout,
-            failed_attempts: state.failed_attempts,
-            last_attempt_time: state.last_attempt_time,
-            rate_limit_window: state.rate_limit_window,
-            encryption_salt: state.encryption_salt.clone(),
-            folders: state.folders.clone(),
-            tags: state.tags.clone(),
-        };
-        drop(state);
+    check_rate_limit()?;
 
-        save_vault_to_disk(&state_snapshot, &master_password)?;
+    state = get_state_mut().map_err(|e| e.to_string())?;
+    let result = state
+        .vault_mut()
+        .unlock_with_recovery_code(&recovery_code, &new_master_password);
+    recovery_code.zeroize();
+    drop(state);
 
-        // [SÜPER YAMA] İlk kurulumda da şifreyi kaydet
-        #[cfg(windows)]
-        {
-            if let Ok(entry) = keyring::Entry::new("ConfPass", "master_password") {
-                let _ = entry.set_password(&master_password);
-            }
+    if let Err(e) = result {
+        new_master_password.zeroize();
+        if e == "Kurtarma anahtarı geçersiz" {
+            return Err(record_failed_attempt(e));
         }
+        return Err(e);
+    }
+    reset_failed_attempts();
 
-        master_password.zeroize();
-        return Ok(true);
+    if let Ok(mut set_time) = MASTER_PASSWORD_SET_TIME.lock() {
+        *set_time = Some(SystemTime::now());
     }
 
-    let loaded_state = match load_vault_from_disk(&master_password) {
-        Ok(state) => state,
-        Err(e) => {
-            let mut state = get_state_mut().map_err(|e| e.to_string())?;
-            if e.contains("Decrypt hatası")
-                || e.contains("decrypt")
-                || e.contains("Şifre çözme")
-                || e.contains("decrypt")
-            {
-                state.failed_attempts += 1;
-                state.last_attempt_time = Some(SystemTime::now());
-                return Err("Yanlış ana şifre".to_string());
+    #[cfg(windows)]
+    {
+        if let Ok(entry) = keyring::Entry::new("ConfPass", "master_password") {
+            if let Err(e) = entry.set_password(&new_master_password) {
+                eprintln!("[ERROR] Windows Kasası güncellenemedi: {}", e);
             }
-            state.failed_attempts += 1;
-            state.last_attempt_time = Some(SystemTime::now());
-            return Err(format!("Vault yüklenemedi: {}", e));
-        }
-    };
-
-    let mut state = get_state_mut().map_err(|e| e.to_string())?;
-    check_rate_limit(&mut state).map_err(|e| e.to_string())?;
-
-    use argon2::password_hash::{PasswordHash, PasswordVerifier};
-    use argon2::Argon2;
-
-    let stored_hash = loaded_state.master_password_hash.as_ref().ok_or_else(|| {
-        state.failed_attempts += 1;
-        state.last_attempt_time = Some(SystemTime::now());
-        "Hash bulunamadı".to_string()
-    })?;
-
-    let parsed_hash = match PasswordHash::new(stored_hash) {
-        Ok(h) => h,
-        Err(e) => {
-            state.failed_attempts += 1;
-            state.last_attempt_time = Some(SystemTime::now());
-            return Err(format!("Hash parse hatası: {}", e));
         }
-    };
-
-    let argon2 = Argon2::default();
-    match argon2.verify_password(master_password.as_bytes(), &parsed_hash) {
-        Ok(()) => {
-            state.entries = loaded_state.entries;
-            state.master_password_hash = loaded_state.master_password_hash;
-            state.encryption_salt = loaded_state.encryption_salt;
-            state.folders = loaded_state.folders;
-            state.tags = loaded_state.tags;
-            state.vault_locked = false;
-            state.failed_attempts = 0;
-            state.last_attempt_time = None;
-
-            drop(state);
-
-            // [SÜPER YAMA] Şifre doğrulandığı an Windows Kasasına yaz
-            #[cfg(windows)]
-            {
-                match keyring::Entry::new("ConfPass", "master_password") {
-                    Ok(entry) => {
-                        println!("[DEBUG] Windows Kasasına yazılıyor...");
-                        if let Err(e) = entry.set_password(&master_password) {
-                            eprintln!("[ERROR] Windows Kasasına yazılamadı: {}", e);
-                        } else {
-                            println!("[SUCCESS] Windows Kasasına yazıldı!");
-                        }
-                    }
-                    Err(e) => eprintln!("[ERROR] Keyring girişi oluşturulamadı: {}", e),
-                }
-            }
-
-            {
-                let mut master_pwd = MASTER_PASSWORD
-                    .lock()
-                    .map_err(|_| "Master password kilidi alinamadi".to_string())?;
-                *master_pwd = Some(SecurePassword::new(master_password.clone()));
-            }
+    }
 
-            // Set password rotation time
-            if let Ok(mut set_time) = MASTER_PASSWORD_SET_TIME.lock() {
-                *set_time = Some(SystemTime::now());
-            }
+    new_master_password.zeroize();
 
-            master_password.zeroize();
+    migrate_plaintext_passkeys();
+    purge_expired_trash();
 
-            // Sync passkeys.json with vault entries (remove stale passkeys)
-            sync_passkeys_with_vault();
+    Ok(true)
+}
 
-            Ok(true)
-        }
-        Err(_) => {
-            state.failed_attempts += 1;
-            state.last_attempt_time = Some(SystemTime::now());
-            Err("Yanlış ana şifre".to_string())
-        }
+#[tauri::command]
+fn get_kdf_params() -> Result<KdfParams, String> {
+    let state = get_state().map_err(|e| e.to_string())?;
+    if state.vault_locked {
+        return Err(VaultError::Locked.to_string());
     }
+    state
+        .keys
+        .as_ref()
+        .map(|keys| keys.password_slot.kdf)
+        .ok_or_else(|| VaultError::Locked.to_string())
 }
 
 #[tauri::command]
-fn lock_vault() -> Result<(), String> {
+fn set_kdf_params(kdf: KdfParams) -> Result<(), String> {
     let mut state = get_state_mut().map_err(|e| e.to_string())?;
-
-    let master_pwd = MASTER_PASSWORD
-        .lock()
-        .map_err(|_| "Master password lock hatası".to_string())?;
-
-    if let Some(ref pwd) = *master_pwd {
-        save_vault_to_disk(&state, pwd.as_str())
-            .map_err(|e| format!("Kilitlenmeden önce kaydetme hatası: {}", e))?;
-    }
-    drop(master_pwd);
-
-    state.vault_locked = true;
-    state.entries.clear(); // [Deep Lock] Clear entries from memory when locked
-
-    {
-        let mut master_pwd = MASTER_PASSWORD
-            .lock()
-            .map_err(|_| "Master password kilidi alinamadi".to_string())?;
-        if let Some(mut pwd) = master_pwd.take() {
-            pwd.zeroize();
-        }
+    if state.vault_locked {
+        return Err(VaultError::Locked.to_string());
     }
+    state.vault_mut().set_kdf(kdf)?;
+    drop(state);
 
+    backup_after_save(&get_vault_path()?);
     Ok(())
 }
 
+/// Benchmarks Argon2id on this machine and rewraps the vault key so unlocking takes
+/// roughly `target_ms` (1 second by default).
 #[tauri::command]
-fn is_vault_locked() -> Result<bool, String> {
-    let vault_path = get_vault_path()?;
-    if !vault_path.exists() {
-        return Ok(true);
-    }
-
-    let master_pwd = MASTER_PASSWORD
-        .lock()
-        .map_err(|_| "Lock hatası".to_string())?;
-    if master_pwd.is_none() {
-        return Ok(true);
-    }
+async fn calibrate_kdf(target_ms: Option<u64>) -> Result<KdfParams, String> {
+    let target = Duration::from_millis(target_ms.unwrap_or(1000).clamp(250, 10_000));
+    let kdf = tokio::task::spawn_blocking(move || KdfParams::calibrate(target))
+        .await
+        .map_err(|e| format!("Kalibrasyon hatası: {}", e))??;
 
-    let state = get_state().map_err(|e| e.to_string())?;
-    Ok(state.vault_locked)
+    set_kdf_params(kdf)?;
+    Ok(kdf)
 }
 
 #[tauri::command]
@@ -719,6 +570,7 @@ fn add_password_entry(
     category: String,
     extra_fields: Option<HashMap<String, String>>,
     folder_id: Option<String>,
+    data: Option<EntryData>,
 ) -> Result<PasswordEntry, String> {
     let mut state = get_state_mut().map_err(|e| e.to_string())?;
 
@@ -728,25 +580,11 @@ fn add_password_entry(
 
     validate_input(&title, 1, 200, "Başlık").map_err(|e| e.to_string())?;
 
-    const VALID_CATEGORIES: &[&str] = &[
-        "accounts",
-        "bank_cards",
-        "documents",
-        "addresses",
-        "notes",
-        "passkeys",
-        "authenticator",
-    ];
     if !VALID_CATEGORIES.contains(&category.as_str()) {
         return Err("Geçersiz kategori".to_string());
     }
 
-    if category != "notes"
-        && category != "passkeys"
-        && category != "authenticator"
-        && category != "addresses"
-        && category != "documents"
-    {
+    if requires_credentials(&category) {
         validate_input(&username, 1, 200, "Kullanıcı adı").map_err(|e| e.to_string())?;
         validate_input(&password, 1, 500, "Şifre").map_err(|e| e.to_string())?;
     }
@@ -760,18 +598,13 @@ fn add_password_entry(
         }
     }
 
-    if let Some(ref notes_str) = notes {
-        validate_input(notes_str, 0, 5000, "Notlar").map_err(|e| e.to_string())?;
-    }
-
-    let id = format!("entry_{}", uuid::Uuid::new_v4());
     let now = SystemTime::now()
         .duration_since(UNIX_EPOCH)
         .map_err(|e| format!("Time error: {}", e))?
         .as_secs() as i64;
 
-    let entry = PasswordEntry {
-        id: id.clone(),
+    let mut entry = PasswordEntry {
+        id: format!("entry_{}", uuid::Uuid::new_v4()),
         title: title.trim().to_string(),
         username: username.trim().to_string(),
         password,
@@ -786,26 +619,19 @@ fn add_password_entry(
         folder_id,
         tags: None,
         attachments: None,
+        data,
+        deleted_at: None,
     };
+    prepare_entry(&mut entry).map_err(|e| e.to_string())?;
 
-    let entry_clone = entry.clone();
-    state.entries.insert(id, entry);
-
-    let pwd_string = {
-        let master_pwd = MASTER_PASSWORD
-            .lock()
-            .map_err(|_| "Master password lock hatası".to_string())?;
-
-        let pwd = master_pwd
-            .as_ref()
-            .ok_or_else(|| "Master password bulunamadı. Lütfen kasa kilidini açın.".to_string())?;
-
-        pwd.as_str().to_string()
-    };
-
-    save_vault_to_disk(&state, &pwd_string).map_err(|e| format!("Kayıt kaydedilemedi: {}", e))?;
+    state
+        .vault_mut()
+        .add_entry(entry.clone())
+        .map_err(|e| format!("Kayıt kaydedilemedi: {}", e))?;
+    drop(state);
 
-    Ok(entry_clone)
+    backup_after_save(&get_vault_path()?);
+    Ok(entry)
 }
 
 #[tauri::command]
@@ -858,10 +684,9 @@ fn create_folder(
 
     state.folders.push(folder.clone());
 
-    let master_pwd = get_master_password()?;
     let state_snapshot = state.clone();
     drop(state);
-    save_vault_to_disk(&state_snapshot, &master_pwd)?;
+    save_vault_to_disk(&state_snapshot)?;
 
     Ok(folder)
 }
@@ -900,10 +725,9 @@ fn update_folder(
 
     let updated_folder = folder.clone();
 
-    let master_pwd = get_master_password()?;
     let state_snapshot = state.clone();
     drop(state);
-    save_vault_to_disk(&state_snapshot, &master_pwd)?;
+    save_vault_to_disk(&state_snapshot)?;
 
     Ok(updated_folder)
 }
@@ -923,10 +747,9 @@ fn delete_folder(id: String) -> Result<(), String> {
         .entries
         .retain(|_, entry| entry.folder_id.as_ref() != Some(&id));
 
-    let master_pwd = get_master_password()?;
     let state_snapshot = state.clone();
     drop(state);
-    save_vault_to_disk(&state_snapshot, &master_pwd)?;
+    save_vault_to_disk(&state_snapshot)?;
 
     Ok(())
 }
@@ -946,10 +769,9 @@ fn move_entry_to_folder(entry_id: String, folder_id: Option<String>) -> Result<(
     entry.folder_id = folder_id;
     entry.updated_at = chrono::Utc::now().timestamp();
 
-    let master_pwd = get_master_password()?;
     let state_snapshot = state.clone();
     drop(state);
-    save_vault_to_disk(&state_snapshot, &master_pwd)?;
+    save_vault_to_disk(&state_snapshot)?;
 
     Ok(())
 }
@@ -964,17 +786,17 @@ fn bulk_delete_entries(ids: Vec<String>) -> Result<u32, String> {
     }
 
     let mut deleted = 0u32;
+    let now = chrono::Utc::now().timestamp();
     for id in &ids {
-        if state.entries.remove(id).is_some() {
+        if confpass_core::trash::trash_entry(&mut state, id, now).is_ok() {
             deleted += 1;
         }
     }
 
     if deleted > 0 {
-        let master_pwd = get_master_password()?;
         let state_snapshot = state.clone();
         drop(state);
-        save_vault_to_disk(&state_snapshot, &master_pwd)?;
+        save_vault_to_disk(&state_snapshot)?;
     }
 
     Ok(deleted)
@@ -991,17 +813,24 @@ fn bulk_move_to_folder(ids: Vec<String>, folder_id: Option<String>) -> Result<u3
     let now = chrono::Utc::now().timestamp();
     for id in &ids {
         if let Some(entry) = state.entries.get_mut(id) {
+            let before = entry.clone();
             entry.folder_id = folder_id.clone();
             entry.updated_at = now;
+            confpass_core::revisions::record_change(
+                &mut state,
+                id,
+                &before,
+                RevisionSource::Ui,
+                now,
+            )?;
             moved += 1;
         }
     }
 
     if moved > 0 {
-        let master_pwd = get_master_password()?;
         let state_snapshot = state.clone();
         drop(state);
-        save_vault_to_disk(&state_snapshot, &master_pwd)?;
+        save_vault_to_disk(&state_snapshot)?;
     }
 
     Ok(moved)
@@ -1033,10 +862,9 @@ fn create_tag(name: String, color: String) -> Result<Tag, String> {
 
     state.tags.push(tag.clone());
 
-    let master_pwd = get_master_password()?;
     let state_snapshot = state.clone();
     drop(state);
-    save_vault_to_disk(&state_snapshot, &master_pwd)?;
+    save_vault_to_disk(&state_snapshot)?;
 
     Ok(tag)
 }
@@ -1057,10 +885,9 @@ fn delete_tag(id: String) -> Result<(), String> {
         }
     }
 
-    let master_pwd = get_master_password()?;
     let state_snapshot = state.clone();
     drop(state);
-    save_vault_to_disk(&state_snapshot, &master_pwd)?;
+    save_vault_to_disk(&state_snapshot)?;
 
     Ok(())
 }
@@ -1080,10 +907,9 @@ fn update_entry_tags(entry_id: String, tags: Vec<String>) -> Result<(), String>
     entry.tags = Some(tags);
     entry.updated_at = chrono::Utc::now().timestamp();
 
-    let master_pwd = get_master_password()?;
     let state_snapshot = state.clone();
     drop(state);
-    save_vault_to_disk(&state_snapshot, &master_pwd)?;
+    save_vault_to_disk(&state_snapshot)?;
 
     Ok(())
 }
@@ -1114,6 +940,7 @@ fn update_password_entry(
     url: Option<String>,
     notes: Option<String>,
     category: Option<String>,
+    data: Option<EntryData>,
 ) -> Result<PasswordEntry, String> {
     let mut state = get_state_mut().map_err(|e| e.to_string())?;
 
@@ -1121,95 +948,54 @@ fn update_password_entry(
         return Err(VaultError::Locked.to_string());
     }
 
-    let entry = state
-        .entries
-        .get_mut(&id)
-        .ok_or_else(|| VaultError::NotFound.to_string())?;
-
-    if let Some(t) = title {
-        validate_input(&t, 1, 200, "Başlık").map_err(|e| e.to_string())?;
-        entry.title = t.trim().to_string();
-    }
-    if let Some(u) = username {
-        if entry.category != "notes"
-            && entry.category != "passkeys"
-            && entry.category != "authenticator"
-            && entry.category != "addresses"
-            && entry.category != "documents"
-        {
-            validate_input(&u, 1, 200, "Kullanıcı adı").map_err(|e| e.to_string())?;
+    // The vault edits a copy, so a rejected field leaves the stored entry untouched
+    let entry = state.vault_mut().update_entry(&id, |entry| {
+        if let Some(t) = title {
+            validate_input(&t, 1, 200, "Başlık").map_err(|e| e.to_string())?;
+            entry.title = t.trim().to_string();
         }
-        entry.username = u.trim().to_string();
-    }
-    if let Some(p) = password {
-        if entry.category != "notes"
-            && entry.category != "passkeys"
-            && entry.category != "authenticator"
-            && entry.category != "addresses"
-            && entry.category != "documents"
-        {
-            validate_input(&p, 1, 500, "Şifre").map_err(|e| e.to_string())?;
+        if let Some(u) = username {
+            if requires_credentials(&entry.category) {
+                validate_input(&u, 1, 200, "Kullanıcı adı").map_err(|e| e.to_string())?;
+            }
+            entry.username = u.trim().to_string();
         }
-        entry.password = p;
-    }
-    if let Some(u) = url {
-        if !u.trim().is_empty() {
-            if !u.trim().starts_with("http://") && !u.trim().starts_with("https://") {
-                return Err("URL http:// veya https:// ile başlamalı".to_string());
+        if let Some(p) = password {
+            if requires_credentials(&entry.category) {
+                validate_input(&p, 1, 500, "Şifre").map_err(|e| e.to_string())?;
             }
-            validate_input(&u, 1, 500, "URL").map_err(|e| e.to_string())?;
-            entry.url = Some(u.trim().to_string());
-        } else {
-            entry.url = None;
+            entry.password = p;
         }
-    }
-    if let Some(n) = notes {
-        if !n.trim().is_empty() {
-            validate_input(&n, 0, 5000, "Notlar").map_err(|e| e.to_string())?;
-            entry.notes = Some(n.trim().to_string());
-        } else {
-            entry.notes = None;
+        if let Some(u) = url {
+            if !u.trim().is_empty() {
+                if !u.trim().starts_with("http://") && !u.trim().starts_with("https://") {
+                    return Err("URL http:// veya https:// ile başlamalı".to_string());
+                }
+                validate_input(&u, 1, 500, "URL").map_err(|e| e.to_string())?;
+                entry.url = Some(u.trim().to_string());
+            } else {
+                entry.url = None;
+            }
         }
-    }
-    if let Some(c) = category {
-        const VALID_CATEGORIES: &[&str] = &[
-            "accounts",
-            "bank_cards",
-            "documents",
-            "addresses",
-            "notes",
-            "passkeys",
-            "authenticator",
-        ];
-        if !VALID_CATEGORIES.contains(&c.as_str()) {
-            return Err("Geçersiz kategori".to_string());
+        if let Some(n) = notes {
+            validate_input(&n, 0, NOTES_MAX_LEN, "Notlar").map_err(|e| e.to_string())?;
+            entry.notes = Some(n.trim().to_string()).filter(|n| !n.is_empty());
         }
-        entry.category = c;
-    }
-
-    entry.updated_at = SystemTime::now()
-        .duration_since(UNIX_EPOCH)
-        .map_err(|e| format!("Time error: {}", e))?
-        .as_secs() as i64;
-
-    let entry_clone = entry.clone();
-
-    let pwd_string = {
-        let master_pwd = MASTER_PASSWORD
-            .lock()
-            .map_err(|_| "Master password lock hatası".to_string())?;
-
-        let pwd = master_pwd
-            .as_ref()
-            .ok_or_else(|| "Master password bulunamadı. Lütfen kasa kilidini açın.".to_string())?;
-
-        pwd.as_str().to_string()
-    };
-
-    save_vault_to_disk(&state, &pwd_string)
-        .map_err(|e| format!("Güncelleme kaydedilemedi: {}", e))?;
+        if let Some(c) = category {
+            if !VALID_CATEGORIES.contains(&c.as_str()) {
+                return Err("Geçersiz kategori".to_string());
+            }
+            entry.category = c;
+        }
+        if let Some(d) = data {
+            entry.data = Some(d);
+        }
+        prepare_entry(entry).map_err(|e| e.to_string())
+    })?;
+    drop(state);
 
-    Ok(entry_clone)
+    backup_after_save(&get_vault_path()?);
+    Ok(entry)
 }
 
 #[tauri::command]
@@ -1220,143 +1006,66 @@ fn delete_password_entry(id: String) -> Result<(), String> {
         return Err(VaultError::Locked.to_string());
     }
 
-    state
-        .entries
-        .remove(&id)
-        .ok_or_else(|| VaultError::NotFound.to_string())?;
-
-    let pwd_string = {
-        let master_pwd = MASTER_PASSWORD
-            .lock()
-            .map_err(|_| "Master password lock hatası".to_string())?;
-
-        let pwd = master_pwd
-            .as_ref()
-            .ok_or_else(|| "Master password bulunamadı. Lütfen kasa kilidini açın.".to_string())?;
-
-        pwd.as_str().to_string()
-    };
+    // Deleted entries go to the trash and are purged after the retention period
+    confpass_core::trash::trash_entry(&mut state, &id, chrono::Utc::now().timestamp())?;
 
-    save_vault_to_disk(&state, &pwd_string)
-        .map_err(|e| format!("Silme işlemi kaydedilemedi: {}", e))?;
+    save_vault_to_disk(&state).map_err(|e| format!("Silme işlemi kaydedilemedi: {}", e))?;
 
     Ok(())
 }
 
 #[tauri::command]
-fn soft_delete_authenticator(id: String) -> Result<(), String> {
+fn restore_entry(id: String) -> Result<(), String> {
     let mut state = get_state_mut().map_err(|e| e.to_string())?;
 
     if state.vault_locked {
         return Err(VaultError::Locked.to_string());
     }
 
-    let entry = state
-        .entries
-        .get_mut(&id)
-        .ok_or_else(|| VaultError::NotFound.to_string())?;
-
-    // Move to trash by changing category
-    if entry.category == "authenticator" {
-        entry.category = "authenticator_trash".to_string();
-    } else {
-        return Err("Bu giriş bir kimlik doğrulayıcı değil".to_string());
-    }
-
-    let pwd_string = {
-        let master_pwd = MASTER_PASSWORD
-            .lock()
-            .map_err(|_| "Master password lock hatası".to_string())?;
+    confpass_core::trash::restore_entry(&mut state, &id)?;
 
-        let pwd = master_pwd
-            .as_ref()
-            .ok_or_else(|| "Master password bulunamadı".to_string())?;
-
-        pwd.as_str().to_string()
-    };
-
-    save_vault_to_disk(&state, &pwd_string).map_err(|e| format!("İşlem kaydedilemedi: {}", e))?;
+    save_vault_to_disk(&state).map_err(|e| format!("İşlem kaydedilemedi: {}", e))?;
 
     Ok(())
 }
 
 #[tauri::command]
-fn restore_authenticator(id: String) -> Result<(), String> {
+fn permanently_delete_entry(id: String) -> Result<(), String> {
     let mut state = get_state_mut().map_err(|e| e.to_string())?;
 
     if state.vault_locked {
         return Err(VaultError::Locked.to_string());
     }
 
-    let entry = state
-        .entries
-        .get_mut(&id)
-        .ok_or_else(|| VaultError::NotFound.to_string())?;
+    // A passkey's private key goes with its entry on save
+    confpass_core::trash::delete_permanently(&mut state, &id)?;
 
-    // Restore from trash
-    if entry.category == "authenticator_trash" {
-        entry.category = "authenticator".to_string();
-    } else {
-        return Err("Bu giriş çöp kutusunda değil".to_string());
-    }
-
-    let pwd_string = {
-        let master_pwd = MASTER_PASSWORD
-            .lock()
-            .map_err(|_| "Master password lock hatası".to_string())?;
-
-        let pwd = master_pwd
-            .as_ref()
-            .ok_or_else(|| "Master password bulunamadı".to_string())?;
-
-        pwd.as_str().to_string()
-    };
-
-    save_vault_to_disk(&state, &pwd_string).map_err(|e| format!("İşlem kaydedilemedi: {}", e))?;
+    save_vault_to_disk(&state).map_err(|e| format!("Silme işlemi kaydedilemedi: {}", e))?;
+    remove_orphan_attachments(&state);
 
     Ok(())
 }
 
 #[tauri::command]
-fn permanently_delete_authenticator(id: String) -> Result<(), String> {
+fn empty_trash() -> Result<u32, String> {
     let mut state = get_state_mut().map_err(|e| e.to_string())?;
 
     if state.vault_locked {
         return Err(VaultError::Locked.to_string());
     }
 
-    // Only allow permanent deletion from trash
-    let entry = state
-        .entries
-        .get(&id)
-        .ok_or_else(|| VaultError::NotFound.to_string())?;
-
-    if entry.category != "authenticator_trash" {
-        return Err("Kalıcı silme sadece çöp kutusundan yapılabilir".to_string());
+    let removed = confpass_core::trash::empty_trash(&mut state);
+    if removed > 0 {
+        save_vault_to_disk(&state).map_err(|e| format!("Silme işlemi kaydedilemedi: {}", e))?;
+        remove_orphan_attachments(&state);
     }
 
-    state.entries.remove(&id);
-
-    let pwd_string = {
-        let master_pwd = MASTER_PASSWORD
-            .lock()
-            .map_err(|_| "Master password lock hatası".to_string())?;
-
-        let pwd = master_pwd
-            .as_ref()
-            .ok_or_else(|| "Master password bulunamadı".to_string())?;
-
-        pwd.as_str().to_string()
-    };
-
-    save_vault_to_disk(&state, &pwd_string)
-        .map_err(|e| format!("Silme işlemi kaydedilemedi: {}", e))?;
-
-    Ok(())
+    Ok(removed as u32)
 }
 
-#[tauri::command]
-fn soft_delete_passkey(id: String) -> Result<(), String> {
+/// Trash and restore for the authenticator and passkey views; they only act
+/// on entries of `category`.
+fn set_trashed_in(id: &str, category: &str, trashed: bool) -> Result<(), String> {
     let mut state = get_state_mut().map_err(|e| e.to_string())?;
 
     if state.vault_locked {
@@ -1365,220 +1074,58 @@ fn soft_delete_passkey(id: String) -> Result<(), String> {
 
     let entry = state
         .entries
-        .get_mut(&id)
+        .get(id)
         .ok_or_else(|| VaultError::NotFound.to_string())?;
+    if entry.category != category {
+        return Err(if !trashed {
+            "Bu giriş çöp kutusunda değil".to_string()
+        } else if category == PASSKEY_CATEGORY {
+            "Bu giriş bir geçiş anahtarı değil".to_string()
+        } else {
+            "Bu giriş bir kimlik doğrulayıcı değil".to_string()
+        });
+    }
 
-    // Move to trash by changing category
-    if entry.category == "passkeys" {
-        // Extract credentialId before changing category
-        let credential_id_to_delete = entry
-            .notes
-            .as_ref()
-            .and_then(|notes| serde_json::from_str::<serde_json::Value>(notes).ok())
-            .and_then(|json| {
-                json.get("credentialId")
-                    .and_then(|v| v.as_str())
-                    .map(|s| s.to_string())
-            });
-
-        entry.category = "passkeys_trash".to_string();
-
-        // Also delete from passkeys.json so it doesn't show in browser
-        if let Some(cred_id) = credential_id_to_delete {
-            if let Ok(mut passkeys) = load_passkeys() {
-                let original_len = passkeys.len();
-                passkeys.retain(|p| p.credential_id != cred_id);
-                if passkeys.len() < original_len {
-                    let _ = save_passkeys(&passkeys);
-                    eprintln!(
-                        "[Passkey Delete] Removed passkey with credentialId: {} from passkeys.json",
-                        cred_id
-                    );
-                }
-            }
-        }
+    if trashed {
+        confpass_core::trash::trash_entry(&mut state, id, chrono::Utc::now().timestamp())?;
     } else {
-        return Err("Bu giriş bir geçiş anahtarı değil".to_string());
+        confpass_core::trash::restore_entry(&mut state, id)?;
     }
 
-    let pwd_string = {
-        let master_pwd = MASTER_PASSWORD
-            .lock()
-            .map_err(|_| "Master password lock hatası".to_string())?;
-
-        let pwd = master_pwd
-            .as_ref()
-            .ok_or_else(|| "Master password bulunamadı".to_string())?;
-
-        pwd.as_str().to_string()
-    };
-
-    save_vault_to_disk(&state, &pwd_string).map_err(|e| format!("İşlem kaydedilemedi: {}", e))?;
+    save_vault_to_disk(&state).map_err(|e| format!("İşlem kaydedilemedi: {}", e))?;
 
     Ok(())
 }
 
 #[tauri::command]
-fn restore_passkey(id: String) -> Result<(), String> {
-    let mut state = get_state_mut().map_err(|e| e.to_string())?;
-
-    if state.vault_locked {
-        return Err(VaultError::Locked.to_string());
-    }
-
-    let entry = state
-        .entries
-        .get_mut(&id)
-        .ok_or_else(|| VaultError::NotFound.to_string())?;
-
-    // Restore from trash
-    if entry.category == "passkeys_trash" {
-        // Extract passkey data from notes to restore to passkeys.json
-        let passkey_to_restore = entry
-            .notes
-            .as_ref()
-            .and_then(|notes| serde_json::from_str::<serde_json::Value>(notes).ok())
-            .and_then(|json| {
-                let credential_id = json.get("credentialId").and_then(|v| v.as_str())?;
-                let private_key = json.get("privateKey").and_then(|v| v.as_str())?;
-                let rp_id = json
-                    .get("rpId")
-                    .or(json.get("domain"))
-                    .and_then(|v| v.as_str())?;
-                let rp_name = json.get("rpName").and_then(|v| v.as_str()).unwrap_or("");
-                let user_id = json.get("userId").and_then(|v| v.as_str()).unwrap_or("");
-                let user_name = json.get("username").and_then(|v| v.as_str()).unwrap_or("");
-                let user_display_name = json
-                    .get("userDisplayName")
-                    .and_then(|v| v.as_str())
-                    .unwrap_or(user_name);
-                let counter = json.get("counter").and_then(|v| v.as_u64()).unwrap_or(0) as u32;
-                let created_at = json
-                    .get("createdAt")
-                    .and_then(|v| v.as_i64())
-                    .unwrap_or_else(|| {
-                        std::time::SystemTime::now()
-                            .duration_since(std::time::UNIX_EPOCH)
-                            .map(|d| d.as_secs() as i64)
-                            .unwrap_or(0)
-                    });
-
-                Some(StoredPasskey {
-                    credential_id: credential_id.to_string(),
-                    private_key: private_key.to_string(),
-                    rp_id: rp_id.to_string(),
-                    rp_name: rp_name.to_string(),
-                    user_id: user_id.to_string(),
-                    user_name: user_name.to_string(),
-                    user_display_name: user_display_name.to_string(),
-                    counter,
-                    created_at,
-                })
-            });
-
-        entry.category = "passkeys".to_string();
-
-        // Restore to passkeys.json if we have the data
-        if let Some(passkey) = passkey_to_restore {
-            if let Ok(mut passkeys) = load_passkeys() {
-                // Check if not already exists
-                if !passkeys
-                    .iter()
-                    .any(|p| p.credential_id == passkey.credential_id)
-                {
-                    passkeys.push(passkey);
-                    let _ = save_passkeys(&passkeys);
-                    eprintln!("[Passkey Restore] Restored passkey to passkeys.json");
-                }
-            }
-        } else {
-            eprintln!(
-                "[Passkey Restore] Warning: Could not extract passkey data from notes for restore"
-            );
-        }
-    } else {
-        return Err("Bu giriş çöp kutusunda değil".to_string());
-    }
-
-    let pwd_string = {
-        let master_pwd = MASTER_PASSWORD
-            .lock()
-            .map_err(|_| "Master password lock hatası".to_string())?;
-
-        let pwd = master_pwd
-            .as_ref()
-            .ok_or_else(|| "Master password bulunamadı".to_string())?;
-
-        pwd.as_str().to_string()
-    };
-
-    save_vault_to_disk(&state, &pwd_string).map_err(|e| format!("İşlem kaydedilemedi: {}", e))?;
-
-    Ok(())
+fn soft_delete_authenticator(id: String) -> Result<(), String> {
+    set_trashed_in(&id, "authenticator", true)
 }
 
 #[tauri::command]
-fn permanently_delete_passkey(id: String) -> Result<(), String> {
-    let mut state = get_state_mut().map_err(|e| e.to_string())?;
-
-    if state.vault_locked {
-        return Err(VaultError::Locked.to_string());
-    }
-
-    // Only allow permanent deletion from trash
-    let entry = state
-        .entries
-        .get(&id)
-        .ok_or_else(|| VaultError::NotFound.to_string())?;
-
-    if entry.category != "passkeys_trash" {
-        return Err("Kalıcı silme sadece çöp kutusundan yapılabilir".to_string());
-    }
-
-    // Extract credentialId from notes to delete from passkeys.json
-    let credential_id_to_delete = entry
-        .notes
-        .as_ref()
-        .and_then(|notes| serde_json::from_str::<serde_json::Value>(notes).ok())
-        .and_then(|json| {
-            json.get("credentialId")
-                .and_then(|v| v.as_str())
-                .map(|s| s.to_string())
-        });
-
-    state.entries.remove(&id);
-
-    let pwd_string = {
-        let master_pwd = MASTER_PASSWORD
-            .lock()
-            .map_err(|_| "Master password lock hatası".to_string())?;
-
-        let pwd = master_pwd
-            .as_ref()
-            .ok_or_else(|| "Master password bulunamadı".to_string())?;
-
-        pwd.as_str().to_string()
-    };
+fn restore_authenticator(id: String) -> Result<(), String> {
+    set_trashed_in(&id, "authenticator", false)
+}
 
-    save_vault_to_disk(&state, &pwd_string)
-        .map_err(|e| format!("Silme işlemi kaydedilemedi: {}", e))?;
+#[tauri::command]
+fn permanently_delete_authenticator(id: String) -> Result<(), String> {
+    permanently_delete_entry(id)
+}
 
-    // Also delete from passkeys.json if credentialId was found
-    if let Some(cred_id) = credential_id_to_delete {
-        if let Ok(mut passkeys) = load_passkeys() {
-            let original_len = passkeys.len();
-            passkeys.retain(|p| p.credential_id != cred_id);
-            if passkeys.len() < original_len {
-                let _ = save_passkeys(&passkeys);
-                eprintln!(
-                    "[Passkey Delete] Removed passkey with credentialId: {} from passkeys.json",
-                    cred_id
-                );
-            }
-        }
-    }
+#[tauri::command]
+fn soft_delete_passkey(id: String) -> Result<(), String> {
+    // Trashed passkeys are not served to the browser
+    set_trashed_in(&id, PASSKEY_CATEGORY, true)
+}
 
-    Ok(())
+#[tauri::command]
+fn restore_passkey(id: String) -> Result<(), String> {
+    set_trashed_in(&id, PASSKEY_CATEGORY, false)
+}
+
+#[tauri::command]
+fn permanently_delete_passkey(id: String) -> Result<(), String> {
+    permanently_delete_entry(id)
 }
 
 #[tauri::command]
@@ -1712,60 +1259,23 @@ fn find_password_by_url(url: String) -> Result<Option<PasswordEntry>, String> {
     let url_lower = url.trim().to_lowercase();
     let url_domain = extract_domain(&url_lower);
 
-    let matching_entry = state.entries.values().find(|entry| {
-        entry.url.as_ref().map_or(false, |entry_url| {
-            let entry_url_lower = entry_url.to_lowercase();
-            entry_url_lower.contains(&url_lower)
-                || url_domain
-                    .as_ref()
-                    .map_or(false, |domain| entry_url_lower.contains(domain))
-        })
-    });
+    let matching_entry = state
+        .entries
+        .values()
+        .filter(|e| !is_trashed(e))
+        .find(|entry| {
+            entry.url.as_ref().map_or(false, |entry_url| {
+                let entry_url_lower = entry_url.to_lowercase();
+                entry_url_lower.contains(&url_lower)
+                    || url_domain
+                        .as_ref()
+                        .map_or(false, |domain| entry_url_lower.contains(domain))
+            })
+        });
 
     Ok(matching_entry.cloned())
 }
 
-fn extract_domain(url: &str) -> Option<String> {
-    let url_str = url.trim();
-    if url_str.is_empty() {
-        return None;
-    }
-
-    // Try to parse using Url crate
-    // If it fails (e.g. no protocol), try adding https://
-    let parsed_url = url::Url::parse(url_str)
-        .or_else(|_| url::Url::parse(&format!("https://{}", url_str)))
-        .ok();
-
-    if let Some(u) = parsed_url {
-        if let Some(host) = u.host_str() {
-            // Remove www. prefix if present
-            let domain = host.strip_prefix("www.").unwrap_or(host);
-            return Some(domain.to_lowercase());
-        }
-    }
-
-    // Fallback: simple split if everything fails (should be rare with Url crate)
-    let without_protocol = url_str
-        .strip_prefix("http://")
-        .or_else(|| url_str.strip_prefix("https://"))
-        .unwrap_or(url_str);
-
-    let without_www = without_protocol
-        .strip_prefix("www.")
-        .unwrap_or(without_protocol);
-
-    let domain = without_www.split('/').next().unwrap_or(without_www);
-    let domain = domain.split(':').next().unwrap_or(domain);
-    let domain = domain.split('?').next().unwrap_or(domain); // Handle queries
-
-    if domain.is_empty() {
-        None
-    } else {
-        Some(domain.to_lowercase())
-    }
-}
-
 #[tauri::command]
 fn export_vault() -> Result<String, String> {
     let state = get_state().map_err(|e| e.to_string())?;
@@ -1788,8 +1298,13 @@ fn export_vault() -> Result<String, String> {
     serde_json::to_string_pretty(&export_data).map_err(|e| format!("Export error: {}", e))
 }
 
+/// Imports a ConfPass JSON export; entries already in the vault are merged
+/// according to `strategy`, skipped by default.
 #[tauri::command]
-fn import_vault(json_data: String) -> Result<u32, String> {
+fn import_vault(
+    json_data: String,
+    strategy: Option<MergeStrategy>,
+) -> Result<ImportSummary, String> {
     let mut state = get_state_mut().map_err(|e| e.to_string())?;
 
     if state.vault_locked {
@@ -1800,59 +1315,64 @@ fn import_vault(json_data: String) -> Result<u32, String> {
         return Err(VaultError::InvalidInput("Import data boş olamaz".to_string()).to_string());
     }
 
-    let parsed: serde_json::Value =
-        serde_json::from_str(&json_data).map_err(|e| format!("JSON parse error: {}", e))?;
+    let now = unix_now() as i64;
+    let preview = importers::parse(ImportFormat::Confpass, json_data.as_bytes(), now)?;
+    let skipped_count = preview.skipped;
+    let summary = importers::apply(&mut state, preview, strategy.unwrap_or_default(), now)?;
 
-    let entries: Vec<PasswordEntry> = if let Some(entries_array) = parsed.get("entries") {
-        serde_json::from_value(entries_array.clone())
-            .map_err(|e| format!("Entries parse error: {}", e))?
-    } else if parsed.is_array() {
-        serde_json::from_value(parsed).map_err(|e| format!("Array parse error: {}", e))?
-    } else {
-        return Err("Geçersiz import formatı".to_string());
-    };
+    if summary.changes.is_empty() && skipped_count > 0 {
+        return Err(format!(
+            "Hiçbir kayıt import edilemedi. {} kayıt atlandı.",
+            skipped_count
+        ));
+    }
 
-    let mut imported_count = 0;
-    let mut skipped_count = 0;
+    if let Err(e) = save_vault_to_disk(&state) {
+        eprintln!("Import sonrası kaydetme hatası: {}", e);
+    }
 
-    for entry in entries {
-        if entry.id.trim().is_empty() {
-            skipped_count += 1;
-            continue;
-        }
+    Ok(summary)
+}
 
-        if state.entries.contains_key(&entry.id) {
-            skipped_count += 1;
-            continue;
-        }
+/// Imports another password manager's or a browser's export. With `dry_run`
+/// the vault is left untouched and the parsed preview, warnings included, is
+/// returned; otherwise the import summary. Duplicates of vault entries are
+/// merged according to `strategy`, skipped by default.
+#[tauri::command]
+fn import_entries(
+    format: ImportFormat,
+    data: Vec<u8>,
+    dry_run: bool,
+    strategy: Option<MergeStrategy>,
+) -> Result<serde_json::Value, String> {
+    let mut state = get_state_mut().map_err(|e| e.to_string())?;
 
-        if entry.title.trim().is_empty() || entry.username.trim().is_empty() {
-            skipped_count += 1;
-            continue;
-        }
+    if state.vault_locked {
+        return Err(VaultError::Locked.to_string());
+    }
 
-        state.entries.insert(entry.id.clone(), entry);
-        imported_count += 1;
+    if data.is_empty() {
+        return Err(VaultError::InvalidInput("Import data boş olamaz".to_string()).to_string());
     }
 
-    if imported_count == 0 && skipped_count > 0 {
-        return Err(format!(
-            "Hiçbir kayıt import edilemedi. {} kayıt atlandı.",
-            skipped_count
-        ));
+    let now = unix_now() as i64;
+    let preview = importers::parse(format, &data, now)?;
+    if dry_run {
+        return serde_json::to_value(&preview).map_err(|e| format!("JSON error: {}", e));
     }
 
-    if let Ok(master_pwd) = MASTER_PASSWORD.lock() {
-        if let Some(ref pwd) = *master_pwd {
-            if let Err(e) = save_vault_to_disk(&state, pwd.as_str()) {
-                eprintln!("Import sonrası kaydetme hatası: {}", e);
-            }
-        }
+    if preview.entries.is_empty() {
+        return Err("İçe aktarılacak kayıt bulunamadı".to_string());
     }
+    let summary = importers::apply(&mut state, preview, strategy.unwrap_or_default(), now)?;
+    save_vault_to_disk(&state)?;
 
-    Ok(imported_count)
+    serde_json::to_value(&summary).map_err(|e| format!("JSON error: {}", e))
 }
 
+/// Exports the whole vault, folders, tags, history, passkeys and attachment
+/// contents included, as a `confpass_encrypted_v2` archive sealed with the
+/// vault's own Argon2id cost.
 #[tauri::command]
 fn export_vault_encrypted(mut export_password: String) -> Result<String, String> {
     let state = get_state().map_err(|e| e.to_string())?;
@@ -1867,40 +1387,30 @@ fn export_vault_encrypted(mut export_password: String) -> Result<String, String>
         return Err("Şifre en az 8 karakter olmalı".to_string());
     }
 
-    let entries: Vec<&PasswordEntry> = state.entries.values().collect();
-    let export_data = serde_json::json!({
-        "version": "1.0",
-        "encrypted": true,
-        "exported_at": SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .map_err(|e| format!("Time error: {}", e))?
-            .as_secs(),
-        "entry_count": entries.len(),
-        "entries": entries
-    });
-
-    let json_str = serde_json::to_string(&export_data).map_err(|e| format!("JSON error: {}", e))?;
-
-    // Generate salt and encrypt
-    let mut salt = [0u8; 32];
-    OsRng.fill_bytes(&mut salt);
-    let encrypted = encrypt_vault_data(&json_str, &export_password, &salt)?;
-
+    let kdf = state
+        .keys
+        .as_ref()
+        .map(|keys| keys.password_slot.kdf)
+        .filter(|kdf| !kdf.is_legacy())
+        .unwrap_or_default();
+    let archive = archive::export(
+        &state,
+        &get_attachments_dir()?,
+        &export_password,
+        kdf,
+        unix_now() as i64,
+    );
     export_password.zeroize();
 
-    Ok(serde_json::json!({
-        "format": "confpass_encrypted_v1",
-        "salt": general_purpose::STANDARD.encode(&salt),
-        "data": encrypted
-    })
-    .to_string())
+    archive
 }
 
 #[tauri::command]
 fn import_vault_encrypted(
     encrypted_json: String,
     mut import_password: String,
-) -> Result<u32, String> {
+    strategy: Option<MergeStrategy>,
+) -> Result<ImportSummary, String> {
     let mut state = get_state_mut().map_err(|e| e.to_string())?;
 
     if state.vault_locked {
@@ -1916,6 +1426,20 @@ fn import_vault_encrypted(
         .as_str()
         .ok_or("Format bilgisi bulunamadi")?;
 
+    if format == archive::ARCHIVE_FORMAT {
+        let opened = archive::open(&encrypted_json, &import_password);
+        import_password.zeroize();
+        let summary = archive::restore(
+            &mut state,
+            opened?,
+            strategy.unwrap_or_default(),
+            &get_attachments_dir()?,
+            unix_now() as i64,
+        )?;
+        save_vault_to_disk(&state)?;
+        return Ok(summary);
+    }
+
     if format != "confpass_encrypted_v1" {
         return Err("Desteklenmeyen dosya formatı".to_string());
     }
@@ -1940,57 +1464,111 @@ fn import_vault_encrypted(
 
     import_password.zeroize();
 
-    // Parse decrypted data
-    let vault_data: serde_json::Value = serde_json::from_str(&decrypted)
-        .map_err(|e| format!("Decrypted JSON parse error: {}", e))?;
+    let now = unix_now() as i64;
+    let preview = importers::parse(ImportFormat::Confpass, decrypted.as_bytes(), now)?;
+    let summary = importers::apply(&mut state, preview, strategy.unwrap_or_default(), now)?;
 
-    let entries: Vec<PasswordEntry> = if let Some(entries_array) = vault_data.get("entries") {
-        serde_json::from_value(entries_array.clone())
-            .map_err(|e| format!("Entries parse error: {}", e))?
-    } else {
-        return Err("Geçersiz import formatı".to_string());
-    };
+    // Save to disk
+    if let Err(e) = save_vault_to_disk(&state) {
+        eprintln!("Import sonrası kaydetme hatası: {}", e);
+    }
 
-    let mut imported_count = 0u32;
+    Ok(summary)
+}
 
-    for entry in entries {
-        if entry.id.trim().is_empty() || entry.title.trim().is_empty() {
-            continue;
-        }
+/// Reads the key file of a KeePass database; an empty path means none.
+fn read_kdbx_keyfile(keyfile: Option<String>) -> Result<Option<Vec<u8>>, String> {
+    keyfile
+        .filter(|path| !path.trim().is_empty())
+        .map(|path| fs::read(path.trim()).map_err(|e| format!("Anahtar dosyası okunamadı: {}", e)))
+        .transpose()
+}
 
-        if state.entries.contains_key(&entry.id) {
-            // Generate new ID for duplicate
-            let new_entry = PasswordEntry {
-                id: uuid::Uuid::new_v4().to_string(),
-                ..entry
-            };
-            state.entries.insert(new_entry.id.clone(), new_entry);
-        } else {
-            state.entries.insert(entry.id.clone(), entry);
-        }
-        imported_count += 1;
+/// Writes the vault to `path` as a KeePass KDBX 4 database, with the
+/// vault's own Argon2id cost.
+#[tauri::command]
+fn export_vault_kdbx(
+    path: String,
+    mut password: String,
+    keyfile: Option<String>,
+) -> Result<(), String> {
+    let state = get_state().map_err(|e| e.to_string())?;
+
+    if state.vault_locked {
+        return Err(VaultError::Locked.to_string());
     }
 
-    // Save to disk
-    if let Ok(master_pwd) = MASTER_PASSWORD.lock() {
-        if let Some(ref pwd) = *master_pwd {
-            if let Err(e) = save_vault_to_disk(&state, pwd.as_str()) {
-                eprintln!("Import sonrası kaydetme hatası: {}", e);
-            }
-        }
+    let keyfile = read_kdbx_keyfile(keyfile)?;
+    if keyfile.is_none() && password.len() < 8 {
+        password.zeroize();
+        return Err("Şifre en az 8 karakter olmalı".to_string());
+    }
+
+    let kdf = state
+        .keys
+        .as_ref()
+        .map(|keys| keys.password_slot.kdf)
+        .unwrap_or_default();
+    let data = confpass_core::kdbx::write(
+        &state,
+        &get_attachments_dir()?,
+        &password,
+        keyfile.as_deref(),
+        kdf,
+        unix_now() as i64,
+    );
+    password.zeroize();
+
+    confpass_core::storage::write_atomic(std::path::Path::new(path.trim()), &data?)
+}
+
+/// Imports a KeePass KDBX 4 database. With `dry_run` only the preview is
+/// returned; otherwise its binaries are stored as attachments and the
+/// import summary is returned. Duplicates are merged according to `strategy`.
+#[tauri::command]
+fn import_vault_kdbx(
+    path: String,
+    mut password: String,
+    keyfile: Option<String>,
+    dry_run: bool,
+    strategy: Option<MergeStrategy>,
+) -> Result<serde_json::Value, String> {
+    let mut state = get_state_mut().map_err(|e| e.to_string())?;
+
+    if state.vault_locked {
+        return Err(VaultError::Locked.to_string());
+    }
+
+    let data = fs::read(path.trim()).map_err(|e| format!("Dosya okunamadı: {}", e))?;
+    let keyfile = read_kdbx_keyfile(keyfile)?;
+    let now = unix_now() as i64;
+    let preview = confpass_core::kdbx::read(&data, &password, keyfile.as_deref(), now);
+    password.zeroize();
+    let preview = preview?;
+
+    if dry_run {
+        return serde_json::to_value(&preview).map_err(|e| format!("JSON error: {}", e));
     }
 
-    Ok(imported_count)
+    if preview.entries.is_empty() {
+        return Err("İçe aktarılacak kayıt bulunamadı".to_string());
+    }
+    let summary = importers::apply_with_files(
+        &mut state,
+        preview,
+        strategy.unwrap_or_default(),
+        now,
+        &get_attachments_dir()?,
+    )?;
+    save_vault_to_disk(&state)?;
+
+    serde_json::to_value(&summary).map_err(|e| format!("JSON error: {}", e))
 }
 
 // ==================== File Attachments ====================
 
 fn get_attachments_dir() -> Result<PathBuf, String> {
-    let vault_path = get_vault_path()?;
-    let attachments_dir = vault_path
-        .parent()
-        .ok_or("Vault path parent bulunamadi")?
-        .join("attachments");
+    let attachments_dir = confpass_core::attachments::attachments_dir(&get_vault_path()?)?;
 
     fs::create_dir_all(&attachments_dir)
         .map_err(|e| format!("Attachments dizini oluşturulamadı: {}", e))?;
@@ -2011,13 +1589,21 @@ fn add_attachment(entry_id: String, file_path: String) -> Result<FileAttachment,
         return Err(VaultError::NotFound.to_string());
     }
 
-    // Read file
-    let content = fs::read(&file_path).map_err(|e| format!("Dosya okunamadı: {}", e))?;
-
-    // Max file size check (10MB)
-    const MAX_FILE_SIZE: usize = 10 * 1024 * 1024;
-    if content.len() > MAX_FILE_SIZE {
-        return Err("Dosya boyutu 10MB'dan büyük olamaz".to_string());
+    // Max file size check; the limit is enforced again while the file is read
+    let max_attachment_mb = get_settings()
+        .map(|s| s.max_attachment_mb)
+        .unwrap_or(DEFAULT_MAX_ATTACHMENT_MB);
+    let max_size = u64::from(max_attachment_mb) * 1024 * 1024;
+    let mut file = fs::File::open(&file_path).map_err(|e| format!("Dosya okunamadı: {}", e))?;
+    let file_size = file
+        .metadata()
+        .map_err(|e| format!("Dosya okunamadı: {}", e))?
+        .len();
+    if file_size > max_size {
+        return Err(format!(
+            "Dosya boyutu {} MB'dan büyük olamaz",
+            max_attachment_mb
+        ));
     }
 
     let path = std::path::Path::new(&file_path);
@@ -2027,55 +1613,40 @@ fn add_attachment(entry_id: String, file_path: String) -> Result<FileAttachment,
         .unwrap_or("file")
         .to_string();
 
-    // Guess mime type from extension
-    let mime_type = match path.extension().and_then(|e| e.to_str()) {
-        Some("pdf") => "application/pdf",
-        Some("png") => "image/png",
-        Some("jpg") | Some("jpeg") => "image/jpeg",
-        Some("gif") => "image/gif",
-        Some("txt") => "text/plain",
-        Some("doc") | Some("docx") => "application/msword",
-        Some("xls") | Some("xlsx") => "application/vnd.ms-excel",
-        Some("zip") => "application/zip",
-        _ => "application/octet-stream",
-    }
-    .to_string();
-
-    // Get master password for encryption
-    let master_pwd = MASTER_PASSWORD
-        .lock()
-        .map_err(|_| "Master password kilidi alinamadi")?;
-    let pwd = master_pwd.as_ref().ok_or("Vault kilitli")?;
-
-    // Generate salt and encrypt file content
-    let mut salt = [0u8; 32];
-    OsRng.fill_bytes(&mut salt);
-    let content_b64 = general_purpose::STANDARD.encode(&content);
-    let encrypted = encrypt_vault_data(&content_b64, pwd.as_str(), &salt)?;
+    // Detect the type from the first bytes; they are encrypted along with the rest
+    let mut head = Vec::with_capacity(mime::SNIFF_LEN);
+    (&mut file)
+        .take(mime::SNIFF_LEN as u64)
+        .read_to_end(&mut head)
+        .map_err(|e| format!("Dosya okunamadı: {}", e))?;
+    let mime_type = mime::detect(&head, &filename).to_string();
 
     // Create attachment metadata
-    let attachment = FileAttachment {
+    let mut attachment = FileAttachment {
         id: uuid::Uuid::new_v4().to_string(),
         filename,
         mime_type,
-        size: content.len() as u64,
+        size: file_size,
         created_at: SystemTime::now()
             .duration_since(UNIX_EPOCH)
             .map_err(|e| format!("Time error: {}", e))?
             .as_secs() as i64,
     };
 
-    // Save encrypted file
-    let attachments_dir = get_attachments_dir()?;
-    let att_path = attachments_dir.join(format!("{}.enc", attachment.id));
-
-    let encrypted_file_data = serde_json::json!({
-        "salt": general_purpose::STANDARD.encode(&salt),
-        "data": encrypted
-    });
-
-    fs::write(&att_path, encrypted_file_data.to_string())
-        .map_err(|e| format!("Ek kaydedilemedi: {}", e))?;
+    // Encrypt in chunks with the vault data key, bound to the attachment id
+    let keys = state
+        .keys
+        .as_ref()
+        .ok_or_else(|| VaultError::Locked.to_string())?;
+    let written = confpass_core::attachments::write_attachment_from(
+        &get_attachments_dir()?,
+        &attachment.id,
+        (&head[..]).chain(file),
+        &keys.data_key,
+        Some(max_size),
+    );
+    head.zeroize();
+    attachment.size = written.map_err(|e| format!("Ek kaydedilemedi: {}", e))?;
 
     // Add attachment to entry
     if let Some(entry) = state.entries.get_mut(&entry_id) {
@@ -2084,47 +1655,176 @@ fn add_attachment(entry_id: String, file_path: String) -> Result<FileAttachment,
     }
 
     // Save vault
-    save_vault_to_disk(&state, pwd.as_str())?;
+    save_vault_to_disk(&state)?;
 
     Ok(attachment)
 }
 
 #[tauri::command]
-fn get_attachment(attachment_id: String) -> Result<String, String> {
+fn get_attachment(entry_id: String, attachment_id: String) -> Result<String, String> {
     let state = get_state().map_err(|e| e.to_string())?;
 
     if state.vault_locked {
         return Err(VaultError::Locked.to_string());
     }
 
-    // Get master password for decryption
-    let master_pwd = MASTER_PASSWORD
-        .lock()
-        .map_err(|_| "Master password kilidi alinamadi")?;
-    let pwd = master_pwd.as_ref().ok_or("Vault kilitli")?;
+    confpass_core::attachments::owned_attachment(&state, &entry_id, &attachment_id)?;
 
-    // Read encrypted file
-    let attachments_dir = get_attachments_dir()?;
-    let att_path = attachments_dir.join(format!("{}.enc", attachment_id));
+    let keys = state
+        .keys
+        .as_ref()
+        .ok_or_else(|| VaultError::Locked.to_string())?;
 
-    let encrypted_content =
-        fs::read_to_string(&att_path).map_err(|e| format!("Ek okunamadı: {}", e))?;
+    let mut content = confpass_core::attachments::read_attachment(
+        &get_attachments_dir()?,
+        &attachment_id,
+        &keys.data_key,
+    )?;
 
-    let parsed: serde_json::Value =
-        serde_json::from_str(&encrypted_content).map_err(|e| format!("JSON parse error: {}", e))?;
+    // Return as base64 (frontend will handle download)
+    let encoded = general_purpose::STANDARD.encode(&content);
+    content.zeroize();
+    Ok(encoded)
+}
 
-    let salt_b64 = parsed["salt"].as_str().ok_or("Salt bulunamadi")?;
-    let salt = general_purpose::STANDARD
-        .decode(salt_b64)
-        .map_err(|e| format!("Salt decode error: {}", e))?;
+/// Largest image attachment that gets a thumbnail.
+const PREVIEW_IMAGE_MAX_SIZE: u64 = 32 * 1024 * 1024;
+const PREVIEW_THUMBNAIL_SIZE: u32 = 256;
+const PREVIEW_TEXT_BYTES: usize = 4096;
+const PREVIEW_TEXT_LINES: usize = 40;
+
+#[derive(Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+enum AttachmentPreview {
+    /// PNG thumbnail as base64, at most `PREVIEW_THUMBNAIL_SIZE` on each side
+    Image {
+        mime_type: String,
+        data: String,
+        width: u32,
+        height: u32,
+    },
+    Text {
+        text: String,
+        truncated: bool,
+    },
+    Unsupported {
+        mime_type: String,
+    },
+}
 
-    let encrypted_data = parsed["data"].as_str().ok_or("Encrypted data bulunamadi")?;
+/// Inline preview of an attachment, decrypted in memory only: a thumbnail
+/// for images, the first lines for text files.
+#[tauri::command]
+fn get_attachment_preview(
+    entry_id: String,
+    attachment_id: String,
+) -> Result<AttachmentPreview, String> {
+    let state = get_state().map_err(|e| e.to_string())?;
 
-    // Decrypt
-    let decrypted_b64 = decrypt_vault_data(encrypted_data, pwd.as_str(), &salt)?;
+    if state.vault_locked {
+        return Err(VaultError::Locked.to_string());
+    }
 
-    // Return as base64 (frontend will handle download)
-    Ok(decrypted_b64)
+    let attachment =
+        confpass_core::attachments::owned_attachment(&state, &entry_id, &attachment_id)?;
+    let mime_type = attachment.mime_type.clone();
+    let size = attachment.size;
+
+    let keys = state
+        .keys
+        .as_ref()
+        .ok_or_else(|| VaultError::Locked.to_string())?;
+    let dir = get_attachments_dir()?;
+
+    if mime::is_text_type(&mime_type) {
+        let mut head = confpass_core::attachments::read_attachment_head(
+            &dir,
+            &attachment_id,
+            &keys.data_key,
+            PREVIEW_TEXT_BYTES,
+        )?;
+        let (text, truncated) = mime::text_excerpt(&head, PREVIEW_TEXT_BYTES, PREVIEW_TEXT_LINES);
+        head.zeroize();
+        return Ok(AttachmentPreview::Text {
+            text,
+            truncated: truncated || size > PREVIEW_TEXT_BYTES as u64,
+        });
+    }
+
+    if !mime::is_previewable_image(&mime_type) || size > PREVIEW_IMAGE_MAX_SIZE {
+        return Ok(AttachmentPreview::Unsupported { mime_type });
+    }
+
+    let mut content =
+        confpass_core::attachments::read_attachment(&dir, &attachment_id, &keys.data_key)?;
+    let decoded = image::load_from_memory(&content);
+    content.zeroize();
+    let thumbnail = match decoded {
+        Ok(image) => image
+            .thumbnail(PREVIEW_THUMBNAIL_SIZE, PREVIEW_THUMBNAIL_SIZE)
+            .to_rgba8(),
+        Err(e) => {
+            eprintln!("[Attachment] Önizleme oluşturulamadı: {}", e);
+            return Ok(AttachmentPreview::Unsupported { mime_type });
+        }
+    };
+
+    let (width, height) = thumbnail.dimensions();
+    let mut raw = thumbnail.into_raw();
+    let mut buffer = Vec::new();
+    let encoded = {
+        use image::ImageEncoder;
+        image::codecs::png::PngEncoder::new(&mut buffer).write_image(
+            &raw,
+            width,
+            height,
+            image::ExtendedColorType::Rgba8,
+        )
+    };
+    raw.zeroize();
+    encoded.map_err(|e| format!("PNG encode hatası: {}", e))?;
+
+    let data = general_purpose::STANDARD.encode(&buffer);
+    buffer.zeroize();
+    Ok(AttachmentPreview::Image {
+        mime_type,
+        data,
+        width,
+        height,
+    })
+}
+
+/// Decrypts an attachment straight to `path` without passing its content
+/// over IPC. Returns the number of bytes written.
+#[tauri::command]
+fn save_attachment_to(
+    entry_id: String,
+    attachment_id: String,
+    path: String,
+) -> Result<u64, String> {
+    let state = get_state().map_err(|e| e.to_string())?;
+
+    if state.vault_locked {
+        return Err(VaultError::Locked.to_string());
+    }
+
+    confpass_core::attachments::owned_attachment(&state, &entry_id, &attachment_id)?;
+
+    if path.trim().is_empty() {
+        return Err(VaultError::InvalidInput("Hedef yol boş olamaz".to_string()).to_string());
+    }
+
+    let keys = state
+        .keys
+        .as_ref()
+        .ok_or_else(|| VaultError::Locked.to_string())?;
+
+    confpass_core::attachments::save_attachment_to(
+        &get_attachments_dir()?,
+        &attachment_id,
+        &keys.data_key,
+        std::path::Path::new(path.trim()),
+    )
 }
 
 #[tauri::command]
@@ -2135,94 +1835,267 @@ fn delete_attachment(entry_id: String, attachment_id: String) -> Result<(), Stri
         return Err(VaultError::Locked.to_string());
     }
 
-    // Get master password
-    let master_pwd = MASTER_PASSWORD
-        .lock()
-        .map_err(|_| "Master password kilidi alinamadi")?;
-    let pwd = master_pwd.as_ref().ok_or("Vault kilitli")?;
+    confpass_core::attachments::owned_attachment(&state, &entry_id, &attachment_id)?;
 
     // Remove from entry
-    if let Some(entry) = state.entries.get_mut(&entry_id) {
-        if let Some(ref mut atts) = entry.attachments {
-            atts.retain(|a| a.id != attachment_id);
+    if let Some(atts) = state
+        .entries
+        .get_mut(&entry_id)
+        .and_then(|entry| entry.attachments.as_mut())
+    {
+        atts.retain(|a| a.id != attachment_id);
+    }
+
+    // Save vault; the encrypted file goes once no entry refers to it
+    save_vault_to_disk(&state)?;
+    remove_orphan_attachments(&state);
+
+    Ok(())
+}
+
+/// Removes attachment files no entry refers to any more. Runs after the vault
+/// has been saved, so a failed save never leaves an entry without its file.
+fn remove_orphan_attachments(state: &VaultState) {
+    let result = get_attachments_dir()
+        .and_then(|dir| confpass_core::attachments::collect_orphans(state, &dir, true));
+    match result {
+        Ok(report) if !report.attachment_ids.is_empty() => eprintln!(
+            "[Attachments] Removed {} orphan files ({} bytes)",
+            report.attachment_ids.len(),
+            report.total_size
+        ),
+        Ok(_) => {}
+        Err(e) => eprintln!("[Attachments] Orphan cleanup failed: {}", e),
+    }
+}
+
+/// Finds attachment files that belong to no entry and reports their total
+/// size; unless `dry_run` is set they are deleted.
+#[tauri::command]
+fn collect_attachment_garbage(dry_run: bool) -> Result<OrphanReport, String> {
+    let state = get_state().map_err(|e| e.to_string())?;
+
+    if state.vault_locked {
+        return Err(VaultError::Locked.to_string());
+    }
+
+    confpass_core::attachments::collect_orphans(&state, &get_attachments_dir()?, !dry_run)
+}
+
+/// Scope a route of the HTTP API needs. `Ok(None)` routes are open to every
+/// paired client; unlisted routes to none.
+fn route_scope(path: &str) -> Result<Option<Scope>, ()> {
+    match path {
+        "/ping" | "/focus_window" | "/passkey_detected" => Ok(None),
+        "/get_password"
+        | "/get_passwords_for_site"
+        | "/get_totp_code"
+        | "/check_duplicate"
+        | "/get_password_entry" => Ok(Some(Scope::AutofillRead)),
+        "/save_password" | "/save_entry" => Ok(Some(Scope::Save)),
+        "/get_passkeys" | "/passkey_create" | "/passkey_assert" => Ok(Some(Scope::Passkeys)),
+        "/get_cards" | "/get_addresses" => Ok(Some(Scope::Cards)),
+        _ => Err(()),
+    }
+}
+
+/// Scope a client needs on top of the route's to read or write entries of
+/// `category`. `Ok(None)` for logins; notes, documents and authenticator
+/// entries are never handed to clients.
+fn category_scope(category: &str) -> Result<Option<Scope>, ()> {
+    match category {
+        "accounts" => Ok(None),
+        "bank_cards" | "addresses" => Ok(Some(Scope::Cards)),
+        PASSKEY_CATEGORY => Ok(Some(Scope::Passkeys)),
+        _ => Err(()),
+    }
+}
+
+fn client_may_access(client: &ApiClient, category: &str) -> bool {
+    match category_scope(category) {
+        Ok(None) => true,
+        Ok(Some(scope)) => client.allows(scope),
+        Err(()) => false,
+    }
+}
+
+async fn auth_middleware(
+    mut req: axum::extract::Request,
+    next: axum::middleware::Next,
+) -> Result<axum::response::Response, StatusCode> {
+    let token = req
+        .headers()
+        .get("Authorization")
+        .and_then(|value| value.to_str().ok())
+        .map(|value| value.strip_prefix("Bearer ").unwrap_or(value))
+        .ok_or(StatusCode::UNAUTHORIZED)?;
+
+    let client = {
+        let mut registry = API_CLIENTS
+            .lock()
+            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+        let client = registry
+            .authenticate(token)
+            .cloned()
+            .ok_or(StatusCode::UNAUTHORIZED)?;
+        if registry.touch(&client.id, unix_timestamp()) {
+            if let Err(e) = registry.save() {
+                eprintln!("[API Clients] Kaydedilemedi: {}", e);
+            }
+        }
+        client
+    };
+
+    match route_scope(req.uri().path()) {
+        Ok(Some(scope)) if !client.allows(scope) => return Err(StatusCode::FORBIDDEN),
+        Ok(_) => {}
+        Err(()) => return Err(StatusCode::FORBIDDEN),
+    }
+
+    if req.uri().path() != "/ping" {
+        record_activity();
+    }
+    req.extensions_mut().insert(client);
+    Ok(next.run(req).await)
+}
+
+/// Loads the paired clients and removes the shared token file older
+/// versions handed to every client.
+fn load_api_clients() -> Result<(), String> {
+    let data_dir = get_vault_path()?
+        .parent()
+        .ok_or_else(|| "Vault path parent bulunamadı".to_string())?
+        .to_path_buf();
+
+    let legacy_token = data_dir.join("native_auth_token");
+    if legacy_token.exists() {
+        if let Err(e) = fs::remove_file(&legacy_token) {
+            eprintln!("[API Clients] Eski token dosyası silinemedi: {}", e);
+        }
+    }
+
+    let registry = ClientRegistry::load(&data_dir)?;
+    *API_CLIENTS
+        .lock()
+        .map_err(|_| "Failed to lock API_CLIENTS".to_string())? = registry;
+    Ok(())
+}
+
+#[derive(Deserialize)]
+struct PairRequestPayload {
+    name: String,
+    #[serde(default)]
+    scopes: Vec<Scope>,
+}
+
+/// Unauthenticated: a client asks to be paired. The user is shown the request
+/// in the app; the client polls `/pair_status` with the returned secret.
+async fn pair_request_handler(
+    Json(payload): Json<PairRequestPayload>,
+) -> Result<Json<serde_json::Value>, StatusCode> {
+    let scopes = if payload.scopes.is_empty() {
+        Scope::ALL.to_vec()
+    } else {
+        payload.scopes
+    };
+
+    let result = API_CLIENTS
+        .lock()
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .request_pairing(&payload.name, &scopes, unix_timestamp());
+    let (request, secret) = match result {
+        Ok(pairing) => pairing,
+        Err(e) => return Ok(Json(json!({"success": false, "error": e}))),
+    };
+
+    if let Some(app_handle) = get_app_handle() {
+        let _ = app_handle.emit("api-pairing-request", request.clone());
+        if let Some(window) = app_handle.get_webview_window("main") {
+            let _ = window.show();
+            let _ = window.set_focus();
         }
-    } else {
-        return Err(VaultError::NotFound.to_string());
     }
 
-    // Delete encrypted file
-    let attachments_dir = get_attachments_dir()?;
-    let att_path = attachments_dir.join(format!("{}.enc", attachment_id));
+    Ok(Json(json!({
+        "success": true,
+        "request_id": request.id,
+        "secret": secret.as_str(),
+    })))
+}
 
-    if att_path.exists() {
-        fs::remove_file(&att_path).map_err(|e| format!("Ek dosyası silinemedi: {}", e))?;
-    }
+#[derive(Deserialize)]
+struct PairStatusPayload {
+    request_id: String,
+    secret: String,
+}
 
-    // Save vault
-    save_vault_to_disk(&state, pwd.as_str())?;
+async fn pair_status_handler(
+    Json(payload): Json<PairStatusPayload>,
+) -> Result<Json<serde_json::Value>, StatusCode> {
+    let status = API_CLIENTS
+        .lock()
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .pairing_status(&payload.request_id, &payload.secret, unix_timestamp());
 
-    Ok(())
+    Ok(Json(match status {
+        PairingStatus::Pending => json!({"success": true, "status": "pending"}),
+        PairingStatus::Approved(token) => {
+            json!({"success": true, "status": "approved", "token": token.as_str()})
+        }
+        PairingStatus::Denied => json!({"success": true, "status": "denied"}),
+        PairingStatus::Unknown => json!({"success": true, "status": "unknown"}),
+    }))
 }
 
-async fn auth_middleware(
-    req: axum::extract::Request,
-    next: axum::middleware::Next,
-) -> Result<axum::response::Response, StatusCode> {
-    let auth_header = req
-        .headers()
-        .get("Authorization")
-        .and_then(|value| value.to_str().ok());
-
-    let token = {
-        let token_lock = AUTH_TOKEN
-            .lock()
-            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
-        token_lock.clone()
-    };
+#[tauri::command]
+fn list_api_clients() -> Result<Vec<ApiClient>, String> {
+    let registry = API_CLIENTS.lock().map_err(|e| e.to_string())?;
+    Ok(registry.clients())
+}
 
-    match (auth_header, token) {
-        (Some(auth), Some(expected)) if auth == expected => Ok(next.run(req).await),
-        _ => Err(StatusCode::UNAUTHORIZED),
+#[tauri::command]
+fn revoke_api_client(client_id: String) -> Result<(), String> {
+    let mut registry = API_CLIENTS.lock().map_err(|e| e.to_string())?;
+    if !registry.revoke(&client_id) {
+        return Err(VaultError::NotFound.to_string());
     }
+    registry.save()
 }
 
-async fn generate_and_save_auth_token() -> Result<(), String> {
-    let token = uuid::Uuid::new_v4().to_string();
+#[tauri::command]
+fn list_pairing_requests() -> Result<Vec<PairingRequest>, String> {
+    let mut registry = API_CLIENTS.lock().map_err(|e| e.to_string())?;
+    Ok(registry.pending_pairings(unix_timestamp()))
+}
 
-    // Save to memory
-    if let Ok(mut token_lock) = AUTH_TOKEN.lock() {
-        *token_lock = Some(token.clone());
-    } else {
-        return Err("Failed to lock AUTH_TOKEN".to_string());
+/// Approves a pairing request with the scopes the user left ticked.
+#[tauri::command]
+fn approve_pairing_request(request_id: String, scopes: Vec<Scope>) -> Result<ApiClient, String> {
+    if get_state().map_err(|e| e.to_string())?.vault_locked {
+        return Err(VaultError::Locked.to_string());
     }
 
-    // Save to file
-    let token_path = get_vault_path()?
-        .parent()
-        .ok_or_else(|| "Vault path parent bulunamadı".to_string())?
-        .join("native_auth_token");
-
-    fs::write(&token_path, &token).map_err(|e| format!("Token dosyası yazılamadı: {}", e))?;
+    let mut registry = API_CLIENTS.lock().map_err(|e| e.to_string())?;
+    let client = registry.approve(&request_id, &scopes, unix_timestamp())?;
+    if let Err(e) = registry.save() {
+        registry.revoke(&client.id);
+        return Err(e);
+    }
+    Ok(client)
+}
 
-    Ok(())
+#[tauri::command]
+fn deny_pairing_request(request_id: String) -> Result<(), String> {
+    let mut registry = API_CLIENTS.lock().map_err(|e| e.to_string())?;
+    registry.deny(&request_id, unix_timestamp())
 }
 
 fn register_native_messaging_host(app_handle: &tauri::AppHandle) -> Result<(), String> {
-    use std::fs::OpenOptions;
-    use std::io::Write;
     use std::path::PathBuf;
     use winreg::enums::*;
     use winreg::RegKey;
 
-    let log_debug = |msg: &str| {
-        if let Ok(mut file) = OpenOptions::new()
-            .create(true)
-            .append(true)
-            .open("C:\\Users\\Public\\confpass_native_debug.log")
-        {
-            let _ = writeln!(file, "[{}] {}", chrono::Local::now(), msg);
-        }
-    };
+    let log_debug = |msg: &str| log_to_file(&format!("[Native Messaging] {}", msg));
 
     log_debug("--- Native Messaging Registration Started ---");
 
@@ -2366,13 +2239,8 @@ fn register_native_messaging_host(app_handle: &tauri::AppHandle) -> Result<(), S
     Ok(())
 }
 
-async fn start_http_server() {
-    let cors = CorsLayer::new()
-        .allow_origin(Any)
-        .allow_methods(Any)
-        .allow_headers(Any);
-
-    let router = Router::new()
+fn api_router() -> Router {
+    Router::new()
         .route(
             "/ping",
             get(|| async { Json(json!({"status": "ok"})) })
@@ -2381,12 +2249,9 @@ async fn start_http_server() {
         .route("/get_password", post(get_password_handler))
         .route("/save_password", post(save_password_handler))
         .route("/passkey_detected", post(passkey_detected_handler))
-        .route("/save_passkey", post(save_passkey_handler))
         .route("/get_passkeys", post(get_passkeys_handler))
-        .route(
-            "/update_passkey_counter",
-            post(update_passkey_counter_handler),
-        )
+        .route("/passkey_create", post(passkey_create_handler))
+        .route("/passkey_assert", post(passkey_assert_handler))
         .route("/focus_window", post(focus_window_handler))
         .route(
             "/get_passwords_for_site",
@@ -2399,12 +2264,239 @@ async fn start_http_server() {
         .route("/save_entry", post(save_entry_handler))
         .route("/get_password_entry", post(get_password_entry_handler))
         .layer(axum::middleware::from_fn(auth_middleware))
-        .layer(cors);
+        .route("/pair_request", post(pair_request_handler))
+        .route("/pair_status", post(pair_status_handler))
+}
+
+/// Opt-in fallback for clients that cannot reach the local channel. Off by
+/// default: every local process can connect to a TCP port.
+async fn start_tcp_server(router: Router) {
+    match tokio::net::TcpListener::bind("127.0.0.1:1421").await {
+        Ok(listener) => {
+            eprintln!("HTTP Server listening on 127.0.0.1:1421");
+            let _ = axum::serve(listener, router).await;
+        }
+        Err(e) => eprintln!("[API] 127.0.0.1:1421 dinlenemedi: {}", e),
+    }
+}
+
+/// Runs one framed IPC request through the HTTP routes.
+async fn dispatch_ipc(router: Router, frame: &[u8]) -> IpcResponse {
+    use tower::ServiceExt;
+
+    let failure = |status: StatusCode, message: &str| IpcResponse {
+        status: status.as_u16(),
+        body: json!({"success": false, "error": message}),
+    };
+
+    let request: IpcRequest = match serde_json::from_slice(frame) {
+        Ok(request) => request,
+        Err(_) => return failure(StatusCode::BAD_REQUEST, "Geçersiz istek"),
+    };
+    if !ipc::is_valid_endpoint(&request.endpoint) {
+        return failure(StatusCode::NOT_FOUND, "Bilinmeyen istek");
+    }
+
+    let mut builder = axum::http::Request::builder()
+        .method("POST")
+        .uri(format!("/{}", request.endpoint))
+        .header("Content-Type", "application/json");
+    if let Some(token) = &request.token {
+        builder = builder.header("Authorization", token.as_str());
+    }
+    let body = serde_json::to_vec(&request.body).unwrap_or_default();
+    let http_request = match builder.body(axum::body::Body::from(body)) {
+        Ok(http_request) => http_request,
+        Err(_) => return failure(StatusCode::BAD_REQUEST, "Geçersiz istek"),
+    };
+
+    let response = match router.oneshot(http_request).await {
+        Ok(response) => response,
+        Err(never) => match never {},
+    };
+    let status = response.status().as_u16();
+    let body = match axum::body::to_bytes(response.into_body(), ipc::MAX_FRAME_LEN / 2).await {
+        Ok(bytes) if bytes.is_empty() => serde_json::Value::Null,
+        Ok(bytes) => serde_json::from_slice(&bytes).unwrap_or_else(|_| {
+            serde_json::Value::String(String::from_utf8_lossy(&bytes).into_owned())
+        }),
+        Err(_) => return failure(StatusCode::INTERNAL_SERVER_ERROR, "Yanıt çok büyük"),
+    };
+    IpcResponse { status, body }
+}
+
+/// Answers framed requests on one connection until the client hangs up.
+async fn serve_ipc_connection<S>(mut stream: S, router: Router)
+where
+    S: tokio::io::AsyncRead + tokio::io::AsyncWrite + Unpin,
+{
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    loop {
+        let len = match stream.read_u32_le().await {
+            Ok(len) => len as usize,
+            Err(_) => return,
+        };
+        if len > ipc::MAX_FRAME_LEN {
+            return;
+        }
+        let mut frame = vec![0u8; len];
+        if stream.read_exact(&mut frame).await.is_err() {
+            return;
+        }
+
+        let response = dispatch_ipc(router.clone(), &frame).await;
+        frame.zeroize();
+        let Ok(mut reply) = serde_json::to_vec(&response) else {
+            return;
+        };
+        let written = async {
+            stream.write_u32_le(reply.len() as u32).await?;
+            stream.write_all(&reply).await?;
+            stream.flush().await
+        }
+        .await;
+        reply.zeroize();
+        if written.is_err() {
+            return;
+        }
+    }
+}
+
+fn ipc_channel_path() -> Result<PathBuf, String> {
+    let data_dir = get_vault_path()?
+        .parent()
+        .ok_or_else(|| "Vault path parent bulunamadı".to_string())?
+        .to_path_buf();
+    ipc::channel_path(&data_dir)
+}
+
+/// Serves the native host on a socket only the current user can open, and
+/// drops connections whose peer runs as someone else.
+#[cfg(unix)]
+async fn start_ipc_server(router: Router) -> Result<(), String> {
+    use std::os::unix::fs::{MetadataExt, PermissionsExt};
+
+    let path = ipc_channel_path()?;
+    if path.exists() {
+        if tokio::net::UnixStream::connect(&path).await.is_ok() {
+            return Err("Başka bir ConfPass örneği zaten çalışıyor".to_string());
+        }
+        // Left over from a previous run
+        fs::remove_file(&path).map_err(|e| format!("Eski soket silinemedi: {}", e))?;
+    }
+
+    let listener = tokio::net::UnixListener::bind(&path)
+        .map_err(|e| format!("Soket oluşturulamadı: {}", e))?;
+    fs::set_permissions(&path, fs::Permissions::from_mode(0o600))
+        .map_err(|e| format!("Soket izinleri ayarlanamadı: {}", e))?;
+    let own_uid = fs::metadata(&path)
+        .map_err(|e| format!("Soket okunamadı: {}", e))?
+        .uid();
+    eprintln!("IPC listening on {}", path.display());
+
+    loop {
+        let stream = match listener.accept().await {
+            Ok((stream, _)) => stream,
+            Err(e) => {
+                eprintln!("[IPC] Bağlantı kabul edilemedi: {}", e);
+                continue;
+            }
+        };
+        match stream.peer_cred() {
+            Ok(cred) if cred.uid() == own_uid => {
+                tokio::spawn(serve_ipc_connection(stream, router.clone()));
+            }
+            _ => eprintln!("[IPC] Başka bir kullanıcının bağlantısı reddedildi"),
+        }
+    }
+}
+
+/// Serves the native host on a local-only named pipe and drops connections
+/// from processes of other users.
+#[cfg(windows)]
+async fn start_ipc_server(router: Router) -> Result<(), String> {
+    use tokio::net::windows::named_pipe::ServerOptions;
+
+    let name = ipc_channel_path()?;
+    let mut server = ServerOptions::new()
+        .first_pipe_instance(true)
+        .reject_remote_clients(true)
+        .create(&name)
+        .map_err(|e| format!("Named pipe oluşturulamadı: {}", e))?;
+    eprintln!("IPC listening on {}", name.display());
+
+    loop {
+        server
+            .connect()
+            .await
+            .map_err(|e| format!("Named pipe bağlantı hatası: {}", e))?;
+        let client = server;
+        server = ServerOptions::new()
+            .reject_remote_clients(true)
+            .create(&name)
+            .map_err(|e| format!("Named pipe oluşturulamadı: {}", e))?;
+
+        if pipe_client_is_current_user(&client) {
+            tokio::spawn(serve_ipc_connection(client, router.clone()));
+        } else {
+            eprintln!("[IPC] Başka bir kullanıcının bağlantısı reddedildi");
+        }
+    }
+}
+
+/// Whether the process on the other end of `pipe` runs as the same user as
+/// this one.
+#[cfg(windows)]
+fn pipe_client_is_current_user(pipe: &tokio::net::windows::named_pipe::NamedPipeServer) -> bool {
+    use std::os::windows::io::AsRawHandle;
+    use windows::Win32::Foundation::{CloseHandle, HANDLE};
+    use windows::Win32::Security::{
+        EqualSid, GetTokenInformation, TokenUser, TOKEN_QUERY, TOKEN_USER,
+    };
+    use windows::Win32::System::Pipes::GetNamedPipeClientProcessId;
+    use windows::Win32::System::Threading::{
+        GetCurrentProcess, OpenProcess, OpenProcessToken, PROCESS_QUERY_LIMITED_INFORMATION,
+    };
+
+    // TOKEN_USER of `process`, in a buffer that also holds the SID it points to
+    unsafe fn token_user(process: HANDLE) -> Option<Vec<u64>> {
+        let mut token = HANDLE::default();
+        OpenProcessToken(process, TOKEN_QUERY, &mut token).ok()?;
+        let mut len = 0u32;
+        let _ = GetTokenInformation(token, TokenUser, None, 0, &mut len);
+        let mut buffer = vec![0u64; (len as usize).div_ceil(8)];
+        let result = GetTokenInformation(
+            token,
+            TokenUser,
+            Some(buffer.as_mut_ptr().cast()),
+            len,
+            &mut len,
+        );
+        let _ = CloseHandle(token);
+        result.ok()?;
+        Some(buffer)
+    }
 
-    let listener = tokio::net::TcpListener::bind("127.0.0.1:1421").await;
-    if let Ok(listener) = listener {
-        eprintln!("HTTP Server listening on 127.0.0.1:1421");
-        let _ = axum::serve(listener, router).await;
+    unsafe {
+        let mut pid = 0u32;
+        if GetNamedPipeClientProcessId(HANDLE(pipe.as_raw_handle()), &mut pid).is_err() {
+            return false;
+        }
+        let Ok(process) = OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, false, pid) else {
+            return false;
+        };
+        let client_user = token_user(process);
+        let _ = CloseHandle(process);
+
+        match (client_user, token_user(GetCurrentProcess())) {
+            (Some(client_user), Some(own_user)) => {
+                let client_sid = (*(client_user.as_ptr() as *const TOKEN_USER)).User.Sid;
+                let own_sid = (*(own_user.as_ptr() as *const TOKEN_USER)).User.Sid;
+                EqualSid(client_sid, own_sid).is_ok()
+            }
+            _ => false,
+        }
     }
 }
 
@@ -2429,15 +2521,19 @@ async fn get_password_handler(
         let url_lower = url.trim().to_lowercase();
         let url_domain = extract_domain(&url_lower);
 
-        let matching_entry = state.entries.values().find(|entry| {
-            entry.url.as_ref().map_or(false, |entry_url| {
-                let entry_url_lower = entry_url.to_lowercase();
-                entry_url_lower.contains(&url_lower)
-                    || url_domain
-                        .as_ref()
-                        .map_or(false, |domain| entry_url_lower.contains(domain))
-            })
-        });
+        let matching_entry = state
+            .entries
+            .values()
+            .filter(|e| !is_trashed(e))
+            .find(|entry| {
+                entry.url.as_ref().map_or(false, |entry_url| {
+                    let entry_url_lower = entry_url.to_lowercase();
+                    entry_url_lower.contains(&url_lower)
+                        || url_domain
+                            .as_ref()
+                            .map_or(false, |domain| entry_url_lower.contains(domain))
+                })
+            });
 
         if let Some(entry) = matching_entry {
             return Ok(json!({
@@ -2543,23 +2639,13 @@ async fn save_password_handler(
             folder_id: None,
             tags: None,
             attachments: None,
+            data: None,
+            deleted_at: None,
         };
 
         state.entries.insert(id.clone(), entry);
 
-        let pwd_string = {
-            let master_pwd = MASTER_PASSWORD
-                .lock()
-                .map_err(|_| "Master password lock hatası".to_string())?;
-
-            let pwd = master_pwd.as_ref().ok_or_else(|| {
-                "Master password bulunamadı. Lütfen kasa kilidini açın.".to_string()
-            })?;
-
-            pwd.as_str().to_string()
-        };
-
-        save_vault_to_disk(&state, &pwd_string)
+        save_vault_to_disk(&state)
             .map_err(|e| format!("Browser extension kaydetme hatası: {}", e))?;
 
         Ok(id)
@@ -2672,19 +2758,7 @@ async fn passkey_detected_handler(
 
 // ========== Passkey Storage ==========
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
-struct StoredPasskey {
-    credential_id: String,
-    private_key: String,
-    rp_id: String,
-    rp_name: String,
-    user_id: String,
-    user_name: String,
-    user_display_name: String,
-    counter: u32,
-    created_at: i64,
-}
-
+// Location of the plaintext passkeys.json written by older releases
 fn get_passkeys_path() -> Result<PathBuf, String> {
     let app_data_dir = if cfg!(windows) {
         env::var("APPDATA")
@@ -2696,30 +2770,47 @@ fn get_passkeys_path() -> Result<PathBuf, String> {
         PathBuf::from(home).join(".config").join("confpass")
     };
 
-    fs::create_dir_all(&app_data_dir).map_err(|e| format!("Failed to create dir: {}", e))?;
-    Ok(app_data_dir.join("passkeys.json"))
+    Ok(app_data_dir.join(confpass_core::passkeys::PASSKEYS_FILE_NAME))
 }
 
-fn load_passkeys() -> Result<Vec<StoredPasskey>, String> {
-    let path = get_passkeys_path()?;
-    if !path.exists() {
-        return Ok(Vec::new());
+// Move passkeys.json and private keys kept in entry notes into the vault, then delete the file
+fn migrate_plaintext_passkeys() {
+    let (passkeys_path, vault_path) = match (get_passkeys_path(), get_vault_path()) {
+        (Ok(p), Ok(v)) => (p, v),
+        _ => return,
+    };
+
+    let mut state = match get_state_mut() {
+        Ok(s) => s,
+        Err(_) => return,
+    };
+
+    if state.vault_locked {
+        return;
     }
 
-    let content = fs::read_to_string(&path).map_err(|e| format!("Read error: {}", e))?;
-    serde_json::from_str(&content).map_err(|e| format!("Parse error: {}", e))
+    match confpass_core::passkeys::migrate_plaintext_passkeys(
+        &passkeys_path,
+        &vault_path,
+        &mut state,
+        unix_now() as i64,
+    ) {
+        Ok(0) => {}
+        Ok(imported) => eprintln!(
+            "[Passkey Migration] Moved {} passkeys from passkeys.json into the vault",
+            imported
+        ),
+        Err(e) => eprintln!("[Passkey Migration] Failed: {}", e),
+    }
 }
 
-fn save_passkeys(passkeys: &[StoredPasskey]) -> Result<(), String> {
-    let path = get_passkeys_path()?;
-    let content =
-        serde_json::to_string_pretty(passkeys).map_err(|e| format!("Serialize error: {}", e))?;
-    fs::write(&path, content).map_err(|e| format!("Write error: {}", e))
-}
+// Remove entries that have been in the trash longer than the retention period
+fn purge_expired_trash() {
+    let retention_days = get_settings()
+        .map(|s| s.trash_retention_days)
+        .unwrap_or(DEFAULT_RETENTION_DAYS);
 
-// Sync passkeys.json with vault entries - remove stale passkeys
-fn sync_passkeys_with_vault() {
-    let state = match get_state() {
+    let mut state = match get_state_mut() {
         Ok(s) => s,
         Err(_) => return,
     };
@@ -2728,223 +2819,73 @@ fn sync_passkeys_with_vault() {
         return;
     }
 
-    // Collect all credentialIds from vault passkey entries
-    let vault_credential_ids: std::collections::HashSet<String> = state
-        .entries
-        .values()
-        .filter(|e| e.category == "passkeys")
-        .filter_map(|e| {
-            e.notes
-                .as_ref()
-                .and_then(|notes| serde_json::from_str::<serde_json::Value>(notes).ok())
-                .and_then(|json| {
-                    json.get("credentialId")
-                        .and_then(|v| v.as_str())
-                        .map(|s| s.to_string())
-                })
-        })
-        .collect();
-
-    // Load passkeys.json and remove any that are not in vault
-    if let Ok(mut passkeys) = load_passkeys() {
-        let original_len = passkeys.len();
-        passkeys.retain(|p| vault_credential_ids.contains(&p.credential_id));
-
-        if passkeys.len() < original_len {
-            let removed = original_len - passkeys.len();
-            eprintln!(
-                "[Passkey Sync] Removed {} stale passkeys from passkeys.json",
-                removed
-            );
-            let _ = save_passkeys(&passkeys);
+    let purged = confpass_core::trash::purge_expired(
+        &mut state,
+        retention_days,
+        chrono::Utc::now().timestamp(),
+    );
+    if purged > 0 {
+        match save_vault_to_disk(&state) {
+            Ok(()) => {
+                eprintln!("[Trash] Purged {} expired entries", purged);
+                remove_orphan_attachments(&state);
+            }
+            Err(e) => eprintln!("[Trash] Purge could not be saved: {}", e),
         }
     }
 }
 
-async fn save_passkey_handler(
+fn base64url(bytes: &[u8]) -> String {
+    general_purpose::URL_SAFE_NO_PAD.encode(bytes)
+}
+
+// Creates the key pair in the vault; the extension only gets the public attestation
+async fn passkey_create_handler(
     Json(payload): Json<serde_json::Value>,
 ) -> Result<Json<serde_json::Value>, StatusCode> {
     use tauri::Emitter;
 
-    eprintln!("[Passkey Storage] Saving new passkey: {:?}", payload);
-
-    let credential_id = payload
-        .get("credentialId")
-        .and_then(|v| v.as_str())
-        .unwrap_or("")
-        .to_string();
-    let private_key = payload
-        .get("privateKey")
-        .and_then(|v| v.as_str())
-        .unwrap_or("")
-        .to_string();
-    let rp_id = payload
-        .get("rpId")
-        .and_then(|v| v.as_str())
-        .unwrap_or("")
-        .to_string();
-    let rp_name = payload
-        .get("rpName")
-        .and_then(|v| v.as_str())
-        .unwrap_or("")
-        .to_string();
-    let user_id = payload
-        .get("userId")
-        .and_then(|v| v.as_str())
-        .unwrap_or("")
-        .to_string();
-    let user_name = payload
-        .get("userName")
-        .and_then(|v| v.as_str())
-        .unwrap_or("")
-        .to_string();
-    let user_display_name = payload
-        .get("userDisplayName")
-        .and_then(|v| v.as_str())
-        .unwrap_or("")
-        .to_string();
-    let counter = payload.get("counter").and_then(|v| v.as_u64()).unwrap_or(0) as u32;
-    let created_at = payload
-        .get("createdAt")
-        .and_then(|v| v.as_i64())
-        .unwrap_or_else(|| {
-            SystemTime::now()
-                .duration_since(UNIX_EPOCH)
-                .map(|d| d.as_secs() as i64)
-                .unwrap_or(0)
-        });
-
-    // Clone values needed for blocking task and event
-    let rp_id_clone = rp_id.clone();
-    let rp_name_clone = rp_name.clone();
-    let user_name_clone = user_name.clone();
-    let user_display_name_clone = user_display_name.clone();
-
-    let result = tokio::task::spawn_blocking(move || {
-        let passkey = StoredPasskey {
-            credential_id: credential_id.clone(),
-            private_key,
-            rp_id: rp_id.clone(),
-            rp_name: rp_name.clone(),
-            user_id: user_id.clone(),
-            user_name: user_name.clone(),
-            user_display_name: user_display_name.clone(),
-            counter,
-            created_at,
-        };
-
-        if passkey.credential_id.is_empty() || passkey.private_key.is_empty() {
-            return Err("Missing required fields".to_string());
-        }
-
-        // Clone values needed for vault entry before moving passkey
-        let private_key_for_notes = passkey.private_key.clone();
-
-        let mut passkeys = load_passkeys().unwrap_or_default();
-
-        // Check for duplicate
-        if passkeys.iter().any(|p| p.credential_id == passkey.credential_id) {
-            return Err("Passkey already exists".to_string());
+    let request: confpass_core::webauthn::CreateRequest = match serde_json::from_value(payload) {
+        Ok(r) => r,
+        Err(e) => {
+            return Ok(Json(
+                json!({"success": false, "error": format!("Invalid request: {}", e)}),
+            ))
         }
+    };
 
-        passkeys.push(passkey);
-        save_passkeys(&passkeys)?;
-
-        eprintln!("[Passkey Storage] Passkey saved to passkeys.json. Total: {}", passkeys.len());
+    eprintln!(
+        "[Passkey Storage] Creating passkey for rpId: {}",
+        request.rp_id
+    );
 
-        // Also add to vault entries for UI display
+    let result = tokio::task::spawn_blocking(move || {
         let mut state = get_state_mut().map_err(|e| e.to_string())?;
 
-        if !state.vault_locked {
-            // Create passkey data for notes field (includes all data for restore)
-            let passkey_data = json!({
-                "username": user_name,
-                "email": if user_display_name.contains('@') { Some(user_display_name.clone()) } else { None::<String> },
-                "domain": rp_id.clone(),
-                "credentialId": credential_id,
-                "privateKey": private_key_for_notes,
-                "rpId": rp_id.clone(),
-                "rpName": rp_name.clone(),
-                "userId": user_id,
-                "userDisplayName": user_display_name,
-                "counter": counter,
-                "createdAt": created_at
-            });
-
-            let entry_id = format!("entry_{}", uuid::Uuid::new_v4());
-            let now = SystemTime::now()
-                .duration_since(UNIX_EPOCH)
-                .map_err(|e| format!("Time error: {}", e))?
-                .as_secs() as i64;
-
-            // Create URL from rpId
-            let url = if rp_id.starts_with("http://") || rp_id.starts_with("https://") {
-                rp_id.clone()
-            } else {
-                format!("https://{}", rp_id)
-            };
-
-            // Get title from rpName or rpId
-            let title = if !rp_name.is_empty() {
-                rp_name
-            } else {
-                rp_id.split('.').next().unwrap_or(&rp_id).to_string()
-            };
-
-            let entry = PasswordEntry {
-                id: entry_id.clone(),
-                title,
-                username: user_name,
-                password: String::new(), // Passkeys don't have passwords
-                url: Some(url),
-                notes: Some(passkey_data.to_string()),
-                created_at: now,
-                updated_at: now,
-                category: "passkeys".to_string(),
-                extra_fields: None,
-                folder_id: None,
-                tags: None,
-                attachments: None,
-            };
-
-            state.entries.insert(entry_id, entry);
-
-            // Save vault to disk
-            let pwd_string = {
-                let master_pwd = MASTER_PASSWORD.lock()
-                    .map_err(|_| "Master password lock error".to_string())?;
-
-                match master_pwd.as_ref() {
-                    Some(pwd) => pwd.as_str().to_string(),
-                    None => return Ok(()), // Can't save without master password, but passkey is in passkeys.json
-                }
-            };
-
-            if let Err(e) = save_vault_to_disk(&state, &pwd_string) {
-                eprintln!("[Passkey Storage] Warning: Could not save to vault: {}", e);
-            } else {
-                eprintln!("[Passkey Storage] Passkey also added to vault entries");
-            }
-        } else {
-            eprintln!("[Passkey Storage] Vault is locked, passkey saved only to passkeys.json");
+        // Private keys are only ever written into the encrypted vault
+        if state.vault_locked {
+            return Err("Vault is locked".to_string());
         }
 
-        Ok(())
-    }).await;
+        let created =
+            confpass_core::webauthn::create_credential(&mut state, &request, unix_now() as i64)?;
+        save_vault_to_disk(&state)?;
+
+        eprintln!("[Passkey Storage] Passkey saved to vault");
+        Ok((request, created))
+    })
+    .await;
 
     match result {
-        Ok(Ok(_)) => {
+        Ok(Ok((request, created))) => {
             // Emit event to frontend to show notification
             if let Some(app_handle) = get_app_handle() {
                 let passkey_info = json!({
-                    "rpId": rp_id_clone,
-                    "rpName": rp_name_clone,
-                    "userName": user_name_clone,
-                    "userDisplayName": user_display_name_clone,
-                    "timestamp": SystemTime::now()
-                        .duration_since(UNIX_EPOCH)
-                        .map(|d| d.as_secs())
-                        .unwrap_or(0)
+                    "rpId": request.rp_id,
+                    "rpName": request.rp_name,
+                    "userName": request.user_name,
+                    "userDisplayName": request.user_display_name,
+                    "timestamp": unix_now()
                 });
 
                 if let Err(e) = app_handle.emit("passkey-saved", passkey_info) {
@@ -2957,8 +2898,63 @@ async fn save_passkey_handler(
                 }
             }
 
-            Ok(Json(json!({"success": true})))
+            Ok(Json(json!({
+                "success": true,
+                "credentialId": created.credential_id,
+                "clientDataJSON": base64url(&created.client_data_json),
+                "authenticatorData": base64url(&created.authenticator_data),
+                "attestationObject": base64url(&created.attestation_object),
+                "publicKey": base64url(&created.public_key)
+            })))
+        }
+        Ok(Err(msg)) => Ok(Json(json!({"success": false, "error": msg}))),
+        Err(e) => {
+            eprintln!("[Passkey Storage] Task error: {}", e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+// Signs the login challenge in the vault and bumps the signature counter
+async fn passkey_assert_handler(
+    Json(payload): Json<serde_json::Value>,
+) -> Result<Json<serde_json::Value>, StatusCode> {
+    let request: confpass_core::webauthn::AssertRequest = match serde_json::from_value(payload) {
+        Ok(r) => r,
+        Err(e) => {
+            return Ok(Json(
+                json!({"success": false, "error": format!("Invalid request: {}", e)}),
+            ))
+        }
+    };
+
+    eprintln!(
+        "[Passkey Storage] Signing assertion for rpId: {}",
+        request.rp_id
+    );
+
+    let result = tokio::task::spawn_blocking(move || {
+        let mut state = get_state_mut().map_err(|e| e.to_string())?;
+
+        if state.vault_locked {
+            return Err("Vault is locked".to_string());
         }
+
+        let assertion = confpass_core::webauthn::assert_credential(&mut state, &request)?;
+        save_vault_to_disk(&state)?;
+        Ok(assertion)
+    })
+    .await;
+
+    match result {
+        Ok(Ok(assertion)) => Ok(Json(json!({
+            "success": true,
+            "credentialId": assertion.credential_id,
+            "clientDataJSON": base64url(&assertion.client_data_json),
+            "authenticatorData": base64url(&assertion.authenticator_data),
+            "signature": base64url(&assertion.signature),
+            "userHandle": assertion.user_handle.as_deref().map(base64url)
+        }))),
         Ok(Err(msg)) => Ok(Json(json!({"success": false, "error": msg}))),
         Err(e) => {
             eprintln!("[Passkey Storage] Task error: {}", e);
@@ -2976,18 +2972,27 @@ async fn get_passkeys_handler(
         .unwrap_or("")
         .to_string();
 
+    // Passkeys are only ever listed for one site
+    if rp_id.is_empty() {
+        return Ok(Json(
+            json!({"success": false, "error": "rpId gerekli", "passkeys": []}),
+        ));
+    }
+
     eprintln!("[Passkey Storage] Getting passkeys for rpId: {}", rp_id);
 
     let result = tokio::task::spawn_blocking(move || {
-        let passkeys = load_passkeys().unwrap_or_default();
+        let state = get_state().map_err(|e| e.to_string())?;
 
-        let matching: Vec<_> = passkeys
+        if state.vault_locked {
+            return Err("Vault is locked".to_string());
+        }
+
+        let matching: Vec<_> = confpass_core::passkeys::active_passkeys(&state, &rp_id)
             .into_iter()
-            .filter(|p| p.rp_id == rp_id || rp_id.is_empty())
             .map(|p| {
                 json!({
                     "credentialId": p.credential_id,
-                    "privateKey": p.private_key,
                     "rpId": p.rp_id,
                     "rpName": p.rp_name,
                     "userId": p.user_id,
@@ -3000,53 +3005,15 @@ async fn get_passkeys_handler(
             .collect();
 
         eprintln!("[Passkey Storage] Found {} passkeys", matching.len());
-        matching
-    })
-    .await;
-
-    match result {
-        Ok(passkeys) => Ok(Json(json!({"success": true, "passkeys": passkeys}))),
-        Err(e) => {
-            eprintln!("[Passkey Storage] Task error: {}", e);
-            Err(StatusCode::INTERNAL_SERVER_ERROR)
-        }
-    }
-}
-
-async fn update_passkey_counter_handler(
-    Json(payload): Json<serde_json::Value>,
-) -> Result<Json<serde_json::Value>, StatusCode> {
-    let credential_id = payload
-        .get("credentialId")
-        .and_then(|v| v.as_str())
-        .unwrap_or("")
-        .to_string();
-    let counter = payload.get("counter").and_then(|v| v.as_u64()).unwrap_or(0) as u32;
-
-    eprintln!(
-        "[Passkey Storage] Updating counter for {}: {}",
-        credential_id, counter
-    );
-
-    let result = tokio::task::spawn_blocking(move || {
-        let mut passkeys = load_passkeys()?;
-
-        if let Some(passkey) = passkeys
-            .iter_mut()
-            .find(|p| p.credential_id == credential_id)
-        {
-            passkey.counter = counter;
-            save_passkeys(&passkeys)?;
-            Ok(())
-        } else {
-            Err("Passkey not found".to_string())
-        }
+        Ok(matching)
     })
     .await;
 
     match result {
-        Ok(Ok(_)) => Ok(Json(json!({"success": true}))),
-        Ok(Err(msg)) => Ok(Json(json!({"success": false, "error": msg}))),
+        Ok(Ok(passkeys)) => Ok(Json(json!({"success": true, "passkeys": passkeys}))),
+        Ok(Err(msg)) => Ok(Json(
+            json!({"success": false, "error": msg, "passkeys": []}),
+        )),
         Err(e) => {
             eprintln!("[Passkey Storage] Task error: {}", e);
             Err(StatusCode::INTERNAL_SERVER_ERROR)
@@ -3104,12 +3071,14 @@ async fn get_passwords_for_site_handler(
         let authenticators: Vec<_> = state
             .entries
             .values()
+            .filter(|e| !is_trashed(e))
             .filter(|entry| entry.category == "authenticator")
             .collect();
 
         let matching_entries: Vec<_> = state
             .entries
             .values()
+            .filter(|e| !is_trashed(e))
             .filter(|entry| {
                 // Only return "accounts" category for login autofill
                 if entry.category != "accounts" {
@@ -3168,28 +3137,8 @@ async fn get_passwords_for_site_handler(
                 let totp_info = authenticators
                     .iter()
                     .find(|auth| {
-                        // Get authenticator account from notes or username field
-                        let auth_account = auth
-                            .notes
-                            .as_ref()
-                            .and_then(|n| serde_json::from_str::<serde_json::Value>(n).ok())
-                            .and_then(|json| {
-                                json.get("account")
-                                    .and_then(|a| a.as_str())
-                                    .map(|s| s.to_lowercase())
-                            })
-                            .unwrap_or_else(|| auth.username.to_lowercase());
-
-                        let auth_issuer = auth
-                            .notes
-                            .as_ref()
-                            .and_then(|n| serde_json::from_str::<serde_json::Value>(n).ok())
-                            .and_then(|json| {
-                                json.get("issuer")
-                                    .and_then(|a| a.as_str())
-                                    .map(|s| s.to_lowercase())
-                            })
-                            .unwrap_or_else(|| auth.title.to_lowercase());
+                        let auth_account = totp_account(auth).to_lowercase();
+                        let auth_issuer = totp_issuer(auth).to_lowercase();
 
                         // Check if the entry's username/email matches authenticator's account
                         let entry_username = entry.username.to_lowercase();
@@ -3213,33 +3162,16 @@ async fn get_passwords_for_site_handler(
                         account_match && domain_match
                     })
                     .and_then(|auth| {
-                        // Extract TOTP secret from notes
-                        auth.notes
-                            .as_ref()
-                            .and_then(|notes| serde_json::from_str::<serde_json::Value>(notes).ok())
-                            .and_then(|json| {
-                                json.get("secret")
-                                    .and_then(|s| s.as_str())
-                                    .map(|s| s.to_string())
-                            })
-                            .or_else(|| {
-                                // Fallback to password field if notes don't have secret
-                                if !auth.password.is_empty() {
-                                    Some(auth.password.clone())
-                                } else {
-                                    None
-                                }
-                            })
-                            .and_then(|secret| {
-                                // Generate current TOTP code
-                                generate_totp_code_internal(&secret).ok().map(|code| {
-                                    json!({
-                                        "hasTotp": true,
-                                        "totpCode": code,
-                                        "totpIssuer": auth.title
-                                    })
+                        totp_secret(auth).and_then(|secret| {
+                            // Generate current TOTP code
+                            generate_totp_code_internal(&secret).ok().map(|code| {
+                                json!({
+                                    "hasTotp": true,
+                                    "totpCode": code,
+                                    "totpIssuer": auth.title
                                 })
                             })
+                        })
                     });
 
                 let mut entry_json = json!({
@@ -3265,19 +3197,10 @@ async fn get_passwords_for_site_handler(
         let domain_authenticators: Vec<_> = state
             .entries
             .values()
+            .filter(|e| !is_trashed(e))
             .filter(|entry| entry.category == "authenticator")
             .filter(|auth| {
-                // Get issuer from notes or title
-                let auth_issuer = auth
-                    .notes
-                    .as_ref()
-                    .and_then(|n| serde_json::from_str::<serde_json::Value>(n).ok())
-                    .and_then(|json| {
-                        json.get("issuer")
-                            .and_then(|a| a.as_str())
-                            .map(|s| s.to_lowercase())
-                    })
-                    .unwrap_or_else(|| auth.title.to_lowercase());
+                let auth_issuer = totp_issuer(auth).to_lowercase();
 
                 // Check if issuer matches domain
                 url_domain.as_ref().map_or(false, |search_domain| {
@@ -3285,34 +3208,8 @@ async fn get_passwords_for_site_handler(
                 })
             })
             .filter_map(|auth| {
-                // Extract TOTP secret and generate code
-                let secret = auth
-                    .notes
-                    .as_ref()
-                    .and_then(|n| serde_json::from_str::<serde_json::Value>(n).ok())
-                    .and_then(|json| {
-                        json.get("secret")
-                            .and_then(|s| s.as_str())
-                            .map(|s| s.to_string())
-                    })
-                    .or_else(|| {
-                        if !auth.password.is_empty() {
-                            Some(auth.password.clone())
-                        } else {
-                            None
-                        }
-                    })?;
-
-                let account = auth
-                    .notes
-                    .as_ref()
-                    .and_then(|n| serde_json::from_str::<serde_json::Value>(n).ok())
-                    .and_then(|json| {
-                        json.get("account")
-                            .and_then(|a| a.as_str())
-                            .map(|s| s.to_string())
-                    })
-                    .unwrap_or_else(|| auth.username.clone());
+                let secret = totp_secret(auth)?;
+                let account = totp_account(auth);
 
                 generate_totp_code_internal(&secret).ok().map(|code| {
                     json!({
@@ -3375,7 +3272,7 @@ async fn get_totp_code_handler(
 
         let domain_lower = domain.to_lowercase();
 
-        for entry in state.entries.values() {
+        for entry in state.entries.values().filter(|e| !is_trashed(e)) {
             if entry.category != "authenticator" {
                 continue;
             }
@@ -3394,22 +3291,18 @@ async fn get_totp_code_handler(
                 && (entry_domain.contains(&domain_lower) || domain_lower.contains(&entry_domain));
 
             if issuer_match || domain_match {
-                if let Some(ref notes) = entry.notes {
-                    if let Ok(auth_data) = serde_json::from_str::<serde_json::Value>(notes) {
-                        if let Some(secret) = auth_data.get("secret").and_then(|s| s.as_str()) {
-                            match generate_totp_code_internal(secret) {
-                                Ok(code) => {
-                                    eprintln!("[TOTP HTTP] Generated code for {}", entry.title);
-                                    return Ok(json!({
-                                        "code": code,
-                                        "issuer": entry.title,
-                                        "account": entry.username
-                                    }));
-                                }
-                                Err(e) => {
-                                    eprintln!("[TOTP HTTP] Error generating code: {}", e);
-                                }
-                            }
+                if let Some(totp) = totp_item(entry).filter(|t| !t.secret.is_empty()) {
+                    match generate_totp_code_internal(&totp.secret) {
+                        Ok(code) => {
+                            eprintln!("[TOTP HTTP] Generated code for {}", entry.title);
+                            return Ok(json!({
+                                "code": code,
+                                "issuer": entry.title,
+                                "account": entry.username
+                            }));
+                        }
+                        Err(e) => {
+                            eprintln!("[TOTP HTTP] Error generating code: {}", e);
                         }
                     }
                 }
@@ -3445,30 +3338,35 @@ async fn get_totp_code_handler(
     }
 }
 
-fn generate_totp_code_internal(secret: &str) -> Result<String, String> {
-    use totp_lite::{totp_custom, Sha1};
-
-    let cleaned_secret = secret.replace(" ", "").replace("-", "").to_uppercase();
+fn totp_item(entry: &PasswordEntry) -> Option<&TotpItem> {
+    match entry.data.as_ref()? {
+        EntryData::Totp(totp) => Some(totp),
+        _ => None,
+    }
+}
 
-    let secret_bytes = match base32::decode(
-        base32::Alphabet::RFC4648 { padding: false },
-        &cleaned_secret,
-    ) {
-        Some(bytes) if !bytes.is_empty() => bytes,
-        _ => match base64::engine::general_purpose::STANDARD.decode(secret) {
-            Ok(bytes) if !bytes.is_empty() => bytes,
-            _ => return Err("Geçersiz TOTP secret formatı".to_string()),
-        },
-    };
+// Older authenticator entries kept the secret in the password field
+fn totp_secret(entry: &PasswordEntry) -> Option<String> {
+    totp_item(entry)
+        .map(|t| t.secret.clone())
+        .filter(|s| !s.is_empty())
+        .or_else(|| Some(entry.password.clone()).filter(|p| !p.is_empty()))
+}
 
-    let timestamp = std::time::SystemTime::now()
-        .duration_since(std::time::UNIX_EPOCH)
-        .map_err(|e| format!("Zaman hatası: {}", e))?
-        .as_secs();
+fn totp_account(entry: &PasswordEntry) -> String {
+    totp_item(entry)
+        .and_then(|t| t.account.clone())
+        .unwrap_or_else(|| entry.username.clone())
+}
 
-    let code = totp_custom::<Sha1>(30, 6, secret_bytes.as_slice(), timestamp);
+fn totp_issuer(entry: &PasswordEntry) -> String {
+    totp_item(entry)
+        .and_then(|t| t.issuer.clone())
+        .unwrap_or_else(|| entry.title.clone())
+}
 
-    Ok(code)
+fn generate_totp_code_internal(secret: &str) -> Result<String, String> {
+    confpass_core::totp::generate_totp_code(secret)
 }
 
 async fn get_cards_handler() -> Result<Json<serde_json::Value>, StatusCode> {
@@ -3485,13 +3383,13 @@ async fn get_cards_handler() -> Result<Json<serde_json::Value>, StatusCode> {
         let cards: Vec<_> = state
             .entries
             .values()
+            .filter(|e| !is_trashed(e))
             .filter(|entry| entry.category == "bank_cards")
             .map(|entry| {
-                let mut card_data = entry
-                    .notes
-                    .as_ref()
-                    .and_then(|n| serde_json::from_str::<serde_json::Value>(n).ok())
-                    .unwrap_or_else(|| json!({}));
+                let mut card_data = match entry.data.as_ref() {
+                    Some(EntryData::Card(card)) => serde_json::to_value(card).unwrap_or_default(),
+                    _ => json!({}),
+                };
 
                 // Add entry info to card data
                 card_data["id"] = json!(entry.id);
@@ -3530,13 +3428,15 @@ async fn get_addresses_handler() -> Result<Json<serde_json::Value>, StatusCode>
         let addresses: Vec<_> = state
             .entries
             .values()
+            .filter(|e| !is_trashed(e))
             .filter(|entry| entry.category == "addresses")
             .map(|entry| {
-                let mut address_data = entry
-                    .notes
-                    .as_ref()
-                    .and_then(|n| serde_json::from_str::<serde_json::Value>(n).ok())
-                    .unwrap_or_else(|| json!({}));
+                let mut address_data = match entry.data.as_ref() {
+                    Some(EntryData::Address(address)) => {
+                        serde_json::to_value(address).unwrap_or_default()
+                    }
+                    _ => json!({}),
+                };
 
                 // Add entry info to address data
                 address_data["id"] = json!(entry.id);
@@ -3595,58 +3495,48 @@ async fn check_duplicate_handler(
             .and_then(|v| v.as_str())
             .unwrap_or("");
 
-        let url_domain = extract_domain(&url.to_lowercase());
-
         let exists = match category {
-            "accounts" => state.entries.values().any(|entry| {
-                if entry.category != "accounts" {
-                    return false;
-                }
-
-                // Match username (case-insensitive)
-                let username_match = entry.username.to_lowercase() == username.to_lowercase();
-                if !username_match {
-                    return false;
-                }
+            "accounts" => state
+                .entries
+                .values()
+                .filter(|e| !is_trashed(e))
+                .any(|entry| {
+                    if entry.category != "accounts" {
+                        return false;
+                    }
 
-                // Match password (exact)
-                let password_match = entry.password == password;
-                if !password_match {
-                    return false;
-                }
+                    // Match username (case-insensitive)
+                    let username_match = entry.username.to_lowercase() == username.to_lowercase();
+                    if !username_match {
+                        return false;
+                    }
 
-                // Match domain (smarter check)
-                entry.url.as_ref().map_or(false, |entry_url| {
-                    let entry_domain = extract_domain(&entry_url.to_lowercase());
-                    url_domain.as_ref().map_or(false, |ud| {
-                        entry_domain.as_ref().map_or(false, |ed| {
-                            // Exact match or subdomain match
-                            ed == ud
-                                || ed.ends_with(&format!(".{}", ud))
-                                || ud.ends_with(&format!(".{}", ed))
-                        })
-                    })
-                })
-            }),
-            "bank_cards" => {
-                let clean_card = card_number.replace(" ", "").replace("-", "");
-                state.entries.values().any(|entry| {
-                    if entry.category != "bank_cards" {
+                    // Match password (exact)
+                    let password_match = entry.password == password;
+                    if !password_match {
                         return false;
                     }
-                    entry.notes.as_ref().map_or(false, |notes| {
-                        serde_json::from_str::<serde_json::Value>(notes)
-                            .ok()
-                            .and_then(|json| {
-                                json.get("cardNumber")
-                                    .and_then(|c| c.as_str())
-                                    .map(|s| s.to_string())
-                            })
-                            .map_or(false, |stored_card| {
-                                stored_card.replace(" ", "").replace("-", "") == clean_card
+
+                    // Same domain or a subdomain of it
+                    entry
+                        .url
+                        .as_deref()
+                        .is_some_and(|entry_url| duplicates::same_site(entry_url, url))
+                }),
+            "bank_cards" => {
+                let clean_card = duplicates::normalize_card_number(card_number);
+                state
+                    .entries
+                    .values()
+                    .filter(|e| !is_trashed(e))
+                    .any(|entry| match entry.data.as_ref() {
+                        Some(EntryData::Card(card)) if entry.category == "bank_cards" => {
+                            card.card_number.as_ref().is_some_and(|stored_card| {
+                                duplicates::normalize_card_number(stored_card) == clean_card
                             })
+                        }
+                        _ => false,
                     })
-                })
             }
             "addresses" => {
                 let street = payload
@@ -3659,27 +3549,17 @@ async fn check_duplicate_handler(
                     .and_then(|v| v.as_str())
                     .unwrap_or("");
 
-                state.entries.values().any(|entry| {
-                    if entry.category != "addresses" {
-                        return false;
-                    }
-                    entry.notes.as_ref().map_or(false, |notes| {
-                        serde_json::from_str::<serde_json::Value>(notes)
-                            .ok()
-                            .map_or(false, |json| {
-                                let stored_street = json
-                                    .get("street")
-                                    .and_then(|s| s.as_str())
-                                    .unwrap_or("")
-                                    .to_lowercase();
-                                let stored_postal = json
-                                    .get("postalCode")
-                                    .and_then(|s| s.as_str())
-                                    .unwrap_or("");
-                                stored_street == street && stored_postal == postal
-                            })
+                state
+                    .entries
+                    .values()
+                    .filter(|e| !is_trashed(e))
+                    .any(|entry| match entry.data.as_ref() {
+                        Some(EntryData::Address(address)) if entry.category == "addresses" => {
+                            address.street.as_deref().unwrap_or("").to_lowercase() == street
+                                && address.postal_code.as_deref().unwrap_or("") == postal
+                        }
+                        _ => false,
                     })
-                })
             }
             _ => false,
         };
@@ -3700,8 +3580,17 @@ async fn check_duplicate_handler(
 }
 
 async fn save_entry_handler(
+    Extension(client): Extension<ApiClient>,
     Json(payload): Json<serde_json::Value>,
 ) -> Result<Json<serde_json::Value>, StatusCode> {
+    let category = payload
+        .get("category")
+        .and_then(|v| v.as_str())
+        .unwrap_or("accounts");
+    if !client_may_access(&client, category.trim()) {
+        return Err(StatusCode::FORBIDDEN);
+    }
+
     let result = tokio::task::spawn_blocking(move || {
         let mut state = match get_state_mut() {
             Ok(s) => s,
@@ -3742,6 +3631,13 @@ async fn save_entry_handler(
             .and_then(|v| v.as_str())
             .unwrap_or("")
             .to_string();
+        let data: Option<EntryData> = match payload.get("data") {
+            Some(data) if !data.is_null() => Some(
+                serde_json::from_value(data.clone())
+                    .map_err(|e| format!("Kayıt verisi okunamadı: {}", e))?,
+            ),
+            _ => None,
+        };
 
         if title.is_empty() {
             return Err("Başlık gerekli".to_string());
@@ -3754,7 +3650,7 @@ async fn save_entry_handler(
             .as_secs() as i64;
 
         let category_clone = category.clone();
-        let entry = PasswordEntry {
+        let mut entry = PasswordEntry {
             id: id.clone(),
             title,
             username,
@@ -3768,21 +3664,14 @@ async fn save_entry_handler(
             folder_id: None,
             tags: None,
             attachments: None,
+            data,
+            deleted_at: None,
         };
+        prepare_entry(&mut entry).map_err(|e| e.to_string())?;
 
         state.entries.insert(id.clone(), entry);
 
-        let pwd_string = {
-            let master_pwd = MASTER_PASSWORD
-                .lock()
-                .map_err(|_| "Master password lock hatası".to_string())?;
-            let pwd = master_pwd
-                .as_ref()
-                .ok_or_else(|| "Master password bulunamadı".to_string())?;
-            pwd.as_str().to_string()
-        };
-
-        save_vault_to_disk(&state, &pwd_string)?;
+        save_vault_to_disk(&state)?;
 
         eprintln!(
             "[Save Entry] Saved entry {} with category {}",
@@ -3819,7 +3708,6 @@ async fn save_entry_handler(
     }
 }
 
-use base32;
 use chrono;
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -3831,6 +3719,37 @@ struct AppSettings {
     use_biometric: bool,
     #[serde(default)]
     stream_protection: bool,
+    #[serde(default = "default_true")]
+    lock_on_sleep: bool,
+    #[serde(default = "default_true")]
+    lock_on_screen_lock: bool,
+    // 0 = kapalı; aksi halde bu kadar hatalı denemeden sonra kasa silinir
+    #[serde(default)]
+    wipe_after_failures: u32,
+    // 0 = çöp kutusu elle boşaltılana kadar saklanır
+    #[serde(default = "default_trash_retention_days")]
+    trash_retention_days: u32,
+    #[serde(default = "default_max_attachment_mb")]
+    max_attachment_mb: u32,
+    #[serde(default)]
+    backup_schedule: BackupSchedule,
+    #[serde(default)]
+    backup_retention: BackupRetention,
+    // The native host uses the local channel; TCP is only for older clients
+    #[serde(default)]
+    tcp_api_enabled: bool,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+fn default_trash_retention_days() -> u32 {
+    DEFAULT_RETENTION_DAYS
+}
+
+fn default_max_attachment_mb() -> u32 {
+    DEFAULT_MAX_ATTACHMENT_MB
 }
 
 fn get_settings_path() -> Result<PathBuf, String> {
@@ -3867,6 +3786,14 @@ fn get_settings() -> Result<AppSettings, String> {
             auto_lock_timeout: 300,
             use_biometric: false,
             stream_protection: false,
+            lock_on_sleep: true,
+            lock_on_screen_lock: true,
+            wipe_after_failures: 0,
+            trash_retention_days: DEFAULT_RETENTION_DAYS,
+            max_attachment_mb: DEFAULT_MAX_ATTACHMENT_MB,
+            backup_schedule: BackupSchedule::default(),
+            backup_retention: BackupRetention::default(),
+            tcp_api_enabled: false,
         });
     }
 
@@ -3884,64 +3811,318 @@ fn save_settings(settings: &AppSettings) -> Result<(), String> {
     let json = serde_json::to_string_pretty(settings)
         .map_err(|e| format!("Ayarlar serialize edilemedi: {}", e))?;
 
-    fs::write(&settings_path, json).map_err(|e| format!("Ayarlar kaydedilemedi: {}", e))?;
+    fs::write(&settings_path, json).map_err(|e| format!("Ayarlar kaydedilemedi: {}", e))?;
+
+    Ok(())
+}
+
+#[tauri::command]
+fn set_minimize_to_tray(enabled: bool) -> Result<(), String> {
+    let mut settings = get_settings()?;
+    settings.minimize_to_tray = enabled;
+    save_settings(&settings)?;
+    Ok(())
+}
+
+#[tauri::command]
+fn set_auto_start(enabled: bool) -> Result<(), String> {
+    #[cfg(windows)]
+    {
+        use winreg::enums::*;
+        use winreg::RegKey;
+
+        let hkcu = RegKey::predef(HKEY_CURRENT_USER);
+        let path = r"Software\Microsoft\Windows\CurrentVersion\Run";
+        let (key, _) = hkcu
+            .create_subkey(path)
+            .map_err(|e| format!("Registry key oluşturulamadı: {}", e))?;
+
+        if enabled {
+            let exe_path =
+                std::env::current_exe().map_err(|e| format!("Exe path bulunamadı: {}", e))?;
+            let exe_path_str = exe_path.to_string_lossy().to_string();
+            key.set_value("ConfPass", &exe_path_str)
+                .map_err(|e| format!("Auto-start kaydedilemedi: {}", e))?;
+        } else {
+            let _ = key.delete_value("ConfPass");
+        }
+    }
+
+    #[cfg(not(windows))]
+    {
+        return Err("Auto-start sadece Windows'ta destekleniyor".to_string());
+    }
+
+    let mut settings = get_settings()?;
+    settings.auto_start = enabled;
+    save_settings(&settings)?;
+    Ok(())
+}
+
+#[tauri::command]
+fn set_auto_lock_timeout(timeout: u64) -> Result<(), String> {
+    let mut settings = get_settings()?;
+    settings.auto_lock_timeout = timeout;
+    save_settings(&settings)?;
+
+    let mut state = get_state_mut().map_err(|e| e.to_string())?;
+    state.auto_lock_timeout = if timeout > 0 { Some(timeout) } else { None };
+
+    Ok(())
+}
+
+#[tauri::command]
+fn set_lock_on_sleep(enabled: bool) -> Result<(), String> {
+    let mut settings = get_settings()?;
+    settings.lock_on_sleep = enabled;
+    save_settings(&settings)?;
+
+    if let Ok(mut lock_on_sleep) = LOCK_ON_SLEEP.lock() {
+        *lock_on_sleep = enabled;
+    }
+    Ok(())
+}
+
+#[tauri::command]
+fn set_lock_on_screen_lock(enabled: bool) -> Result<(), String> {
+    let mut settings = get_settings()?;
+    settings.lock_on_screen_lock = enabled;
+    save_settings(&settings)?;
+
+    if let Ok(mut lock_on_screen_lock) = LOCK_ON_SCREEN_LOCK.lock() {
+        *lock_on_screen_lock = enabled;
+    }
+    Ok(())
+}
+
+#[tauri::command]
+fn set_wipe_after_failures(attempts: u32) -> Result<(), String> {
+    // Eşik, geri çekilme süreleri devreye girmeden kasayı silmemeli
+    if attempts != 0 && !(FREE_ATTEMPTS * 2..=100).contains(&attempts) {
+        return Err(format!(
+            "Silme eşiği {} ile 100 arasında olmalı",
+            FREE_ATTEMPTS * 2
+        ));
+    }
+
+    let mut settings = get_settings()?;
+    settings.wipe_after_failures = attempts;
+    save_settings(&settings)
+}
+
+#[tauri::command]
+fn set_trash_retention_days(days: u32) -> Result<(), String> {
+    if days > MAX_RETENTION_DAYS {
+        return Err(format!(
+            "Saklama süresi en fazla {} gün olabilir",
+            MAX_RETENTION_DAYS
+        ));
+    }
+
+    let mut settings = get_settings()?;
+    settings.trash_retention_days = days;
+    save_settings(&settings)?;
+
+    purge_expired_trash();
+    Ok(())
+}
+
+/// Takes effect the next time the app starts.
+#[tauri::command]
+fn set_tcp_api_enabled(enabled: bool) -> Result<(), String> {
+    let mut settings = get_settings()?;
+    settings.tcp_api_enabled = enabled;
+    save_settings(&settings)
+}
+
+#[tauri::command]
+fn set_max_attachment_size(megabytes: u32) -> Result<(), String> {
+    if !(1..=MAX_ATTACHMENT_MB).contains(&megabytes) {
+        return Err(format!(
+            "Ek boyutu sınırı 1 - {} MB arasında olmalı",
+            MAX_ATTACHMENT_MB
+        ));
+    }
+
+    let mut settings = get_settings()?;
+    settings.max_attachment_mb = megabytes;
+    save_settings(&settings)
+}
+
+/// Kasayı diskten okuyup doğrular; kasa kilitliyken de çalışır. Şifre çözülemezse
+/// hatalı deneme olarak sayılır.
+fn verify_vault_on_disk(master_password: &str) -> Result<IntegrityReport, String> {
+    validate_input(master_password, 8, 128, "Ana şifre").map_err(|e| e.to_string())?;
+    check_rate_limit()?;
+
+    let report = confpass_core::integrity::verify_vault(&get_vault_path()?, master_password)?;
+    if report
+        .issues
+        .iter()
+        .any(|issue| issue.kind == IssueKind::DecryptFailed)
+    {
+        return Err(record_failed_attempt(
+            "Yanlış ana şifre veya bozuk kasa".to_string(),
+        ));
+    }
+    // The password was checked unless the file or its header could not be read
+    let password_checked = !report.issues.iter().any(|issue| {
+        matches!(
+            issue.kind,
+            IssueKind::MissingVaultFile | IssueKind::InvalidHeader | IssueKind::MissingSalt
+        )
+    });
+    if password_checked {
+        reset_failed_attempts();
+    }
+    Ok(report)
+}
+
+#[tauri::command]
+fn verify_vault(mut master_password: String) -> Result<IntegrityReport, String> {
+    let report = verify_vault_on_disk(&master_password);
+    master_password.zeroize();
+    report
+}
+
+/// Onarılabilir sorun varsa kasayı kilitler (bellekteki durum diske yazılır),
+/// diskteki kasayı yedekleyip onarır. Kasa kilitli kalır.
+#[tauri::command]
+fn repair_vault(mut master_password: String) -> Result<IntegrityReport, String> {
+    let report = match verify_vault_on_disk(&master_password) {
+        Ok(report) => report,
+        Err(e) => {
+            master_password.zeroize();
+            return Err(e);
+        }
+    };
+    if report.is_fatal() || !report.issues.iter().any(|issue| issue.repairable) {
+        master_password.zeroize();
+        return Ok(report);
+    }
+
+    if let Err(e) = lock_vault_state() {
+        master_password.zeroize();
+        return Err(e);
+    }
+
+    let repaired = confpass_core::integrity::repair_vault(
+        &get_vault_path()?,
+        &master_password,
+        chrono::Utc::now().timestamp(),
+    );
+    master_password.zeroize();
+    let repaired = repaired?;
 
-    Ok(())
+    log_to_file(&format!(
+        "Kasa onarıldı: {} sorun giderildi, {} sorun kaldı",
+        repaired.repaired.len(),
+        repaired.issues.len()
+    ));
+    Ok(repaired)
 }
 
 #[tauri::command]
-fn set_minimize_to_tray(enabled: bool) -> Result<(), String> {
+fn set_backup_settings(schedule: BackupSchedule, retention: BackupRetention) -> Result<(), String> {
+    if retention.recent == 0 {
+        return Err("En az bir yedek saklanmalı".to_string());
+    }
+    if retention.recent > 100 || retention.daily > 100 || retention.weekly > 100 {
+        return Err("Yedek sayısı en fazla 100 olabilir".to_string());
+    }
+
     let mut settings = get_settings()?;
-    settings.minimize_to_tray = enabled;
+    settings.backup_schedule = schedule;
+    settings.backup_retention = retention;
     save_settings(&settings)?;
+
+    confpass_core::backups::prune_backups(&get_vault_path()?, retention)?;
     Ok(())
 }
 
 #[tauri::command]
-fn set_auto_start(enabled: bool) -> Result<(), String> {
-    #[cfg(windows)]
-    {
-        use winreg::enums::*;
-        use winreg::RegKey;
+fn list_backups() -> Result<Vec<BackupInfo>, String> {
+    confpass_core::backups::list_backups(&get_vault_path()?)
+}
 
-        let hkcu = RegKey::predef(HKEY_CURRENT_USER);
-        let path = r"Software\Microsoft\Windows\CurrentVersion\Run";
-        let (key, _) = hkcu
-            .create_subkey(path)
-            .map_err(|e| format!("Registry key oluşturulamadı: {}", e))?;
+#[tauri::command]
+fn create_backup() -> Result<BackupInfo, String> {
+    let state = get_state().map_err(|e| e.to_string())?;
+    if state.vault_locked {
+        return Err(VaultError::Locked.to_string());
+    }
 
-        if enabled {
-            let exe_path =
-                std::env::current_exe().map_err(|e| format!("Exe path bulunamadı: {}", e))?;
-            let exe_path_str = exe_path.to_string_lossy().to_string();
-            key.set_value("ConfPass", &exe_path_str)
-                .map_err(|e| format!("Auto-start kaydedilemedi: {}", e))?;
-        } else {
-            let _ = key.delete_value("ConfPass");
-        }
+    let vault_path = get_vault_path()?;
+    confpass_core::storage::save_vault_to_disk(&vault_path, &state)?;
+    confpass_core::backups::create_backup(&vault_path, chrono::Utc::now().timestamp())
+}
+
+/// Yedeği ana şifreyle doğrular, mevcut kasayı yedekleyip yerine koyar ve kasayı
+/// kilitler; kullanıcı geri yüklenen kasayı o yedeğin şifresiyle açar.
+#[tauri::command]
+fn restore_backup(backup_id: String, mut master_password: String) -> Result<(), String> {
+    validate_input(&master_password, 8, 128, "Ana şifre").map_err(|e| e.to_string())?;
+
+    if let Err(e) = check_rate_limit() {
+        master_password.zeroize();
+        return Err(e);
     }
 
-    #[cfg(not(windows))]
+    let vault_path = get_vault_path()?;
+    if let Err(e) = confpass_core::backups::verify_backup(&vault_path, &backup_id, &master_password)
     {
-        return Err("Auto-start sadece Windows'ta destekleniyor".to_string());
+        master_password.zeroize();
+        if e.contains("Yedek çözülemedi") {
+            return Err(record_failed_attempt(
+                "Yanlış ana şifre veya bozuk yedek".to_string(),
+            ));
+        }
+        return Err(e);
     }
 
-    let mut settings = get_settings()?;
-    settings.auto_start = enabled;
-    save_settings(&settings)?;
+    // Kilitlerken bellekteki kasa diske yazılır; geri yükleme bundan sonra yapılmalı
+    if let Err(e) = lock_vault_state() {
+        master_password.zeroize();
+        return Err(e);
+    }
+
+    let restored = confpass_core::backups::restore_backup(
+        &vault_path,
+        &backup_id,
+        &master_password,
+        chrono::Utc::now().timestamp(),
+    );
+    master_password.zeroize();
+    restored?;
+
+    reset_failed_attempts();
+    log_to_file(&format!("Kasa {} yedeğinden geri yüklendi", backup_id));
     Ok(())
 }
 
-#[tauri::command]
-fn set_auto_lock_timeout(timeout: u64) -> Result<(), String> {
-    let mut settings = get_settings()?;
-    settings.auto_lock_timeout = timeout;
-    save_settings(&settings)?;
+#[derive(Serialize)]
+struct UnlockLockoutStatus {
+    failed_attempts: u32,
+    remaining_seconds: u64,
+    wipe_after_failures: u32,
+}
 
-    let mut state = get_state_mut().map_err(|e| e.to_string())?;
-    state.auto_lock_timeout = if timeout > 0 { Some(timeout) } else { None };
+#[tauri::command]
+fn get_unlock_lockout() -> Result<UnlockLockoutStatus, String> {
+    let now = unix_now();
+    let attempts = attempt_store()?.load(now)?;
+
+    Ok(UnlockLockoutStatus {
+        failed_attempts: attempts.failed_attempts,
+        remaining_seconds: attempts.remaining_lockout(now),
+        wipe_after_failures: get_settings()?.wipe_after_failures,
+    })
+}
 
-    Ok(())
+/// Arayüzdeki kullanıcı etkinliğini (fare, klavye) bekleme sayacına işler.
+#[tauri::command]
+fn record_user_activity() {
+    record_activity();
 }
 
 #[tauri::command]
@@ -3971,8 +4152,8 @@ fn set_use_biometric(enabled: bool) -> Result<(), String> {
         // If vault is already unlocked, save password to keyring immediately
         #[cfg(windows)]
         {
-            let master_pwd_guard = MASTER_PASSWORD.lock().map_err(|_| "Lock error")?;
-            if let Some(ref pwd) = *master_pwd_guard {
+            let state = get_state().map_err(|e| e.to_string())?;
+            if let Some(pwd) = state.vault().master_password() {
                 if let Ok(entry) = keyring::Entry::new("ConfPass", "master_password") {
                     let _ = entry.set_password(pwd.as_str());
                 }
@@ -4257,29 +4438,7 @@ fn start_stream_monitor(app_handle: tauri::AppHandle) {
 
 #[tauri::command]
 fn generate_totp_code(secret: String) -> Result<String, String> {
-    use totp_lite::{totp_custom, Sha1};
-
-    let cleaned_secret = secret.replace(" ", "").replace("-", "").to_uppercase();
-
-    let secret_bytes = match base32::decode(
-        base32::Alphabet::RFC4648 { padding: false },
-        &cleaned_secret,
-    ) {
-        Some(bytes) if !bytes.is_empty() => bytes,
-        _ => match base64::engine::general_purpose::STANDARD.decode(&secret) {
-            Ok(bytes) if !bytes.is_empty() => bytes,
-            _ => return Err("Geçersiz TOTP secret formatı".to_string()),
-        },
-    };
-
-    let timestamp = std::time::SystemTime::now()
-        .duration_since(std::time::UNIX_EPOCH)
-        .map_err(|e| format!("Zaman hatası: {}", e))?
-        .as_secs();
-
-    let code = totp_custom::<Sha1>(30, 6, secret_bytes.as_slice(), timestamp);
-
-    Ok(code)
+    confpass_core::totp::generate_totp_code(&secret)
 }
 
 #[tauri::command]
@@ -4288,15 +4447,19 @@ fn generate_totp_qr_code(
     issuer: String,
     account: String,
 ) -> Result<String, String> {
-    use qrcode::QrCode;
-
     let otp_url = format!(
         "otpauth://totp/{}:{}?secret={}&issuer={}&algorithm=SHA1&digits=6&period=30",
         issuer, account, secret, issuer
     );
 
-    let qr =
-        QrCode::new(otp_url.as_bytes()).map_err(|e| format!("QR kod oluşturulamadı: {}", e))?;
+    render_qr_png_base64(otp_url.as_bytes())
+}
+
+/// Renders `data` as a 200x200 QR code and returns the PNG as base64.
+fn render_qr_png_base64(data: &[u8]) -> Result<String, String> {
+    use qrcode::QrCode;
+
+    let qr = QrCode::new(data).map_err(|e| format!("QR kod oluşturulamadı: {}", e))?;
 
     let image = qr
         .render::<image::Rgb<u8>>()
@@ -4325,7 +4488,7 @@ fn reset_vault() -> Result<(), String> {
         .parent()
         .ok_or_else(|| "Vault path parent bulunamadı".to_string())?;
 
-    let salt_path = vault_dir.join("vault.salt");
+    let salt_path = vault_dir.join(confpass_core::storage::SALT_FILE_NAME);
 
     if vault_path.exists() {
         fs::remove_file(&vault_path).map_err(|e| format!("Vault dosyası silinemedi: {}", e))?;
@@ -4335,28 +4498,19 @@ fn reset_vault() -> Result<(), String> {
         fs::remove_file(&salt_path).map_err(|e| format!("Salt dosyası silinemedi: {}", e))?;
     }
 
-    let mut state = get_state_mut().map_err(|e| e.to_string())?;
-    state.entries.clear();
-    state.master_password_hash = None;
-    state.encryption_salt = None;
-    state.vault_locked = true;
-    state.failed_attempts = 0;
-    state.last_attempt_time = None;
-
-    {
-        let mut master_pwd = MASTER_PASSWORD
-            .lock()
-            .map_err(|_| "Master password kilidi alinamadi".to_string())?;
-        *master_pwd = None;
+    let recovery_path = confpass_core::recovery::recovery_path(&vault_path)?;
+    if recovery_path.exists() {
+        fs::remove_file(&recovery_path)
+            .map_err(|e| format!("Kurtarma dosyası silinemedi: {}", e))?;
     }
 
-    Ok(())
+    reset_failed_attempts();
+
+    close_vault_session(false)
 }
 
 #[tauri::command]
 fn reset_vault_with_password(mut master_password: String) -> Result<(), String> {
-    use argon2::password_hash::{PasswordHash, PasswordVerifier};
-    use argon2::Argon2;
     use zeroize::Zeroize;
 
     validate_input(&master_password, 8, 128, "Ana şifre").map_err(|e| e.to_string())?;
@@ -4367,46 +4521,28 @@ fn reset_vault_with_password(mut master_password: String) -> Result<(), String>
         return Err("Kasa bulunamadı".to_string());
     }
 
-    // Load vault to verify password
-    let loaded_state = match load_vault_from_disk(&master_password) {
-        Ok(state) => state,
-        Err(e) => {
-            master_password.zeroize();
-            if e.contains("Decrypt hatası") || e.contains("decrypt") || e.contains("Şifre çözme")
-            {
-                return Err("Yanlış ana şifre".to_string());
-            }
-            return Err(format!("Vault doğrulanamadı: {}", e));
-        }
-    };
-
-    // Verify password hash
-    let stored_hash = loaded_state.master_password_hash.as_ref().ok_or_else(|| {
-        master_password.zeroize();
-        "Hash bulunamadı".to_string()
-    })?;
-
-    let parsed_hash = PasswordHash::new(stored_hash).map_err(|e| {
-        master_password.zeroize();
-        format!("Hash parse hatası: {}", e)
-    })?;
-
-    if Argon2::default()
-        .verify_password(master_password.as_bytes(), &parsed_hash)
-        .is_err()
-    {
+    if let Err(e) = check_rate_limit() {
         master_password.zeroize();
-        return Err("Yanlış ana şifre".to_string());
+        return Err(e);
     }
 
+    let verified = get_state()
+        .map_err(|e| e.to_string())
+        .and_then(|state| state.vault().verify_password(&master_password));
     master_password.zeroize();
 
+    match verified {
+        Ok(()) => {}
+        Err(e) if e == "Yanlış ana şifre" => return Err(record_failed_attempt(e)),
+        Err(e) => return Err(format!("Vault doğrulanamadı: {}", e)),
+    }
+
     // Password verified, now reset everything
     let vault_dir = vault_path
         .parent()
         .ok_or_else(|| "Vault path parent bulunamadı".to_string())?;
 
-    let salt_path = vault_dir.join("vault.salt");
+    let salt_path = vault_dir.join(confpass_core::storage::SALT_FILE_NAME);
     let activity_log_path = vault_dir.join("activity_log.json");
     let settings_path = vault_dir.join("settings.json");
 
@@ -4419,6 +4555,17 @@ fn reset_vault_with_password(mut master_password: String) -> Result<(), String>
         fs::remove_file(&salt_path).map_err(|e| format!("Salt dosyası silinemedi: {}", e))?;
     }
 
+    let recovery_path = confpass_core::recovery::recovery_path(&vault_path)?;
+    if recovery_path.exists() {
+        fs::remove_file(&recovery_path)
+            .map_err(|e| format!("Kurtarma dosyası silinemedi: {}", e))?;
+    }
+
+    let backups_dir = confpass_core::backups::backups_dir(&vault_path)?;
+    if backups_dir.exists() {
+        fs::remove_dir_all(&backups_dir).map_err(|e| format!("Yedek dizini silinemedi: {}", e))?;
+    }
+
     if activity_log_path.exists() {
         let _ = fs::remove_file(&activity_log_path);
     }
@@ -4428,22 +4575,7 @@ fn reset_vault_with_password(mut master_password: String) -> Result<(), String>
     }
 
     // Clear in-memory state
-    let mut state = get_state_mut().map_err(|e| e.to_string())?;
-    state.entries.clear();
-    state.master_password_hash = None;
-    state.encryption_salt = None;
-    state.vault_locked = true;
-    state.failed_attempts = 0;
-    state.last_attempt_time = None;
-
-    {
-        let mut master_pwd = MASTER_PASSWORD
-            .lock()
-            .map_err(|_| "Master password kilidi alinamadi".to_string())?;
-        *master_pwd = None;
-    }
-
-    Ok(())
+    close_vault_session(false)
 }
 
 #[tauri::command]
@@ -4496,6 +4628,7 @@ async fn check_password_breach(password: String) -> Result<serde_json::Value, St
 }
 
 async fn get_password_entry_handler(
+    Extension(client): Extension<ApiClient>,
     Json(payload): Json<serde_json::Value>,
 ) -> Result<Json<serde_json::Value>, (StatusCode, String)> {
     let id = payload
@@ -4509,7 +4642,13 @@ async fn get_password_entry_handler(
         return Err((StatusCode::FORBIDDEN, "Kasa kilitli".to_string()));
     }
 
-    if let Some(entry) = state.entries.get(id) {
+    if let Some(entry) = state.entries.get(id).filter(|e| !is_trashed(e)) {
+        if !client_may_access(&client, &entry.category) {
+            return Err((
+                StatusCode::FORBIDDEN,
+                "Bu kayda erişim izni yok".to_string(),
+            ));
+        }
         Ok(Json(serde_json::to_value(entry).map_err(|e| {
             (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
         })?))
@@ -4574,62 +4713,115 @@ async fn check_email_breach(email: String) -> Result<serde_json::Value, String>
 
 #[tauri::command]
 fn add_password_history(entry_id: String, old_password: String) -> Result<(), String> {
-    let state = get_state().map_err(|e| e.to_string())?;
+    let mut state = get_state_mut().map_err(|e| e.to_string())?;
 
+    if state.vault_locked {
+        return Err(VaultError::Locked.to_string());
+    }
     if !state.entries.contains_key(&entry_id) {
         return Err("Entry bulunamadı".to_string());
     }
 
-    let history_path = get_vault_path()?
-        .parent()
-        .ok_or_else(|| "Vault path parent bulunamadı".to_string())?
-        .join("history.json");
+    confpass_core::history::record_password_change(
+        &mut state,
+        &entry_id,
+        old_password,
+        chrono::Utc::now().timestamp(),
+    );
 
-    let mut history: std::collections::HashMap<String, Vec<serde_json::Value>> =
-        if history_path.exists() {
-            let content = fs::read_to_string(&history_path)
-                .map_err(|e| format!("History okunamadı: {}", e))?;
-            serde_json::from_str(&content).unwrap_or_default()
-        } else {
-            std::collections::HashMap::new()
-        };
+    save_vault_to_disk(&state).map_err(|e| format!("History kaydedilemedi: {}", e))
+}
 
-    let entry_history = history.entry(entry_id).or_insert_with(Vec::new);
-    entry_history.push(serde_json::json!({
-        "password": old_password,
-        "changed_at": chrono::Utc::now().timestamp()
-    }));
+#[tauri::command]
+fn get_password_history(entry_id: String) -> Result<Vec<PasswordHistoryItem>, String> {
+    let state = get_state().map_err(|e| e.to_string())?;
 
-    if entry_history.len() > 10 {
-        entry_history.remove(0);
+    if state.vault_locked {
+        return Err(VaultError::Locked.to_string());
     }
 
-    let json = serde_json::to_string_pretty(&history)
-        .map_err(|e| format!("History serialize edilemedi: {}", e))?;
+    Ok(state
+        .password_history
+        .get(&entry_id)
+        .cloned()
+        .unwrap_or_default())
+}
+
+#[tauri::command]
+fn restore_password_from_history(entry_id: String, index: usize) -> Result<PasswordEntry, String> {
+    let mut state = get_state_mut().map_err(|e| e.to_string())?;
 
-    fs::write(&history_path, json).map_err(|e| format!("History kaydedilemedi: {}", e))?;
+    if state.vault_locked {
+        return Err(VaultError::Locked.to_string());
+    }
 
-    Ok(())
+    let before = state
+        .entries
+        .get(&entry_id)
+        .cloned()
+        .ok_or_else(|| VaultError::NotFound.to_string())?;
+    let now = chrono::Utc::now().timestamp();
+    let entry = confpass_core::history::restore_password(&mut state, &entry_id, index, now)?;
+    confpass_core::revisions::record_change(
+        &mut state,
+        &entry_id,
+        &before,
+        RevisionSource::Ui,
+        now,
+    )?;
+
+    save_vault_to_disk(&state).map_err(|e| format!("Geri yükleme kaydedilemedi: {}", e))?;
+
+    Ok(entry)
 }
 
 #[tauri::command]
-fn get_password_history(entry_id: String) -> Result<Vec<serde_json::Value>, String> {
-    let history_path = get_vault_path()?
-        .parent()
-        .ok_or_else(|| "Vault path parent bulunamadı".to_string())?
-        .join("history.json");
+fn get_entry_revisions(entry_id: String) -> Result<Vec<EntryRevision>, String> {
+    let state = get_state().map_err(|e| e.to_string())?;
 
-    if !history_path.exists() {
-        return Ok(Vec::new());
+    if state.vault_locked {
+        return Err(VaultError::Locked.to_string());
     }
 
-    let content =
-        fs::read_to_string(&history_path).map_err(|e| format!("History okunamadı: {}", e))?;
+    Ok(confpass_core::revisions::revisions(&state, &entry_id).to_vec())
+}
+
+/// Versions count revisions: `0` is the entry before its first recorded
+/// change and `revisions.len()` is the entry as it is now.
+#[tauri::command]
+fn diff_entry_revisions(
+    entry_id: String,
+    from_version: usize,
+    to_version: usize,
+) -> Result<Vec<FieldChange>, String> {
+    let state = get_state().map_err(|e| e.to_string())?;
+
+    if state.vault_locked {
+        return Err(VaultError::Locked.to_string());
+    }
+
+    confpass_core::revisions::diff_versions(&state, &entry_id, from_version, to_version)
+}
+
+#[tauri::command]
+fn rollback_entry(entry_id: String, version: usize) -> Result<PasswordEntry, String> {
+    let mut state = get_state_mut().map_err(|e| e.to_string())?;
+
+    if state.vault_locked {
+        return Err(VaultError::Locked.to_string());
+    }
+
+    let entry = confpass_core::revisions::rollback(
+        &mut state,
+        &entry_id,
+        version,
+        RevisionSource::Ui,
+        chrono::Utc::now().timestamp(),
+    )?;
 
-    let history: std::collections::HashMap<String, Vec<serde_json::Value>> =
-        serde_json::from_str(&content).map_err(|e| format!("History parse edilemedi: {}", e))?;
+    save_vault_to_disk(&state).map_err(|e| format!("Geri alma kaydedilemedi: {}", e))?;
 
-    Ok(history.get(&entry_id).cloned().unwrap_or_default())
+    Ok(entry)
 }
 
 #[tauri::command]
@@ -4823,25 +5015,30 @@ fn perform_auto_type() {
     }
 
     // Heuristic Search
-    let matched_entry = state_guard.entries.values().find(|entry| {
-        // A. Match Title
-        if window_title.contains(&entry.title.to_lowercase()) {
-            return true;
-        }
-        // B. Match URL/Domain
-        if let Some(url) = &entry.url {
-            if let Some(domain) = extract_domain(&url.to_lowercase()) {
-                // Remove TLD for better matching (e.g. "instagram" from "instagram.com")
-                let domain_parts: Vec<&str> = domain.split('.').collect();
-                if let Some(main_part) = domain_parts.get(domain_parts.len().saturating_sub(2)) {
-                    if window_title.contains(main_part) {
-                        return true;
+    let matched_entry = state_guard
+        .entries
+        .values()
+        .filter(|e| !is_trashed(e))
+        .find(|entry| {
+            // A. Match Title
+            if window_title.contains(&entry.title.to_lowercase()) {
+                return true;
+            }
+            // B. Match URL/Domain
+            if let Some(url) = &entry.url {
+                if let Some(domain) = extract_domain(&url.to_lowercase()) {
+                    // Remove TLD for better matching (e.g. "instagram" from "instagram.com")
+                    let domain_parts: Vec<&str> = domain.split('.').collect();
+                    if let Some(main_part) = domain_parts.get(domain_parts.len().saturating_sub(2))
+                    {
+                        if window_title.contains(main_part) {
+                            return true;
+                        }
                     }
                 }
             }
-        }
-        false
-    });
+            false
+        });
 
     if let Some(entry) = matched_entry {
         log_to_file(&format!("Auto-Type: Match Found -> {}", entry.title));
@@ -4952,20 +5149,10 @@ fn start_password_rotation_timer() {
             };
 
             if should_clear {
-                // Clear master password from memory
-                if let Ok(mut pwd) = MASTER_PASSWORD.lock() {
-                    if let Some(mut p) = pwd.take() {
-                        p.zeroize();
-                    }
-                }
-                // Clear set time
-                if let Ok(mut t) = MASTER_PASSWORD_SET_TIME.lock() {
-                    *t = None;
-                }
-                // Lock vault
-                if let Ok(mut state) = VAULT_STATE.lock() {
-                    state.vault_locked = true;
-                    state.entries.clear();
+                // Lock vault, clearing the master password from memory
+                if let Err(e) = lock_vault_state() {
+                    log_to_file(&format!("Password rotation: Kilitleme başarısız: {}", e));
+                    continue;
                 }
                 log_to_file("Password rotation: Master password cleared from memory");
             }
@@ -4973,87 +5160,292 @@ fn start_password_rotation_timer() {
     });
 }
 
+// Auto-Lock - Hareketsizlik, uyku ve ekran kilidinde kasayı kilitle
+const AUTO_LOCK_TICK: Duration = Duration::from_secs(5);
+// Bir tur bu kadar gecikirse sistemin uykuya geçtiği kabul edilir
+const SLEEP_DETECTION_THRESHOLD: Duration = Duration::from_secs(30);
+
+// Sadece durum okuyan veya periyodik çağrılan komutlar etkinlik sayılmaz
+const PASSIVE_COMMANDS: &[&str] = &[
+    "is_vault_locked",
+    "generate_totp_code",
+    "get_stream_protection_status",
+    "check_streaming_apps",
+];
+
+fn record_activity() {
+    if let Ok(mut last) = LAST_ACTIVITY.lock() {
+        *last = SystemTime::now();
+    }
+}
+
+fn apply_lock_settings(settings: &AppSettings) {
+    if let Ok(mut state) = get_state_mut() {
+        state.auto_lock_timeout = if settings.auto_lock_timeout > 0 {
+            Some(settings.auto_lock_timeout)
+        } else {
+            None
+        };
+    }
+    if let Ok(mut lock_on_sleep) = LOCK_ON_SLEEP.lock() {
+        *lock_on_sleep = settings.lock_on_sleep;
+    }
+    if let Ok(mut lock_on_screen_lock) = LOCK_ON_SCREEN_LOCK.lock() {
+        *lock_on_screen_lock = settings.lock_on_screen_lock;
+    }
+}
+
+#[cfg(windows)]
+fn is_screen_locked() -> bool {
+    use windows::Win32::System::StationsAndDesktops::{
+        CloseDesktop, OpenInputDesktop, SwitchDesktop, DESKTOP_CONTROL_FLAGS, DESKTOP_SWITCHDESKTOP,
+    };
+
+    // Kilit ekranında giriş masaüstüne geçilemez. Masaüstü hiç açılamıyorsa
+    // (ör. UAC güvenli masaüstü) kilit durumu bilinmez; kilitli sayılmaz.
+    unsafe {
+        match OpenInputDesktop(DESKTOP_CONTROL_FLAGS(0), false, DESKTOP_SWITCHDESKTOP) {
+            Ok(desktop) => {
+                let locked = SwitchDesktop(desktop).is_err();
+                let _ = CloseDesktop(desktop);
+                locked
+            }
+            Err(_) => false,
+        }
+    }
+}
+
+// Linux'ta ekran kilidi logind sinyaliyle gelir; bkz. start_session_lock_listener
+#[cfg(not(windows))]
+fn is_screen_locked() -> bool {
+    false
+}
+
+/// systemd-logind oturumun `Lock` sinyalini dinler ve kasayı hemen kilitler.
+#[cfg(target_os = "linux")]
+fn start_session_lock_listener() {
+    std::thread::spawn(|| {
+        if let Err(e) = listen_for_session_lock() {
+            log_to_file(&format!("logind Lock sinyali dinlenemiyor: {}", e));
+        }
+    });
+}
+
+#[cfg(target_os = "linux")]
+fn listen_for_session_lock() -> zbus::Result<()> {
+    use zbus::blocking::{Connection, Proxy};
+    use zbus::zvariant::OwnedObjectPath;
+
+    let connection = Connection::system()?;
+    let manager = Proxy::new(
+        &connection,
+        "org.freedesktop.login1",
+        "/org/freedesktop/login1",
+        "org.freedesktop.login1.Manager",
+    )?;
+    let session_id = env::var("XDG_SESSION_ID").unwrap_or_else(|_| "auto".to_string());
+    let session_path: OwnedObjectPath = manager.call("GetSession", &(session_id.as_str(),))?;
+    let session = Proxy::new(
+        &connection,
+        "org.freedesktop.login1",
+        session_path,
+        "org.freedesktop.login1.Session",
+    )?;
+
+    for _ in session.receive_signal("Lock")? {
+        let unlocked = get_state()
+            .map(|state| !state.vault_locked)
+            .unwrap_or(false);
+        if unlocked && LOCK_ON_SCREEN_LOCK.lock().map(|v| *v).unwrap_or(true) {
+            auto_lock("screen_lock");
+        }
+    }
+    Ok(())
+}
+
+fn auto_lock(reason: &str) {
+    if let Err(e) = lock_vault_state() {
+        log_to_file(&format!("Auto-lock ({}) başarısız: {}", reason, e));
+        return;
+    }
+    log_to_file(&format!("Auto-lock: Kasa kilitlendi ({})", reason));
+
+    if let Some(app_handle) = get_app_handle() {
+        let _ = app_handle.emit("vault-locked", json!({ "reason": reason }));
+    }
+}
+
+fn start_auto_lock_timer() {
+    std::thread::spawn(|| {
+        let mut last_tick = SystemTime::now();
+
+        loop {
+            std::thread::sleep(AUTO_LOCK_TICK);
+
+            // Duvar saati uykuda da ilerler; beklenenden uzun süren tur uyku demektir
+            let now = SystemTime::now();
+            let woke_from_sleep = now.duration_since(last_tick).unwrap_or_default()
+                > AUTO_LOCK_TICK + SLEEP_DETECTION_THRESHOLD;
+            last_tick = now;
+
+            let timeout = match get_state() {
+                Ok(state) if !state.vault_locked => state.auto_lock_timeout,
+                _ => continue,
+            };
+
+            if woke_from_sleep && LOCK_ON_SLEEP.lock().map(|v| *v).unwrap_or(true) {
+                auto_lock("sleep");
+                continue;
+            }
+
+            if LOCK_ON_SCREEN_LOCK.lock().map(|v| *v).unwrap_or(true) && is_screen_locked() {
+                auto_lock("screen_lock");
+                continue;
+            }
+
+            let Some(timeout) = timeout else {
+                continue;
+            };
+            let idle = match LAST_ACTIVITY.lock() {
+                Ok(last) => last.elapsed().unwrap_or_default(),
+                Err(_) => continue,
+            };
+            if idle > Duration::from_secs(timeout) {
+                auto_lock("idle");
+            }
+        }
+    });
+}
+
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
     start_global_shortcut_listener();
     start_password_rotation_timer();
+    if let Ok(settings) = get_settings() {
+        apply_lock_settings(&settings);
+    }
+    start_auto_lock_timer();
+    #[cfg(target_os = "linux")]
+    start_session_lock_listener();
+    let commands: fn(tauri::ipc::Invoke) -> bool = tauri::generate_handler![
+        unlock_vault,
+        lock_vault,
+        is_vault_locked,
+        change_master_password,
+        generate_recovery_key,
+        has_recovery_key,
+        unlock_with_recovery_key,
+        get_kdf_params,
+        set_kdf_params,
+        calibrate_kdf,
+        add_password_entry,
+        get_password_entries,
+        get_password_entry,
+        update_password_entry,
+        delete_password_entry,
+        restore_entry,
+        permanently_delete_entry,
+        empty_trash,
+        soft_delete_authenticator,
+        restore_authenticator,
+        permanently_delete_authenticator,
+        soft_delete_passkey,
+        restore_passkey,
+        permanently_delete_passkey,
+        generate_password,
+        check_password_strength,
+        find_password_by_url,
+        export_vault,
+        import_vault,
+        import_entries,
+        export_vault_kdbx,
+        import_vault_kdbx,
+        export_vault_encrypted,
+        import_vault_encrypted,
+        // Attachment commands
+        add_attachment,
+        get_attachment,
+        save_attachment_to,
+        get_attachment_preview,
+        delete_attachment,
+        collect_attachment_garbage,
+        // HTTP API clients
+        list_api_clients,
+        revoke_api_client,
+        list_pairing_requests,
+        approve_pairing_request,
+        deny_pairing_request,
+        get_settings,
+        set_minimize_to_tray,
+        set_auto_start,
+        set_auto_lock_timeout,
+        set_lock_on_sleep,
+        set_tcp_api_enabled,
+        set_lock_on_screen_lock,
+        record_user_activity,
+        set_wipe_after_failures,
+        set_trash_retention_days,
+        set_max_attachment_size,
+        set_backup_settings,
+        list_backups,
+        create_backup,
+        restore_backup,
+        verify_vault,
+        repair_vault,
+        get_unlock_lockout,
+        set_password_rotation_timeout,
+        get_password_rotation_timeout,
+        generate_totp_code,
+        generate_totp_qr_code,
+        check_password_breach,
+        check_email_breach,
+        add_password_history,
+        get_password_history,
+        get_entry_revisions,
+        diff_entry_revisions,
+        rollback_entry,
+        restore_password_from_history,
+        log_activity,
+        get_activity_log,
+        check_biometric_available,
+        biometric_authenticate,
+        unlock_vault_biometric,
+        set_use_biometric,
+        reset_vault,
+        reset_vault_with_password,
+        set_stream_protection,
+        get_stream_protection_status,
+        check_streaming_apps,
+        // Folder commands
+        get_folders,
+        create_folder,
+        update_folder,
+        delete_folder,
+        move_entry_to_folder,
+        bulk_delete_entries,
+        bulk_move_to_folder,
+        // Tag commands
+        get_tags,
+        create_tag,
+        delete_tag,
+        update_entry_tags,
+    ];
     tauri::Builder::default()
         .plugin(tauri_plugin_opener::init())
         .plugin(tauri_plugin_fs::init())
-        .invoke_handler(tauri::generate_handler![
-            unlock_vault,
-            lock_vault,
-            is_vault_locked,
-            add_password_entry,
-            get_password_entries,
-            get_password_entry,
-            update_password_entry,
-            delete_password_entry,
-            soft_delete_authenticator,
-            restore_authenticator,
-            permanently_delete_authenticator,
-            soft_delete_passkey,
-            restore_passkey,
-            permanently_delete_passkey,
-            generate_password,
-            check_password_strength,
-            find_password_by_url,
-            export_vault,
-            import_vault,
-            export_vault_encrypted,
-            import_vault_encrypted,
-            // Attachment commands
-            add_attachment,
-            get_attachment,
-            delete_attachment,
-            get_settings,
-            set_minimize_to_tray,
-            set_auto_start,
-            set_auto_lock_timeout,
-            set_password_rotation_timeout,
-            get_password_rotation_timeout,
-            generate_totp_code,
-            generate_totp_qr_code,
-            check_password_breach,
-            check_email_breach,
-            add_password_history,
-            get_password_history,
-            log_activity,
-            get_activity_log,
-            check_biometric_available,
-            biometric_authenticate,
-            unlock_vault_biometric,
-            set_use_biometric,
-            reset_vault,
-            reset_vault_with_password,
-            set_stream_protection,
-            get_stream_protection_status,
-            check_streaming_apps,
-            // Folder commands
-            get_folders,
-            create_folder,
-            update_folder,
-            delete_folder,
-            move_entry_to_folder,
-            bulk_delete_entries,
-            bulk_move_to_folder,
-            // Tag commands
-            get_tags,
-            create_tag,
-            delete_tag,
-            update_entry_tags,
-        ])
+        .invoke_handler(move |invoke| {
+            if !PASSIVE_COMMANDS.contains(&invoke.message.command()) {
+                record_activity();
+            }
+            commands(invoke)
+        })
         .on_window_event(|window, event| {
             if let tauri::WindowEvent::CloseRequested { api, .. } = event {
-                let settings = get_settings().unwrap_or(AppSettings {
-                    minimize_to_tray: false,
-                    auto_start: false,
-                    auto_lock_timeout: 300,
-                    use_biometric: false,
-                    stream_protection: false,
-                });
+                let minimize_to_tray = get_settings()
+                    .map(|settings| settings.minimize_to_tray)
+                    .unwrap_or(false);
 
-                if settings.minimize_to_tray {
+                if minimize_to_tray {
                     api.prevent_close();
                     if let Err(e) = window.hide() {
                         eprintln!("Pencere gizlenemedi: {}", e);
@@ -5150,10 +5542,24 @@ pub fn run() {
             std::thread::spawn(move || {
                 if let Ok(rt) = tokio::runtime::Runtime::new() {
                     rt.block_on(async {
-                        if let Err(e) = generate_and_save_auth_token().await {
-                            eprintln!("Failed to generate auth token: {}", e);
+                        if let Err(e) = load_api_clients() {
+                            eprintln!("Failed to load API clients: {}", e);
                         }
-                        start_http_server().await
+                        let router = api_router();
+                        let tcp_enabled = get_settings()
+                            .map(|settings| settings.tcp_api_enabled)
+                            .unwrap_or(false);
+                        let tcp = async {
+                            if tcp_enabled {
+                                start_tcp_server(router.clone()).await;
+                            }
+                        };
+                        let local = async {
+                            if let Err(e) = start_ipc_server(router.clone()).await {
+                                eprintln!("[IPC] Yerel kanal başlatılamadı: {}", e);
+                            }
+                        };
+                        tokio::join!(tcp, local);
                     });
                 } else {
                     eprintln!("Failed to create tokio runtime for HTTP server");
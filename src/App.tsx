@@ -1,12 +1,12 @@
-import { useState, useEffect, useMemo, useCallback, useRef, memo } from 'react';
+import { useState, useEffect, useMemo, useCallback, memo } from 'react';
 import { invoke } from '@tauri-apps/api/core';
 import { getCurrentWindow } from '@tauri-apps/api/window';
 import { listen } from '@tauri-apps/api/event';
 import { Lock, Unlock, Plus, Search, Key, Shield, Settings as SettingsIcon, Home, CheckCircle, XCircle, Info, AlertCircle, ChevronDown, Minus, Maximize2, X, Grid3x3, Star, Download, HelpCircle, AlertTriangle, Clock, KeyRound, Fingerprint, Folder as FolderIcon, FolderPlus, MoreHorizontal, Edit3, Trash2, ChevronRight } from 'lucide-react';
 import { version as appVersion } from '../package.json';
-import { CATEGORY_NAMES, CATEGORY_OPTIONS, DEBOUNCE_DELAY, AUTO_LOCK_TIMEOUT, TOAST_DURATION } from './constants';
-import { validateUrl } from './utils';
-import type { PasswordEntry, ToastMessage, ConfirmDialog, BankCardData, DocumentData, AddressData, PasskeyData, Folder } from './types';
+import { CATEGORY_NAMES, CATEGORY_OPTIONS, DEBOUNCE_DELAY, ACTIVITY_REPORT_INTERVAL, TOAST_DURATION } from './constants';
+import { entryFields, toEntryData, validateUrl } from './utils';
+import type { PasswordEntry, ToastMessage, ConfirmDialog, BankCardData, DocumentData, AddressData, PasskeyData, Folder, PairingRequest } from './types';
 import EntryCard from './components/EntryCard';
 import Settings from './components/Settings';
 import PasswordGeneratorModal from './components/PasswordGeneratorModal';
@@ -15,6 +15,9 @@ import ActivityLogModal from './components/ActivityLogModal';
 import AuthenticatorView from './components/AuthenticatorView';
 import AddAuthenticatorModal from './components/AddAuthenticatorModal';
 import PasskeysView from './components/PasskeysView';
+import TrashView from './components/TrashView';
+import VaultIntegrityModal from './components/VaultIntegrityModal';
+import PairingRequestModal from './components/PairingRequestModal';
 import Dashboard from './components/Dashboard';
 import SecurityCheckPage from './components/SecurityCheckPage';
 import { usePasswordSecurity } from './hooks/usePasswordSecurity';
@@ -163,20 +166,23 @@ function App() {
   const [editingEntry, setEditingEntry] = useState<PasswordEntry | null>(null);
   const [totpModal, setTotpModal] = useState<{ secret: string; issuer?: string; account?: string } | null>(null);
   const [showActivityLog, setShowActivityLog] = useState(false);
-  const autoLockTimerRef = useRef<ReturnType<typeof setTimeout> | null>(null);
   const [toast, setToast] = useState<ToastMessage | null>(null);
   const [unlockError, setUnlockError] = useState(false);
+  const [lockoutSeconds, setLockoutSeconds] = useState(0);
   const [confirmDialog, setConfirmDialog] = useState<ConfirmDialog | null>(null);
   const [displayTitle, setDisplayTitle] = useState('');
   const [displaySubtitle, setDisplaySubtitle] = useState('');
   const [showAddDropdown, setShowAddDropdown] = useState(false);
   const [viewMode, setViewMode] = useState<'all' | 'favorites' | 'entries'>('all');
   const [favorites, setFavorites] = useState<Set<string>>(new Set());
-  const [currentPage, setCurrentPage] = useState<'home' | 'settings' | 'password-check' | 'authenticator' | 'passkeys'>('home');
+  const [currentPage, setCurrentPage] = useState<'home' | 'settings' | 'password-check' | 'authenticator' | 'passkeys' | 'trash'>('home');
   const [showAddAuthenticator, setShowAddAuthenticator] = useState(false);
   const [showAddPasskey, setShowAddPasskey] = useState(false);
     const [showForgotPasswordModal, setShowForgotPasswordModal] = useState(false);
+  const [vaultDamaged, setVaultDamaged] = useState(false);
+  const [showIntegrityModal, setShowIntegrityModal] = useState(false);
   const [detectedPasskey, setDetectedPasskey] = useState<{ rpId: string; userName: string; userDisplayName: string } | null>(null);
+  const [pairingRequests, setPairingRequests] = useState<PairingRequest[]>([]);
 
   // Folder system state
   const [folders, setFolders] = useState<Folder[]>([]);
@@ -195,7 +201,11 @@ function App() {
     setTimeout(() => setToast(null), TOAST_DURATION);
   }, []);
 
-  const { passwordSecurity } = usePasswordSecurity(entries, vaultLocked);
+  // Trashed entries are only shown in the trash views
+  const activeEntries = useMemo(() => entries.filter(e => !e.deleted_at), [entries]);
+  const trashCount = entries.length - activeEntries.length;
+
+  const { passwordSecurity } = usePasswordSecurity(activeEntries, vaultLocked);
   const { available: biometricAvailable, checkAvailability } = useBiometric();
   const { updateInfo } = useUpdateCheck();
 
@@ -364,12 +374,12 @@ function App() {
     if (selectedEntries.size === 0) return;
 
     setConfirmDialog({
-      message: `${selectedEntries.size} kaydı silmek istediğinize emin misiniz?`,
+      message: `${selectedEntries.size} kaydı çöp kutusuna taşımak istediğinize emin misiniz?`,
       onConfirm: async () => {
         try {
           const ids = Array.from(selectedEntries);
           const deleted = await invoke<number>('bulk_delete_entries', { ids });
-          showToast(`${deleted} kayıt silindi`, 'success');
+          showToast(`${deleted} kayıt çöp kutusuna taşındı`, 'success');
           setSelectedEntries(new Set());
           setSelectionMode(false);
           loadEntries();
@@ -554,7 +564,33 @@ function App() {
     }
   }, [showToast]);
 
+  const refreshLockout = useCallback(async () => {
+    try {
+      const status = await invoke<{ remaining_seconds: number }>('get_unlock_lockout');
+      setLockoutSeconds(status.remaining_seconds);
+    } catch (error) {
+      console.error('Lockout status error:', error);
+    }
+  }, []);
+
+  useEffect(() => {
+    if (vaultLocked) {
+      refreshLockout();
+    }
+  }, [vaultLocked, refreshLockout]);
+
+  useEffect(() => {
+    if (lockoutSeconds <= 0) {
+      return;
+    }
+    const timer = setTimeout(() => setLockoutSeconds(seconds => seconds - 1), 1000);
+    return () => clearTimeout(timer);
+  }, [lockoutSeconds]);
+
   const handleUnlock = useCallback(async () => {
+    if (lockoutSeconds > 0) {
+      return;
+    }
     if (!masterPassword.trim()) {
       showToast('Lütfen ana şifrenizi girin', 'error');
       setUnlockError(true);
@@ -583,13 +619,14 @@ function App() {
         errorMessage = 'Vault dosyası bulunamadı. Lütfen ilk kurulumu yapın.';
         setUnlockError(true);
       } else if (errorStr.includes('Vault yüklenemedi')) {
-        errorMessage = 'Vault yüklenemedi. Dosya bozuk olabilir.';
+        errorMessage = 'Vault yüklenemedi. Dosya bozuk olabilir; kasayı doğrulayıp onarabilirsiniz.';
+        setVaultDamaged(true);
         setUnlockError(true);
       } else if (errorStr.includes('Decrypt hatası')) {
         errorMessage = 'Yanlış ana şifre veya bozuk veri';
         setUnlockError(true);
       } else if (errorStr.includes('Çok fazla deneme')) {
-        errorMessage = 'Çok fazla deneme. Lütfen bekleyin.';
+        errorMessage = errorStr;
         setUnlockError(true);
       } else if (errorStr) {
         errorMessage = errorStr;
@@ -598,8 +635,9 @@ function App() {
 
       showToast(errorMessage, 'error');
       setUnlockError(true);
+      await refreshLockout();
     }
-  }, [masterPassword, loadEntries, loadFolders, showToast]);
+  }, [masterPassword, lockoutSeconds, loadEntries, loadFolders, refreshLockout, showToast]);
 
   useEffect(() => {
     checkVaultStatus();
@@ -654,40 +692,75 @@ function App() {
 
   useEffect(() => {
     if (vaultLocked) {
-      if (autoLockTimerRef.current) {
-        clearTimeout(autoLockTimerRef.current);
-        autoLockTimerRef.current = null;
-      }
       return;
     }
-    
+
+    // The backend owns the idle timer; just tell it the user is still here
+    let lastReport = 0;
     const handleActivity = () => {
-      if (autoLockTimerRef.current) {
-        clearTimeout(autoLockTimerRef.current);
+      const now = Date.now();
+      if (now - lastReport < ACTIVITY_REPORT_INTERVAL) {
+        return;
       }
-      
-      autoLockTimerRef.current = setTimeout(() => {
-        handleLock();
-      }, AUTO_LOCK_TIMEOUT);
+      lastReport = now;
+      invoke('record_user_activity').catch((error) => {
+        console.error('Activity report failed:', error);
+      });
     };
-    
-    handleActivity();
-    
+
     const events = ['mousedown', 'mousemove', 'keypress', 'scroll', 'touchstart'];
     events.forEach(event => {
       document.addEventListener(event, handleActivity, true);
     });
-    
+
     return () => {
       events.forEach(event => {
         document.removeEventListener(event, handleActivity, true);
       });
-      if (autoLockTimerRef.current) {
-        clearTimeout(autoLockTimerRef.current);
-        autoLockTimerRef.current = null;
-      }
     };
-  }, [vaultLocked, handleLock]);
+  }, [vaultLocked]);
+
+  useEffect(() => {
+    const lockMessages: Record<string, string> = {
+      'idle': 'Kasa hareketsizlik nedeniyle kilitlendi',
+      'sleep': 'Kasa uyku modu nedeniyle kilitlendi',
+      'screen_lock': 'Kasa ekran kilidi nedeniyle kilitlendi'
+    };
+
+    const unlistenPromise = listen<{ reason: string }>('vault-locked', (event) => {
+      setVaultLocked(true);
+      setEntries([]);
+      setFolders([]);
+      setSelectedFolder(null);
+      setMasterPassword('');
+      showToast(lockMessages[event.payload.reason] || 'Kasa kilitlendi', 'info');
+    });
+
+    return () => {
+      unlistenPromise.then(unlisten => unlisten());
+    };
+  }, [showToast]);
+
+  // Browser extensions asking for access; approving needs an unlocked vault
+  useEffect(() => {
+    if (vaultLocked) {
+      return;
+    }
+
+    invoke<PairingRequest[]>('list_pairing_requests')
+      .then(setPairingRequests)
+      .catch(error => console.error('Pairing requests could not be loaded:', error));
+
+    const unlistenPromise = listen<PairingRequest>('api-pairing-request', (event) => {
+      setPairingRequests(current =>
+        current.some(request => request.id === event.payload.id) ? current : [...current, event.payload]
+      );
+    });
+
+    return () => {
+      unlistenPromise.then(unlisten => unlisten());
+    };
+  }, [vaultLocked]);
 
   const filteredEntries = useMemo(() => {
     // Helper function to detect if entry has TOTP data
@@ -710,7 +783,7 @@ function App() {
     };
 
     const query = debouncedSearchQuery.toLowerCase().trim();
-    let filtered = entries.filter(entry => {
+    let filtered = activeEntries.filter(entry => {
       const matchesSearch = !query ||
         entry.title.toLowerCase().includes(query) ||
         entry.username.toLowerCase().includes(query) ||
@@ -740,12 +813,12 @@ function App() {
       return matchesSearch && matchesCategory && matchesView && matchesTotpFilter && matchesFolder;
     });
     return filtered;
-  }, [entries, debouncedSearchQuery, selectedCategory, viewMode, favorites, selectedFolder]);
+  }, [activeEntries, debouncedSearchQuery, selectedCategory, viewMode, favorites, selectedFolder]);
 
 
   const categoryCounts = useMemo(() => {
     const counts = {
-      all: entries.length,
+      all: activeEntries.length,
       accounts: 0,
       bank_cards: 0,
       documents: 0,
@@ -755,7 +828,7 @@ function App() {
       authenticator: 0,
     };
 
-    for (const entry of entries) {
+    for (const entry of activeEntries) {
       switch (entry.category) {
         case 'accounts':
           counts.accounts++;
@@ -782,18 +855,18 @@ function App() {
     }
 
     return counts;
-  }, [entries]);
+  }, [activeEntries]);
 
   // Calculate folder entry counts
   const folderCounts = useMemo(() => {
     const counts: Record<string, number> = {};
-    for (const entry of entries) {
+    for (const entry of activeEntries) {
       if (entry.folder_id) {
         counts[entry.folder_id] = (counts[entry.folder_id] || 0) + 1;
       }
     }
     return counts;
-  }, [entries]);
+  }, [activeEntries]);
 
   // Organize folders hierarchically
   const rootFolders = useMemo(() => {
@@ -881,9 +954,9 @@ function App() {
               className={`master-password-input ${unlockError ? 'error' : ''}`}
               autoFocus
             />
-            <button onClick={handleUnlock} className="unlock-button">
+            <button onClick={handleUnlock} className="unlock-button" disabled={lockoutSeconds > 0}>
               <Unlock size={20} />
-              Kasa Aç
+              {lockoutSeconds > 0 ? `${lockoutSeconds} sn bekleyin` : 'Kasa Aç'}
             </button>
             {biometricAvailable && (
               <button 
@@ -919,6 +992,22 @@ function App() {
           >
             Ana parolanızı mı unuttunuz?
           </button>
+          {vaultDamaged && (
+            <button
+              onClick={() => setShowIntegrityModal(true)}
+              style={{
+                background: 'transparent',
+                border: 'none',
+                color: 'var(--accent)',
+                fontSize: '0.85rem',
+                cursor: 'pointer',
+                textDecoration: 'underline',
+                padding: '0.5rem'
+              }}
+            >
+              Kasayı doğrula ve onar
+            </button>
+          )}
         </div>
         {toast && (
           <div className={`toast toast-${toast.type}`}>
@@ -929,9 +1018,33 @@ function App() {
           </div>
         )}
         
+        {showIntegrityModal && (
+          <VaultIntegrityModal
+            onClose={() => setShowIntegrityModal(false)}
+            showToast={showToast}
+            onRepaired={() => setVaultDamaged(false)}
+          />
+        )}
+
         {showForgotPasswordModal && (
           <ForgotPasswordModal
             onClose={() => setShowForgotPasswordModal(false)}
+            onRecover={async (recoveryCode, newMasterPassword) => {
+              try {
+                await invoke<boolean>('unlock_with_recovery_key', { recoveryCode, newMasterPassword });
+                setShowForgotPasswordModal(false);
+                setVaultLocked(false);
+                setMasterPassword('');
+                setUnlockError(false);
+                await loadEntries();
+                await loadFolders();
+                showToast('Kasa açıldı ve yeni ana şifre ayarlandı', 'success');
+              } catch (error) {
+                showToast(String(error || 'Kurtarma anahtarı ile açılamadı'), 'error');
+                console.error('Recovery unlock error:', error);
+                await refreshLockout();
+              }
+            }}
             onReset={async () => {
               try {
                 await invoke('reset_vault');
@@ -1072,6 +1185,14 @@ function App() {
             <span>Kimlik Doğrulayıcı</span>
             {categoryCounts.authenticator > 0 && <span className="nav-count">{categoryCounts.authenticator}</span>}
           </button>
+          <button
+            className={`nav-item ${currentPage === 'trash' ? 'active' : ''}`}
+            onClick={() => setCurrentPage('trash')}
+          >
+            <Trash2 size={18} />
+            <span>Çöp Kutusu</span>
+            {trashCount > 0 && <span className="nav-count">{trashCount}</span>}
+          </button>
           <button className="nav-item" onClick={() => setShowPasswordGenerator(true)}>
             <Key size={18} />
             <span>Parola Oluşturucu</span>
@@ -1275,7 +1396,7 @@ function App() {
           />
         ) : currentPage === 'password-check' ? (
           <SecurityCheckPage
-            entries={entries}
+            entries={activeEntries}
             onBack={() => setCurrentPage('home')}
             onEdit={setEditingEntry}
             showToast={showToast}
@@ -1295,9 +1416,16 @@ function App() {
             loadEntries={loadEntries}
             setConfirmDialog={setConfirmDialog}
           />
+        ) : currentPage === 'trash' ? (
+          <TrashView
+            entries={entries}
+            showToast={showToast}
+            loadEntries={loadEntries}
+            setConfirmDialog={setConfirmDialog}
+          />
         ) : selectedCategory === 'all' && viewMode === 'all' ? (
           <Dashboard
-            entries={entries}
+            entries={activeEntries}
             favorites={favorites}
             passwordSecurity={passwordSecurity}
             onNavigateToCategory={(category) => {
@@ -1606,6 +1734,15 @@ function App() {
       </div>
       </div>
 
+      {pairingRequests.length > 0 && (
+        <PairingRequestModal
+          key={pairingRequests[0].id}
+          request={pairingRequests[0]}
+          onDone={() => setPairingRequests(current => current.slice(1))}
+          showToast={showToast}
+        />
+      )}
+
       {showAddModal && (
         <AddEntryModal
           onClose={() => {
@@ -1759,8 +1896,9 @@ function App() {
                       username: detectedPasskey.userName,
                       password: '',
                       url: url,
-                      notes: JSON.stringify(passkeyData),
+                      notes: null,
                       category: 'passkeys',
+                      data: toEntryData('passkeys', passkeyData),
                     });
                     
                     try {
@@ -2099,9 +2237,10 @@ function AddBankCardModal({ onClose, showToast, folderId }: { onClose: () => voi
         username: cardNumber.replace(/\s/g, ''),
         password: cvv,
         url: null,
-        notes: JSON.stringify(cardData),
+        notes: null,
         category: 'bank_cards',
         folderId: folderId || null,
+        data: toEntryData('bank_cards', cardData),
       });
 
       try {
@@ -2313,9 +2452,10 @@ function AddDocumentModal({ onClose, showToast, folderId }: { onClose: () => voi
         username: documentType || 'Belge',
         password: 'document',
         url: null,
-        notes: JSON.stringify(documentData) + (notes.trim() ? '\n' + notes.trim() : ''),
+        notes: notes.trim() || null,
         category: 'documents',
         folderId: folderId || null,
+        data: toEntryData('documents', documentData),
       });
 
       try {
@@ -2431,9 +2571,10 @@ function AddAddressModal({ onClose, showToast, folderId }: { onClose: () => void
         username: streetAddress.trim(),
         password: '',
         url: null,
-        notes: JSON.stringify(addressData) + (notes.trim() ? '\n' + notes.trim() : ''),
+        notes: notes.trim() || null,
         category: 'addresses',
         folderId: folderId || null,
+        data: toEntryData('addresses', addressData),
       });
 
       try {
@@ -2590,9 +2731,10 @@ function AddPasskeyModal({ onClose, showToast, folderId }: { onClose: () => void
         username: username.trim() || email.trim() || '',
         password: '',
         url: url,
-        notes: JSON.stringify(passkeyData),
+        notes: null,
         category: 'passkeys',
         folderId: folderId,
+        data: toEntryData('passkeys', passkeyData),
       });
       
       try {
@@ -2936,14 +3078,7 @@ function EditAccountModal({ entry, onClose, showToast }: { entry: PasswordEntry;
 }
 
 function EditBankCardModal({ entry, onClose, showToast }: { entry: PasswordEntry; onClose: () => void; showToast: (message: string, type?: 'success' | 'error' | 'info') => void }) {
-  let cardData: BankCardData = {};
-  try {
-    if (entry.notes) {
-      cardData = JSON.parse(entry.notes) as BankCardData;
-    }
-  } catch (e) {
-    cardData = {};
-  }
+  const cardData = entryFields<BankCardData>(entry);
 
   // Parse existing expiry - could be MM/YY or MM/YYYY format
   const parseExpiry = (expiryStr: string | undefined) => {
@@ -3072,8 +3207,9 @@ function EditBankCardModal({ entry, onClose, showToast }: { entry: PasswordEntry
         username: cardNumber.replace(/\s/g, '') !== entry.username ? cardNumber.replace(/\s/g, '') : null,
         password: cvv !== entry.password ? cvv : null,
         url: null,
-        notes: JSON.stringify(newCardData),
+        notes: null,
         category: null,
+        data: toEntryData('bank_cards', newCardData, entry),
       });
 
       try {
@@ -3261,26 +3397,12 @@ function EditBankCardModal({ entry, onClose, showToast }: { entry: PasswordEntry
 }
 
 function EditDocumentModal({ entry, onClose, showToast }: { entry: PasswordEntry; onClose: () => void; showToast: (message: string, type?: 'success' | 'error' | 'info') => void }) {
-  let docData: DocumentData = {};
-  let extraNotes = '';
-  try {
-    if (entry.notes) {
-      const parts = entry.notes.split('\n');
-      if (parts[0].startsWith('{')) {
-        docData = JSON.parse(parts[0]) as DocumentData;
-        extraNotes = parts.slice(1).join('\n');
-      } else {
-        extraNotes = entry.notes;
-      }
-    }
-  } catch (e) {
-    extraNotes = entry.notes || '';
-  }
+  const docData = entryFields<DocumentData>(entry);
 
   const [documentName, setDocumentName] = useState(entry.title);
   const [documentType, setDocumentType] = useState(entry.username !== 'Belge' ? entry.username : docData.documentType || '');
   const [filePath, setFilePath] = useState(docData.filePath || '');
-  const [notes, setNotes] = useState(extraNotes);
+  const [notes, setNotes] = useState(entry.notes || '');
 
   const handleSubmit = async () => {
     if (!documentName.trim()) {
@@ -3300,8 +3422,9 @@ function EditDocumentModal({ entry, onClose, showToast }: { entry: PasswordEntry
         username: documentType !== entry.username ? (documentType || 'Belge') : null,
         password: null,
         url: null,
-        notes: JSON.stringify(documentData) + (notes.trim() ? '\n' + notes.trim() : ''),
+        notes: notes.trim(),
         category: null,
+        data: toEntryData('documents', documentData, entry),
       });
       
       try {
@@ -3382,21 +3505,7 @@ function EditDocumentModal({ entry, onClose, showToast }: { entry: PasswordEntry
 }
 
 function EditAddressModal({ entry, onClose, showToast }: { entry: PasswordEntry; onClose: () => void; showToast: (message: string, type?: 'success' | 'error' | 'info') => void }) {
-  let addressData: AddressData = {};
-  let extraNotes = '';
-  try {
-    if (entry.notes) {
-      const parts = entry.notes.split('\n');
-      if (parts[0].startsWith('{')) {
-        addressData = JSON.parse(parts[0]) as AddressData;
-        extraNotes = parts.slice(1).join('\n');
-      } else {
-        extraNotes = entry.notes;
-      }
-    }
-  } catch (e) {
-    extraNotes = entry.notes || '';
-  }
+  const addressData = entryFields<AddressData>(entry);
 
   const [addressName, setAddressName] = useState(entry.title);
   const [streetAddress, setStreetAddress] = useState(entry.username || addressData.street || '');
@@ -3404,7 +3513,7 @@ function EditAddressModal({ entry, onClose, showToast }: { entry: PasswordEntry;
   const [state, setState] = useState(addressData.state || '');
   const [postalCode, setPostalCode] = useState(entry.password || addressData.postalCode || '');
   const [country, setCountry] = useState(addressData.country || 'Türkiye');
-  const [notes, setNotes] = useState(extraNotes);
+  const [notes, setNotes] = useState(entry.notes || '');
 
   const handleSubmit = async () => {
     if (!addressName.trim() || !streetAddress.trim() || !city.trim()) {
@@ -3427,8 +3536,9 @@ function EditAddressModal({ entry, onClose, showToast }: { entry: PasswordEntry;
         username: streetAddress.trim() !== entry.username ? streetAddress.trim() : null,
         password: '',
         url: null,
-        notes: JSON.stringify(newAddressData) + (notes.trim() ? '\n' + notes.trim() : ''),
+        notes: notes.trim(),
         category: null,
+        data: toEntryData('addresses', newAddressData, entry),
       });
       
       try {
@@ -3540,26 +3650,12 @@ function EditAddressModal({ entry, onClose, showToast }: { entry: PasswordEntry;
 }
 
 function EditPasskeyModal({ entry, onClose, showToast }: { entry: PasswordEntry; onClose: () => void; showToast: (message: string, type?: 'success' | 'error' | 'info') => void }) {
-  let passkeyData: PasskeyData | null = null;
-  let extraNotes = '';
-  try {
-    if (entry.notes) {
-      const parts = entry.notes.split('\n');
-      if (parts[0].startsWith('{')) {
-        passkeyData = JSON.parse(parts[0]) as PasskeyData;
-        extraNotes = parts.slice(1).join('\n');
-      } else {
-        extraNotes = entry.notes;
-      }
-    }
-  } catch (e) {
-    extraNotes = entry.notes || '';
-  }
+  const passkeyData = entryFields<PasskeyData>(entry);
 
   const [serviceName, setServiceName] = useState(entry.title);
-  const [domain, setDomain] = useState(entry.url || passkeyData?.domain || '');
-  const [username, setUsername] = useState(passkeyData?.username || entry.username || '');
-  const [email, setEmail] = useState(passkeyData?.email || '');
+  const [domain, setDomain] = useState(entry.url || passkeyData.domain || '');
+  const [username, setUsername] = useState(passkeyData.username || entry.username || '');
+  const [email, setEmail] = useState(passkeyData.email || '');
   const [isSubmitting, setIsSubmitting] = useState(false);
 
   const handleSubmit = async () => {
@@ -3597,8 +3693,9 @@ function EditPasskeyModal({ entry, onClose, showToast }: { entry: PasswordEntry;
         username: username.trim() || email.trim() || '',
         password: null,
         url: url !== entry.url ? url : null,
-        notes: JSON.stringify(newPasskeyData) + (extraNotes.trim() ? '\n' + extraNotes.trim() : ''),
+        notes: null,
         category: null,
+        data: toEntryData('passkeys', newPasskeyData, entry),
       });
       
       try {
@@ -3766,7 +3863,40 @@ function EditNoteModal({ entry, onClose, showToast }: { entry: PasswordEntry; on
   );
 }
 
-function ForgotPasswordModal({ onClose, onReset }: { onClose: () => void; onReset: () => void }) {
+function ForgotPasswordModal({ onClose, onRecover, onReset }: {
+  onClose: () => void;
+  onRecover: (recoveryCode: string, newMasterPassword: string) => Promise<void>;
+  onReset: () => void;
+}) {
+  const [recoveryCode, setRecoveryCode] = useState('');
+  const [newPassword, setNewPassword] = useState('');
+  const [confirmPassword, setConfirmPassword] = useState('');
+  const [isRecovering, setIsRecovering] = useState(false);
+  const [recoveryError, setRecoveryError] = useState('');
+
+  const handleRecover = async () => {
+    if (!recoveryCode.trim()) {
+      setRecoveryError('Kurtarma anahtarını girin');
+      return;
+    }
+    if (newPassword.length < 8) {
+      setRecoveryError('Yeni ana şifre en az 8 karakter olmalı');
+      return;
+    }
+    if (newPassword !== confirmPassword) {
+      setRecoveryError('Yeni şifreler eşleşmiyor');
+      return;
+    }
+
+    setRecoveryError('');
+    setIsRecovering(true);
+    try {
+      await onRecover(recoveryCode, newPassword);
+    } finally {
+      setIsRecovering(false);
+    }
+  };
+
   return (
     <div className="modal-overlay">
       <div className="modal-content" style={{ maxWidth: '500px', position: 'relative' }}>
@@ -3781,6 +3911,51 @@ function ForgotPasswordModal({ onClose, onReset }: { onClose: () => void; onRese
           </p>
         </div>
 
+        <div style={{ marginBottom: '1.5rem' }}>
+          <p style={{ fontWeight: 600, marginBottom: '0.5rem' }}>Kurtarma anahtarınız var mı?</p>
+          <p style={{ color: 'var(--text-secondary)', fontSize: '0.85rem', marginBottom: '0.75rem' }}>
+            Ayarlar'dan oluşturduğunuz kurtarma anahtarıyla kasayı açabilir ve yeni bir ana şifre belirleyebilirsiniz.
+          </p>
+          <div className="form-group">
+            <input
+              type="text"
+              placeholder="XXXX-XXXX-XXXX-XXXX-XXXX-XXXX-XXXX-XXXX"
+              value={recoveryCode}
+              onChange={(e) => setRecoveryCode(e.target.value)}
+              autoComplete="off"
+              spellCheck={false}
+            />
+          </div>
+          <div className="form-group">
+            <input
+              type="password"
+              placeholder="Yeni ana şifre"
+              value={newPassword}
+              onChange={(e) => setNewPassword(e.target.value)}
+            />
+          </div>
+          <div className="form-group">
+            <input
+              type="password"
+              placeholder="Yeni ana şifre (tekrar)"
+              value={confirmPassword}
+              onChange={(e) => setConfirmPassword(e.target.value)}
+              onKeyDown={(e) => e.key === 'Enter' && handleRecover()}
+            />
+          </div>
+          {recoveryError && (
+            <p style={{ color: '#ff4757', fontSize: '0.85rem', margin: '0 0 0.75rem' }}>{recoveryError}</p>
+          )}
+          <button
+            onClick={handleRecover}
+            className="submit-button"
+            disabled={isRecovering}
+            style={{ width: '100%' }}
+          >
+            {isRecovering ? 'Açılıyor...' : 'Kurtarma Anahtarıyla Aç'}
+          </button>
+        </div>
+
         <div style={{
           background: 'rgba(255, 71, 87, 0.1)',
           border: '1px solid rgba(255, 71, 87, 0.3)',
@@ -1,6 +1,7 @@
 import { useState, useEffect } from 'react';
 import { invoke } from '@tauri-apps/api/core';
 import { X, Shield, Key, User, AlertCircle, CheckCircle } from 'lucide-react';
+import { toEntryData } from '../utils';
 import type { AuthenticatorData } from '../types';
 
 interface AddAuthenticatorModalProps {
@@ -97,8 +98,9 @@ export default function AddAuthenticatorModal({
         username: account.trim(),
         password: cleanedSecret,
         url: '',
-        notes: JSON.stringify(authData),
-        category: 'authenticator'
+        notes: null,
+        category: 'authenticator',
+        data: toEntryData('authenticator', authData)
       });
 
       await loadEntries();
@@ -1,6 +1,7 @@
 import { useState, useEffect, useCallback, useRef } from 'react';
 import { invoke } from '@tauri-apps/api/core';
 import { Plus, Copy, Trash2, Shield, RefreshCw, Clock, Eye, EyeOff, Search, RotateCcw, Trash, FileText, Save, X, ChevronDown, ChevronUp } from 'lucide-react';
+import { entryFields, toEntryData } from '../utils';
 import type { PasswordEntry, AuthenticatorData } from '../types';
 
 interface AuthenticatorViewProps {
@@ -40,16 +41,19 @@ export default function AuthenticatorView({
 
   // Filter authenticator entries and parse data
   const parseAuthenticators = useCallback(async () => {
-    const activeEntries = entries.filter(e => e.category === 'authenticator');
-    const trashEntries = entries.filter(e => e.category === 'authenticator_trash');
+    const activeEntries = entries.filter(e => e.category === 'authenticator' && !e.deleted_at);
+    const trashEntries = entries.filter(e => e.category === 'authenticator' && e.deleted_at);
 
     const parseEntries = async (entryList: PasswordEntry[]): Promise<AuthenticatorItem[]> => {
       const items: AuthenticatorItem[] = [];
       for (const entry of entryList) {
         try {
-          const data: AuthenticatorData = entry.notes
-            ? JSON.parse(entry.notes)
-            : { secret: entry.password, issuer: entry.title, account: entry.username };
+          const data: AuthenticatorData = {
+            secret: entry.password,
+            issuer: entry.title,
+            account: entry.username,
+            ...entryFields<AuthenticatorData>(entry),
+          };
 
           let code = '------';
           try {
@@ -168,10 +172,6 @@ export default function AuthenticatorView({
 
     try {
       const entry = showBackupModal.entry;
-      let notesData: AuthenticatorData = showBackupModal.data;
-      notesData.backupCodes = codes;
-
-      const updatedNotes = JSON.stringify(notesData);
 
       await invoke('update_password_entry', {
         id: entry.id,
@@ -179,8 +179,9 @@ export default function AuthenticatorView({
         username: entry.username,
         password: entry.password,
         url: entry.url || '',
-        notes: updatedNotes,
-        category: entry.category
+        notes: null,
+        category: entry.category,
+        data: toEntryData(entry.category, { backupCodes: codes }, entry)
       });
 
       await loadEntries();
@@ -2,8 +2,8 @@ import { memo, useCallback, useState, useEffect } from 'react';
 import { invoke } from '@tauri-apps/api/core';
 import { openUrl } from '@tauri-apps/plugin-opener';
 import { Edit, Eye, EyeOff, Trash2, Copy, ExternalLink, Star, MoreVertical, Globe, AlertTriangle, Shield, ShieldCheck, CreditCard, KeyRound, MapPin, Building2, Mail, FileText, FolderOpen, Map } from 'lucide-react';
-import { clearClipboard } from '../utils';
-import type { PasswordEntry, TotpData, PasskeyData } from '../types';
+import { clearClipboard, entryFields } from '../utils';
+import type { PasswordEntry, TotpData, PasskeyData, BankCardData, AddressData, DocumentData } from '../types';
 
 interface EntryCardProps {
   entry: PasswordEntry;
@@ -104,7 +104,7 @@ const EntryCard = memo(function EntryCard({
 
   const handleDelete = useCallback(() => {
     setConfirmDialog({
-      message: 'Bu kaydı silmek istediğinize emin misiniz?',
+      message: 'Bu kaydı çöp kutusuna taşımak istediğinize emin misiniz?',
       onConfirm: async () => {
         try {
           await invoke('delete_password_entry', { id: entry.id });
@@ -120,7 +120,7 @@ const EntryCard = memo(function EntryCard({
           }
           
           loadEntries();
-          showToast('Kayıt başarıyla silindi', 'success');
+          showToast('Kayıt çöp kutusuna taşındı', 'success');
           setConfirmDialog(null);
         } catch (error) {
           const errorStr = String(error || '');
@@ -199,13 +199,9 @@ const EntryCard = memo(function EntryCard({
     return `**** **** **** ${last4}`;
   };
 
-  const getCardData = () => {
-    if (entry.category !== 'bank_cards' || !entry.notes) return null;
-    try {
-      return JSON.parse(entry.notes);
-    } catch {
-      return null;
-    }
+  const getCardData = (): Partial<BankCardData> | null => {
+    if (entry.category !== 'bank_cards' || !entry.data) return null;
+    return entryFields<BankCardData>(entry);
   };
 
   const cardData = getCardData();
@@ -229,70 +225,29 @@ const EntryCard = memo(function EntryCard({
     return null;
   };
 
-  const getAddressData = () => {
-    if (entry.category !== 'addresses' || !entry.notes) return null;
-    try {
-      const parts = entry.notes.split('\n');
-      for (const part of parts) {
-        if (part.startsWith('{') && (part.includes('street') || part.includes('city'))) {
-          const data = JSON.parse(part);
-          if (data.street || data.city) {
-            return data;
-          }
-        }
-      }
-    } catch {
-      return null;
-    }
-    return null;
+  const getAddressData = (): Partial<AddressData> | null => {
+    if (entry.category !== 'addresses') return null;
+    const data = entryFields<AddressData>(entry);
+    return data.street || data.city ? data : null;
   };
 
-  const getDocumentData = () => {
-    if (entry.category !== 'documents' || !entry.notes) return null;
-    try {
-      const parts = entry.notes.split('\n');
-      for (const part of parts) {
-        if (part.startsWith('{') && (part.includes('documentType') || part.includes('filePath'))) {
-          const data = JSON.parse(part);
-          if (data.documentType || data.filePath) {
-            return data;
-          }
-        }
-      }
-    } catch {
-      return null;
-    }
-    return null;
+  const getDocumentData = (): Partial<DocumentData> | null => {
+    if (entry.category !== 'documents') return null;
+    const data = entryFields<DocumentData>(entry);
+    return data.documentType || data.filePath ? data : null;
   };
 
+  // Notes are the user's own text; only a TOTP line of an older account is hidden
   const getPlainNotes = () => {
     if (!entry.notes) return null;
-    // Filter out JSON data for ALL categories (including TOTP data)
-    const parts = entry.notes.split('\n');
-    const jsonPart = parts.find(p => p.startsWith('{'));
-    if (jsonPart) {
-      const remainingParts = parts.filter(p => !p.startsWith('{'));
-      return remainingParts.length > 0 ? remainingParts.join('\n') : null;
-    }
-    return entry.notes;
+    const parts = entry.notes.split('\n').filter(p => !(p.startsWith('{') && p.includes('totp')));
+    return parts.length > 0 ? parts.join('\n') : null;
   };
 
-  const getPasskeyData = (): PasskeyData | null => {
-    if (entry.category !== 'passkeys' || !entry.notes) return null;
-    try {
-      const parts = entry.notes.split('\n');
-      for (const part of parts) {
-        if (part.startsWith('{') && (part.includes('username') || part.includes('email') || part.includes('domain'))) {
-          const data = JSON.parse(part);
-          if (data.username || data.email || data.domain) {
-            return data;
-          }
-        }
-      }
-    } catch {
-      return null;
-    }
-    return null;
+  const getPasskeyData = (): Partial<PasskeyData> | null => {
+    if (entry.category !== 'passkeys') return null;
+    const data = entryFields<PasskeyData>(entry);
+    return data.username || data.email || data.domain ? data : null;
   };
 
   const totpData = getTotpData();
@@ -0,0 +1,135 @@
+import { useState } from 'react';
+import { invoke } from '@tauri-apps/api/core';
+import { X, Download, Upload } from 'lucide-react';
+import type { ImportPreview, ImportSummary, MergeStrategy } from '../types';
+
+interface KdbxModalProps {
+  mode: 'import' | 'export';
+  onClose: () => void;
+  showToast: (message: string, type: 'success' | 'error' | 'info') => void;
+  // Import only: receives the dry-run preview and a callback that runs the import
+  onPreview?: (fileName: string, preview: ImportPreview, commit: (strategy: MergeStrategy) => Promise<ImportSummary>) => void;
+}
+
+const fileNameOf = (path: string) => path.split(/[\\/]/).pop() || path;
+
+export default function KdbxModal({ mode, onClose, showToast, onPreview }: KdbxModalProps) {
+  const [path, setPath] = useState('');
+  const [password, setPassword] = useState('');
+  const [passwordConfirm, setPasswordConfirm] = useState('');
+  const [keyfile, setKeyfile] = useState('');
+  const [isWorking, setIsWorking] = useState(false);
+
+  const handleSubmit = async () => {
+    const filePath = path.trim();
+    if (!filePath) {
+      showToast('Lütfen dosya yolunu girin', 'error');
+      return;
+    }
+    if (mode === 'export' && !keyfile.trim() && password.length < 8) {
+      showToast('Şifre en az 8 karakter olmalı', 'error');
+      return;
+    }
+    if (mode === 'export' && password !== passwordConfirm) {
+      showToast('Şifreler eşleşmiyor', 'error');
+      return;
+    }
+
+    const args = { path: filePath, password, keyfile: keyfile.trim() || null };
+    setIsWorking(true);
+    try {
+      if (mode === 'export') {
+        await invoke('export_vault_kdbx', args);
+        showToast('KeePass veritabanı oluşturuldu', 'success');
+        onClose();
+        return;
+      }
+
+      const preview = await invoke<ImportPreview>('import_vault_kdbx', { ...args, dryRun: true });
+      if (preview.entries.length === 0 && preview.warnings.length === 0) {
+        showToast('Veritabanında aktarılacak kayıt yok', 'error');
+        return;
+      }
+      onPreview?.(fileNameOf(filePath), preview, (strategy) =>
+        invoke<ImportSummary>('import_vault_kdbx', { ...args, dryRun: false, strategy })
+      );
+      onClose();
+    } catch (error) {
+      showToast(String(error || 'KeePass işlemi başarısız'), 'error');
+    } finally {
+      setIsWorking(false);
+    }
+  };
+
+  return (
+    <div className="modal-overlay">
+      <div className="modal-content" style={{ maxWidth: '480px', position: 'relative' }}>
+        <button type="button" className="modal-close-btn" onClick={onClose} style={{ position: 'absolute', top: '16px', right: '16px' }}>
+          <X size={20} />
+        </button>
+        <h2>{mode === 'export' ? 'KeePass Olarak Dışa Aktar' : 'KeePass Veritabanını İçe Aktar'}</h2>
+
+        <p style={{ color: 'var(--text-secondary)', fontSize: '0.9rem', marginBottom: '1rem' }}>
+          {mode === 'export'
+            ? 'Kasa, KeePass ve KeePassXC ile açılabilen bir KDBX 4 dosyasına yazılır. Klasörler grup, etiketler etiket, ekler dosya olarak aktarılır. Geçiş anahtarları aktarılmaz.'
+            : 'KDBX 4 veritabanındaki gruplar klasör, ekler dosya ve TOTP alanları doğrulayıcı olarak aktarılır. İçe aktarmadan önce önizleme gösterilir.'}
+        </p>
+
+        <div className="form-group">
+          <label>Dosya yolu</label>
+          <input
+            type="text"
+            placeholder={mode === 'export' ? 'C:\\Users\\...\\kasa.kdbx' : 'KDBX dosyasının tam yolu'}
+            value={path}
+            onChange={(e) => setPath(e.target.value)}
+            autoFocus
+          />
+        </div>
+
+        <div className="form-group">
+          <label>Veritabanı şifresi</label>
+          <input
+            type="password"
+            placeholder={mode === 'export' ? 'En az 8 karakter' : 'Şifre'}
+            value={password}
+            onChange={(e) => setPassword(e.target.value)}
+            onKeyDown={(e) => e.key === 'Enter' && mode === 'import' && handleSubmit()}
+          />
+        </div>
+
+        {mode === 'export' && (
+          <div className="form-group">
+            <input
+              type="password"
+              placeholder="Şifre tekrar"
+              value={passwordConfirm}
+              onChange={(e) => setPasswordConfirm(e.target.value)}
+            />
+          </div>
+        )}
+
+        <div className="form-group">
+          <label>Anahtar dosyası (isteğe bağlı)</label>
+          <input
+            type="text"
+            placeholder="Anahtar dosyasının tam yolu"
+            value={keyfile}
+            onChange={(e) => setKeyfile(e.target.value)}
+          />
+        </div>
+
+        <div className="modal-actions">
+          <button onClick={onClose} className="cancel-button" disabled={isWorking}>
+            İptal
+          </button>
+          <button onClick={handleSubmit} className="submit-button" disabled={isWorking}>
+            {mode === 'export' ? <Download size={16} /> : <Upload size={16} />}
+            {isWorking
+              ? (mode === 'export' ? 'Dışa aktarılıyor...' : 'Okunuyor...')
+              : (mode === 'export' ? 'Dışa Aktar' : 'Devam')}
+          </button>
+        </div>
+      </div>
+    </div>
+  );
+}
@@ -0,0 +1,88 @@
+import { useState } from 'react';
+import { invoke } from '@tauri-apps/api/core';
+import { X, Link2, ShieldCheck } from 'lucide-react';
+import { API_SCOPE_NAMES } from '../constants';
+import type { ApiScope, PairingRequest } from '../types';
+
+interface PairingRequestModalProps {
+  request: PairingRequest;
+  // Called once the request is approved or denied
+  onDone: () => void;
+  showToast: (message: string, type: 'success' | 'error' | 'info') => void;
+}
+
+export default function PairingRequestModal({ request, onDone, showToast }: PairingRequestModalProps) {
+  const [scopes, setScopes] = useState<ApiScope[]>(request.requested_scopes);
+  const [isWorking, setIsWorking] = useState(false);
+
+  const toggleScope = (scope: ApiScope) => {
+    setScopes(current => current.includes(scope) ? current.filter(s => s !== scope) : [...current, scope]);
+  };
+
+  const respond = async (approve: boolean) => {
+    setIsWorking(true);
+    try {
+      if (approve) {
+        await invoke('approve_pairing_request', { requestId: request.id, scopes });
+        showToast(`${request.name} bağlandı`, 'success');
+      } else {
+        await invoke('deny_pairing_request', { requestId: request.id });
+        showToast('Erişim isteği reddedildi', 'info');
+      }
+    } catch (error) {
+      showToast(String(error || 'Eşleştirme başarısız'), 'error');
+    } finally {
+      setIsWorking(false);
+      onDone();
+    }
+  };
+
+  return (
+    <div className="modal-overlay">
+      <div className="modal-content" style={{ maxWidth: '480px', position: 'relative' }}>
+        <button type="button" className="modal-close-btn" onClick={() => respond(false)} disabled={isWorking} style={{ position: 'absolute', top: '16px', right: '16px' }}>
+          <X size={20} />
+        </button>
+        <h2 style={{ display: 'flex', alignItems: 'center', gap: '0.5rem' }}>
+          <Link2 size={22} />
+          Erişim İsteği
+        </h2>
+
+        <p style={{ color: 'var(--text-secondary)', fontSize: '0.9rem', marginBottom: '1rem' }}>
+          <strong>{request.name}</strong> kasanıza erişmek istiyor. Bu isteği siz başlatmadıysanız reddedin.
+          İzinleri daha sonra Ayarlar'dan kaldırabilirsiniz.
+        </p>
+
+        {request.requested_scopes.map(scope => (
+          <label
+            key={scope}
+            style={{ display: 'flex', alignItems: 'flex-start', gap: '0.75rem', padding: '0.5rem 0', cursor: 'pointer' }}
+          >
+            <input
+              type="checkbox"
+              checked={scopes.includes(scope)}
+              onChange={() => toggleScope(scope)}
+              style={{ marginTop: '0.25rem' }}
+            />
+            <span>
+              <span style={{ display: 'block', fontWeight: 500 }}>{API_SCOPE_NAMES[scope]?.label || scope}</span>
+              <span style={{ color: 'var(--text-secondary)', fontSize: '0.85rem' }}>
+                {API_SCOPE_NAMES[scope]?.description}
+              </span>
+            </span>
+          </label>
+        ))}
+
+        <div className="modal-actions">
+          <button onClick={() => respond(false)} className="cancel-button" disabled={isWorking}>
+            Reddet
+          </button>
+          <button onClick={() => respond(true)} className="submit-button" disabled={isWorking || scopes.length === 0}>
+            <ShieldCheck size={16} />
+            İzin Ver
+          </button>
+        </div>
+      </div>
+    </div>
+  );
+}
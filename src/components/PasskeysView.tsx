@@ -1,6 +1,7 @@
 import { useState, useCallback } from 'react';
 import { invoke } from '@tauri-apps/api/core';
 import { Copy, Trash2, KeyRound, Search, RotateCcw, Trash, Globe, User, Mail, HelpCircle, X, Shield, Chrome, ChevronDown, ChevronUp, FileText, Save } from 'lucide-react';
+import { entryFields, toEntryData } from '../utils';
 import type { PasswordEntry, PasskeyData } from '../types';
 
 interface PasskeysViewProps {
@@ -33,29 +34,17 @@ export default function PasskeysView({
 
   // Parse passkey entries
   const parsePasskeys = useCallback((): { active: PasskeyItem[]; trashed: PasskeyItem[] } => {
-    const activeEntries = entries.filter(e => e.category === 'passkeys');
-    const trashEntries = entries.filter(e => e.category === 'passkeys_trash');
+    const activeEntries = entries.filter(e => e.category === 'passkeys' && !e.deleted_at);
+    const trashEntries = entries.filter(e => e.category === 'passkeys' && e.deleted_at);
 
     const parseEntries = (entryList: PasswordEntry[]): PasskeyItem[] => {
       const items: PasskeyItem[] = [];
       for (const entry of entryList) {
         try {
           let data: PasskeyData = { username: entry.username, domain: '' };
-          if (entry.notes) {
-            try {
-              const parts = entry.notes.split('\n');
-              for (const part of parts) {
-                if (part.startsWith('{')) {
-                  const parsed = JSON.parse(part);
-                  if (parsed.username || parsed.email || parsed.domain) {
-                    data = parsed;
-                    break;
-                  }
-                }
-              }
-            } catch {
-              // Use default data
-            }
+          const stored = entryFields<PasskeyData>(entry);
+          if (stored.username || stored.email || stored.domain) {
+            data = stored;
           }
           items.push({ entry, data });
         } catch (err) {
@@ -114,12 +103,7 @@ export default function PasskeysView({
       .filter(c => c.length > 0);
 
     try {
-      // Update the entry notes with backup codes
       const entry = showBackupModal.entry;
-      let notesData: PasskeyData = showBackupModal.data;
-      notesData.backupCodes = codes;
-
-      const updatedNotes = JSON.stringify(notesData);
 
       await invoke('update_password_entry', {
         id: entry.id,
@@ -127,8 +111,9 @@ export default function PasskeysView({
         username: entry.username,
         password: entry.password,
         url: entry.url || '',
-        notes: updatedNotes,
-        category: entry.category
+        notes: null,
+        category: entry.category,
+        data: toEntryData(entry.category, { backupCodes: codes }, entry)
       });
 
       await loadEntries();
@@ -1,11 +1,88 @@
 import { useState, useEffect, useCallback, useRef } from 'react';
 import { invoke } from '@tauri-apps/api/core';
 import { openUrl } from '@tauri-apps/plugin-opener';
-import { ArrowLeft, Power, Lock, Download, Upload, Info, ChevronDown, CheckCircle, RefreshCw, ExternalLink, AlertTriangle, Trash2, Timer } from 'lucide-react';
+import { ArrowLeft, Power, Lock, Download, Upload, Info, ChevronDown, CheckCircle, RefreshCw, ExternalLink, AlertTriangle, Trash2, Timer, History, RotateCcw, ShieldCheck, Link2 } from 'lucide-react';
 import { listen } from '@tauri-apps/api/event';
 import packageJson from '../../package.json';
+import VaultIntegrityModal from './VaultIntegrityModal';
+import KdbxModal from './KdbxModal';
+import { API_SCOPE_NAMES } from '../constants';
+import type { ApiClient, ImportPreview, ImportSummary, MergeStrategy } from '../types';
 import './Settings.css';
 
+type ImportSource = 'confpass' | 'lastpass' | 'bitwarden' | '1password' | 'keepass' | 'browser';
+
+type ImportFormat = 'bitwarden_json' | 'one_password_pux' | 'keepass_xml' | 'lastpass_csv' | 'browser_csv';
+
+const IMPORT_FILE_TYPES: Record<ImportSource, string> = {
+  confpass: '.json,.txt',
+  lastpass: '.csv',
+  bitwarden: '.json,.csv',
+  '1password': '.1pux,.csv',
+  keepass: '.xml',
+  browser: '.csv',
+};
+
+const IMPORT_CATEGORY_LABELS: Record<string, string> = {
+  accounts: 'Hesap',
+  bank_cards: 'Banka kartı',
+  addresses: 'Adres',
+  notes: 'Not',
+  authenticator: 'Doğrulayıcı',
+  documents: 'Belge',
+};
+
+const MERGE_STRATEGY_OPTIONS: { value: MergeStrategy; label: string; description: string }[] = [
+  { value: 'skip', label: 'Atla', description: 'Kasadaki kayıt olduğu gibi kalır' },
+  { value: 'prefer_newer', label: 'Yeni olanı al', description: 'Daha yakın tarihte değişen kayıt kullanılır' },
+  { value: 'overwrite', label: 'Üzerine yaz', description: 'Kasadaki kayıt içe aktarılanla güncellenir' },
+  { value: 'keep_both', label: 'İkisini de tut', description: 'İçe aktarılan kayıt ayrıca eklenir' },
+];
+
+const IMPORT_ACTION_LABELS: Record<string, string> = {
+  kept_both: 'Ayrıca eklendi',
+  updated: 'Güncellendi',
+  skipped: 'Atlandı',
+};
+
+const importSummaryMessage = (summary: ImportSummary) => {
+  const parts = [`${summary.imported} kayıt içe aktarıldı`];
+  if (summary.updated > 0) parts.push(`${summary.updated} kayıt güncellendi`);
+  if (summary.skipped > 0) parts.push(`${summary.skipped} yinelenen kayıt atlandı`);
+  return parts.join(', ');
+};
+
+const attachmentSizeLabel = (megabytes: number) =>
+  megabytes >= 1024 && megabytes % 1024 === 0 ? `${megabytes / 1024} GB` : `${megabytes} MB`;
+
+const formatFileSize = (bytes: number) =>
+  bytes >= 1024 * 1024 ? `${(bytes / (1024 * 1024)).toFixed(1)} MB` : `${(bytes / 1024).toFixed(1)} KB`;
+
+type KdfParams =
+  | { algorithm: 'pbkdf2_sha256'; iterations: number }
+  | { algorithm: 'argon2id'; memory_kib: number; iterations: number; parallelism: number };
+
+type BackupSchedule = 'every_save' | 'daily' | 'off';
+
+interface BackupRetention {
+  recent: number;
+  daily: number;
+  weekly: number;
+}
+
+interface BackupInfo {
+  id: string;
+  created_at: number;
+  size: number;
+  attachment_count: number;
+}
+
+interface OrphanReport {
+  attachment_ids: string[];
+  total_size: number;
+  removed: boolean;
+}
+
 interface SettingsProps {
   onBack: () => void;
   showToast: (message: string, type: 'success' | 'error' | 'info') => void;
@@ -19,6 +96,8 @@ function Settings({ onBack, showToast, onResetComplete }: SettingsProps) {
   const [useBiometric, setUseBiometric] = useState(false);
   const [isBiometricAvailable, setIsBiometricAvailable] = useState(false);
   const [streamProtection, setStreamProtection] = useState(false);
+  const [lockOnSleep, setLockOnSleep] = useState(true);
+  const [lockOnScreenLock, setLockOnScreenLock] = useState(true);
   const [streamingDetected, setStreamingDetected] = useState(false);
   const [detectedApps, setDetectedApps] = useState<string[]>([]);
   const [isLoading, setIsLoading] = useState(false);
@@ -38,9 +117,48 @@ function Settings({ onBack, showToast, onResetComplete }: SettingsProps) {
   const [importPassword, setImportPassword] = useState('');
   const [encryptedFileContent, setEncryptedFileContent] = useState('');
   const [isImporting, setIsImporting] = useState(false);
+  // A dry-run preview waiting for confirmation; commit runs the actual import
+  const [pendingImport, setPendingImport] = useState<{ fileName: string; preview: ImportPreview; commit: (strategy: MergeStrategy) => Promise<ImportSummary> } | null>(null);
+  const [mergeStrategy, setMergeStrategy] = useState<MergeStrategy>('skip');
+  // Shown after an import that found duplicates
+  const [importReport, setImportReport] = useState<ImportSummary | null>(null);
+  const [kdbxMode, setKdbxMode] = useState<'import' | 'export' | null>(null);
   const [passwordRotationTimeout, setPasswordRotationTimeout] = useState(0);
   const [isRotationDropdownOpen, setIsRotationDropdownOpen] = useState(false);
+  const [kdfParams, setKdfParams] = useState<KdfParams | null>(null);
+  const [isCalibrating, setIsCalibrating] = useState(false);
+  const [showChangePasswordDialog, setShowChangePasswordDialog] = useState(false);
+  const [currentPassword, setCurrentPassword] = useState('');
+  const [newPassword, setNewPassword] = useState('');
+  const [newPasswordConfirm, setNewPasswordConfirm] = useState('');
+  const [isChangingPassword, setIsChangingPassword] = useState(false);
+  const [hasRecoveryKey, setHasRecoveryKey] = useState(false);
+  const [recoveryKit, setRecoveryKit] = useState<{ code: string; qr_code: string } | null>(null);
+  const [isGeneratingRecoveryKey, setIsGeneratingRecoveryKey] = useState(false);
   const rotationDropdownRef = useRef<HTMLDivElement>(null);
+  const [wipeAfterFailures, setWipeAfterFailures] = useState(0);
+  const [isWipeDropdownOpen, setIsWipeDropdownOpen] = useState(false);
+  const wipeDropdownRef = useRef<HTMLDivElement>(null);
+  const [trashRetentionDays, setTrashRetentionDays] = useState(30);
+  const [isTrashDropdownOpen, setIsTrashDropdownOpen] = useState(false);
+  const trashDropdownRef = useRef<HTMLDivElement>(null);
+  const [maxAttachmentMb, setMaxAttachmentMb] = useState(100);
+  const [isAttachmentDropdownOpen, setIsAttachmentDropdownOpen] = useState(false);
+  const attachmentDropdownRef = useRef<HTMLDivElement>(null);
+  const [backupSchedule, setBackupSchedule] = useState<BackupSchedule>('every_save');
+  const [backupRetention, setBackupRetention] = useState<BackupRetention>({ recent: 10, daily: 7, weekly: 4 });
+  const [isBackupDropdownOpen, setIsBackupDropdownOpen] = useState(false);
+  const backupDropdownRef = useRef<HTMLDivElement>(null);
+  const [backups, setBackups] = useState<BackupInfo[]>([]);
+  const [isBackingUp, setIsBackingUp] = useState(false);
+  const [restoringBackup, setRestoringBackup] = useState<BackupInfo | null>(null);
+  const [restorePassword, setRestorePassword] = useState('');
+  const [isRestoring, setIsRestoring] = useState(false);
+  const [showIntegrityModal, setShowIntegrityModal] = useState(false);
+  const [orphanReport, setOrphanReport] = useState<OrphanReport | null>(null);
+  const [isCollectingOrphans, setIsCollectingOrphans] = useState(false);
+  const [apiClients, setApiClients] = useState<ApiClient[]>([]);
+  const [tcpApiEnabled, setTcpApiEnabled] = useState(false);
 
   const timeoutOptions = [
     { value: 60, label: '1 dakika' },
@@ -60,10 +178,43 @@ function Settings({ onBack, showToast, onResetComplete }: SettingsProps) {
     { value: 14400, label: '4 saat' },
   ];
 
+  const wipeOptions = [
+    { value: 0, label: 'Devre dışı' },
+    { value: 10, label: '10 deneme' },
+    { value: 20, label: '20 deneme' },
+    { value: 50, label: '50 deneme' },
+  ];
+
+  const trashRetentionOptions = [
+    { value: 7, label: '7 gün' },
+    { value: 30, label: '30 gün' },
+    { value: 90, label: '90 gün' },
+    { value: 365, label: '1 yıl' },
+    { value: 0, label: 'Elle boşaltılana kadar' },
+  ];
+
+  const attachmentSizeOptions = [
+    { value: 100, label: '100 MB' },
+    { value: 250, label: '250 MB' },
+    { value: 500, label: '500 MB' },
+    { value: 1024, label: '1 GB' },
+    { value: 4096, label: '4 GB' },
+  ];
+
+  const backupScheduleOptions: { value: BackupSchedule; label: string }[] = [
+    { value: 'every_save', label: 'Her kayıtta' },
+    { value: 'daily', label: 'Günde bir' },
+    { value: 'off', label: 'Kapalı' },
+  ];
+
   useEffect(() => {
     loadSettings();
     loadStreamProtectionStatus();
     loadPasswordRotation();
+    loadKdfParams();
+    loadRecoveryKeyStatus();
+    loadBackups();
+    loadApiClients();
 
     // Stream protection event listener
     const unlisten = listen<{
@@ -89,6 +240,18 @@ function Settings({ onBack, showToast, onResetComplete }: SettingsProps) {
       if (rotationDropdownRef.current && !rotationDropdownRef.current.contains(event.target as Node)) {
         setIsRotationDropdownOpen(false);
       }
+      if (wipeDropdownRef.current && !wipeDropdownRef.current.contains(event.target as Node)) {
+        setIsWipeDropdownOpen(false);
+      }
+      if (trashDropdownRef.current && !trashDropdownRef.current.contains(event.target as Node)) {
+        setIsTrashDropdownOpen(false);
+      }
+      if (attachmentDropdownRef.current && !attachmentDropdownRef.current.contains(event.target as Node)) {
+        setIsAttachmentDropdownOpen(false);
+      }
+      if (backupDropdownRef.current && !backupDropdownRef.current.contains(event.target as Node)) {
+        setIsBackupDropdownOpen(false);
+      }
     };
 
     document.addEventListener('mousedown', handleClickOutside);
@@ -105,12 +268,28 @@ function Settings({ onBack, showToast, onResetComplete }: SettingsProps) {
         auto_lock_timeout: number;
         use_biometric: boolean;
         stream_protection: boolean;
+        lock_on_sleep: boolean;
+        lock_on_screen_lock: boolean;
+        wipe_after_failures: number;
+        trash_retention_days: number;
+        max_attachment_mb: number;
+        backup_schedule: BackupSchedule;
+        backup_retention: BackupRetention;
+        tcp_api_enabled: boolean;
       }>('get_settings');
       setMinimizeToTray(settings.minimize_to_tray);
       setAutoStart(settings.auto_start);
       setAutoLockTimeout(settings.auto_lock_timeout);
       setUseBiometric(settings.use_biometric);
       setStreamProtection(settings.stream_protection);
+      setLockOnSleep(settings.lock_on_sleep);
+      setLockOnScreenLock(settings.lock_on_screen_lock);
+      setWipeAfterFailures(settings.wipe_after_failures);
+      setTrashRetentionDays(settings.trash_retention_days);
+      setMaxAttachmentMb(settings.max_attachment_mb);
+      setBackupSchedule(settings.backup_schedule);
+      setBackupRetention(settings.backup_retention);
+      setTcpApiEnabled(settings.tcp_api_enabled);
 
       const available = await invoke<boolean>('check_biometric_available');
       console.log('Biometric availability:', available);
@@ -129,6 +308,75 @@ function Settings({ onBack, showToast, onResetComplete }: SettingsProps) {
     }
   }, []);
 
+  const loadKdfParams = useCallback(async () => {
+    try {
+      setKdfParams(await invoke<KdfParams>('get_kdf_params'));
+    } catch (error) {
+      console.error('KDF parametreleri yüklenemedi:', error);
+    }
+  }, []);
+
+  const loadBackups = useCallback(async () => {
+    try {
+      setBackups(await invoke<BackupInfo[]>('list_backups'));
+    } catch (error) {
+      console.error('Yedekler yüklenemedi:', error);
+    }
+  }, []);
+
+  const loadApiClients = useCallback(async () => {
+    try {
+      setApiClients(await invoke<ApiClient[]>('list_api_clients'));
+    } catch (error) {
+      console.error('Bağlı eklentiler yüklenemedi:', error);
+    }
+  }, []);
+
+  const handleRevokeApiClient = useCallback(async (client: ApiClient) => {
+    try {
+      await invoke('revoke_api_client', { clientId: client.id });
+      setApiClients(current => current.filter(c => c.id !== client.id));
+      showToast(`${client.name} erişimi kaldırıldı`, 'success');
+    } catch (error) {
+      showToast('Erişim kaldırılamadı: ' + error, 'error');
+    }
+  }, [showToast]);
+
+  const loadRecoveryKeyStatus = useCallback(async () => {
+    try {
+      setHasRecoveryKey(await invoke<boolean>('has_recovery_key'));
+    } catch (error) {
+      console.error('Kurtarma anahtarı durumu yüklenemedi:', error);
+    }
+  }, []);
+
+  const handleGenerateRecoveryKey = useCallback(async () => {
+    setIsGeneratingRecoveryKey(true);
+    try {
+      setRecoveryKit(await invoke<{ code: string; qr_code: string }>('generate_recovery_key'));
+      setHasRecoveryKey(true);
+    } catch (error) {
+      showToast('Kurtarma anahtarı oluşturulamadı: ' + error, 'error');
+      console.error('Kurtarma anahtarı hatası:', error);
+    } finally {
+      setIsGeneratingRecoveryKey(false);
+    }
+  }, [showToast]);
+
+  const handleCalibrateKdf = useCallback(async () => {
+    setIsCalibrating(true);
+    try {
+      const params = await invoke<KdfParams>('calibrate_kdf', { targetMs: 1000 });
+      setKdfParams(params);
+      showToast('Anahtar türetme bu cihaza göre ayarlandı', 'success');
+    } catch (error) {
+      showToast('Kalibrasyon başarısız: ' + error, 'error');
+      console.error('KDF kalibrasyon hatası:', error);
+    } finally {
+      setIsCalibrating(false);
+    }
+  }, [showToast]);
+
   const handlePasswordRotationTimeout = useCallback(async (timeout: number) => {
     setIsLoading(true);
     try {
@@ -148,6 +396,132 @@ function Settings({ onBack, showToast, onResetComplete }: SettingsProps) {
     }
   }, [showToast]);
 
+  const handleWipeAfterFailures = useCallback(async (attempts: number) => {
+    setIsLoading(true);
+    try {
+      await invoke('set_wipe_after_failures', { attempts });
+      setWipeAfterFailures(attempts);
+      showToast(
+        attempts === 0
+          ? 'Hatalı denemede silme devre dışı bırakıldı'
+          : `Kasa ${attempts} hatalı denemeden sonra silinecek`,
+        'success'
+      );
+    } catch (error) {
+      showToast('Ayarlar kaydedilemedi: ' + error, 'error');
+      console.error('Wipe after failures hatası:', error);
+    } finally {
+      setIsLoading(false);
+    }
+  }, [showToast]);
+
+  const handleTrashRetention = useCallback(async (days: number) => {
+    setIsLoading(true);
+    try {
+      await invoke('set_trash_retention_days', { days });
+      setTrashRetentionDays(days);
+      showToast(
+        days === 0
+          ? 'Çöp kutusu elle boşaltılana kadar saklanacak'
+          : `Çöp kutusundaki kayıtlar ${days} gün sonra silinecek`,
+        'success'
+      );
+    } catch (error) {
+      showToast('Ayarlar kaydedilemedi: ' + error, 'error');
+      console.error('Trash retention hatası:', error);
+    } finally {
+      setIsLoading(false);
+    }
+  }, [showToast]);
+
+  const handleMaxAttachmentSize = useCallback(async (megabytes: number) => {
+    setIsLoading(true);
+    try {
+      await invoke('set_max_attachment_size', { megabytes });
+      setMaxAttachmentMb(megabytes);
+      showToast(`Ekler en fazla ${attachmentSizeLabel(megabytes)} olabilir`, 'success');
+    } catch (error) {
+      showToast('Ayarlar kaydedilemedi: ' + error, 'error');
+      console.error('Attachment size hatası:', error);
+    } finally {
+      setIsLoading(false);
+    }
+  }, [showToast]);
+
+  const handleBackupSchedule = useCallback(async (schedule: BackupSchedule) => {
+    setIsLoading(true);
+    try {
+      await invoke('set_backup_settings', { schedule, retention: backupRetention });
+      setBackupSchedule(schedule);
+      showToast(schedule === 'off' ? 'Otomatik yedekleme kapatıldı' : 'Yedekleme ayarı kaydedildi', 'success');
+      await loadBackups();
+    } catch (error) {
+      showToast('Ayarlar kaydedilemedi: ' + error, 'error');
+      console.error('Backup schedule hatası:', error);
+    } finally {
+      setIsLoading(false);
+    }
+  }, [backupRetention, loadBackups, showToast]);
+
+  const handleCreateBackup = useCallback(async () => {
+    setIsBackingUp(true);
+    try {
+      await invoke<BackupInfo>('create_backup');
+      showToast('Yedek alındı', 'success');
+      await loadBackups();
+    } catch (error) {
+      showToast('Yedek alınamadı: ' + error, 'error');
+    } finally {
+      setIsBackingUp(false);
+    }
+  }, [loadBackups, showToast]);
+
+  // First click scans; once orphans were found the second click removes them
+  const handleCollectOrphans = useCallback(async () => {
+    const remove = orphanReport !== null && orphanReport.attachment_ids.length > 0;
+    setIsCollectingOrphans(true);
+    try {
+      const report = await invoke<OrphanReport>('collect_attachment_garbage', { dryRun: !remove });
+      if (remove) {
+        showToast(`${report.attachment_ids.length} ek dosyası silindi (${formatFileSize(report.total_size)})`, 'success');
+        setOrphanReport(null);
+      } else {
+        setOrphanReport(report);
+        if (report.attachment_ids.length === 0) {
+          showToast('Kullanılmayan ek dosyası bulunamadı', 'info');
+        }
+      }
+    } catch (error) {
+      showToast('Ek dosyaları taranamadı: ' + error, 'error');
+    } finally {
+      setIsCollectingOrphans(false);
+    }
+  }, [orphanReport, showToast]);
+
+  const closeRestoreDialog = useCallback(() => {
+    setRestoringBackup(null);
+    setRestorePassword('');
+  }, []);
+
+  const handleRestoreBackup = useCallback(async () => {
+    if (!restoringBackup) return;
+
+    setIsRestoring(true);
+    try {
+      await invoke('restore_backup', {
+        backupId: restoringBackup.id,
+        masterPassword: restorePassword,
+      });
+      closeRestoreDialog();
+      showToast('Yedek geri yüklendi. Kasayı yedeğin ana şifresiyle açın.', 'success');
+      onResetComplete?.();
+    } catch (error) {
+      showToast('Geri yükleme başarısız: ' + error, 'error');
+    } finally {
+      setIsRestoring(false);
+    }
+  }, [restoringBackup, restorePassword, closeRestoreDialog, showToast, onResetComplete]);
+
   const handleUseBiometric = useCallback(async (enabled: boolean) => {
     setIsLoading(true);
     try {
@@ -227,6 +601,48 @@ function Settings({ onBack, showToast, onResetComplete }: SettingsProps) {
     }
   }, [showToast]);
 
+  const handleLockOnSleep = useCallback(async (enabled: boolean) => {
+    setIsLoading(true);
+    try {
+      await invoke('set_lock_on_sleep', { enabled });
+      setLockOnSleep(enabled);
+      showToast(enabled ? 'Uyku modunda kilitleme etkinleştirildi' : 'Uyku modunda kilitleme devre dışı bırakıldı', 'success');
+    } catch (error) {
+      showToast('Ayarlar kaydedilemedi', 'error');
+      console.error('Lock on sleep hatası:', error);
+    } finally {
+      setIsLoading(false);
+    }
+  }, [showToast]);
+
+  const handleTcpApi = useCallback(async (enabled: boolean) => {
+    setIsLoading(true);
+    try {
+      await invoke('set_tcp_api_enabled', { enabled });
+      setTcpApiEnabled(enabled);
+      showToast('Uygulama yeniden başlatıldığında geçerli olacak', 'info');
+    } catch (error) {
+      showToast('Ayarlar kaydedilemedi', 'error');
+      console.error('TCP API hatası:', error);
+    } finally {
+      setIsLoading(false);
+    }
+  }, [showToast]);
+
+  const handleLockOnScreenLock = useCallback(async (enabled: boolean) => {
+    setIsLoading(true);
+    try {
+      await invoke('set_lock_on_screen_lock', { enabled });
+      setLockOnScreenLock(enabled);
+      showToast(enabled ? 'Ekran kilidinde kilitleme etkinleştirildi' : 'Ekran kilidinde kilitleme devre dışı bırakıldı', 'success');
+    } catch (error) {
+      showToast('Ayarlar kaydedilemedi', 'error');
+      console.error('Lock on screen lock hatası:', error);
+    } finally {
+      setIsLoading(false);
+    }
+  }, [showToast]);
+
   const handleAutoLockTimeout = useCallback(async (timeout: number) => {
     setIsLoading(true);
     try {
@@ -295,6 +711,13 @@ function Settings({ onBack, showToast, onResetComplete }: SettingsProps) {
     }
   }, [exportPassword, exportPasswordConfirm, showToast]);
 
+  const showImportResult = useCallback((summary: ImportSummary) => {
+    showToast(importSummaryMessage(summary), 'success');
+    if (summary.changes.some(change => change.action !== 'added')) {
+      setImportReport(summary);
+    }
+  }, [showToast]);
+
   const handleImportEncryptedFile = useCallback(() => {
     const input = document.createElement('input');
     input.type = 'file';
@@ -307,14 +730,14 @@ function Settings({ onBack, showToast, onResetComplete }: SettingsProps) {
         const text = await file.text();
         // Check if it's an encrypted file
         const parsed = JSON.parse(text);
-        if (parsed.format === 'confpass_encrypted_v1') {
+        if (parsed.format === 'confpass_encrypted_v1' || parsed.format === 'confpass_encrypted_v2') {
           setEncryptedFileContent(text);
           setShowEncryptedImportModal(true);
           setShowImportModal(false);
         } else {
           // Regular import
-          const count = await invoke<number>('import_vault', { jsonData: text });
-          showToast(`${count} kayıt başarıyla içe aktarıldı`, 'success');
+          const summary = await invoke<ImportSummary>('import_vault', { jsonData: text });
+          showImportResult(summary);
           setShowImportModal(false);
         }
       } catch (error) {
@@ -323,7 +746,7 @@ function Settings({ onBack, showToast, onResetComplete }: SettingsProps) {
       }
     };
     input.click();
-  }, [showToast]);
+  }, [showImportResult, showToast]);
 
   const handleImportEncrypted = useCallback(async () => {
     if (!importPassword) {
@@ -333,11 +756,11 @@ function Settings({ onBack, showToast, onResetComplete }: SettingsProps) {
 
     setIsImporting(true);
     try {
-      const count = await invoke<number>('import_vault_encrypted', {
+      const summary = await invoke<ImportSummary>('import_vault_encrypted', {
         encryptedJson: encryptedFileContent,
         importPassword
       });
-      showToast(`${count} kayıt başarıyla içe aktarıldı`, 'success');
+      showImportResult(summary);
       setShowEncryptedImportModal(false);
       setImportPassword('');
       setEncryptedFileContent('');
@@ -347,7 +770,7 @@ function Settings({ onBack, showToast, onResetComplete }: SettingsProps) {
     } finally {
       setIsImporting(false);
     }
-  }, [importPassword, encryptedFileContent, showToast]);
+  }, [importPassword, encryptedFileContent, showImportResult, showToast]);
 
   // CSV Parser - handles quoted fields and commas within quotes
   const parseCSV = (text: string): string[][] => {
@@ -384,50 +807,6 @@ function Settings({ onBack, showToast, onResetComplete }: SettingsProps) {
     return rows;
   };
 
-  // LastPass CSV Parser
-  const parseLastPassCSV = (text: string) => {
-    const rows = parseCSV(text);
-    if (rows.length < 2) return [];
-
-    const headers = rows[0].map(h => h.toLowerCase());
-    const urlIdx = headers.indexOf('url');
-    const usernameIdx = headers.indexOf('username');
-    const passwordIdx = headers.indexOf('password');
-    const totpIdx = headers.indexOf('totp');
-    const extraIdx = headers.indexOf('extra');
-    const nameIdx = headers.indexOf('name');
-
-    const entries: any[] = [];
-
-    for (let i = 1; i < rows.length; i++) {
-      const row = rows[i];
-      if (row.length < 3) continue;
-
-      const name = nameIdx >= 0 ? row[nameIdx] : '';
-      const url = urlIdx >= 0 ? row[urlIdx] : '';
-      const username = usernameIdx >= 0 ? row[usernameIdx] : '';
-      const password = passwordIdx >= 0 ? row[passwordIdx] : '';
-      const notes = extraIdx >= 0 ? row[extraIdx] : '';
-      const totp = totpIdx >= 0 ? row[totpIdx] : '';
-
-      if (!password && !username) continue;
-
-      entries.push({
-        id: `entry_${crypto.randomUUID()}`,
-        created_at: Math.floor(Date.now() / 1000),
-        updated_at: Math.floor(Date.now() / 1000),
-        category: 'accounts',
-        title: name || (url ? new URL(url).hostname : 'Unnamed'),
-        url: url || '',
-        username: username || '',
-        password: password || '',
-        notes: notes || '',
-        totp_secret: totp || undefined,
-      });
-    }
-    return entries;
-  };
-
   // Bitwarden CSV Parser
   const parseBitwardenCSV = (text: string) => {
     const rows = parseCSV(text);
@@ -569,15 +948,40 @@ function Settings({ onBack, showToast, onResetComplete }: SettingsProps) {
     return entries;
   };
 
-  const handleImportFile = useCallback((source: 'confpass' | 'lastpass' | 'bitwarden' | '1password') => {
+  const handleImportFile = useCallback((source: ImportSource) => {
     const input = document.createElement('input');
     input.type = 'file';
-    input.accept = source === 'confpass' ? '.json,.txt' : '.csv';
+    input.accept = IMPORT_FILE_TYPES[source];
     input.onchange = async (e) => {
       const file = (e.target as HTMLInputElement).files?.[0];
       if (!file) return;
 
       try {
+        // Formats the backend parses get a dry run first; the user confirms the preview
+        const fileName = file.name.toLowerCase();
+        const format: ImportFormat | null =
+          source === 'lastpass' ? 'lastpass_csv'
+            : source === 'keepass' ? 'keepass_xml'
+              : source === 'browser' ? 'browser_csv'
+                : source === 'bitwarden' && fileName.endsWith('.json') ? 'bitwarden_json'
+                  : source === '1password' && fileName.endsWith('.1pux') ? 'one_password_pux'
+                    : null;
+        if (format) {
+          const data = Array.from(new Uint8Array(await file.arrayBuffer()));
+          const preview = await invoke<ImportPreview>('import_entries', { format, data, dryRun: true });
+          if (preview.entries.length === 0 && preview.warnings.length === 0) {
+            showToast('Geçerli bir veri bulunamadı veya format yanlış', 'error');
+            return;
+          }
+          setPendingImport({
+            fileName: file.name,
+            preview,
+            commit: (strategy) => invoke<ImportSummary>('import_entries', { format, data, dryRun: false, strategy })
+          });
+          setShowImportModal(false);
+          return;
+        }
+
         const text = await file.text();
         let entries: any[] = [];
 
@@ -586,13 +990,11 @@ function Settings({ onBack, showToast, onResetComplete }: SettingsProps) {
             entries = parseTxtVaultData(text);
           } else {
             // JSON format
-            const count = await invoke<number>('import_vault', { jsonData: text });
-            showToast(`${count} kayıt başarıyla içe aktarıldı`, 'success');
+            const summary = await invoke<ImportSummary>('import_vault', { jsonData: text });
+            showImportResult(summary);
             setShowImportModal(false);
             return;
           }
-        } else if (source === 'lastpass') {
-          entries = parseLastPassCSV(text);
         } else if (source === 'bitwarden') {
           entries = parseBitwardenCSV(text);
         } else if (source === '1password') {
@@ -605,8 +1007,8 @@ function Settings({ onBack, showToast, onResetComplete }: SettingsProps) {
         }
 
         const jsonData = JSON.stringify({ entries });
-        const count = await invoke<number>('import_vault', { jsonData });
-        showToast(`${count} kayıt başarıyla içe aktarıldı`, 'success');
+        const summary = await invoke<ImportSummary>('import_vault', { jsonData });
+        showImportResult(summary);
         setShowImportModal(false);
       } catch (error) {
         const errorStr = String(error || '');
@@ -614,7 +1016,23 @@ function Settings({ onBack, showToast, onResetComplete }: SettingsProps) {
       }
     };
     input.click();
-  }, [showToast]);
+  }, [showImportResult, showToast]);
+
+  const handleConfirmImport = useCallback(async () => {
+    if (!pendingImport) return;
+
+    setIsImporting(true);
+    try {
+      const summary = await pendingImport.commit(mergeStrategy);
+      showImportResult(summary);
+      setPendingImport(null);
+    } catch (error) {
+      const errorStr = String(error || '');
+      showToast(errorStr || 'İçe aktarma hatası', 'error');
+    } finally {
+      setIsImporting(false);
+    }
+  }, [pendingImport, mergeStrategy, showImportResult, showToast]);
 
   const handleImport = useCallback(() => {
     setShowImportModal(true);
@@ -648,6 +1066,50 @@ function Settings({ onBack, showToast, onResetComplete }: SettingsProps) {
     }
   }, [resetPassword, showToast, onResetComplete]);
 
+  const closeChangePasswordDialog = useCallback(() => {
+    setShowChangePasswordDialog(false);
+    setCurrentPassword('');
+    setNewPassword('');
+    setNewPasswordConfirm('');
+  }, []);
+
+  const handleChangeMasterPassword = useCallback(async () => {
+    if (newPassword.length < 8) {
+      showToast('Yeni ana şifre en az 8 karakter olmalıdır', 'error');
+      return;
+    }
+    if (newPassword !== newPasswordConfirm) {
+      showToast('Yeni şifreler eşleşmiyor', 'error');
+      return;
+    }
+
+    setIsChangingPassword(true);
+    try {
+      await invoke('change_master_password', {
+        oldPassword: currentPassword,
+        newPassword,
+      });
+      showToast('Ana şifre değiştirildi', 'success');
+      closeChangePasswordDialog();
+    } catch (error) {
+      showToast(String(error || '') || 'Ana şifre değiştirilemedi', 'error');
+    } finally {
+      setIsChangingPassword(false);
+    }
+  }, [currentPassword, newPassword, newPasswordConfirm, showToast, closeChangePasswordDialog]);
+
+  const changePasswordInputStyle = {
+    width: '100%',
+    padding: '0.75rem 1rem',
+    borderRadius: '8px',
+    border: '1px solid var(--border)',
+    background: 'var(--bg-tertiary)',
+    color: 'var(--text-primary)',
+    fontSize: '1rem',
+    boxSizing: 'border-box' as const,
+    marginBottom: '0.75rem'
+  };
+
   return (
     <div className="settings-page">
       <div className="settings-header">
@@ -740,6 +1202,38 @@ function Settings({ onBack, showToast, onResetComplete }: SettingsProps) {
             </div>
           </div>
 
+          <div className="settings-item">
+            <div className="settings-item-info">
+              <h3>Uyku Modunda Kilitle</h3>
+              <p>Bilgisayar uykudan uyandığında kasayı kilitle</p>
+            </div>
+            <label className="toggle-switch">
+              <input
+                type="checkbox"
+                checked={lockOnSleep}
+                onChange={(e) => handleLockOnSleep(e.target.checked)}
+                disabled={isLoading}
+              />
+              <span className="toggle-slider"></span>
+            </label>
+          </div>
+
+          <div className="settings-item">
+            <div className="settings-item-info">
+              <h3>Ekran Kilitlendiğinde Kilitle</h3>
+              <p>İşletim sistemi ekranı kilitlendiğinde kasayı kilitle (Windows ve Linux)</p>
+            </div>
+            <label className="toggle-switch">
+              <input
+                type="checkbox"
+                checked={lockOnScreenLock}
+                onChange={(e) => handleLockOnScreenLock(e.target.checked)}
+                disabled={isLoading}
+              />
+              <span className="toggle-slider"></span>
+            </label>
+          </div>
+
           <div className="settings-item">
             <div className="settings-item-info">
               <h3>Windows Hello</h3>
@@ -850,35 +1344,368 @@ function Settings({ onBack, showToast, onResetComplete }: SettingsProps) {
               )}
             </div>
           </div>
-        </div>
-
-        <div className="settings-section">
-          <h2 className="settings-section-title">
-            <Download size={20} />
-            Veri Yönetimi
-          </h2>
-
-          <div className="settings-item">
-            <div className="settings-item-info">
-              <h3>Kasayı Dışa Aktar</h3>
-              <p>Tüm şifrelerinizi JSON formatında dışa aktarın</p>
-            </div>
-            <button className="settings-action-button" onClick={handleExport}>
-              <Download size={18} />
-              Dışa Aktar
-            </button>
-          </div>
 
           <div className="settings-item">
             <div className="settings-item-info">
-              <h3>Kasayı İçe Aktar</h3>
-              <p>ConfPass, LastPass, Bitwarden veya 1Password'dan aktarın</p>
+              <h3>Hatalı Denemede Kasayı Sil</h3>
+              <p>Art arda bu kadar yanlış ana şifre girilirse kasa ve ekler kalıcı olarak silinir</p>
+            </div>
+            <div className="settings-time-selector" ref={wipeDropdownRef}>
+              <button
+                type="button"
+                className="custom-dropdown-button"
+                onClick={() => setIsWipeDropdownOpen(!isWipeDropdownOpen)}
+                disabled={isLoading}
+              >
+                <span>{wipeOptions.find(opt => opt.value === wipeAfterFailures)?.label || 'Devre dışı'}</span>
+                <ChevronDown size={16} className={isWipeDropdownOpen ? 'open' : ''} />
+              </button>
+              {isWipeDropdownOpen && (
+                <div className="custom-dropdown-menu">
+                  {wipeOptions.map(option => (
+                    <button
+                      key={option.value}
+                      type="button"
+                      className={`custom-dropdown-item ${wipeAfterFailures === option.value ? 'selected' : ''}`}
+                      onClick={() => {
+                        handleWipeAfterFailures(option.value);
+                        setIsWipeDropdownOpen(false);
+                      }}
+                    >
+                      {option.label}
+                      {wipeAfterFailures === option.value && <CheckCircle size={16} />}
+                    </button>
+                  ))}
+                </div>
+              )}
+            </div>
+          </div>
+
+          <div className="settings-item">
+            <div className="settings-item-info">
+              <h3>Çöp Kutusu Saklama Süresi</h3>
+              <p>Silinen kayıtlar bu sürenin sonunda çöp kutusundan kalıcı olarak kaldırılır</p>
+            </div>
+            <div className="settings-time-selector" ref={trashDropdownRef}>
+              <button
+                type="button"
+                className="custom-dropdown-button"
+                onClick={() => setIsTrashDropdownOpen(!isTrashDropdownOpen)}
+                disabled={isLoading}
+              >
+                <span>{trashRetentionOptions.find(opt => opt.value === trashRetentionDays)?.label || `${trashRetentionDays} gün`}</span>
+                <ChevronDown size={16} className={isTrashDropdownOpen ? 'open' : ''} />
+              </button>
+              {isTrashDropdownOpen && (
+                <div className="custom-dropdown-menu">
+                  {trashRetentionOptions.map(option => (
+                    <button
+                      key={option.value}
+                      type="button"
+                      className={`custom-dropdown-item ${trashRetentionDays === option.value ? 'selected' : ''}`}
+                      onClick={() => {
+                        handleTrashRetention(option.value);
+                        setIsTrashDropdownOpen(false);
+                      }}
+                    >
+                      {option.label}
+                      {trashRetentionDays === option.value && <CheckCircle size={16} />}
+                    </button>
+                  ))}
+                </div>
+              )}
+            </div>
+          </div>
+
+          <div className="settings-item">
+            <div className="settings-item-info">
+              <h3>Ek Boyutu Sınırı</h3>
+              <p>Kayıtlara eklenebilecek en büyük dosya boyutu</p>
+            </div>
+            <div className="settings-time-selector" ref={attachmentDropdownRef}>
+              <button
+                type="button"
+                className="custom-dropdown-button"
+                onClick={() => setIsAttachmentDropdownOpen(!isAttachmentDropdownOpen)}
+                disabled={isLoading}
+              >
+                <span>{attachmentSizeLabel(maxAttachmentMb)}</span>
+                <ChevronDown size={16} className={isAttachmentDropdownOpen ? 'open' : ''} />
+              </button>
+              {isAttachmentDropdownOpen && (
+                <div className="custom-dropdown-menu">
+                  {attachmentSizeOptions.map(option => (
+                    <button
+                      key={option.value}
+                      type="button"
+                      className={`custom-dropdown-item ${maxAttachmentMb === option.value ? 'selected' : ''}`}
+                      onClick={() => {
+                        handleMaxAttachmentSize(option.value);
+                        setIsAttachmentDropdownOpen(false);
+                      }}
+                    >
+                      {option.label}
+                      {maxAttachmentMb === option.value && <CheckCircle size={16} />}
+                    </button>
+                  ))}
+                </div>
+              )}
+            </div>
+          </div>
+
+          <div className="settings-item">
+            <div className="settings-item-info">
+              <h3>Anahtar Türetme</h3>
+              <p>
+                {kdfParams?.algorithm === 'argon2id'
+                  ? `Argon2id · ${Math.round(kdfParams.memory_kib / 1024)} MiB · ${kdfParams.iterations} tur · ${kdfParams.parallelism} iş parçacığı`
+                  : 'Kasa kilidi açılırken ana şifreden anahtar türetme maliyeti'}
+              </p>
+            </div>
+            <button
+              className="settings-action-button"
+              onClick={handleCalibrateKdf}
+              disabled={isLoading || isCalibrating}
+            >
+              <RefreshCw size={18} />
+              {isCalibrating ? 'Ölçülüyor...' : 'Kalibre Et (~1 sn)'}
+            </button>
+          </div>
+
+          <div className="settings-item">
+            <div className="settings-item-info">
+              <h3>Kasa Bütünlüğü</h3>
+              <p>Kasa dosyasını, klasör ve ek bağlantılarını ve geçiş anahtarlarını kontrol edip onarın</p>
+            </div>
+            <button
+              className="settings-action-button"
+              onClick={() => setShowIntegrityModal(true)}
+              disabled={isLoading}
+            >
+              <ShieldCheck size={18} />
+              Doğrula
+            </button>
+          </div>
+
+          <div className="settings-item">
+            <div className="settings-item-info">
+              <h3>Kullanılmayan Ekler</h3>
+              <p>
+                {orphanReport && orphanReport.attachment_ids.length > 0
+                  ? `Hiçbir kayda ait olmayan ${orphanReport.attachment_ids.length} ek dosyası bulundu (${formatFileSize(orphanReport.total_size)})`
+                  : 'Kalıcı olarak silinen kayıtlardan kalan ek dosyalarını bulup silin. Çöp kutusundaki kayıtların ekleri korunur.'}
+              </p>
+            </div>
+            <button
+              className="settings-action-button"
+              onClick={handleCollectOrphans}
+              disabled={isLoading || isCollectingOrphans}
+            >
+              <Trash2 size={18} />
+              {isCollectingOrphans
+                ? 'Taranıyor...'
+                : orphanReport && orphanReport.attachment_ids.length > 0 ? 'Temizle' : 'Tara'}
+            </button>
+          </div>
+
+          <div className="settings-item">
+            <div className="settings-item-info">
+              <h3>Ana Şifreyi Değiştir</h3>
+              <p>Kasa ve tüm ekler yeni şifreyle yeniden şifrelenir</p>
+            </div>
+            <button
+              className="settings-action-button"
+              onClick={() => setShowChangePasswordDialog(true)}
+              disabled={isLoading}
+            >
+              <Lock size={18} />
+              Değiştir
+            </button>
+          </div>
+
+          <div className="settings-item">
+            <div className="settings-item-info">
+              <h3>Kurtarma Anahtarı</h3>
+              <p>
+                {hasRecoveryKey
+                  ? 'Kurtarma anahtarı oluşturuldu. Yenisi eskisini geçersiz kılar.'
+                  : 'Ana şifrenizi unutursanız kasayı açmak için yazdırılabilir bir kod oluşturun'}
+              </p>
+            </div>
+            <button
+              className="settings-action-button"
+              onClick={handleGenerateRecoveryKey}
+              disabled={isLoading || isGeneratingRecoveryKey}
+            >
+              <Download size={18} />
+              {isGeneratingRecoveryKey ? 'Oluşturuluyor...' : hasRecoveryKey ? 'Yenile' : 'Oluştur'}
+            </button>
+          </div>
+        </div>
+
+        <div className="settings-section">
+          <h2 className="settings-section-title">
+            <Download size={20} />
+            Veri Yönetimi
+          </h2>
+
+          <div className="settings-item">
+            <div className="settings-item-info">
+              <h3>Kasayı Dışa Aktar</h3>
+              <p>Tüm şifrelerinizi JSON formatında dışa aktarın</p>
+            </div>
+            <button className="settings-action-button" onClick={handleExport}>
+              <Download size={18} />
+              Dışa Aktar
+            </button>
+          </div>
+
+          <div className="settings-item">
+            <div className="settings-item-info">
+              <h3>Kasayı İçe Aktar</h3>
+              <p>ConfPass, LastPass, Bitwarden veya 1Password'dan aktarın</p>
             </div>
             <button className="settings-action-button" onClick={handleImport}>
               <Upload size={18} />
               İçe Aktar
             </button>
           </div>
+
+          <div className="settings-item">
+            <div className="settings-item-info">
+              <h3>Otomatik Yedekleme</h3>
+              <p>
+                Şifreli kasa ve ekler kaydedildikçe yedeklenir. Son {backupRetention.recent} yedek ile
+                {' '}{backupRetention.daily} günlük ve {backupRetention.weekly} haftalık yedek saklanır.
+              </p>
+            </div>
+            <div className="settings-time-selector" ref={backupDropdownRef}>
+              <button
+                type="button"
+                className="custom-dropdown-button"
+                onClick={() => setIsBackupDropdownOpen(!isBackupDropdownOpen)}
+                disabled={isLoading}
+              >
+                <span>{backupScheduleOptions.find(opt => opt.value === backupSchedule)?.label || 'Her kayıtta'}</span>
+                <ChevronDown size={16} className={isBackupDropdownOpen ? 'open' : ''} />
+              </button>
+              {isBackupDropdownOpen && (
+                <div className="custom-dropdown-menu">
+                  {backupScheduleOptions.map(option => (
+                    <button
+                      key={option.value}
+                      type="button"
+                      className={`custom-dropdown-item ${backupSchedule === option.value ? 'selected' : ''}`}
+                      onClick={() => {
+                        handleBackupSchedule(option.value);
+                        setIsBackupDropdownOpen(false);
+                      }}
+                    >
+                      {option.label}
+                      {backupSchedule === option.value && <CheckCircle size={16} />}
+                    </button>
+                  ))}
+                </div>
+              )}
+            </div>
+          </div>
+
+          <div className="settings-item">
+            <div className="settings-item-info">
+              <h3>Yedekler</h3>
+              <p>
+                {backups.length === 0
+                  ? 'Henüz yedek yok'
+                  : `${backups.length} yedek · en yenisi ${new Date(backups[0].created_at * 1000).toLocaleString('tr-TR')}`}
+              </p>
+            </div>
+            <button
+              className="settings-action-button"
+              onClick={handleCreateBackup}
+              disabled={isLoading || isBackingUp}
+            >
+              <History size={18} />
+              {isBackingUp ? 'Yedekleniyor...' : 'Şimdi Yedekle'}
+            </button>
+          </div>
+
+          {backups.map(backup => (
+            <div className="settings-item" key={backup.id}>
+              <div className="settings-item-info">
+                <h3>{new Date(backup.created_at * 1000).toLocaleString('tr-TR')}</h3>
+                <p>
+                  {(backup.size / 1024).toFixed(1)} KB
+                  {backup.attachment_count > 0 && ` · ${backup.attachment_count} ek`}
+                </p>
+              </div>
+              <button
+                className="settings-action-button"
+                onClick={() => setRestoringBackup(backup)}
+                disabled={isLoading || isRestoring}
+              >
+                <RotateCcw size={18} />
+                Geri Yükle
+              </button>
+            </div>
+          ))}
+        </div>
+
+        <div className="settings-section">
+          <h2 className="settings-section-title">
+            <Link2 size={20} />
+            Tarayıcı Eklentileri
+          </h2>
+
+          <div className="settings-item">
+            <div className="settings-item-info">
+              <h3>TCP Bağlantısı</h3>
+              <p>
+                Eklenti uygulamaya yerel bir kanal üzerinden bağlanır. Bunu desteklemeyen eski sürümler için
+                127.0.0.1:1421 portunu açar; değişiklik yeniden başlatınca geçerli olur
+              </p>
+            </div>
+            <label className="toggle-switch">
+              <input
+                type="checkbox"
+                checked={tcpApiEnabled}
+                onChange={(e) => handleTcpApi(e.target.checked)}
+                disabled={isLoading}
+              />
+              <span className="toggle-slider"></span>
+            </label>
+          </div>
+
+          {apiClients.length === 0 && (
+            <div className="settings-item">
+              <div className="settings-item-info">
+                <h3>Bağlı eklenti yok</h3>
+                <p>Tarayıcı eklentisi ilk kullanımda erişim ister; isteği onayladığınızda burada listelenir</p>
+              </div>
+            </div>
+          )}
+
+          {apiClients.map(client => (
+            <div className="settings-item" key={client.id}>
+              <div className="settings-item-info">
+                <h3>{client.name}</h3>
+                <p>
+                  {client.scopes.map(scope => API_SCOPE_NAMES[scope]?.label || scope).join(', ')}
+                  {' · '}
+                  {client.last_used_at
+                    ? `son kullanım ${new Date(client.last_used_at * 1000).toLocaleString('tr-TR')}`
+                    : 'henüz kullanılmadı'}
+                </p>
+              </div>
+              <button
+                className="settings-action-button"
+                onClick={() => handleRevokeApiClient(client)}
+                disabled={isLoading}
+              >
+                <Trash2 size={18} />
+                Erişimi Kaldır
+              </button>
+            </div>
+          ))}
         </div>
 
         <div className="settings-section danger-section">
@@ -946,12 +1773,284 @@ function Settings({ onBack, showToast, onResetComplete }: SettingsProps) {
                 }
               }}
             >
-              <ExternalLink size={14} />
-              GitHub Releases
-            </a>
+              <ExternalLink size={14} />
+              GitHub Releases
+            </a>
+          </div>
+        </div>
+      </div>
+
+      {/* Change Master Password Dialog */}
+      {showChangePasswordDialog && (
+        <div className="modal-overlay" style={{
+          position: 'fixed',
+          top: 0,
+          left: 0,
+          right: 0,
+          bottom: 0,
+          background: 'rgba(0, 0, 0, 0.8)',
+          display: 'flex',
+          alignItems: 'center',
+          justifyContent: 'center',
+          zIndex: 1000
+        }}>
+          <div className="modal-content" style={{
+            background: 'var(--bg-secondary)',
+            borderRadius: '16px',
+            padding: '2rem',
+            maxWidth: '450px',
+            width: '90%',
+            border: '1px solid var(--border)'
+          }}>
+            <h2 style={{ margin: '0 0 1.5rem', fontSize: '1.25rem' }}>Ana Şifreyi Değiştir</h2>
+
+            <input
+              type="password"
+              value={currentPassword}
+              onChange={(e) => setCurrentPassword(e.target.value)}
+              placeholder="Mevcut ana şifre"
+              style={changePasswordInputStyle}
+            />
+            <input
+              type="password"
+              value={newPassword}
+              onChange={(e) => setNewPassword(e.target.value)}
+              placeholder="Yeni ana şifre"
+              style={changePasswordInputStyle}
+            />
+            <input
+              type="password"
+              value={newPasswordConfirm}
+              onChange={(e) => setNewPasswordConfirm(e.target.value)}
+              placeholder="Yeni ana şifre (tekrar)"
+              style={{ ...changePasswordInputStyle, marginBottom: '1.5rem' }}
+              onKeyDown={(e) => {
+                if (e.key === 'Enter' && currentPassword && newPassword) {
+                  handleChangeMasterPassword();
+                }
+              }}
+            />
+
+            <div style={{ display: 'flex', gap: '0.75rem' }}>
+              <button
+                onClick={closeChangePasswordDialog}
+                disabled={isChangingPassword}
+                style={{
+                  flex: 1,
+                  padding: '0.75rem',
+                  borderRadius: '8px',
+                  border: '1px solid var(--border)',
+                  background: 'var(--bg-tertiary)',
+                  color: 'var(--text-primary)',
+                  cursor: isChangingPassword ? 'not-allowed' : 'pointer',
+                  fontWeight: 500
+                }}
+              >
+                İptal
+              </button>
+              <button
+                onClick={handleChangeMasterPassword}
+                disabled={isChangingPassword || !currentPassword || !newPassword}
+                style={{
+                  flex: 1,
+                  padding: '0.75rem',
+                  borderRadius: '8px',
+                  border: 'none',
+                  background: 'var(--accent)',
+                  color: 'white',
+                  cursor: isChangingPassword ? 'not-allowed' : 'pointer',
+                  fontWeight: 500,
+                  display: 'flex',
+                  alignItems: 'center',
+                  justifyContent: 'center',
+                  gap: '0.5rem'
+                }}
+              >
+                {isChangingPassword ? (
+                  <>
+                    <RefreshCw size={16} style={{ animation: 'spin 1s linear infinite' }} />
+                    Şifreleniyor...
+                  </>
+                ) : (
+                  'Değiştir'
+                )}
+              </button>
+            </div>
+          </div>
+        </div>
+      )}
+
+      {showIntegrityModal && (
+        <VaultIntegrityModal
+          onClose={() => setShowIntegrityModal(false)}
+          showToast={showToast}
+          onRepaired={() => onResetComplete?.()}
+        />
+      )}
+
+      {kdbxMode && (
+        <KdbxModal
+          mode={kdbxMode}
+          onClose={() => setKdbxMode(null)}
+          showToast={showToast}
+          onPreview={(fileName, preview, commit) => setPendingImport({ fileName, preview, commit })}
+        />
+      )}
+
+      {/* Restore Backup Dialog */}
+      {restoringBackup && (
+        <div className="modal-overlay" style={{
+          position: 'fixed',
+          top: 0,
+          left: 0,
+          right: 0,
+          bottom: 0,
+          background: 'rgba(0, 0, 0, 0.8)',
+          display: 'flex',
+          alignItems: 'center',
+          justifyContent: 'center',
+          zIndex: 1000
+        }}>
+          <div className="modal-content" style={{
+            background: 'var(--bg-secondary)',
+            borderRadius: '16px',
+            padding: '2rem',
+            maxWidth: '450px',
+            width: '90%',
+            border: '1px solid var(--border)'
+          }}>
+            <h2 style={{ margin: '0 0 1rem', fontSize: '1.25rem' }}>Yedeği Geri Yükle</h2>
+            <p style={{ margin: '0 0 1.5rem', color: 'var(--text-secondary)', lineHeight: 1.5 }}>
+              Kasa {new Date(restoringBackup.created_at * 1000).toLocaleString('tr-TR')} tarihindeki
+              haline döner ve kilitlenir. Mevcut kasa önce yedeklenir. Yedeğin alındığı andaki ana
+              şifreyi girin.
+            </p>
+
+            <input
+              type="password"
+              value={restorePassword}
+              onChange={(e) => setRestorePassword(e.target.value)}
+              placeholder="Yedeğin ana şifresi"
+              style={{ ...changePasswordInputStyle, marginBottom: '1.5rem' }}
+              onKeyDown={(e) => {
+                if (e.key === 'Enter' && restorePassword) {
+                  handleRestoreBackup();
+                }
+              }}
+            />
+
+            <div style={{ display: 'flex', gap: '0.75rem' }}>
+              <button
+                onClick={closeRestoreDialog}
+                disabled={isRestoring}
+                style={{
+                  flex: 1,
+                  padding: '0.75rem',
+                  borderRadius: '8px',
+                  border: '1px solid var(--border)',
+                  background: 'var(--bg-tertiary)',
+                  color: 'var(--text-primary)',
+                  cursor: isRestoring ? 'not-allowed' : 'pointer',
+                  fontWeight: 500
+                }}
+              >
+                İptal
+              </button>
+              <button
+                onClick={handleRestoreBackup}
+                disabled={isRestoring || !restorePassword}
+                style={{
+                  flex: 1,
+                  padding: '0.75rem',
+                  borderRadius: '8px',
+                  border: 'none',
+                  background: 'var(--accent)',
+                  color: 'white',
+                  cursor: isRestoring ? 'not-allowed' : 'pointer',
+                  fontWeight: 500,
+                  display: 'flex',
+                  alignItems: 'center',
+                  justifyContent: 'center',
+                  gap: '0.5rem'
+                }}
+              >
+                {isRestoring ? (
+                  <>
+                    <RefreshCw size={16} style={{ animation: 'spin 1s linear infinite' }} />
+                    Doğrulanıyor...
+                  </>
+                ) : (
+                  'Geri Yükle'
+                )}
+              </button>
+            </div>
+          </div>
+        </div>
+      )}
+
+      {/* Recovery Kit Dialog */}
+      {recoveryKit && (
+        <div className="modal-overlay" style={{
+          position: 'fixed',
+          top: 0,
+          left: 0,
+          right: 0,
+          bottom: 0,
+          background: 'rgba(0, 0, 0, 0.8)',
+          display: 'flex',
+          alignItems: 'center',
+          justifyContent: 'center',
+          zIndex: 1000
+        }}>
+          <div className="modal-content" style={{
+            background: 'var(--bg-secondary)',
+            borderRadius: '16px',
+            padding: '2rem',
+            maxWidth: '450px',
+            width: '90%',
+            border: '1px solid var(--border)',
+            textAlign: 'center'
+          }}>
+            <h2 style={{ margin: '0 0 1rem', fontSize: '1.25rem' }}>Kurtarma Anahtarı</h2>
+            <p style={{ color: 'var(--text-secondary)', fontSize: '0.9rem', margin: '0 0 1.25rem' }}>
+              Bu kodu yazdırın veya güvenli bir yere kaydedin. Bir daha gösterilmeyecek.
+            </p>
+            <img
+              src={`data:image/png;base64,${recoveryKit.qr_code}`}
+              alt="Kurtarma anahtarı QR kodu"
+              style={{ width: 200, height: 200, background: 'white', borderRadius: '8px', marginBottom: '1rem' }}
+            />
+            <code style={{
+              display: 'block',
+              padding: '0.75rem',
+              borderRadius: '8px',
+              background: 'var(--bg-tertiary)',
+              fontSize: '0.95rem',
+              letterSpacing: '0.05em',
+              wordBreak: 'break-all',
+              userSelect: 'all',
+              marginBottom: '1.5rem'
+            }}>
+              {recoveryKit.code}
+            </code>
+            <button
+              onClick={() => setRecoveryKit(null)}
+              style={{
+                width: '100%',
+                padding: '0.75rem',
+                borderRadius: '8px',
+                border: 'none',
+                background: 'var(--accent)',
+                color: 'white',
+                cursor: 'pointer',
+                fontWeight: 500
+              }}
+            >
+              Kaydettim
+            </button>
           </div>
         </div>
-      </div>
+      )}
 
       {/* Reset Dialog */}
       {showResetDialog && (
@@ -1337,7 +2436,7 @@ function Settings({ onBack, showToast, onResetComplete }: SettingsProps) {
                 }}>BW</div>
                 <div>
                   <div style={{ fontWeight: 600 }}>Bitwarden</div>
-                  <div style={{ fontSize: '0.85rem', color: 'var(--text-secondary)' }}>CSV dosyası dışa aktarın</div>
+                  <div style={{ fontSize: '0.85rem', color: 'var(--text-secondary)' }}>JSON veya CSV dosyası dışa aktarın</div>
                 </div>
               </button>
 
@@ -1372,7 +2471,113 @@ function Settings({ onBack, showToast, onResetComplete }: SettingsProps) {
                 }}>1P</div>
                 <div>
                   <div style={{ fontWeight: 600 }}>1Password</div>
-                  <div style={{ fontSize: '0.85rem', color: 'var(--text-secondary)' }}>CSV dosyası dışa aktarın</div>
+                  <div style={{ fontSize: '0.85rem', color: 'var(--text-secondary)' }}>1PUX veya CSV dosyası dışa aktarın</div>
+                </div>
+              </button>
+
+              <button
+                onClick={() => handleImportFile('keepass')}
+                style={{
+                  padding: '1rem',
+                  borderRadius: '12px',
+                  border: '1px solid var(--border)',
+                  background: 'var(--bg-tertiary)',
+                  color: 'var(--text-primary)',
+                  cursor: 'pointer',
+                  display: 'flex',
+                  alignItems: 'center',
+                  gap: '1rem',
+                  textAlign: 'left',
+                  transition: 'all 0.2s'
+                }}
+                onMouseOver={(e) => e.currentTarget.style.borderColor = 'var(--accent)'}
+                onMouseOut={(e) => e.currentTarget.style.borderColor = 'var(--border)'}
+              >
+                <div style={{
+                  width: '40px',
+                  height: '40px',
+                  borderRadius: '8px',
+                  background: '#4d8f3a',
+                  display: 'flex',
+                  alignItems: 'center',
+                  justifyContent: 'center',
+                  fontWeight: 'bold',
+                  color: '#fff'
+                }}>KP</div>
+                <div>
+                  <div style={{ fontWeight: 600 }}>KeePass</div>
+                  <div style={{ fontSize: '0.85rem', color: 'var(--text-secondary)' }}>XML dışa aktarımı (KeePass 2, KeePassXC)</div>
+                </div>
+              </button>
+
+              <button
+                onClick={() => { setShowImportModal(false); setKdbxMode('import'); }}
+                style={{
+                  padding: '1rem',
+                  borderRadius: '12px',
+                  border: '1px solid var(--border)',
+                  background: 'var(--bg-tertiary)',
+                  color: 'var(--text-primary)',
+                  cursor: 'pointer',
+                  display: 'flex',
+                  alignItems: 'center',
+                  gap: '1rem',
+                  textAlign: 'left',
+                  transition: 'all 0.2s'
+                }}
+                onMouseOver={(e) => e.currentTarget.style.borderColor = 'var(--accent)'}
+                onMouseOut={(e) => e.currentTarget.style.borderColor = 'var(--border)'}
+              >
+                <div style={{
+                  width: '40px',
+                  height: '40px',
+                  borderRadius: '8px',
+                  background: '#4d8f3a',
+                  display: 'flex',
+                  alignItems: 'center',
+                  justifyContent: 'center',
+                  color: '#fff'
+                }}>
+                  <Lock size={18} />
+                </div>
+                <div>
+                  <div style={{ fontWeight: 600 }}>KeePass Veritabanı</div>
+                  <div style={{ fontSize: '0.85rem', color: 'var(--text-secondary)' }}>.kdbx dosyası (KDBX 4), ekler ve TOTP dahil</div>
+                </div>
+              </button>
+
+              <button
+                onClick={() => handleImportFile('browser')}
+                style={{
+                  padding: '1rem',
+                  borderRadius: '12px',
+                  border: '1px solid var(--border)',
+                  background: 'var(--bg-tertiary)',
+                  color: 'var(--text-primary)',
+                  cursor: 'pointer',
+                  display: 'flex',
+                  alignItems: 'center',
+                  gap: '1rem',
+                  textAlign: 'left',
+                  transition: 'all 0.2s'
+                }}
+                onMouseOver={(e) => e.currentTarget.style.borderColor = 'var(--accent)'}
+                onMouseOut={(e) => e.currentTarget.style.borderColor = 'var(--border)'}
+              >
+                <div style={{
+                  width: '40px',
+                  height: '40px',
+                  borderRadius: '8px',
+                  background: '#4285f4',
+                  display: 'flex',
+                  alignItems: 'center',
+                  justifyContent: 'center',
+                  fontWeight: 'bold',
+                  color: '#fff'
+                }}>WB</div>
+                <div>
+                  <div style={{ fontWeight: 600 }}>Tarayıcı</div>
+                  <div style={{ fontSize: '0.85rem', color: 'var(--text-secondary)' }}>Chrome, Edge, Firefox veya Safari CSV dosyası</div>
                 </div>
               </button>
             </div>
@@ -1385,7 +2590,7 @@ function Settings({ onBack, showToast, onResetComplete }: SettingsProps) {
               fontSize: '0.85rem',
               color: 'var(--text-secondary)'
             }}>
-              <strong style={{ color: 'var(--text-primary)' }}>İpucu:</strong> Diğer uygulamalardan şifrelerinizi CSV formatında dışa aktarın, ardından burada içe aktarın.
+              <strong style={{ color: 'var(--text-primary)' }}>İpucu:</strong> Diğer uygulamalardan şifrelerinizi dışa aktarın, ardından burada içe aktarın. Kayıtlar kasaya eklenmeden önce bir önizleme gösterilir.
             </div>
 
             <button
@@ -1407,6 +2612,214 @@ function Settings({ onBack, showToast, onResetComplete }: SettingsProps) {
         </div>
       )}
 
+      {/* Import Preview Modal */}
+      {pendingImport && (
+        <div className="modal-overlay" style={{
+          position: 'fixed',
+          top: 0,
+          left: 0,
+          right: 0,
+          bottom: 0,
+          background: 'rgba(0, 0, 0, 0.8)',
+          display: 'flex',
+          alignItems: 'center',
+          justifyContent: 'center',
+          zIndex: 1000
+        }}>
+          <div className="modal-content" style={{
+            background: 'var(--bg-secondary)',
+            borderRadius: '16px',
+            padding: '2rem',
+            maxWidth: '560px',
+            width: '90%',
+            border: '1px solid var(--border)'
+          }}>
+            <h2 style={{ margin: '0 0 0.25rem', fontSize: '1.25rem' }}>İçe Aktarma Önizlemesi</h2>
+            <p style={{ margin: '0 0 1rem', color: 'var(--text-secondary)', fontSize: '0.9rem', wordBreak: 'break-all' }}>
+              {pendingImport.fileName}
+            </p>
+
+            <div style={{ display: 'flex', flexWrap: 'wrap', gap: '0.5rem', marginBottom: '1rem' }}>
+              {Object.entries(
+                pendingImport.preview.entries.reduce<Record<string, number>>((counts, entry) => {
+                  counts[entry.category] = (counts[entry.category] || 0) + 1;
+                  return counts;
+                }, {})
+              ).map(([category, count]) => (
+                <span key={category} style={{ padding: '0.25rem 0.75rem', borderRadius: '999px', background: 'var(--bg-tertiary)', fontSize: '0.85rem' }}>
+                  {count} {IMPORT_CATEGORY_LABELS[category] || category}
+                </span>
+              ))}
+            </div>
+
+            <p style={{ margin: '0 0 1rem', fontSize: '0.9rem', color: 'var(--text-secondary)' }}>
+              {pendingImport.preview.entries.length} kayıt, {pendingImport.preview.folders.length} klasör ve {pendingImport.preview.tags.length} etiket bulundu.
+              {' '}Aynı adlı klasör ve etiketler yeniden oluşturulmaz.
+              {pendingImport.preview.entries.some(entry => entry.deleted_at) && ' Arşivlenmiş ve silinmiş kayıtlar çöp kutusuna aktarılır.'}
+              {pendingImport.preview.skipped > 0 && ` ${pendingImport.preview.skipped} satır atlanacak.`}
+            </p>
+
+            {pendingImport.preview.warnings.length > 0 && (
+              <div style={{
+                maxHeight: '220px',
+                overflowY: 'auto',
+                marginBottom: '1.5rem',
+                padding: '0.75rem 1rem',
+                borderRadius: '8px',
+                background: 'var(--bg-tertiary)',
+                fontSize: '0.85rem'
+              }}>
+                {pendingImport.preview.warnings.map((warning, index) => (
+                  <div key={index} style={{ display: 'flex', gap: '0.5rem', alignItems: 'flex-start', marginBottom: '0.4rem' }}>
+                    <AlertTriangle size={14} style={{ color: '#f59e0b', flexShrink: 0, marginTop: '3px' }} />
+                    <span>
+                      <span style={{ color: 'var(--text-secondary)' }}>Satır {warning.row}{warning.title ? ` · ${warning.title}` : ''}:</span> {warning.message}
+                    </span>
+                  </div>
+                ))}
+              </div>
+            )}
+
+            <div style={{ marginBottom: '1.5rem' }}>
+              <div style={{ fontWeight: 600, fontSize: '0.9rem', marginBottom: '0.5rem' }}>Kasada zaten olan kayıtlar</div>
+              <div style={{ display: 'grid', gridTemplateColumns: '1fr 1fr', gap: '0.5rem' }}>
+                {MERGE_STRATEGY_OPTIONS.map(option => (
+                  <button
+                    key={option.value}
+                    onClick={() => setMergeStrategy(option.value)}
+                    disabled={isImporting}
+                    title={option.description}
+                    style={{
+                      padding: '0.5rem 0.75rem',
+                      borderRadius: '8px',
+                      border: `1px solid ${mergeStrategy === option.value ? 'var(--accent)' : 'var(--border)'}`,
+                      background: mergeStrategy === option.value ? 'var(--accent-muted)' : 'var(--bg-tertiary)',
+                      color: 'var(--text-primary)',
+                      cursor: 'pointer',
+                      textAlign: 'left',
+                      fontSize: '0.85rem'
+                    }}
+                  >
+                    {option.label}
+                  </button>
+                ))}
+              </div>
+              <p style={{ margin: '0.5rem 0 0', fontSize: '0.8rem', color: 'var(--text-secondary)' }}>
+                Aynı site ve kullanıcı adına sahip hesaplar, aynı numaralı kartlar ve aynı anahtarlı doğrulayıcılar yinelenen sayılır.
+                {' '}{MERGE_STRATEGY_OPTIONS.find(option => option.value === mergeStrategy)?.description}.
+              </p>
+            </div>
+
+            <div style={{ display: 'flex', gap: '0.75rem' }}>
+              <button
+                onClick={() => setPendingImport(null)}
+                disabled={isImporting}
+                style={{
+                  flex: 1,
+                  padding: '0.75rem',
+                  borderRadius: '8px',
+                  border: '1px solid var(--border)',
+                  background: 'var(--bg-tertiary)',
+                  color: 'var(--text-primary)',
+                  cursor: 'pointer',
+                  fontWeight: 500
+                }}
+              >
+                İptal
+              </button>
+              <button
+                onClick={handleConfirmImport}
+                disabled={isImporting || pendingImport.preview.entries.length === 0}
+                style={{
+                  flex: 1,
+                  padding: '0.75rem',
+                  borderRadius: '8px',
+                  border: 'none',
+                  background: 'var(--accent)',
+                  color: '#000',
+                  cursor: isImporting ? 'wait' : 'pointer',
+                  fontWeight: 600,
+                  opacity: pendingImport.preview.entries.length === 0 ? 0.5 : 1
+                }}
+              >
+                {isImporting ? 'İçe aktarılıyor...' : `İçe Aktar (${pendingImport.preview.entries.length})`}
+              </button>
+            </div>
+          </div>
+        </div>
+      )}
+
+      {/* Import Report Modal */}
+      {importReport && (
+        <div className="modal-overlay" style={{
+          position: 'fixed',
+          top: 0,
+          left: 0,
+          right: 0,
+          bottom: 0,
+          background: 'rgba(0, 0, 0, 0.8)',
+          display: 'flex',
+          alignItems: 'center',
+          justifyContent: 'center',
+          zIndex: 1000
+        }}>
+          <div className="modal-content" style={{
+            background: 'var(--bg-secondary)',
+            borderRadius: '16px',
+            padding: '2rem',
+            maxWidth: '560px',
+            width: '90%',
+            border: '1px solid var(--border)'
+          }}>
+            <h2 style={{ margin: '0 0 0.25rem', fontSize: '1.25rem' }}>İçe Aktarma Raporu</h2>
+            <p style={{ margin: '0 0 1rem', color: 'var(--text-secondary)', fontSize: '0.9rem' }}>
+              {importSummaryMessage(importReport)}.
+            </p>
+
+            <div style={{
+              maxHeight: '300px',
+              overflowY: 'auto',
+              marginBottom: '1.5rem',
+              padding: '0.75rem 1rem',
+              borderRadius: '8px',
+              background: 'var(--bg-tertiary)',
+              fontSize: '0.85rem'
+            }}>
+              {importReport.changes.filter(change => change.action !== 'added').map((change, index) => (
+                <div key={index} style={{ display: 'flex', justifyContent: 'space-between', gap: '1rem', marginBottom: '0.4rem' }}>
+                  <span>
+                    {change.title}
+                    <span style={{ color: 'var(--text-secondary)' }}> · {IMPORT_CATEGORY_LABELS[change.category] || change.category}</span>
+                    {change.changed_fields && change.changed_fields.length > 0 && (
+                      <span style={{ color: 'var(--text-secondary)' }}> ({change.changed_fields.join(', ')})</span>
+                    )}
+                  </span>
+                  <span style={{ flexShrink: 0, color: change.action === 'updated' ? '#10b981' : 'var(--text-secondary)' }}>
+                    {IMPORT_ACTION_LABELS[change.action]}
+                  </span>
+                </div>
+              ))}
+            </div>
+
+            <button
+              onClick={() => setImportReport(null)}
+              style={{
+                width: '100%',
+                padding: '0.75rem',
+                borderRadius: '8px',
+                border: '1px solid var(--border)',
+                background: 'var(--bg-tertiary)',
+                color: 'var(--text-primary)',
+                cursor: 'pointer',
+                fontWeight: 500
+              }}
+            >
+              Kapat
+            </button>
+          </div>
+        </div>
+      )}
+
       {/* Export Modal */}
       {showExportModal && (
         <div className="modal-overlay" style={{
@@ -1485,10 +2898,46 @@ function Settings({ onBack, showToast, onResetComplete }: SettingsProps) {
                 </div>
               </button>
 
+              <button
+                onClick={() => { setShowExportModal(false); setKdbxMode('export'); }}
+                style={{
+                  padding: '1rem',
+                  borderRadius: '12px',
+                  border: '1px solid var(--border)',
+                  background: 'var(--bg-tertiary)',
+                  color: 'var(--text-primary)',
+                  cursor: 'pointer',
+                  display: 'flex',
+                  alignItems: 'center',
+                  gap: '1rem',
+                  textAlign: 'left',
+                  transition: 'all 0.2s'
+                }}
+                onMouseOver={(e) => e.currentTarget.style.borderColor = 'var(--accent)'}
+                onMouseOut={(e) => e.currentTarget.style.borderColor = 'var(--border)'}
+              >
+                <div style={{
+                  width: '40px',
+                  height: '40px',
+                  borderRadius: '8px',
+                  background: '#4d8f3a',
+                  display: 'flex',
+                  alignItems: 'center',
+                  justifyContent: 'center',
+                  color: '#fff'
+                }}>
+                  <Lock size={20} />
+                </div>
+                <div>
+                  <div style={{ fontWeight: 600 }}>KeePass (KDBX)</div>
+                  <div style={{ fontSize: '0.85rem', color: 'var(--text-secondary)' }}>KeePass ve KeePassXC ile açılan şifreli veritabanı</div>
+                </div>
+              </button>
+
               <div style={{ borderTop: '1px solid var(--border)', paddingTop: '1rem' }}>
                 <div style={{ fontWeight: 600, marginBottom: '0.75rem' }}>Şifreli Export (.cpvault)</div>
                 <div style={{ fontSize: '0.85rem', color: 'var(--text-secondary)', marginBottom: '1rem' }}>
-                  Güvenli yedekleme için şifre ile koruma
+                  Kayıtlar, klasörler, etiketler, şifre geçmişi, geçiş anahtarları ve ekler tek bir şifreli dosyada yedeklenir
                 </div>
 
                 <div style={{ marginBottom: '0.75rem' }}>
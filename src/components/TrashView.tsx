@@ -0,0 +1,345 @@
+import { useState, useEffect, useMemo } from 'react';
+import { invoke } from '@tauri-apps/api/core';
+import { Trash, Trash2, RotateCcw, Search, Clock } from 'lucide-react';
+import { CATEGORY_NAMES } from '../constants';
+import type { PasswordEntry } from '../types';
+
+interface TrashViewProps {
+  entries: PasswordEntry[];
+  showToast: (message: string, type: 'success' | 'error' | 'info') => void;
+  loadEntries: () => Promise<void>;
+  setConfirmDialog: (dialog: { message: string; onConfirm: () => void } | null) => void;
+}
+
+const DAY_SECONDS = 24 * 60 * 60;
+
+export default function TrashView({
+  entries,
+  showToast,
+  loadEntries,
+  setConfirmDialog
+}: TrashViewProps) {
+  const [searchQuery, setSearchQuery] = useState('');
+  const [retentionDays, setRetentionDays] = useState(30);
+
+  useEffect(() => {
+    invoke<{ trash_retention_days: number }>('get_settings')
+      .then(settings => setRetentionDays(settings.trash_retention_days))
+      .catch(err => console.error('Ayarlar yüklenemedi:', err));
+  }, []);
+
+  const trashedEntries = useMemo(() => {
+    const query = searchQuery.toLowerCase().trim();
+    return entries
+      .filter(e => e.deleted_at)
+      .filter(e => !query ||
+        e.title.toLowerCase().includes(query) ||
+        e.username.toLowerCase().includes(query))
+      .sort((a, b) => (b.deleted_at || 0) - (a.deleted_at || 0));
+  }, [entries, searchQuery]);
+
+  const remainingDays = (entry: PasswordEntry): number | null => {
+    if (retentionDays === 0 || !entry.deleted_at) return null;
+    const purgeAt = entry.deleted_at + retentionDays * DAY_SECONDS;
+    return Math.max(0, Math.ceil((purgeAt - Date.now() / 1000) / DAY_SECONDS));
+  };
+
+  const handleRestore = async (entry: PasswordEntry) => {
+    try {
+      await invoke('restore_entry', { id: entry.id });
+      await loadEntries();
+      showToast('Başarıyla geri yüklendi', 'success');
+    } catch (err) {
+      showToast('Geri yükleme başarısız: ' + String(err), 'error');
+    }
+  };
+
+  const handlePermanentDelete = (entry: PasswordEntry) => {
+    setConfirmDialog({
+      message: `"${entry.title}" kaydını kalıcı olarak silmek istediğinizden emin misiniz?\n\n⚠️ Bu işlem geri alınamaz!`,
+      onConfirm: async () => {
+        try {
+          await invoke('permanently_delete_entry', { id: entry.id });
+          await loadEntries();
+          showToast('Kalıcı olarak silindi', 'success');
+        } catch (err) {
+          showToast('Silme başarısız: ' + String(err), 'error');
+        }
+        setConfirmDialog(null);
+      }
+    });
+  };
+
+  const handleEmptyTrash = () => {
+    const count = entries.filter(e => e.deleted_at).length;
+    if (count === 0) {
+      showToast('Çöp kutusu zaten boş', 'info');
+      return;
+    }
+
+    setConfirmDialog({
+      message: `Çöp kutusundaki ${count} öğeyi kalıcı olarak silmek istediğinizden emin misiniz?\n\n⚠️ Bu işlem geri alınamaz!`,
+      onConfirm: async () => {
+        try {
+          await invoke<number>('empty_trash');
+          await loadEntries();
+          showToast('Çöp kutusu boşaltıldı', 'success');
+        } catch (err) {
+          showToast('İşlem başarısız: ' + String(err), 'error');
+        }
+        setConfirmDialog(null);
+      }
+    });
+  };
+
+  return (
+    <div className="trash-view">
+      <div className="trash-header">
+        <div className="trash-title-section">
+          <Trash size={28} style={{ color: 'var(--accent)' }} />
+          <div>
+            <h1>Çöp Kutusu</h1>
+            <p className="trash-subtitle">
+              {retentionDays === 0
+                ? 'Kayıtlar çöp kutusu boşaltılana kadar saklanır'
+                : `Kayıtlar ${retentionDays} gün sonra kalıcı olarak silinir`}
+            </p>
+          </div>
+        </div>
+      </div>
+
+      <div className="trash-search">
+        <Search size={18} />
+        <input
+          type="text"
+          placeholder="Çöp kutusunda ara..."
+          value={searchQuery}
+          onChange={(e) => setSearchQuery(e.target.value)}
+        />
+        {trashedEntries.length > 0 && (
+          <button
+            className="empty-trash-btn"
+            onClick={handleEmptyTrash}
+            title="Çöp Kutusunu Boşalt"
+          >
+            <Trash2 size={16} />
+            Boşalt
+          </button>
+        )}
+      </div>
+
+      <div className="trash-list">
+        {trashedEntries.length === 0 ? (
+          <div className="trash-empty">
+            <Trash size={48} />
+            <h3>Çöp kutusu boş</h3>
+            <p>Silinen kayıtlar burada görünür ve geri yüklenebilir</p>
+          </div>
+        ) : (
+          trashedEntries.map(entry => {
+            const days = remainingDays(entry);
+            return (
+              <div key={entry.id} className="trash-card">
+                <div className="trash-card-info">
+                  <div className="trash-card-title">{entry.title}</div>
+                  <div className="trash-card-detail">
+                    <span>{CATEGORY_NAMES[entry.category] || entry.category}</span>
+                    {entry.username && <span>· {entry.username}</span>}
+                  </div>
+                  {days !== null && (
+                    <div className="trash-card-detail">
+                      <Clock size={14} />
+                      <span>{days === 0 ? 'Bugün silinecek' : `${days} gün sonra silinecek`}</span>
+                    </div>
+                  )}
+                </div>
+                <div className="trash-card-actions">
+                  <button
+                    onClick={() => handleRestore(entry)}
+                    className="restore-btn"
+                    title="Geri Yükle"
+                  >
+                    <RotateCcw size={16} />
+                  </button>
+                  <button
+                    onClick={() => handlePermanentDelete(entry)}
+                    className="delete-btn permanent"
+                    title="Kalıcı Sil"
+                  >
+                    <Trash2 size={16} />
+                  </button>
+                </div>
+              </div>
+            );
+          })
+        )}
+      </div>
+
+      <style>{`
+        .trash-view {
+          display: flex;
+          flex-direction: column;
+          height: 100%;
+          background: var(--bg-primary);
+        }
+
+        .trash-header {
+          display: flex;
+          align-items: center;
+          padding: 1.5rem 2rem;
+          background: var(--bg-secondary);
+          border-bottom: 1px solid var(--border);
+        }
+
+        .trash-title-section {
+          display: flex;
+          align-items: center;
+          gap: 1rem;
+        }
+
+        .trash-title-section h1 {
+          font-family: 'Sora', sans-serif;
+          font-size: 1.5rem;
+          font-weight: 600;
+          color: var(--text-primary);
+          margin: 0;
+        }
+
+        .trash-subtitle {
+          font-size: 0.85rem;
+          color: var(--text-secondary);
+          margin: 0;
+        }
+
+        .trash-search {
+          display: flex;
+          align-items: center;
+          gap: 0.75rem;
+          padding: 1rem 2rem;
+          background: var(--bg-secondary);
+        }
+
+        .trash-search svg {
+          color: var(--text-tertiary);
+        }
+
+        .trash-search input {
+          flex: 1;
+          background: var(--bg-tertiary);
+          border: 1px solid var(--border);
+          border-radius: 10px;
+          padding: 0.75rem 1rem;
+          color: var(--text-primary);
+          font-size: 0.9rem;
+        }
+
+        .trash-search input:focus {
+          outline: none;
+          border-color: var(--accent);
+        }
+
+        .trash-view .empty-trash-btn {
+          display: flex;
+          align-items: center;
+          gap: 6px;
+          padding: 0.6rem 1rem;
+          background: rgba(239, 68, 68, 0.1);
+          border: 1px solid rgba(239, 68, 68, 0.3);
+          border-radius: 10px;
+          color: #ef4444;
+          font-size: 0.85rem;
+          font-weight: 500;
+          cursor: pointer;
+        }
+
+        .trash-list {
+          flex: 1;
+          overflow-y: auto;
+          padding: 1.5rem 2rem;
+          display: flex;
+          flex-direction: column;
+          gap: 0.75rem;
+        }
+
+        .trash-empty {
+          display: flex;
+          flex-direction: column;
+          align-items: center;
+          padding: 4rem 2rem;
+          text-align: center;
+          color: var(--text-tertiary);
+        }
+
+        .trash-empty h3 {
+          font-family: 'Sora', sans-serif;
+          font-size: 1.25rem;
+          color: var(--text-secondary);
+          margin: 1rem 0 0.5rem;
+        }
+
+        .trash-card {
+          display: flex;
+          align-items: center;
+          gap: 1rem;
+          padding: 1.25rem;
+          background: linear-gradient(145deg, var(--bg-secondary) 0%, var(--bg-tertiary) 100%);
+          border: 1px solid var(--border);
+          border-left: 4px solid #f59e0b;
+          border-radius: 16px;
+        }
+
+        .trash-card-info {
+          flex: 1;
+          min-width: 0;
+        }
+
+        .trash-card-title {
+          font-family: 'Sora', sans-serif;
+          font-weight: 600;
+          color: var(--text-primary);
+          margin-bottom: 0.4rem;
+          white-space: nowrap;
+          overflow: hidden;
+          text-overflow: ellipsis;
+        }
+
+        .trash-card-detail {
+          display: flex;
+          align-items: center;
+          gap: 0.5rem;
+          color: var(--text-secondary);
+          font-size: 0.85rem;
+          margin-top: 0.25rem;
+        }
+
+        .trash-card-actions {
+          display: flex;
+          gap: 0.5rem;
+        }
+
+        .trash-card-actions button {
+          width: 38px;
+          height: 38px;
+          border-radius: 10px;
+          border: 1px solid transparent;
+          background: var(--bg-tertiary);
+          color: var(--text-tertiary);
+          cursor: pointer;
+          display: flex;
+          align-items: center;
+          justify-content: center;
+        }
+
+        .trash-card-actions .restore-btn:hover {
+          background: rgba(16, 185, 129, 0.15);
+          color: #10b981;
+        }
+
+        .trash-card-actions .delete-btn.permanent:hover {
+          background: #ef4444;
+          color: white;
+        }
+      `}</style>
+    </div>
+  );
+}
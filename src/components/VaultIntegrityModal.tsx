@@ -0,0 +1,169 @@
+import { useState } from 'react';
+import { invoke } from '@tauri-apps/api/core';
+import { X, ShieldCheck, AlertTriangle, CheckCircle, Wrench, RotateCcw } from 'lucide-react';
+
+interface IntegrityIssue {
+  kind: string;
+  message: string;
+  subject: string | null;
+  repairable: boolean;
+}
+
+interface IntegrityReport {
+  issues: IntegrityIssue[];
+  repaired: IntegrityIssue[];
+  backup_id: string | null;
+}
+
+interface BackupInfo {
+  id: string;
+  created_at: number;
+}
+
+interface VaultIntegrityModalProps {
+  onClose: () => void;
+  showToast: (message: string, type: 'success' | 'error' | 'info') => void;
+  // Called after a repair or a backup restore; the backend locks the vault first
+  onRepaired?: () => void;
+}
+
+const UNREADABLE_KINDS = ['missing_vault_file', 'invalid_header', 'missing_salt', 'invalid_json', 'schema_mismatch'];
+
+const isUnreadable = (report: IntegrityReport) =>
+  report.issues.some(issue => UNREADABLE_KINDS.includes(issue.kind));
+
+export default function VaultIntegrityModal({ onClose, showToast, onRepaired }: VaultIntegrityModalProps) {
+  const [password, setPassword] = useState('');
+  const [report, setReport] = useState<IntegrityReport | null>(null);
+  const [isWorking, setIsWorking] = useState(false);
+  const [backups, setBackups] = useState<BackupInfo[]>([]);
+
+  const run = async (command: 'verify_vault' | 'repair_vault') => {
+    if (!password) {
+      showToast('Lütfen ana şifrenizi girin', 'error');
+      return;
+    }
+
+    setIsWorking(true);
+    try {
+      const result = await invoke<IntegrityReport>(command, { masterPassword: password });
+      setReport(result);
+      if (isUnreadable(result)) {
+        setBackups(await invoke<BackupInfo[]>('list_backups'));
+      }
+      if (command === 'repair_vault' && result.backup_id) {
+        showToast(`${result.repaired.length} sorun giderildi`, 'success');
+        onRepaired?.();
+      } else if (result.issues.length === 0) {
+        showToast('Kasada sorun bulunamadı', 'success');
+      }
+    } catch (error) {
+      showToast(String(error || 'Kasa doğrulanamadı'), 'error');
+    } finally {
+      setIsWorking(false);
+    }
+  };
+
+  const handleRestore = async (backup: BackupInfo) => {
+    setIsWorking(true);
+    try {
+      await invoke('restore_backup', { backupId: backup.id, masterPassword: password });
+      showToast('Yedek geri yüklendi. Kasayı yedeğin ana şifresiyle açın.', 'success');
+      onRepaired?.();
+      onClose();
+    } catch (error) {
+      showToast('Geri yükleme başarısız: ' + error, 'error');
+    } finally {
+      setIsWorking(false);
+    }
+  };
+
+  const repairable = report?.issues.filter(issue => issue.repairable).length ?? 0;
+  const unreadable = report !== null && isUnreadable(report);
+
+  return (
+    <div className="modal-overlay">
+      <div className="modal-content" style={{ maxWidth: '520px', position: 'relative' }}>
+        <button type="button" className="modal-close-btn" onClick={onClose} style={{ position: 'absolute', top: '16px', right: '16px' }}>
+          <X size={20} />
+        </button>
+        <h2>Kasa Bütünlüğü</h2>
+
+        <p style={{ color: 'var(--text-secondary)', fontSize: '0.9rem', marginBottom: '1rem' }}>
+          Kasa dosyası, şifre çözme, veri yapısı, klasörler, ekler ve geçiş anahtarları kontrol edilir.
+          Onarımdan önce kasa yedeklenir.
+        </p>
+
+        <div className="form-group">
+          <input
+            type="password"
+            placeholder="Ana şifre"
+            value={password}
+            onChange={(e) => setPassword(e.target.value)}
+            onKeyDown={(e) => e.key === 'Enter' && run('verify_vault')}
+            autoFocus
+          />
+        </div>
+
+        {report && (
+          <div style={{ marginBottom: '1.5rem', maxHeight: '260px', overflowY: 'auto' }}>
+            {report.repaired.map((issue, index) => (
+              <div key={`repaired-${index}`} style={{ display: 'flex', gap: '0.5rem', alignItems: 'flex-start', marginBottom: '0.5rem', fontSize: '0.85rem' }}>
+                <CheckCircle size={16} style={{ color: '#10b981', flexShrink: 0, marginTop: '2px' }} />
+                <span>{issue.message}</span>
+              </div>
+            ))}
+            {report.issues.length === 0 ? (
+              <div style={{ display: 'flex', gap: '0.5rem', alignItems: 'center', color: '#10b981' }}>
+                <ShieldCheck size={18} />
+                <span>Kasada sorun bulunamadı</span>
+              </div>
+            ) : (
+              report.issues.map((issue, index) => (
+                <div key={`issue-${index}`} style={{ display: 'flex', gap: '0.5rem', alignItems: 'flex-start', marginBottom: '0.5rem', fontSize: '0.85rem' }}>
+                  <AlertTriangle size={16} style={{ color: issue.repairable ? '#f59e0b' : '#ff4757', flexShrink: 0, marginTop: '2px' }} />
+                  <span>{issue.message}</span>
+                </div>
+              ))
+            )}
+            {unreadable && (
+              <p style={{ color: 'var(--text-secondary)', fontSize: '0.85rem', marginTop: '0.75rem' }}>
+                {backups.length === 0
+                  ? 'Kasa okunamıyor ve geri yüklenebilecek bir yedek yok.'
+                  : 'Kasa okunamıyor. Aşağıdaki yedeklerden birini, alındığı andaki ana şifreyle geri yükleyebilirsiniz.'}
+              </p>
+            )}
+            {unreadable && backups.slice(0, 5).map(backup => (
+              <div key={backup.id} style={{ display: 'flex', justifyContent: 'space-between', alignItems: 'center', padding: '0.5rem 0', borderTop: '1px solid var(--border)', fontSize: '0.85rem' }}>
+                <span>{new Date(backup.created_at * 1000).toLocaleString('tr-TR')}</span>
+                <button
+                  onClick={() => handleRestore(backup)}
+                  className="cancel-button"
+                  disabled={isWorking}
+                  style={{ display: 'flex', alignItems: 'center', gap: '0.4rem' }}
+                >
+                  <RotateCcw size={14} />
+                  Geri Yükle
+                </button>
+              </div>
+            ))}
+          </div>
+        )}
+
+        <div className="modal-actions">
+          <button onClick={() => run('verify_vault')} className="cancel-button" disabled={isWorking}>
+            {isWorking ? 'Kontrol ediliyor...' : 'Doğrula'}
+          </button>
+          <button
+            onClick={() => run('repair_vault')}
+            className="submit-button"
+            disabled={isWorking || !report || repairable === 0}
+          >
+            <Wrench size={16} />
+            Onar{repairable > 0 ? ` (${repairable})` : ''}
+          </button>
+        </div>
+      </div>
+    </div>
+  );
+}
@@ -18,8 +18,16 @@ export const CATEGORY_OPTIONS = VALID_CATEGORIES.map(cat => ({
   label: CATEGORY_NAMES[cat]
 }));
 
+export const API_SCOPE_NAMES: Record<string, { label: string; description: string }> = {
+  'autofill-read': { label: 'Otomatik doldurma', description: 'Siteye ait hesapları ve doğrulama kodlarını okur' },
+  'save': { label: 'Kaydetme', description: 'Yeni şifreleri ve kayıtları kasaya ekler' },
+  'passkeys': { label: 'Geçiş anahtarları', description: 'Geçiş anahtarlarını listeler, oluşturur ve kullanır' },
+  'cards': { label: 'Kartlar ve adresler', description: 'Banka kartlarını ve adresleri okur' }
+};
+
 export const DEBOUNCE_DELAY = 300;
 
-export const AUTO_LOCK_TIMEOUT = 5 * 60 * 1000;
+// Auto-lock runs in the backend; the UI reports activity at most this often
+export const ACTIVITY_REPORT_INTERVAL = 15 * 1000;
 
 export const TOAST_DURATION = 3000;
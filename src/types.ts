@@ -6,6 +6,12 @@ export interface FileAttachment {
   created_at: number;
 }
 
+/** Inline preview of an attachment; `data` is a base64 PNG thumbnail. */
+export type AttachmentPreview =
+  | { kind: 'image'; mime_type: string; data: string; width: number; height: number }
+  | { kind: 'text'; text: string; truncated: boolean }
+  | { kind: 'unsupported'; mime_type: string };
+
 export interface PasswordEntry {
   id: string;
   title: string;
@@ -19,8 +25,13 @@ export interface PasswordEntry {
   folder_id?: string;
   tags?: string[];
   attachments?: FileAttachment[];
+  data?: EntryData;
+  deleted_at?: number;
 }
 
+/** Structured fields of an entry, tagged with the item type. */
+export type EntryData = { type: string } & Record<string, unknown>;
+
 export interface Folder {
   id: string;
   name: string;
@@ -140,3 +151,52 @@ export interface UpdateInfo {
   error?: string | null;
   url?: string;
 }
+
+// Dry run of an import: what would be added, and what could not be read
+export interface ImportPreview {
+  entries: { title: string; category: string; deleted_at?: number | null }[];
+  folders: { name: string }[];
+  tags: { name: string }[];
+  warnings: { row: number; title: string; message: string }[];
+  skipped: number;
+}
+
+// What an import does with entries that duplicate a vault entry
+export type MergeStrategy = 'keep_both' | 'prefer_newer' | 'overwrite' | 'skip';
+
+export interface ImportChange {
+  id: string;
+  title: string;
+  category: string;
+  action: 'added' | 'kept_both' | 'updated' | 'skipped';
+  duplicate_of: string | null;
+  changed_fields?: string[];
+}
+
+// Permission a paired browser extension holds on the local HTTP API
+export type ApiScope = 'autofill-read' | 'save' | 'passkeys' | 'cards';
+
+export interface ApiClient {
+  id: string;
+  name: string;
+  scopes: ApiScope[];
+  created_at: number;
+  last_used_at: number | null;
+}
+
+// A browser extension waiting for the user to allow it access
+export interface PairingRequest {
+  id: string;
+  name: string;
+  requested_scopes: ApiScope[];
+  created_at: number;
+}
+
+export interface ImportSummary {
+  imported: number;
+  updated: number;
+  skipped: number;
+  folders_created: number;
+  tags_created: number;
+  changes: ImportChange[];
+}
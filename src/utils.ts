@@ -1,3 +1,5 @@
+import type { EntryData, PasswordEntry } from './types';
+
 export const clearClipboard = async (text: string, delay: number = 30000) => {
   try {
     await navigator.clipboard.writeText(text);
@@ -27,6 +29,34 @@ export const validateUrl = (url: string): boolean => {
   }
 };
 
+/** Item type the backend stores in `data` for each category. */
+const DATA_TYPES: Record<string, string> = {
+  accounts: 'login',
+  bank_cards: 'card',
+  addresses: 'address',
+  authenticator: 'totp',
+  passkeys: 'passkey',
+  notes: 'secure_note',
+  documents: 'document',
+};
+
+/** Structured fields of an entry, without the type tag. */
+export const entryFields = <T extends object>(entry: PasswordEntry): Partial<T> => {
+  const fields: Record<string, unknown> = { ...entry.data };
+  delete fields.type;
+  return fields as Partial<T>;
+};
+
+/**
+ * `data` for an entry of `category`. Pass the entry being edited so fields
+ * the form does not show, such as a passkey's credential id, are kept.
+ */
+export const toEntryData = (category: string, fields: object, entry?: PasswordEntry): EntryData => ({
+  ...(entry ? entryFields(entry) : {}),
+  ...fields,
+  type: DATA_TYPES[category] ?? category,
+});
+
 export const formatDate = (timestamp: number): string => {
   const date = new Date(timestamp * 1000);
   return date.toLocaleDateString('tr-TR', {